@@ -1,7 +1,10 @@
 //! Format attributes and meta items.
 
 use rustc_ast::ast;
+use rustc_ast::token::{Delimiter, Token, TokenKind};
+use rustc_ast::tokenstream::TokenTree;
 use rustc_ast::HasAttrs;
+use rustc_ast_pretty::pprust;
 use rustc_span::{symbol::sym, Span};
 
 use self::doc_comment::DocCommentFormatter;
@@ -9,13 +12,17 @@ use crate::comment::{contains_comment, rewrite_doc_comment, CommentStyle};
 use crate::config::lists::*;
 use crate::config::IndentStyle;
 use crate::expr::rewrite_literal;
-use crate::lists::{definitive_tactic, itemize_list, write_list, ListFormatting, Separator};
+use crate::formatting::FormattingError;
+use crate::lists::{
+    definitive_tactic, itemize_list, write_list, ListFormatting, ListItem, Separator,
+};
 use crate::overflow;
 use crate::rewrite::{Rewrite, RewriteContext};
 use crate::shape::Shape;
 use crate::source_map::SpanUtils;
 use crate::types::{rewrite_path, PathContext};
 use crate::utils::{count_newlines, mk_sp};
+use crate::{CodeBlockError, ErrorKind};
 
 mod doc_comment;
 
@@ -212,6 +219,62 @@ where
     &attrs[..len]
 }
 
+/// Rewrites a doc comment starting at `span`. The code blocks in the doc comments of private
+/// items are left alone unless `doc_comment_include_private` is set.
+fn rewrite_item_doc_comment(
+    context: &RewriteContext<'_>,
+    orig: &str,
+    span: Span,
+    shape: Shape,
+) -> Option<String> {
+    if context.is_private_item.get()
+        && context.config.format_code_in_doc_comments()
+        && !context.config.doc_comment_include_private()
+    {
+        let mut config = context.config.clone();
+        config.set().format_code_in_doc_comments(false);
+        return rewrite_doc_comment(orig, shape.comment(&config), &config, &mut Vec::new());
+    }
+    let mut code_block_errors = Vec::new();
+    let rewrite = rewrite_doc_comment(
+        orig,
+        shape.comment(context.config),
+        context.config,
+        &mut code_block_errors,
+    );
+    if context.config.error_on_unformatted() && !code_block_errors.is_empty() {
+        report_code_block_errors(context, orig, span, code_block_errors);
+    }
+    rewrite
+}
+
+/// Warns about the code blocks of the doc comment `orig` that could not be formatted, each at
+/// the line the error points at, or else at the first line of the code block. Doc comments
+/// that are rewritten more than once are only warned about once.
+fn report_code_block_errors(
+    context: &RewriteContext<'_>,
+    orig: &str,
+    span: Span,
+    code_block_errors: Vec<(usize, CodeBlockError)>,
+) {
+    let file_name = context.psess.span_to_filename(span);
+    let errors = code_block_errors
+        .into_iter()
+        .map(|(code_block_start, error)| {
+            let offset = code_block_start + error.line.map_or(0, |line| line - 1);
+            let kind = ErrorKind::UnformattedCodeBlock(error.to_string());
+            let mut error = FormattingError::from_span(span, context.psess, kind);
+            error.line += offset;
+            error.line_buffer = orig.lines().nth(offset).unwrap_or_default().to_owned();
+            error
+        })
+        .filter(|error| !context.report.contains_error(&file_name, error))
+        .collect::<Vec<_>>();
+    if !errors.is_empty() {
+        context.report.append(file_name, errors);
+    }
+}
+
 /// Rewrite the any doc comments which come before any other attributes.
 fn rewrite_initial_doc_comments(
     context: &RewriteContext<'_>,
@@ -231,10 +294,11 @@ fn rewrite_initial_doc_comments(
             .join("\n");
         return Some((
             sugared_docs.len(),
-            Some(rewrite_doc_comment(
+            Some(rewrite_item_doc_comment(
+                context,
                 &snippet,
-                shape.comment(context.config),
-                context.config,
+                sugared_docs[0].span,
+                shape,
             )?),
         ));
     }
@@ -281,19 +345,24 @@ impl Rewrite for ast::MetaItem {
             ast::MetaItemKind::List(ref list) => {
                 let path = rewrite_path(context, PathContext::Type, &None, &self.path, shape)?;
                 let has_trailing_comma = crate::expr::span_ends_with_comma(context, self.span);
+                let trailing_separator = if has_trailing_comma {
+                    SeparatorTactic::Always
+                } else {
+                    SeparatorTactic::Never
+                };
+                // 1 = "]"
+                let shape = shape.sub_width(1)?;
+                if let Some(lints) = sorted_lint_list(context, self, list) {
+                    return rewrite_arg_list(context, &path, &lints, trailing_separator, shape);
+                }
                 overflow::rewrite_with_parens(
                     context,
                     &path,
                     list.iter(),
-                    // 1 = "]"
-                    shape.sub_width(1)?,
+                    shape,
                     self.span,
                     context.config.attr_fn_like_width(),
-                    Some(if has_trailing_comma {
-                        SeparatorTactic::Always
-                    } else {
-                        SeparatorTactic::Never
-                    }),
+                    Some(trailing_separator),
                 )?
             }
             ast::MetaItemKind::NameValue(ref lit) => {
@@ -314,11 +383,102 @@ impl Rewrite for ast::MetaItem {
     }
 }
 
+/// Returns the items of a lint attribute like `#[allow(..)]` with its lints sorted and without
+/// duplicates, as requested by `sort_lint_attributes`. Bare lints come before tool lints such as
+/// `clippy::..`, and a trailing `reason = ".."` stays last.
+///
+/// Returns `None` if the list is already sorted, or if it contains anything but lints and a
+/// trailing reason.
+fn sorted_lint_list(
+    context: &RewriteContext<'_>,
+    meta: &ast::MetaItem,
+    list: &[ast::NestedMetaItem],
+) -> Option<Vec<String>> {
+    let is_lint_attribute = [sym::allow, sym::warn, sym::deny, sym::forbid, sym::expect]
+        .iter()
+        .any(|&name| meta.has_name(name));
+    if !context.config.sort_lint_attributes() || !is_lint_attribute {
+        return None;
+    }
+
+    let (lints, reason) = match list.split_last()? {
+        (last, lints) if last.has_name(sym::reason) && last.value_str().is_some() => {
+            (lints, Some(context.snippet(last.span()).to_owned()))
+        }
+        _ => (list, None),
+    };
+    let lints = lints
+        .iter()
+        .map(|lint| match lint.meta_item() {
+            Some(lint) if lint.is_word() => Some(
+                context
+                    .snippet(lint.span)
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .collect::<String>(),
+            ),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let mut sorted = lints.clone();
+    sorted.sort_by(|a, b| (a.contains("::"), a).cmp(&(b.contains("::"), b)));
+    sorted.dedup();
+    if sorted == lints {
+        return None;
+    }
+    sorted.extend(reason);
+    Some(sorted)
+}
+
+/// Rewrites the already formatted arguments of an attribute the way
+/// `overflow::rewrite_with_parens` would, which can't be used for items that are not in source
+/// order or are not AST nodes.
+fn rewrite_arg_list(
+    context: &RewriteContext<'_>,
+    path: &str,
+    args: &[String],
+    trailing_separator: SeparatorTactic,
+    shape: Shape,
+) -> Option<String> {
+    let items: Vec<_> = args.iter().map(ListItem::from_str).collect();
+    // 2 = `()`
+    let one_line_width = shape.width.saturating_sub(path.len() + 2);
+    let tactic = definitive_tactic(
+        &items,
+        ListTactic::LimitedHorizontalVertical(context.config.attr_fn_like_width()),
+        Separator::Comma,
+        one_line_width,
+    );
+    let horizontal = tactic == DefinitiveListTactic::Horizontal;
+    // 1 = `(`, 1 = `)`
+    let item_shape = argument_shape(path.len() + 1, 1, horizontal, shape, context)?;
+    let fmt = ListFormatting::new(item_shape, context.config)
+        .tactic(tactic)
+        .trailing_separator(if horizontal {
+            SeparatorTactic::Never
+        } else {
+            trailing_separator
+        })
+        .ends_with_newline(false);
+    let list_str = write_list(&items, &fmt)?;
+
+    if horizontal || context.config.indent_style() == IndentStyle::Visual {
+        Some(format!("{path}({list_str})"))
+    } else {
+        Some(format!(
+            "{path}({}{list_str}{})",
+            item_shape.indent.to_string_with_newline(context.config),
+            shape.indent.to_string_with_newline(context.config),
+        ))
+    }
+}
+
 impl Rewrite for ast::Attribute {
     fn rewrite(&self, context: &RewriteContext<'_>, shape: Shape) -> Option<String> {
         let snippet = context.snippet(self.span);
         if self.is_doc_comment() {
-            rewrite_doc_comment(snippet, shape.comment(context.config), context.config)
+            rewrite_item_doc_comment(context, snippet, self.span, shape)
         } else {
             let should_skip = self
                 .ident()
@@ -333,7 +493,12 @@ impl Rewrite for ast::Attribute {
             if let Some(ref meta) = self.meta() {
                 // This attribute is possibly a doc attribute needing normalization to a doc comment
                 if context.config.normalize_doc_attributes() && meta.has_name(sym::doc) {
-                    if let Some(ref literal) = meta.value_str() {
+                    // `value_str` has the escape sequences resolved. A bare carriage return can
+                    // only be written as an escape, as doc comments don't allow it.
+                    if let Some(ref literal) = meta
+                        .value_str()
+                        .filter(|literal| !literal.as_str().replace("\r\n", "\n").contains('\r'))
+                    {
                         let comment_style = match self.style {
                             ast::AttrStyle::Inner => CommentStyle::Doc,
                             ast::AttrStyle::Outer => CommentStyle::TripleSlash,
@@ -343,11 +508,7 @@ impl Rewrite for ast::Attribute {
                         let doc_comment_formatter =
                             DocCommentFormatter::new(literal_str, comment_style);
                         let doc_comment = format!("{doc_comment_formatter}");
-                        return rewrite_doc_comment(
-                            &doc_comment,
-                            shape.comment(context.config),
-                            context.config,
-                        );
+                        return rewrite_item_doc_comment(context, &doc_comment, self.span, shape);
                     }
                 }
 
@@ -366,10 +527,104 @@ impl Rewrite for ast::Attribute {
                     },
                 ))
             } else {
-                Some(snippet.to_owned())
+                Some(
+                    rewrite_listed_attribute_args(context, self, prefix, shape)
+                        .unwrap_or_else(|| snippet.to_owned()),
+                )
+            }
+        }
+    }
+}
+
+/// Rewrites an attribute listed in `format_attribute_args` whose arguments are not all meta
+/// items, like `#[arg(action = ArgAction::Count)]`. Every argument has to be a path, optionally
+/// followed by `= value` or by parenthesized arguments. The arguments are kept as they are, and
+/// only the list is wrapped.
+fn rewrite_listed_attribute_args(
+    context: &RewriteContext<'_>,
+    attr: &ast::Attribute,
+    prefix: &str,
+    shape: Shape,
+) -> Option<String> {
+    let ast::AttrKind::Normal(ref normal_attr) = attr.kind else {
+        return None;
+    };
+    let item = &normal_attr.item;
+    let path = pprust::path_to_string(&item.path);
+    if !context.config.format_attribute_args().contains(&path)
+        || !matches!(item.unsafety, ast::Safety::Default)
+    {
+        return None;
+    }
+    let ast::AttrArgs::Delimited(ref delim_args) = item.args else {
+        return None;
+    };
+    if delim_args.delim != Delimiter::Parenthesis {
+        return None;
+    }
+
+    let mut args = vec![];
+    let mut arg_tokens = vec![];
+    for tt in delim_args.tokens.trees() {
+        match tt {
+            TokenTree::Token(
+                Token {
+                    kind: TokenKind::Comma,
+                    ..
+                },
+                _,
+            ) => {
+                args.push(rewrite_attribute_arg(context, &arg_tokens)?);
+                arg_tokens.clear();
             }
+            _ => arg_tokens.push(tt),
+        }
+    }
+    let trailing_separator = if arg_tokens.is_empty() && !args.is_empty() {
+        SeparatorTactic::Always
+    } else {
+        args.push(rewrite_attribute_arg(context, &arg_tokens)?);
+        SeparatorTactic::Never
+    };
+
+    // 1 = `[`, 1 = `]`
+    let shape = shape.offset_left(prefix.len() + 1)?.sub_width(1)?;
+    let list = rewrite_arg_list(context, &path, &args, trailing_separator, shape)?;
+    Some(format!("{prefix}[{list}]"))
+}
+
+/// Returns the source text of an argument of an attribute listed in `format_attribute_args`, or
+/// `None` if it is not a path optionally followed by `= value` or by parenthesized arguments.
+fn rewrite_attribute_arg(context: &RewriteContext<'_>, tokens: &[&TokenTree]) -> Option<String> {
+    // The path is made of identifiers separated by `::`.
+    let mut path_len = 0;
+    while let Some(tt) = tokens.get(path_len) {
+        let expects_ident = path_len % 2 == 0;
+        let is_ident = matches!(tt, TokenTree::Token(token, _) if token.is_ident());
+        if (expects_ident && !is_ident) || (!expects_ident && !is_token(tt, TokenKind::PathSep)) {
+            break;
         }
+        path_len += 1;
+    }
+    if path_len % 2 == 0 {
+        return None;
     }
+
+    let is_valid = match &tokens[path_len..] {
+        [] => true,
+        [eq, value @ ..] if is_token(eq, TokenKind::Eq) => !value.is_empty(),
+        [TokenTree::Delimited(_, _, Delimiter::Parenthesis, _)] => true,
+        _ => false,
+    };
+    let lo = tokens.first()?.span().lo();
+    let hi = tokens.last()?.span().hi();
+    let snippet = context.snippet(mk_sp(lo, hi));
+    (is_valid && !snippet.contains('\n')).then(|| snippet.to_owned())
+}
+
+/// Returns `true` if `tt` is a token of the given `kind`.
+fn is_token(tt: &TokenTree, kind: TokenKind) -> bool {
+    matches!(tt, TokenTree::Token(token, _) if token.kind == kind)
 }
 
 impl Rewrite for [ast::Attribute] {
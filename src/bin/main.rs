@@ -19,7 +19,7 @@ use getopts::{Matches, Options};
 
 use crate::rustfmt::{
     load_config, CliOptions, Color, Config, Edition, EmitMode, FileLines, FileName,
-    FormatReportFormatterBuilder, Input, Session, Verbosity,
+    FormatReportFormatterBuilder, Input, Session, SummaryFormat, Verbosity,
 };
 
 const BUG_REPORT_URL: &str = "https://github.com/rust-lang/rustfmt/issues/new?labels=bug";
@@ -150,6 +150,45 @@ fn make_opts() -> Options {
         "Prints the names of mismatched files that were formatted. Prints the names of \
          files that would be formatted when used with `--check` mode. ",
     );
+    opts.optflagopt(
+        "",
+        "summary",
+        "Prints the number of lines that would change in each file, followed by the total. \
+         Only valid with `--check`.",
+        "[text|json]",
+    );
+    opts.optopt(
+        "",
+        "diff-tool",
+        "Opens the original and the formatted version of each mismatched file with CMD. \
+         `{old}` and `{new}` in the command are replaced by the paths of the two files. The \
+         command is split at whitespace, quotes are not supported. Only valid with `--check`.",
+        "CMD",
+    );
+    opts.optflag(
+        "",
+        "suggest-skips",
+        "Suggest adding `#[rustfmt::skip]` to items that failed to format or produced \
+         overlong lines.",
+    );
+    opts.optflag(
+        "",
+        "bump-format-version",
+        "Format files with the latest version and update their version stamps. Only has an \
+         effect when `format_version_stamps` is enabled.",
+    );
+    opts.optflag(
+        "",
+        "show-ruler",
+        "Print a column ruler above each file and highlight lines exceeding `max_width`, \
+         followed by a count of those lines. Only valid with `--emit stdout`.",
+    );
+    opts.optflag(
+        "",
+        "chunk-large-files",
+        "Format files that are too large for the parser on a best-effort basis, by splitting \
+         them into chunks at top-level items.",
+    );
     opts.optmulti(
         "",
         "config",
@@ -292,7 +331,9 @@ fn format_string(input: String, options: GetOptsOptions) -> Result<i32> {
     let mut session = Session::new(config, Some(out));
     format_and_emit_report(&mut session, Input::Text(input));
 
-    let exit_code = if session.has_operational_errors() || session.has_parsing_errors() {
+    let exit_code = if session.has_long_line_errors() {
+        2
+    } else if session.has_operational_errors() || session.has_parsing_errors() {
         1
     } else {
         0
@@ -356,7 +397,9 @@ fn format(
         file.write_all(toml.as_bytes())?;
     }
 
-    let exit_code = if session.has_operational_errors()
+    let exit_code = if session.has_long_line_errors() {
+        2
+    } else if session.has_operational_errors()
         || session.has_parsing_errors()
         || ((session.has_diff() || session.has_check_errors()) && options.check)
     {
@@ -519,6 +562,13 @@ struct GetOptsOptions {
     unstable_features: bool,
     error_on_unformatted: Option<bool>,
     print_misformatted_file_names: bool,
+    summary: Option<SummaryFormat>,
+    suggest_skips: bool,
+    bump_format_version: bool,
+    diff_tool: bool,
+    diff_command: Option<String>,
+    show_ruler: bool,
+    chunk_large_files: bool,
 }
 
 impl GetOptsOptions {
@@ -613,6 +663,46 @@ impl GetOptsOptions {
             options.print_misformatted_file_names = true;
         }
 
+        if matches.opt_present("summary") {
+            if !options.check {
+                return Err(format_err!("`--summary` can only be used with `--check`"));
+            }
+            options.summary = Some(match matches.opt_str("summary").as_deref() {
+                None | Some("text") => SummaryFormat::Text,
+                Some("json") => SummaryFormat::Json,
+                Some(summary) => return Err(format_err!("Invalid summary format: {}", summary)),
+            });
+        }
+
+        if matches.opt_present("suggest-skips") {
+            options.suggest_skips = true;
+        }
+
+        if matches.opt_present("bump-format-version") {
+            options.bump_format_version = true;
+        }
+
+        if matches.opt_present("diff-tool") {
+            if !options.check {
+                return Err(format_err!("`--diff-tool` can only be used with `--check`"));
+            }
+            options.diff_tool = true;
+            options.diff_command = matches.opt_str("diff-tool");
+        }
+
+        if matches.opt_present("show-ruler") {
+            if options.emit_mode != Some(EmitMode::Stdout) {
+                return Err(format_err!(
+                    "`--show-ruler` can only be used with `--emit stdout`"
+                ));
+            }
+            options.show_ruler = true;
+        }
+
+        if matches.opt_present("chunk-large-files") {
+            options.chunk_large_files = true;
+        }
+
         if !rust_nightly {
             if let Some(ref emit_mode) = options.emit_mode {
                 if !STABLE_EMIT_MODES.contains(emit_mode) {
@@ -681,10 +771,38 @@ impl CliOptions for GetOptsOptions {
         if self.print_misformatted_file_names {
             config.set().print_misformatted_file_names(true);
         }
+        if let Some(summary) = self.summary {
+            config.set().summary(summary);
+        }
+        if self.suggest_skips {
+            config.set().suggest_skips(true);
+        }
+        if self.bump_format_version {
+            config.set().bump_format_version(true);
+        }
+        if self.diff_tool {
+            config.set().diff_tool(true);
+        }
+        if let Some(diff_command) = self.diff_command {
+            config.set().diff_command(diff_command);
+        }
+        if self.show_ruler {
+            config.set().show_ruler(true);
+        }
+        if self.chunk_large_files {
+            config.set().chunk_large_files(true);
+        }
 
         for (key, val) in self.inline_config {
             config.override_value(&key, &val);
         }
+
+        // Standalone files have no `Cargo.toml` to take the edition from, so default to the
+        // latest stable edition, and fall back to older ones for files that don't parse with it.
+        if self.edition.is_none() && !config.was_set().edition() {
+            config.set().edition(Edition::Edition2021);
+            config.set().detect_edition(true);
+        }
     }
 
     fn config_path(&self) -> Option<&Path> {
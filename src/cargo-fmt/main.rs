@@ -71,6 +71,16 @@ pub struct Opts {
     /// Run rustfmt in check mode
     #[arg(long = "check")]
     check: bool,
+
+    /// Print the number of lines that would change in each file in check mode: text|json
+    #[arg(
+        long = "summary",
+        value_name = "summary",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "text"
+    )]
+    summary: Option<String>,
 }
 
 fn main() {
@@ -125,6 +135,9 @@ fn execute() -> i32 {
             rustfmt_args.push(check_flag.to_owned());
         }
     }
+    if let Some(summary) = opts.summary {
+        rustfmt_args.push(format!("--summary={summary}"));
+    }
     if let Some(message_format) = opts.message_format {
         if let Err(msg) = convert_message_format_to_rustfmt_args(&message_format, &mut rustfmt_args)
         {
@@ -16,6 +16,7 @@ fn default_options() {
     assert_eq!(false, o.format_all);
     assert_eq!(None, o.manifest_path);
     assert_eq!(None, o.message_format);
+    assert_eq!(None, o.summary);
 }
 
 #[test]
@@ -44,6 +45,14 @@ fn good_options() {
     assert_eq!(Some(String::from("short")), o.message_format);
 }
 
+#[test]
+fn summary_option() {
+    let o = Opts::parse_from(["test", "--check", "--summary"]);
+    assert_eq!(Some(String::from("text")), o.summary);
+    let o = Opts::parse_from(["test", "--check", "--summary=json"]);
+    assert_eq!(Some(String::from("json")), o.summary);
+}
+
 #[test]
 fn unexpected_option() {
     assert!(
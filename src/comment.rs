@@ -4,16 +4,20 @@ use std::{borrow::Cow, iter};
 
 use itertools::{multipeek, MultiPeek};
 use rustc_span::Span;
+use thiserror::Error;
 
-use crate::config::Config;
+use crate::config::{
+    CommentOnlyLines, Config, EmphasisMarker, HardBreakStyle, ThematicBreakStyle,
+    TrailingBlankLines,
+};
 use crate::rewrite::RewriteContext;
 use crate::shape::{Indent, Shape};
-use crate::string::{rewrite_string, StringFormat};
+use crate::string::{find_url_scheme, rewrite_string, StringFormat, TrimEnd};
 use crate::utils::{
     count_newlines, first_line_width, last_line_width, trim_left_preserve_layout,
     trimmed_last_line_width, unicode_str_width,
 };
-use crate::{ErrorKind, FormattingError};
+use crate::{CodeBlockError, ErrorKind, FormattingError};
 
 fn is_custom_comment(comment: &str) -> bool {
     if !comment.starts_with("//") {
@@ -235,8 +239,471 @@ pub(crate) fn combine_strs_with_missing_comments(
     Some(result)
 }
 
-pub(crate) fn rewrite_doc_comment(orig: &str, shape: Shape, config: &Config) -> Option<String> {
-    identify_comment(orig, false, shape, config, true)
+/// Rewrites a doc comment. The rust code blocks that could not be formatted are added to
+/// `code_block_errors`, along with the 0-based line of the comment they start at.
+pub(crate) fn rewrite_doc_comment(
+    orig: &str,
+    shape: Shape,
+    config: &Config,
+    code_block_errors: &mut Vec<(usize, CodeBlockError)>,
+) -> Option<String> {
+    if config.doc_comment_preserve_prose() {
+        return identify_comment(orig, false, shape, config, true, code_block_errors);
+    }
+    let (orig, removed_lines) = remove_unused_link_definitions(orig, config);
+    let orig = sort_link_definitions(&orig, config);
+    let orig = sort_footnote_definitions(&orig, config);
+    let (orig, trimmed_lines) = adjust_doc_comment_blank_lines(&orig, config);
+    let mut errors = Vec::new();
+    let rewrite = identify_comment(&orig, false, shape, config, true, &mut errors);
+    code_block_errors.extend(errors.into_iter().map(|(line, error)| {
+        let mut line = trimmed_lines + line;
+        for &removed in &removed_lines {
+            if removed <= line {
+                line += 1;
+            }
+        }
+        (line, error)
+    }));
+    rewrite
+}
+
+/// Why `format_markdown` could not format a Markdown document.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum MarkdownError {
+    /// The document could not be rewritten within the configured widths.
+    #[error("the document could not be formatted")]
+    Unformatted,
+    /// A Rust code block could not be formatted. This is only an error with `error_on_unformatted`.
+    #[error("line {line}: {message}")]
+    UnformattedCodeBlock {
+        /// The 1-based line of the document the error points at.
+        line: usize,
+        message: String,
+    },
+}
+
+/// Formats a standalone Markdown document, such as a `.md` file, the way the Markdown of doc
+/// comments is formatted with `config`. This is the supported entry point for formatting
+/// Markdown outside of Rust source files.
+///
+/// ```
+/// use rustfmt_nightly::{format_markdown, Config};
+///
+/// let mut config = Config::default();
+/// config.set().wrap_comments(true);
+/// config.set().comment_width(20);
+/// let formatted = format_markdown("# Title\n\nLorem ipsum dolor sit amet.\n", &config);
+/// assert_eq!(
+///     formatted.unwrap(),
+///     "# Title\n\nLorem ipsum dolor\nsit amet.\n"
+/// );
+/// ```
+pub fn format_markdown(input: &str, config: &Config) -> Result<String, MarkdownError> {
+    // The document is formatted as the text of a doc comment, from which the `/// ` of each
+    // line is removed again. The widths are widened by as much, so that they apply to the text.
+    const PREFIX: &str = "/// ";
+    let orig = input
+        .lines()
+        .map(|line| format!("{PREFIX}{line}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let (max_width, comment_width) = (config.max_width(), config.comment_width());
+    let mut config = config.clone();
+    config.set().max_width(max_width + PREFIX.len());
+    config.set().comment_width(comment_width + PREFIX.len());
+    let shape = Shape::legacy(config.max_width(), Indent::empty()).comment(&config);
+    let mut code_block_errors = Vec::new();
+    let rewrite = rewrite_doc_comment(&orig, shape, &config, &mut code_block_errors)
+        .ok_or(MarkdownError::Unformatted)?;
+    if config.error_on_unformatted() {
+        if let Some((code_block_start, error)) = code_block_errors.into_iter().next() {
+            return Err(MarkdownError::UnformattedCodeBlock {
+                line: code_block_start + error.line.unwrap_or(1),
+                message: error.message,
+            });
+        }
+    }
+
+    let mut result = rewrite
+        .lines()
+        .map(|line| {
+            line.strip_prefix(PREFIX)
+                .or_else(|| line.strip_prefix(PREFIX.trim_end()))
+                .unwrap_or(line)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if input.ends_with('\n') {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+fn is_line_doc_comment(line: &str) -> bool {
+    let line = line.trim_start();
+    (line.starts_with("///") && !line.starts_with("////")) || line.starts_with("//!")
+}
+
+/// Normalizes a link label the way Markdown matches them, i.e. case-insensitively and with any
+/// run of whitespace counting as a single space.
+fn normalize_link_label(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Returns the normalized label of a reference-link definition like `[label]: destination`.
+/// Footnote definitions like `[^note]: text` aren't link definitions.
+fn link_definition_label(text: &str) -> Option<String> {
+    let indent = text.len() - text.trim_start_matches(' ').len();
+    let (label, destination) = text[indent..].strip_prefix('[')?.split_once("]:")?;
+    if indent > 3
+        || label.trim().is_empty()
+        || label.starts_with('^')
+        || label.contains(['[', ']'])
+        || destination.trim().is_empty()
+    {
+        return None;
+    }
+    Some(normalize_link_label(label))
+}
+
+/// A reference-link definition like `[label]: destination` in a doc comment.
+struct LinkDefinition {
+    /// The index of the line it is on.
+    line: usize,
+    /// The normalized label.
+    label: String,
+    /// Whether its title is on the next line.
+    has_title_line: bool,
+}
+
+/// Returns the text of the lines of a `///` or `//!` doc comment without their comment markers,
+/// or `None` if some of the lines aren't line doc comments.
+fn doc_comment_texts<'a>(lines: &[&'a str]) -> Option<Vec<&'a str>> {
+    if !lines.iter().all(|line| is_line_doc_comment(line)) {
+        return None;
+    }
+    let texts = lines
+        .iter()
+        .map(|line| {
+            let text = &line.trim_start()[3..];
+            text.strip_prefix(' ').unwrap_or(text)
+        })
+        .collect();
+    Some(texts)
+}
+
+/// Returns the reference-link definitions of a doc comment, skipping the ones in code blocks.
+fn link_definitions(texts: &[&str]) -> Vec<LinkDefinition> {
+    let mut definitions = vec![];
+    let mut in_code_block = false;
+    let mut i = 0;
+    while i < texts.len() {
+        let text = texts[i];
+        let trimmed = text.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+        }
+        if let Some(label) = link_definition_label(text).filter(|_| !in_code_block) {
+            let has_title_line = texts.get(i + 1).map_or(false, |next| {
+                next.trim_start().starts_with(['"', '\'', '('])
+            });
+            definitions.push(LinkDefinition {
+                line: i,
+                label,
+                has_title_line,
+            });
+            if has_title_line {
+                i += 1;
+            }
+        }
+        i += 1;
+    }
+    definitions
+}
+
+/// Removes the reference-link definitions of a `///` or `//!` doc comment that no link in it
+/// refers to, as requested by `doc_comment_remove_unused_link_definitions`. Also returns the
+/// indices of the removed lines. Definitions in code blocks, definitions with a title on the
+/// next line and doc comments made up of nothing but unused definitions are left alone.
+fn remove_unused_link_definitions<'a>(
+    orig: &'a str,
+    config: &Config,
+) -> (Cow<'a, str>, Vec<usize>) {
+    if !config.doc_comment_remove_unused_link_definitions() {
+        return (Cow::Borrowed(orig), vec![]);
+    }
+    let lines = orig.lines().collect::<Vec<_>>();
+    let Some(texts) = doc_comment_texts(&lines) else {
+        return (Cow::Borrowed(orig), vec![]);
+    };
+    let definitions = link_definitions(&texts)
+        .into_iter()
+        .filter(|definition| !definition.has_title_line)
+        .collect::<Vec<_>>();
+
+    let mut references = String::new();
+    for (i, text) in texts.iter().enumerate() {
+        if !definitions.iter().any(|definition| definition.line == i) {
+            references.push_str(text);
+            references.push(' ');
+        }
+    }
+    // A definition is used by `[label]`, `[text][label]` and `[label][]` alike.
+    let references = normalize_link_label(&references)
+        .replace("[ ", "[")
+        .replace(" ]", "]");
+    let removed = definitions
+        .into_iter()
+        .filter(|definition| !references.contains(&format!("[{}]", definition.label)))
+        .map(|definition| definition.line)
+        .collect::<Vec<_>>();
+    if removed.is_empty() || removed.len() == lines.len() {
+        return (Cow::Borrowed(orig), vec![]);
+    }
+
+    let mut result = lines
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !removed.contains(i))
+        .map(|(_, line)| *line)
+        .collect::<Vec<_>>()
+        .join("\n");
+    // The first line has no indentation, unlike the others.
+    if removed[0] == 0 {
+        result = result.trim_start().to_owned();
+    }
+    (Cow::Owned(result), removed)
+}
+
+/// Sorts the runs of consecutive reference-link definitions of a `///` or `//!` doc comment by
+/// their labels, as requested by `doc_comment_sort_link_definitions`. A title on the line after
+/// a definition moves along with it.
+fn sort_link_definitions<'a>(orig: &'a str, config: &Config) -> Cow<'a, str> {
+    if !config.doc_comment_sort_link_definitions() {
+        return Cow::Borrowed(orig);
+    }
+    let lines = orig.lines().collect::<Vec<_>>();
+    let Some(texts) = doc_comment_texts(&lines) else {
+        return Cow::Borrowed(orig);
+    };
+    let definitions = link_definitions(&texts);
+
+    // The first line has no indentation, unlike the others.
+    let mut sorted = lines
+        .iter()
+        .map(|line| line.trim_start())
+        .collect::<Vec<_>>();
+    let mut run_start = 0;
+    for i in 1..=definitions.len() {
+        let previous = &definitions[i - 1];
+        let previous_end = previous.line + 1 + usize::from(previous.has_title_line);
+        if definitions
+            .get(i)
+            .map_or(false, |next| next.line == previous_end)
+        {
+            continue;
+        }
+        let run = &definitions[run_start..i];
+        let mut units = run
+            .iter()
+            .map(|definition| {
+                let end = definition.line + 1 + usize::from(definition.has_title_line);
+                (&definition.label, &sorted[definition.line..end])
+            })
+            .collect::<Vec<_>>();
+        units.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let run_lines = units
+            .into_iter()
+            .flat_map(|(_, unit)| unit.iter().copied())
+            .collect::<Vec<_>>();
+        sorted.splice(run[0].line..previous_end, run_lines);
+        run_start = i;
+    }
+
+    let indent = lines
+        .get(1)
+        .map_or("", |line| &line[..line.len() - line.trim_start().len()]);
+    let result = sorted.join(&format!("\n{indent}"));
+    if result == orig {
+        Cow::Borrowed(orig)
+    } else {
+        Cow::Owned(result)
+    }
+}
+
+/// Returns the normalized label of a footnote definition like `[^label]: text`.
+fn footnote_definition_label(text: &str) -> Option<String> {
+    let indent = text.len() - text.trim_start_matches(' ').len();
+    let (label, _) = text[indent..].strip_prefix("[^")?.split_once("]:")?;
+    if indent > 3 || label.trim().is_empty() || label.contains(['[', ']']) {
+        return None;
+    }
+    Some(normalize_link_label(label))
+}
+
+/// A footnote definition like `[^label]: text` in a doc comment, along with the lines indented
+/// under it that continue its text.
+struct FootnoteDefinition {
+    /// The index of the line it starts on.
+    line: usize,
+    /// The index of the line after it.
+    end: usize,
+    /// The normalized label.
+    label: String,
+}
+
+/// Returns the footnote definitions of a doc comment, skipping the ones in code blocks.
+fn footnote_definitions(texts: &[&str]) -> Vec<FootnoteDefinition> {
+    let mut definitions = vec![];
+    let mut in_code_block = false;
+    let mut i = 0;
+    while i < texts.len() {
+        let trimmed = texts[i].trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+        }
+        match footnote_definition_label(texts[i]).filter(|_| !in_code_block) {
+            Some(label) => {
+                let continuation = texts[i + 1..]
+                    .iter()
+                    .take_while(|text| text.starts_with("    ") || text.starts_with('\t'))
+                    .count();
+                definitions.push(FootnoteDefinition {
+                    line: i,
+                    end: i + 1 + continuation,
+                    label,
+                });
+                i += 1 + continuation;
+            }
+            None => i += 1,
+        }
+    }
+    definitions
+}
+
+/// Sorts the runs of consecutive footnote definitions of a `///` or `//!` doc comment in the
+/// order of the first reference to each of them, as requested by
+/// `doc_comment_sort_footnote_definitions`. Definitions that nothing refers to go last.
+fn sort_footnote_definitions<'a>(orig: &'a str, config: &Config) -> Cow<'a, str> {
+    if !config.doc_comment_sort_footnote_definitions() {
+        return Cow::Borrowed(orig);
+    }
+    let lines = orig.lines().collect::<Vec<_>>();
+    let Some(texts) = doc_comment_texts(&lines) else {
+        return Cow::Borrowed(orig);
+    };
+    let definitions = footnote_definitions(&texts);
+    if definitions.is_empty() {
+        return Cow::Borrowed(orig);
+    }
+
+    // The references are looked for outside of the definitions, wherever they are.
+    let mut references = String::new();
+    for (i, text) in texts.iter().enumerate() {
+        if !definitions.iter().any(|d| (d.line..d.end).contains(&i)) {
+            references.push_str(text);
+            references.push(' ');
+        }
+    }
+    let references = normalize_link_label(&references);
+    let first_reference = |label: &str| {
+        references
+            .find(&format!("[^{label}]"))
+            .unwrap_or(usize::MAX)
+    };
+
+    // The first line has no indentation, unlike the others.
+    let mut sorted = lines
+        .iter()
+        .map(|line| line.trim_start())
+        .collect::<Vec<_>>();
+    let mut run_start = 0;
+    for i in 1..=definitions.len() {
+        if definitions
+            .get(i)
+            .map_or(false, |next| next.line == definitions[i - 1].end)
+        {
+            continue;
+        }
+        let run = &definitions[run_start..i];
+        let mut units = run
+            .iter()
+            .map(|definition| {
+                let unit = &sorted[definition.line..definition.end];
+                (first_reference(&definition.label), unit)
+            })
+            .collect::<Vec<_>>();
+        units.sort_by_key(|&(first_reference, _)| first_reference);
+        let run_lines = units
+            .into_iter()
+            .flat_map(|(_, unit)| unit.iter().copied())
+            .collect::<Vec<_>>();
+        sorted.splice(run[0].line..run[run.len() - 1].end, run_lines);
+        run_start = i;
+    }
+
+    let indent = lines
+        .get(1)
+        .map_or("", |line| &line[..line.len() - line.trim_start().len()]);
+    let result = sorted.join(&format!("\n{indent}"));
+    if result == orig {
+        Cow::Borrowed(orig)
+    } else {
+        Cow::Owned(result)
+    }
+}
+
+/// Removes the blank lines at the start of a `///` or `//!` doc comment and sets the number of
+/// blank lines it ends with, as requested by `doc_comment_trim_leading_blank_lines` and
+/// `doc_comment_trailing_blank_lines`. Also returns the number of lines removed from the start.
+/// Doc comments that are blank altogether are left alone.
+fn adjust_doc_comment_blank_lines<'a>(orig: &'a str, config: &Config) -> (Cow<'a, str>, usize) {
+    let trailing_blank_lines = config.doc_comment_trailing_blank_lines();
+    if !config.doc_comment_trim_leading_blank_lines()
+        && trailing_blank_lines == TrailingBlankLines::Preserve
+    {
+        return (Cow::Borrowed(orig), 0);
+    }
+    let lines = orig.lines().collect::<Vec<_>>();
+    let is_blank = |line: &str| matches!(line.trim(), "///" | "//!");
+    if !lines.iter().all(|line| is_line_doc_comment(line))
+        || lines.iter().all(|line| is_blank(line))
+    {
+        return (Cow::Borrowed(orig), 0);
+    }
+
+    let start = if config.doc_comment_trim_leading_blank_lines() {
+        lines.iter().take_while(|line| is_blank(line)).count()
+    } else {
+        0
+    };
+    let end = lines.len() - lines.iter().rev().take_while(|line| is_blank(line)).count();
+    let mut result = lines[start..end].join("\n");
+    match trailing_blank_lines {
+        TrailingBlankLines::Exactly(count) => {
+            let marker = &lines[end - 1].trim_start()[..3];
+            for _ in 0..count {
+                result.push('\n');
+                result.push_str(marker);
+            }
+        }
+        TrailingBlankLines::Preserve => {
+            for line in &lines[end..] {
+                result.push('\n');
+                result.push_str(line);
+            }
+        }
+    }
+    // The first line has no indentation, unlike the others.
+    if start > 0 {
+        result = result.trim_start().to_owned();
+    }
+    (Cow::Owned(result), start)
 }
 
 pub(crate) fn rewrite_comment(
@@ -245,7 +712,7 @@ pub(crate) fn rewrite_comment(
     shape: Shape,
     config: &Config,
 ) -> Option<String> {
-    identify_comment(orig, block_style, shape, config, false)
+    identify_comment(orig, block_style, shape, config, false, &mut Vec::new())
 }
 
 fn identify_comment(
@@ -254,6 +721,7 @@ fn identify_comment(
     shape: Shape,
     config: &Config,
     is_doc_comment: bool,
+    code_block_errors: &mut Vec<(usize, CodeBlockError)>,
 ) -> Option<String> {
     let style = comment_style(orig, false);
 
@@ -347,36 +815,81 @@ fn identify_comment(
     let rewritten_first_group =
         if !config.normalize_comments() && has_bare_lines && style.is_block_comment() {
             trim_left_preserve_layout(first_group, shape.indent, config)?
+        } else if is_doc_comment
+            && config.doc_comment_preserve_prose()
+            && config.format_code_in_doc_comments()
+        {
+            rewrite_comment_inner(
+                first_group,
+                block_style,
+                style,
+                shape,
+                config,
+                true,
+                code_block_errors,
+            )?
+        } else if is_doc_comment && config.doc_comment_preserve_prose() {
+            light_rewrite_comment(first_group, shape.indent, config, true)
         } else if !config.normalize_comments()
             && !config.wrap_comments()
             && !(
-                // `format_code_in_doc_comments` should only take effect on doc comments,
-                // so we only consider it when this comment block is a doc comment block.
-                is_doc_comment && config.format_code_in_doc_comments()
+                // `format_code_in_doc_comments` and the `doc_comment_*` markers should only take
+                // effect on doc comments, so we only consider them when this comment block is a
+                // doc comment block.
+                is_doc_comment
+                    && (config.format_code_in_doc_comments()
+                        || config.doc_comment_thematic_break() != ThematicBreakStyle::Preserve
+                        || config.doc_comment_hard_break() != HardBreakStyle::Preserve
+                        || config.doc_comment_emphasis_marker() != EmphasisMarker::Preserve
+                        || config.markdown_normalize_dashes())
             )
         {
             light_rewrite_comment(first_group, shape.indent, config, is_doc_comment)
         } else {
-            rewrite_comment_inner(
+            let mut first_group_errors = Vec::new();
+            let rewrite = rewrite_comment_inner(
                 first_group,
                 block_style,
                 style,
                 shape,
                 config,
                 is_doc_comment || style.is_doc_comment(),
-            )?
+                &mut first_group_errors,
+            )?;
+            if !block_style && prefers_block_comment(first_group, style, &rewrite, config) {
+                rewrite_comment_inner(
+                    first_group,
+                    true,
+                    style,
+                    shape,
+                    config,
+                    false,
+                    code_block_errors,
+                )?
+            } else {
+                code_block_errors.append(&mut first_group_errors);
+                rewrite
+            }
         };
     if rest.is_empty() {
         Some(rewritten_first_group)
     } else {
-        identify_comment(
+        let rest_start = count_newlines(&orig[..orig.len() - rest.trim_start().len()]);
+        let mut rest_errors = Vec::new();
+        let rewritten_rest = identify_comment(
             rest.trim_start(),
             block_style,
             shape,
             config,
             is_doc_comment,
-        )
-        .map(|rest_str| {
+            &mut rest_errors,
+        );
+        code_block_errors.extend(
+            rest_errors
+                .into_iter()
+                .map(|(line, error)| (rest_start + line, error)),
+        );
+        rewritten_rest.map(|rest_str| {
             format!(
                 "{}\n{}{}{}",
                 rewritten_first_group,
@@ -393,6 +906,30 @@ fn identify_comment(
     }
 }
 
+/// Returns `true` if a group of `//` comments that was rewritten as `rewrite` should be
+/// written as a single `/* */` comment instead, as requested by
+/// `prefer_block_comments_for_long_code`.
+fn prefers_block_comment(
+    orig: &str,
+    style: CommentStyle<'_>,
+    rewrite: &str,
+    config: &Config,
+) -> bool {
+    if !config.prefer_block_comments_for_long_code()
+        || !config.wrap_comments()
+        || style != CommentStyle::DoubleSlash
+    {
+        return false;
+    }
+    // The text of the comment must not end the block comment early.
+    if orig.contains("*/") || orig.contains("/*") {
+        return false;
+    }
+    // Only comments that were wrapped onto more lines are converted.
+    let lines = count_newlines(rewrite) + 1;
+    lines > count_newlines(orig.trim_end()) + 1 && lines > config.block_comment_min_lines()
+}
+
 /// Enum indicating if the code block contains rust based on attributes
 enum CodeBlockAttribute {
     Rust,
@@ -400,19 +937,53 @@ enum CodeBlockAttribute {
 }
 
 impl CodeBlockAttribute {
-    /// Parse comma separated attributes list. Return rust only if all
+    /// Parse comma or space separated attributes list. Return rust only if all
     /// attributes are valid rust attributes
     /// See <https://doc.rust-lang.org/rustdoc/print.html#attributes>
-    fn new(attributes: &str) -> CodeBlockAttribute {
-        for attribute in attributes.split(',') {
-            match attribute.trim() {
-                "" | "rust" | "should_panic" | "no_run" | "edition2015" | "edition2018"
-                | "edition2021" => (),
-                "ignore" | "compile_fail" | "text" => return CodeBlockAttribute::NotRust,
-                _ => return CodeBlockAttribute::NotRust,
-            }
+    ///
+    /// Any other attribute, like the name of another language (`text`, `sh`, `console`, ...),
+    /// `ignore` or `compile_fail`, means the block must be left as it is, even if it looks like
+    /// Rust code. The common shorthand `rs`, in any case, is taken as `rust` when it is the first
+    /// attribute.
+    ///
+    /// The language of the block must also be one of `doc_comment_code_block_languages` and none
+    /// of `doc_comment_code_block_skip_languages`. A block whose first attribute is not the name
+    /// of a language, such as `no_run`, is in `rust`.
+    fn new(attributes: &str, config: &Config) -> CodeBlockAttribute {
+        let is_rust_attribute = |attribute: &str| {
+            matches!(
+                attribute,
+                "" | "rust"
+                    | "should_panic"
+                    | "no_run"
+                    | "edition2015"
+                    | "edition2018"
+                    | "edition2021"
+            )
+        };
+        // Attributes are separated by commas, spaces or both.
+        let mut attributes = attributes
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|attribute| !attribute.is_empty())
+            .peekable();
+        let first = attributes.peek().copied().unwrap_or_default();
+        let language = if first.eq_ignore_ascii_case("rs") || is_rust_attribute(first) {
+            attributes.next_if(|attribute| attribute.eq_ignore_ascii_case("rs"));
+            "rust"
+        } else {
+            attributes.next();
+            first
+        };
+        if config.doc_comment_code_block_languages().contains(language)
+            && !config
+                .doc_comment_code_block_skip_languages()
+                .contains(language)
+            && attributes.all(is_rust_attribute)
+        {
+            CodeBlockAttribute::Rust
+        } else {
+            CodeBlockAttribute::NotRust
         }
-        CodeBlockAttribute::Rust
     }
 }
 
@@ -435,6 +1006,9 @@ struct ItemizedBlock {
     opener: String,
     /// sequence of characters (typically whitespaces) to prefix new lines that are part of the item
     line_start: String,
+    /// Whether the following lines of a blockquote that carry the same `> ` markers are part of
+    /// the block, see `comment_reflow_blockquotes`.
+    joins_quoted_lines: bool,
 }
 
 impl ItemizedBlock {
@@ -446,7 +1020,8 @@ impl ItemizedBlock {
     /// This function recognizes item markers that correspond to CommonMark's
     /// ["bullet list marker"](https://spec.commonmark.org/0.30/#bullet-list-marker),
     /// ["block quote marker"](https://spec.commonmark.org/0.30/#block-quote-marker), and/or
-    /// ["ordered list marker"](https://spec.commonmark.org/0.30/#ordered-list-marker).
+    /// ["ordered list marker"](https://spec.commonmark.org/0.30/#ordered-list-marker). The `:`
+    /// that starts the definition of a term in a definition list is a marker too.
     ///
     /// Compared to CommonMark specification, the number of digits that are allowed in an ["ordered
     /// list marker"](https://spec.commonmark.org/0.30/#ordered-list-marker) is more limited (to at
@@ -460,8 +1035,8 @@ impl ItemizedBlock {
     /// ```
     fn get_marker_length(trimmed: &str) -> Option<usize> {
         // https://spec.commonmark.org/0.30/#bullet-list-marker or
-        // https://spec.commonmark.org/0.30/#block-quote-marker
-        let itemized_start = ["* ", "- ", "> ", "+ "];
+        // https://spec.commonmark.org/0.30/#block-quote-marker, or the marker of a definition
+        let itemized_start = ["* ", "- ", "> ", "+ ", ": "];
         if itemized_start.iter().any(|s| trimmed.starts_with(s)) {
             return Some(2); // All items in `itemized_start` have length 2.
         }
@@ -503,6 +1078,7 @@ impl ItemizedBlock {
             indent,
             opener: line[..indent].to_string(),
             line_start,
+            joins_quoted_lines: false,
         })
     }
 
@@ -514,7 +1090,7 @@ impl ItemizedBlock {
             line_start: "",
             line_end: "",
             shape: Shape::legacy(fmt.shape.width.saturating_sub(self.indent), Indent::empty()),
-            trim_end: true,
+            trim_end: TrimEnd::All,
             config: fmt.config,
         }
     }
@@ -522,6 +1098,22 @@ impl ItemizedBlock {
     /// Returns `true` if the line is part of the current itemized block.
     /// If it is, then it is added to the internal lines list.
     fn add_line(&mut self, line: &str) -> bool {
+        if self.joins_quoted_lines && self.opener.trim_start().starts_with('>') {
+            // A line continues the quoted paragraph if it is quoted exactly as deeply and does
+            // not start a new block of its own.
+            let Some(rest) = line.strip_prefix(self.line_start.as_str()) else {
+                return false;
+            };
+            if rest.trim().is_empty()
+                || rest.starts_with(|c: char| c == '>' || c.is_whitespace())
+                || ItemizedBlock::get_marker_length(rest).is_some()
+                || code_fence(rest).is_some()
+            {
+                return false;
+            }
+            self.lines.push(rest.to_string());
+            return true;
+        }
         if ItemizedBlock::get_marker_length(line.trim_start()).is_none()
             && self.indent <= line.chars().take_while(|c| c.is_whitespace()).count()
         {
@@ -545,6 +1137,260 @@ impl ItemizedBlock {
     }
 }
 
+/// Splits the leading blockquote markers off `line`, returning how deeply the rest of the
+/// line is quoted.
+fn split_blockquote_markers(mut line: &str) -> (usize, &str) {
+    let mut depth = 0;
+    while let Some(rest) = line.strip_prefix('>') {
+        depth += 1;
+        line = rest.strip_prefix(' ').unwrap_or(rest);
+    }
+    (depth, line)
+}
+
+/// Splits a line that ends with a hard line break into its text and the marker of the break,
+/// i.e. two or more trailing spaces or a backslash.
+fn split_hard_break(line: &str) -> Option<(&str, &str)> {
+    let text = if line.ends_with("  ") {
+        line.trim_end()
+    } else {
+        let without_backslashes = line.trim_end_matches('\\');
+        // An escaped backslash is not a line break.
+        if (line.len() - without_backslashes.len()) % 2 == 0 {
+            return None;
+        }
+        &line[..line.len() - 1]
+    };
+    if text.trim().is_empty() {
+        return None;
+    }
+    Some((text, &line[text.len()..]))
+}
+
+/// Returns the prose `text` of a doc comment, with its dashes and emphasis delimiters normalized
+/// if asked to.
+fn prose(text: &str, normalize_dashes: bool, emphasis_marker: EmphasisMarker) -> Cow<'_, str> {
+    let text = if normalize_dashes {
+        self::normalize_dashes(text)
+    } else {
+        Cow::Borrowed(text)
+    };
+    let marker = match emphasis_marker {
+        EmphasisMarker::Preserve => return text,
+        EmphasisMarker::Asterisk => '*',
+        EmphasisMarker::Underscore => '_',
+    };
+    match normalize_emphasis(&text, marker) {
+        Cow::Borrowed(_) => text,
+        Cow::Owned(normalized) => Cow::Owned(normalized),
+    }
+}
+
+/// Replaces the delimiters of the emphasis and strong emphasis spans of `text`, such as `*a*`,
+/// `__b__` or `***c***`, with `marker`. Only the spans that open and close within `text` are
+/// changed. The delimiters in code spans, autolinks, URLs and link destinations are left alone,
+/// and so are the spans inside a word, like `a*b*c`, which can't be written with underscores.
+fn normalize_emphasis(text: &str, marker: char) -> Cow<'_, str> {
+    if !text.contains(['*', '_']) {
+        return Cow::Borrowed(text);
+    }
+    let bytes = text.as_bytes();
+    let mut skipped = vec![false; text.len()];
+    let mut i = 0;
+    while i < text.len() {
+        let end = match bytes[i] {
+            b'`' => {
+                let ticks = text[i..].len() - text[i..].trim_start_matches('`').len();
+                closing_backticks(&text[i + ticks..], ticks).map_or(i + ticks, |j| i + ticks + j)
+            }
+            b'<' => text[i..].find('>').map_or(i + 1, |j| i + j + 1),
+            b']' if text[i + 1..].starts_with('(') => {
+                text[i..].find(')').map_or(text.len(), |j| i + j + 1)
+            }
+            _ if find_url_scheme(&text[i..]) == Some(0) => text[i..]
+                .find(char::is_whitespace)
+                .map_or(text.len(), |j| i + j),
+            _ => i + 1,
+        };
+        if end > i + 1 {
+            skipped[i..end].fill(true);
+        }
+        i = end.max(i + 1);
+    }
+
+    // The delimiter runs, as their range and whether they can open or close a span.
+    let mut runs = vec![];
+    let mut i = 0;
+    while i < text.len() {
+        let c = bytes[i];
+        if !(c == b'*' || c == b'_') || skipped[i] {
+            i += 1;
+            continue;
+        }
+        let end = i + text[i..].len() - text[i..].trim_start_matches(c as char).len();
+        let before = text[..i].chars().next_back();
+        let after = text[end..].chars().next();
+        let is_word = |c: Option<char>| c.map_or(false, char::is_alphanumeric);
+        let is_space = |c: Option<char>| c.map_or(true, char::is_whitespace);
+        let can_open = !is_space(after) && (c != b'_' || !is_word(before));
+        let can_close = !is_space(before) && (c != b'_' || !is_word(after));
+        if end - i <= 3 && before != Some('\\') && (can_open || can_close) {
+            runs.push((i..end, can_open, can_close));
+        }
+        i = end;
+    }
+
+    let mut result = text.as_bytes().to_vec();
+    let mut openers: Vec<usize> = vec![];
+    for (r, &(ref range, can_open, can_close)) in runs.iter().enumerate() {
+        let opener = openers.iter().rposition(|&o| {
+            let open = &runs[o].0;
+            can_close && bytes[open.start] == bytes[range.start] && open.len() == range.len()
+        });
+        match opener {
+            Some(o) => {
+                let open = runs[openers[o]].0.clone();
+                openers.truncate(o);
+                let is_intraword = text[..open.start]
+                    .chars()
+                    .next_back()
+                    .map_or(false, char::is_alphanumeric)
+                    || text[range.end..]
+                        .chars()
+                        .next()
+                        .map_or(false, char::is_alphanumeric);
+                if !(marker == '_' && is_intraword) {
+                    result[open].fill(marker as u8);
+                    result[range.clone()].fill(marker as u8);
+                }
+            }
+            None if can_open => openers.push(r),
+            None => {}
+        }
+    }
+    match String::from_utf8(result) {
+        Ok(result) if result != text => Cow::Owned(result),
+        _ => Cow::Borrowed(text),
+    }
+}
+
+/// Returns the offset in `text` right after the run of exactly `ticks` backticks that closes an
+/// inline code span, if there is one. Longer or shorter runs are part of the code.
+fn closing_backticks(text: &str, ticks: usize) -> Option<usize> {
+    let mut pos = 0;
+    while let Some(start) = text[pos..].find('`').map(|j| pos + j) {
+        let run = text[start..].len() - text[start..].trim_start_matches('`').len();
+        if run == ticks {
+            return Some(start + run);
+        }
+        pos = start + run;
+    }
+    None
+}
+
+/// Replaces the en dashes (`–`) and em dashes (`—`) of `text` with ` -- ` and ` --- `,
+/// the way they are written in plain Markdown. The dashes in code spans are left alone.
+fn normalize_dashes(text: &str) -> Cow<'_, str> {
+    if !text.contains(['–', '—']) {
+        return Cow::Borrowed(text);
+    }
+    let mut result = String::with_capacity(text.len() + 8);
+    // The number of backticks that opened the current code span.
+    let mut code_span = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '`' => {
+                let ticks = text[i..].len() - text[i..].trim_start_matches('`').len();
+                for _ in 1..ticks {
+                    chars.next();
+                }
+                code_span = match code_span {
+                    None => Some(ticks),
+                    Some(open) if open == ticks => None,
+                    open => open,
+                };
+                result.push_str(&text[i..i + ticks]);
+            }
+            // A line holding only dashes would read as a setext heading underline once they
+            // are replaced by hyphens.
+            '–' | '—' if code_span.is_none() && !is_dash_line(text, i) => {
+                result.truncate(result.trim_end_matches(' ').len());
+                if !result.is_empty() {
+                    result.push(' ');
+                }
+                result.push_str(if c == '–' { "--" } else { "---" });
+                while chars.next_if(|&(_, c)| c == ' ').is_some() {}
+                if chars.peek().is_some() {
+                    result.push(' ');
+                }
+            }
+            _ => result.push(c),
+        }
+    }
+    Cow::Owned(result)
+}
+
+/// Returns `true` if the line of `text` holding the byte `pos` has nothing but dashes.
+fn is_dash_line(text: &str, pos: usize) -> bool {
+    let start = text[..pos].rfind('\n').map_or(0, |i| i + 1);
+    let end = text[pos..].find('\n').map_or(text.len(), |i| pos + i);
+    text[start..end]
+        .chars()
+        .all(|c| matches!(c, '–' | '—' | ' ' | '\t'))
+}
+
+/// Returns the marker of the given style that should replace `line`, if `line` is a thematic break.
+fn thematic_break_marker(
+    line: &str,
+    style: ThematicBreakStyle,
+    follows_paragraph: bool,
+) -> Option<&'static str> {
+    let marker = match style {
+        ThematicBreakStyle::Preserve => return None,
+        ThematicBreakStyle::Dash => "---",
+        ThematicBreakStyle::Asterisk => "***",
+        ThematicBreakStyle::Underscore => "___",
+    };
+    // A thematic break may be indented by up to three spaces.
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let trimmed = trimmed.trim_end();
+    let c = trimmed
+        .chars()
+        .next()
+        .filter(|c| matches!(c, '-' | '*' | '_'))?;
+    let is_spaced = trimmed.contains([' ', '\t']);
+    if trimmed.chars().filter(|&x| x == c).count() < 3
+        || trimmed.chars().any(|x| !matches!(x, ' ' | '\t') && x != c)
+    {
+        return None;
+    }
+    // Right below a paragraph, an unspaced line of `-` underlines a heading, and so would `---`.
+    if follows_paragraph && (marker == "---" || c == '-' && !is_spaced) {
+        return None;
+    }
+    Some(marker)
+}
+
+/// Strips exactly `depth` blockquote markers from `line`. Returns `None` if the line is not
+/// quoted that deeply.
+fn strip_blockquote_markers(mut line: &str, depth: usize) -> Option<&str> {
+    for _ in 0..depth {
+        let rest = line.strip_prefix('>')?;
+        line = rest.strip_prefix(' ').unwrap_or(rest);
+    }
+    Some(line)
+}
+
+/// Removes up to `indent` spaces from the start of `line`.
+fn strip_indent(line: &str, indent: usize) -> &str {
+    let spaces = line.len() - line.trim_start_matches(' ').len();
+    &line[spaces.min(indent)..]
+}
+
 /// Determine the line_start when formatting markdown block quotes.
 /// The original line_start likely contains indentation (whitespaces), which we'd like to
 /// replace with '> ' characters.
@@ -569,6 +1415,39 @@ struct CommentRewrite<'a> {
     code_block_buffer: String,
     is_prev_line_multi_line: bool,
     code_block_attr: Option<CodeBlockAttribute>,
+    /// The fence that opened the current fenced code block, e.g. ```` ``` ```` or `~~~~`. It is
+    /// only closed by a fence of the same character that is at least as long.
+    code_block_fence: String,
+    /// The `> ` markers in front of the current fenced code block, if it is inside a
+    /// blockquote.
+    code_block_quote: String,
+    /// The indentation of the current fenced code block, if it is nested in a list item.
+    code_block_indent: usize,
+    /// The content columns of the list items that the current line may belong to, innermost
+    /// last.
+    list_item_columns: Vec<usize>,
+    is_indented_code_block: bool,
+    /// The 0-based line of the comment that the current code block starts at.
+    code_block_start: usize,
+    /// The code blocks that could not be formatted, along with the line they start at.
+    code_block_errors: Vec<(usize, CodeBlockError)>,
+    /// The number of blank lines seen since the end of a fenced code block, while it is not
+    /// yet known whether another code block follows them.
+    blank_lines_after_code_block: Option<usize>,
+    is_prev_line_empty: bool,
+    /// The number of blank lines in a row that end at the current line.
+    blank_lines_in_a_row: usize,
+    /// Marks the lines of the comment that are kept as they are, i.e. the lines of hand-drawn
+    /// ASCII tables and of math blocks.
+    verbatim_lines: Vec<bool>,
+    is_verbatim_line: bool,
+    /// Whether the en and em dashes of the prose are replaced, see `normalize_dashes`.
+    normalize_dashes: bool,
+    /// The delimiter that the emphasis spans of the prose get, see `normalize_emphasis`.
+    emphasis_marker: EmphasisMarker,
+    /// Whether only the fenced code blocks are rewritten, see `doc_comment_preserve_prose`. The
+    /// other lines are verbatim lines then.
+    preserve_prose: bool,
     item_block: Option<ItemizedBlock>,
     comment_line_separator: String,
     indent_str: String,
@@ -610,6 +1489,21 @@ impl<'a> CommentRewrite<'a> {
             code_block_buffer: String::with_capacity(128),
             is_prev_line_multi_line: false,
             code_block_attr: None,
+            code_block_fence: String::new(),
+            code_block_quote: String::new(),
+            code_block_indent: 0,
+            list_item_columns: Vec::new(),
+            is_indented_code_block: false,
+            code_block_start: 0,
+            code_block_errors: Vec::new(),
+            blank_lines_after_code_block: None,
+            is_prev_line_empty: true,
+            blank_lines_in_a_row: 0,
+            verbatim_lines: Vec::new(),
+            is_verbatim_line: false,
+            normalize_dashes: false,
+            emphasis_marker: EmphasisMarker::Preserve,
+            preserve_prose: false,
             item_block: None,
             comment_line_separator: format!("{indent_str}{line_start}"),
             max_width,
@@ -622,7 +1516,7 @@ impl<'a> CommentRewrite<'a> {
                 line_start,
                 line_end: "",
                 shape: Shape::legacy(max_width, shape.indent),
-                trim_end: true,
+                trim_end: TrimEnd::All,
                 config,
             },
 
@@ -658,13 +1552,118 @@ impl<'a> CommentRewrite<'a> {
         self.result.len() != self.opener.len()
     }
 
-    fn finish(mut self) -> String {
+    /// Write out the lines of a fenced code block, prefixed with the `> ` markers of the
+    /// blockquote it belongs to and indented to the content of the list item it is nested in.
+    fn push_fenced_code_block(&mut self, code_block: &str) {
+        if !code_block.is_empty() {
+            let separator = format!(
+                "{}{}{}",
+                self.comment_line_separator,
+                self.code_block_quote,
+                " ".repeat(self.code_block_indent)
+            );
+            // `join_block` drops the blank lines at the end of the block, so add them back.
+            let code = code_block.trim_end_matches('\n');
+            self.result.push_str(&separator);
+            self.result.push_str(&Self::join_block(code, &separator));
+            for _ in code.len()..code_block.len() {
+                self.result.push_str(separator.trim_end());
+            }
+        }
+        self.code_block_buffer.clear();
+    }
+
+    /// Returns the code of the current fenced code block as it was written, for when it isn't
+    /// formatted.
+    fn unformatted_code_block(&self) -> String {
+        match self.code_block_attr {
+            Some(CodeBlockAttribute::Rust) => trim_custom_comment_prefix(&self.code_block_buffer),
+            _ => self
+                .code_block_buffer
+                .strip_suffix('\n')
+                .unwrap_or(&self.code_block_buffer)
+                .to_owned(),
+        }
+    }
+
+    /// Format a rust code block found in a doc comment, returning the original code if it
+    /// cannot be formatted. Why it could not be formatted is kept in `code_block_errors`.
+    fn format_code_block(&mut self, code_block: &str) -> String {
+        let mut config = self.fmt.config.clone();
+        config.set().wrap_comments(false);
+        let comment_max_width = config
+            .doc_comment_code_block_width()
+            .min(config.max_width());
+        config.set().max_width(comment_max_width);
+        let code = code_block
+            .lines()
+            .map(unescape_sharp)
+            .collect::<Vec<_>>()
+            .join("\n");
+        match crate::try_format_code_block(&code, &config, false) {
+            Ok(s) => s
+                .snippet
+                .lines()
+                .map(|line| reindent_hidden_line(&escape_sharp(line)))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Err(error) => {
+                self.code_block_errors.push((self.code_block_start, error));
+                trim_custom_comment_prefix(code_block)
+            }
+        }
+    }
+
+    /// Write out the buffered indented code block, re-indenting each line with
+    /// `indented_code_block_indent`. Blank lines at the end of the buffer separate the block from
+    /// what follows, so they are kept out of the code that gets formatted.
+    fn flush_indented_code_block(&mut self) {
+        let buffer = std::mem::take(&mut self.code_block_buffer);
+        let code_block = buffer.trim_end_matches('\n');
+        let num_trailing_blank_lines = count_newlines(&buffer[code_block.len()..]) - 1;
+        let code_block = self.format_code_block(code_block);
+        // The indented lines of a list item are not turned into a fenced code block, as they
+        // may be a paragraph of the item.
+        let is_fenced = self.fmt.config.doc_comment_fence_indented_code_blocks()
+            && self.list_item_columns.is_empty();
+        let (indent, fence) = if is_fenced {
+            (String::new(), Some("```"))
+        } else {
+            (indented_code_block_indent(self.fmt.config), None)
+        };
+
+        for line in fence
+            .into_iter()
+            .chain(code_block.lines())
+            .chain(fence)
+            .map(Some)
+            .chain(std::iter::repeat(None).take(num_trailing_blank_lines))
+        {
+            match line {
+                Some(line) if !line.is_empty() => {
+                    if self.buffer_contains_comment() {
+                        self.result.push_str(&self.comment_line_separator);
+                    }
+                    self.result.push_str(&indent);
+                    self.result.push_str(line);
+                }
+                _ => self.result.push_str(self.comment_line_separator.trim_end()),
+            }
+        }
+        self.is_indented_code_block = false;
+    }
+
+    fn finish(mut self) -> (String, Vec<(usize, CodeBlockError)>) {
+        if self.is_indented_code_block {
+            self.flush_indented_code_block();
+        }
+
         if !self.code_block_buffer.is_empty() {
             // There is a code block that is not properly enclosed by backticks.
             // We will leave them untouched.
             self.result.push_str(&self.comment_line_separator);
             self.result.push_str(&Self::join_block(
-                &trim_custom_comment_prefix(&self.code_block_buffer),
+                &self.unformatted_code_block(),
                 &self.comment_line_separator,
             ));
         }
@@ -681,7 +1680,11 @@ impl<'a> CommentRewrite<'a> {
 
             self.result.push_str(&ib.opener);
             match rewrite_string(
-                &ib.trimmed_block_as_string(),
+                &prose(
+                    &ib.trimmed_block_as_string(),
+                    self.normalize_dashes,
+                    self.emphasis_marker,
+                ),
                 &item_fmt,
                 self.max_width.saturating_sub(ib.indent),
             ) {
@@ -702,7 +1705,7 @@ impl<'a> CommentRewrite<'a> {
             self.result.pop();
         }
 
-        self.result
+        (self.result, self.code_block_errors)
     }
 
     fn handle_line(
@@ -712,9 +1715,32 @@ impl<'a> CommentRewrite<'a> {
         line: &'a str,
         has_leading_whitespace: bool,
         is_doc_comment: bool,
+        is_next_line_blank: bool,
     ) -> bool {
         let num_newlines = count_newlines(orig);
         let is_last = i == num_newlines;
+        let is_prev_line_empty = std::mem::replace(&mut self.is_prev_line_empty, line.is_empty());
+        self.blank_lines_in_a_row = if line.is_empty() {
+            self.blank_lines_in_a_row + 1
+        } else {
+            0
+        };
+        if let Some(blank_lines) = self.blank_lines_after_code_block.take() {
+            if line.is_empty() && !is_last {
+                self.blank_lines_after_code_block = Some(blank_lines + 1);
+                return false;
+            }
+            // Blank lines between sibling code blocks are collapsed into exactly one, which keeps
+            // the blocks apart without leaving a gap.
+            let blank_lines = if code_fence(line).is_some() {
+                blank_lines.min(1)
+            } else {
+                blank_lines
+            };
+            for _ in 0..blank_lines {
+                self.result.push_str(self.comment_line_separator.trim_end());
+            }
+        }
         let needs_new_comment_line = if self.style.is_block_comment() {
             num_newlines > 0 || self.buffer_contains_comment()
         } else {
@@ -736,7 +1762,11 @@ impl<'a> CommentRewrite<'a> {
 
             self.result.push_str(&ib.opener);
             match rewrite_string(
-                &ib.trimmed_block_as_string(),
+                &prose(
+                    &ib.trimmed_block_as_string(),
+                    self.normalize_dashes,
+                    self.emphasis_marker,
+                ),
                 &item_fmt,
                 self.max_width.saturating_sub(ib.indent),
             ) {
@@ -750,56 +1780,269 @@ impl<'a> CommentRewrite<'a> {
                 )),
             };
         } else if self.code_block_attr.is_some() {
-            if line.starts_with("```") {
-                let code_block = match self.code_block_attr.as_ref().unwrap() {
-                    CodeBlockAttribute::Rust
-                        if self.fmt.config.format_code_in_doc_comments()
-                            && !self.code_block_buffer.trim().is_empty() =>
-                    {
-                        let mut config = self.fmt.config.clone();
-                        config.set().wrap_comments(false);
-                        let comment_max_width = config
-                            .doc_comment_code_block_width()
-                            .min(config.max_width());
-                        config.set().max_width(comment_max_width);
-                        if let Some(s) =
-                            crate::format_code_block(&self.code_block_buffer, &config, false)
+            let quote_depth = self.code_block_quote.len() / 2;
+            let code_block_indent = self.code_block_indent;
+            match strip_blockquote_markers(line, quote_depth)
+                .map(|code| strip_indent(code, code_block_indent))
+            {
+                Some(code)
+                    if code_fence(code)
+                        .map_or(false, |fence| fence.starts_with(&self.code_block_fence)) =>
+                {
+                    let code_block = match self.code_block_attr.as_ref().unwrap() {
+                        CodeBlockAttribute::Rust
+                            if self.fmt.config.format_code_in_doc_comments()
+                                && !self.code_block_buffer.trim().is_empty() =>
                         {
-                            trim_custom_comment_prefix(&s.snippet)
-                        } else {
-                            trim_custom_comment_prefix(&self.code_block_buffer)
+                            let code_block = self.code_block_buffer.clone();
+                            self.format_code_block(&code_block)
                         }
-                    }
-                    _ => trim_custom_comment_prefix(&self.code_block_buffer),
-                };
-                if !code_block.is_empty() {
+                        _ => self.unformatted_code_block(),
+                    };
+                    self.push_fenced_code_block(&code_block);
                     self.result.push_str(&self.comment_line_separator);
-                    self.result
-                        .push_str(&Self::join_block(&code_block, &self.comment_line_separator));
+                    self.result.push_str(line);
+                    self.code_block_attr = None;
+                    if is_doc_comment
+                        && self.fmt.config.format_code_in_doc_comments()
+                        && self.code_block_quote.is_empty()
+                        && !self.preserve_prose
+                    {
+                        self.blank_lines_after_code_block = Some(0);
+                    }
+                    return false;
                 }
-                self.code_block_buffer.clear();
-                self.result.push_str(&self.comment_line_separator);
-                self.result.push_str(line);
-                self.code_block_attr = None;
-            } else {
+                Some(code) => {
+                    // Only Rust code has hidden lines. Other code, such as a table in a `text`
+                    // block, is kept byte for byte, trailing whitespace included.
+                    if matches!(self.code_block_attr, Some(CodeBlockAttribute::Rust)) {
+                        self.code_block_buffer
+                            .push_str(&hide_sharp_behind_comment(code));
+                    } else {
+                        self.code_block_buffer.push_str(code);
+                    }
+                    self.code_block_buffer.push('\n');
+                    return false;
+                }
+                None => {
+                    // A line without enough `>` markers ends the blockquote, and with it the
+                    // code block, which is written out as is.
+                    let code_block = self.unformatted_code_block();
+                    self.push_fenced_code_block(&code_block);
+                    self.code_block_attr = None;
+                }
+            }
+        } else if self.is_indented_code_block {
+            // An indented code block ends at the first line that is indented by less than four
+            // spaces. The last line of the comment is never kept when it is blank.
+            if let Some(code) = indented_code_line(line).filter(|_| !(is_last && line.is_empty())) {
                 self.code_block_buffer
-                    .push_str(&hide_sharp_behind_comment(line));
+                    .push_str(&hide_sharp_behind_comment(code));
                 self.code_block_buffer.push('\n');
+                return false;
             }
-            return false;
+            self.flush_indented_code_block();
         }
 
         self.code_block_attr = None;
         self.item_block = None;
-        if let Some(stripped) = line.strip_prefix("```") {
-            self.code_block_attr = Some(CodeBlockAttribute::new(stripped))
+        if line.is_empty()
+            && !is_last
+            && !self.preserve_prose
+            && (is_prev_line_empty && self.fmt.config.comment_collapse_blank_lines()
+                || is_doc_comment
+                    && self.blank_lines_in_a_row
+                        > self.fmt.config.max_empty_lines_in_doc_comments())
+        {
+            return false;
+        }
+        let list_fence_indent = if is_doc_comment && !self.preserve_prose {
+            self.track_list_items(line)
+        } else {
+            None
+        };
+        if is_doc_comment
+            && !self.preserve_prose
+            && list_fence_indent.is_none()
+            && is_prev_line_empty
+            && self.fmt.config.format_code_in_doc_comments()
+            && !line.trim().is_empty()
+        {
+            // Like a fenced code block, an indented code block can't interrupt a paragraph, so
+            // it must follow a blank line.
+            if let Some(code) = indented_code_line(line) {
+                self.is_indented_code_block = true;
+                self.code_block_start = i;
+                self.code_block_buffer
+                    .push_str(&hide_sharp_behind_comment(code));
+                self.code_block_buffer.push('\n');
+                return false;
+            }
+        }
+
+        let mut line = match thematic_break_marker(
+            line,
+            self.fmt.config.doc_comment_thematic_break(),
+            !is_prev_line_empty,
+        ) {
+            Some(marker) if is_doc_comment && !self.preserve_prose => marker,
+            _ => line,
+        };
+
+        self.is_verbatim_line = self.verbatim_lines.get(i).copied().unwrap_or(false);
+        if self.is_verbatim_line {
+            // The lines of a table or of a math block are kept as they are, apart from the text
+            // around them.
+            self.is_prev_line_multi_line = false;
+            return self.handle_text_line(
+                orig,
+                is_last,
+                line,
+                has_leading_whitespace,
+                is_doc_comment,
+                is_next_line_blank,
+            );
+        }
+
+        let (quote_depth, unquoted) = split_blockquote_markers(line);
+        if let Some(indent) = list_fence_indent {
+            let fence = code_fence(&line[indent..]).unwrap_or_default();
+            self.code_block_attr = Some(CodeBlockAttribute::new(
+                &line[indent + fence.len()..],
+                self.fmt.config,
+            ));
+            self.code_block_fence = fence.to_owned();
+            self.code_block_quote.clear();
+            self.code_block_indent = indent;
+            self.code_block_start = i + 1;
+        } else if let Some(fence) = code_fence(unquoted) {
+            self.code_block_attr = Some(CodeBlockAttribute::new(
+                &unquoted[fence.len()..],
+                self.fmt.config,
+            ));
+            self.code_block_fence = fence.to_owned();
+            self.code_block_quote = "> ".repeat(quote_depth);
+            self.code_block_indent = 0;
+            self.code_block_start = i + 1;
+        }
+        if self.code_block_attr.is_some() {
+            // Trailing spaces after the language tag of a fence are not a hard line break.
+            line = line.trim_end();
         } else if self.fmt.config.wrap_comments() {
-            if let Some(ib) = ItemizedBlock::new(line) {
+            if let Some(mut ib) = ItemizedBlock::new(line) {
+                ib.joins_quoted_lines = self.fmt.config.comment_reflow_blockquotes();
                 self.item_block = Some(ib);
                 return false;
             }
         }
 
+        if quote_depth == 0 && self.code_block_attr.is_none() && self.is_url_own_line(line) {
+            // The text before each URL, the URL and the text after it go on lines of their own.
+            let mut rest = line;
+            while let Some((before, url, after)) = split_around_url(rest) {
+                for text in [before, url].into_iter().filter(|text| !text.is_empty()) {
+                    self.handle_text_line(
+                        orig,
+                        is_last,
+                        text,
+                        has_leading_whitespace,
+                        is_doc_comment,
+                        false,
+                    );
+                    self.is_prev_line_multi_line = false;
+                }
+                rest = after;
+            }
+            if rest.is_empty() {
+                return false;
+            }
+            line = rest;
+        }
+
+        self.handle_text_line(
+            orig,
+            is_last,
+            line,
+            has_leading_whitespace,
+            is_doc_comment,
+            is_next_line_blank,
+        )
+    }
+
+    /// Keeps track of the list items that `line` is nested in. Returns the indentation of `line`
+    /// if it opens a fenced code block inside a list item.
+    fn track_list_items(&mut self, line: &str) -> Option<usize> {
+        if line.trim().is_empty() {
+            return None;
+        }
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        // A line that is indented less than the content of a list item ends that item.
+        self.list_item_columns.retain(|&column| column <= indent);
+        let rest = &line[indent..];
+        if code_fence(rest).is_some() {
+            // The content of a list item may be indented by up to three more spaces.
+            return self
+                .list_item_columns
+                .last()
+                .filter(|&&column| (column..column + 4).contains(&indent))
+                .map(|_| indent);
+        }
+        match ItemizedBlock::get_marker_length(rest) {
+            Some(marker_length) if !rest.starts_with('>') => {
+                self.list_item_columns.push(indent + marker_length);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Returns `true` if the URLs of `line` should be put on lines of their own, as requested by
+    /// `comment_urls_on_own_line`.
+    fn is_url_own_line(&self, line: &str) -> bool {
+        self.fmt.config.wrap_comments()
+            && self.fmt.config.comment_urls_on_own_line()
+            && has_url(line)
+            && !line.starts_with('#')
+            && !is_table_item(line)
+            && !is_reference_link_definition(line)
+    }
+
+    /// Returns the spaces put between a wrapped line and the next line of text when they are
+    /// joined. A sentence that ends the wrapped line is followed by two spaces with
+    /// `comment_preserve_sentence_spacing`, like the sentences within a line.
+    fn line_joiner(&self) -> &'static str {
+        if self.fmt.config.comment_preserve_sentence_spacing()
+            && self.result.ends_with(['.', '?', '!'])
+        {
+            "  "
+        } else {
+            " "
+        }
+    }
+
+    /// Writes out a line of text, wrapping it if needed.
+    fn handle_text_line(
+        &mut self,
+        orig: &str,
+        is_last: bool,
+        line: &str,
+        has_leading_whitespace: bool,
+        is_doc_comment: bool,
+        is_next_line_blank: bool,
+    ) -> bool {
+        // A hard line break only counts inside a paragraph, i.e. when the next line has text too.
+        let hard_break = split_hard_break(line)
+            .filter(|_| is_doc_comment && !is_next_line_blank && !self.is_verbatim_line);
+        let line = hard_break.map_or(line, |(text, _)| text);
+        let line = if self.is_verbatim_line {
+            Cow::Borrowed(line)
+        } else {
+            prose(line, self.normalize_dashes, self.emphasis_marker)
+        };
+        // An HTML comment that starts a line stays on a line of its own.
+        let joins_prev_line =
+            self.is_prev_line_multi_line && !line.is_empty() && !line.starts_with("<!--");
+
         if self.result == self.opener {
             let force_leading_whitespace = &self.opener == "/* " && count_newlines(orig) == 0;
             if !has_leading_whitespace && !force_leading_whitespace && self.result.ends_with(' ') {
@@ -808,10 +2051,17 @@ impl<'a> CommentRewrite<'a> {
             if line.is_empty() {
                 return false;
             }
-        } else if self.is_prev_line_multi_line && !line.is_empty() {
-            self.result.push(' ')
-        } else if is_last && line.is_empty() {
-            // trailing blank lines are unwanted
+        } else if joins_prev_line {
+            self.result.push_str(self.line_joiner())
+        } else if is_last
+            && line.is_empty()
+            && !(is_doc_comment
+                && self.style.is_line_comment()
+                && self.fmt.config.doc_comment_trailing_blank_lines()
+                    != TrailingBlankLines::Preserve)
+        {
+            // trailing blank lines are unwanted, unless `doc_comment_trailing_blank_lines`
+            // asks for them
             if !self.closer.is_empty() {
                 self.result.push_str(&self.indent_str);
             }
@@ -834,42 +2084,45 @@ impl<'a> CommentRewrite<'a> {
         // should be changed accordingly.
         let should_wrap_comment = self.fmt.config.wrap_comments()
             && !is_markdown_header_doc_comment
-            && unicode_str_width(line) > self.fmt.shape.width
-            && !has_url(line)
-            && !is_table_item(line);
+            && unicode_str_width(&line) > self.fmt.shape.width
+            && !has_url(&line)
+            && !is_table_item(&line)
+            && !self.is_verbatim_line
+            && !self.preserve_prose;
 
         if should_wrap_comment {
-            match rewrite_string(line, &self.fmt, self.max_width) {
+            match rewrite_string(&line, &self.fmt, self.max_width) {
                 Some(ref s) => {
                     self.is_prev_line_multi_line = s.contains('\n');
                     self.result.push_str(s);
                 }
-                None if self.is_prev_line_multi_line => {
+                None if joins_prev_line => {
                     // We failed to put the current `line` next to the previous `line`.
                     // Remove the trailing space, then start rewrite on the next line.
-                    self.result.pop();
+                    self.result
+                        .truncate(self.result.trim_end_matches(' ').len());
                     self.result.push_str(&self.comment_line_separator);
                     self.fmt.shape = Shape::legacy(self.max_width, self.fmt_indent);
-                    match rewrite_string(line, &self.fmt, self.max_width) {
+                    match rewrite_string(&line, &self.fmt, self.max_width) {
                         Some(ref s) => {
                             self.is_prev_line_multi_line = s.contains('\n');
                             self.result.push_str(s);
                         }
                         None => {
                             self.is_prev_line_multi_line = false;
-                            self.result.push_str(line);
+                            self.result.push_str(&line);
                         }
                     }
                 }
                 None => {
                     self.is_prev_line_multi_line = false;
-                    self.result.push_str(line);
+                    self.result.push_str(&line);
                 }
             }
 
             self.fmt.shape = if self.is_prev_line_multi_line {
-                // 1 = " "
-                let offset = 1 + last_line_width(&self.result) - self.line_start.len();
+                let offset = self.line_joiner().len() + last_line_width(&self.result)
+                    - self.line_start.len();
                 Shape {
                     width: self.max_width.saturating_sub(offset),
                     indent: self.fmt_indent,
@@ -883,7 +2136,19 @@ impl<'a> CommentRewrite<'a> {
                 // Remove space if this is an empty comment or a doc comment.
                 self.result.pop();
             }
-            self.result.push_str(line);
+            self.result.push_str(&line);
+            self.fmt.shape = Shape::legacy(self.max_width, self.fmt_indent);
+            self.is_prev_line_multi_line = false;
+        }
+
+        if let Some((_, marker)) = hard_break {
+            // The next line must stay on a line of its own.
+            self.result
+                .push_str(match self.fmt.config.doc_comment_hard_break() {
+                    HardBreakStyle::Preserve => marker,
+                    HardBreakStyle::Spaces => "  ",
+                    HardBreakStyle::Backslash => "\\",
+                });
             self.fmt.shape = Shape::legacy(self.max_width, self.fmt_indent);
             self.is_prev_line_multi_line = false;
         }
@@ -899,6 +2164,7 @@ fn rewrite_comment_inner(
     shape: Shape,
     config: &Config,
     is_doc_comment: bool,
+    code_block_errors: &mut Vec<(usize, CodeBlockError)>,
 ) -> Option<String> {
     let mut rewriter = CommentRewrite::new(orig, block_style, shape, config);
 
@@ -925,23 +2191,128 @@ fn rewrite_comment_inner(
             } else {
                 (line, has_leading_whitespace || config.normalize_comments())
             }
-        });
+        })
+        .collect::<Vec<_>>();
+    if !is_doc_comment && lines.len() == 1 && lines[0].0.trim().is_empty() {
+        // A one-line comment without any text is written as its markers alone, e.g. `//` or
+        // `/* */`. Longer comments go on, so that their blank lines are kept or collapsed as
+        // configured, and so do doc comments, to get the blank lines of
+        // `doc_comment_trailing_blank_lines`.
+        return Some(format!("{}{}", rewriter.opener.trim_end(), rewriter.closer));
+    }
+    let texts = lines.iter().map(|(line, _)| *line).collect::<Vec<_>>();
+    if config.wrap_comments() {
+        rewriter.verbatim_lines = ascii_table_lines(&texts);
+    }
+    if is_doc_comment && config.doc_comment_preserve_prose() {
+        // Only the fenced code blocks are rewritten, the lines around them are kept as they are.
+        rewriter.preserve_prose = true;
+        rewriter.verbatim_lines = texts
+            .iter()
+            .map(|line| code_fence(split_blockquote_markers(line).1).is_none())
+            .collect();
+    } else {
+        rewriter.normalize_dashes = is_doc_comment && config.markdown_normalize_dashes();
+        if is_doc_comment {
+            rewriter.emphasis_marker = config.doc_comment_emphasis_marker();
+        }
+    }
+    if is_doc_comment && !rewriter.preserve_prose {
+        let math_lines = math_block_lines(&texts);
+        let html_lines = html_definition_list_lines(&texts);
+        rewriter.verbatim_lines.resize(math_lines.len(), false);
+        for ((verbatim, is_math), is_html) in rewriter
+            .verbatim_lines
+            .iter_mut()
+            .zip(math_lines)
+            .zip(html_lines)
+        {
+            *verbatim |= is_math || is_html;
+        }
+    }
 
-    for (i, (line, has_leading_whitespace)) in lines.enumerate() {
-        if rewriter.handle_line(orig, i, line, has_leading_whitespace, is_doc_comment) {
+    let mut lines = lines.into_iter().enumerate().peekable();
+    while let Some((i, (line, has_leading_whitespace))) = lines.next() {
+        let is_next_line_blank = lines
+            .peek()
+            .map_or(true, |(_, (next, _))| next.trim().is_empty());
+        if rewriter.handle_line(
+            orig,
+            i,
+            line,
+            has_leading_whitespace,
+            is_doc_comment,
+            is_next_line_blank,
+        ) {
             break;
         }
     }
 
-    Some(rewriter.finish())
+    let (result, mut errors) = rewriter.finish();
+    code_block_errors.append(&mut errors);
+    Some(result)
 }
 
 const RUSTFMT_CUSTOM_COMMENT_PREFIX: &str = "//#### ";
 
+/// Returns the fence that `line` opens or closes a fenced code block with, i.e. its leading run
+/// of at least three backticks or tildes.
+fn code_fence(line: &str) -> Option<&str> {
+    let fence_char = line.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let len = line.len() - line.trim_start_matches(fence_char).len();
+    (len >= 3).then(|| &line[..len])
+}
+
+/// Returns the code of a line that belongs to a markdown indented code block, i.e. the line
+/// without its four spaces or tab of indentation. Blank lines are part of the block as well.
+fn indented_code_line(line: &str) -> Option<&str> {
+    if line.is_empty() {
+        Some(line)
+    } else {
+        line.strip_prefix("    ")
+            .or_else(|| line.strip_prefix('\t'))
+    }
+}
+
+/// Returns the indentation of the lines of an indented code block: a tab with `hard_tabs`,
+/// `tab_spaces` spaces otherwise, but never less than the four columns that make a line part of
+/// the block.
+fn indented_code_block_indent(config: &Config) -> String {
+    if config.hard_tabs() {
+        String::from("\t")
+    } else {
+        " ".repeat(config.tab_spaces().max(4))
+    }
+}
+
+/// Hides a hidden line of a code block behind a comment, so that the code block can be
+/// formatted. Its trailing whitespace, which often comes from copy-pasting, is removed so that
+/// the inner formatter doesn't report it.
 fn hide_sharp_behind_comment(s: &str) -> Cow<'_, str> {
     let s_trimmed = s.trim();
     if s_trimmed.starts_with("# ") || s_trimmed == "#" {
-        Cow::from(format!("{RUSTFMT_CUSTOM_COMMENT_PREFIX}{s}"))
+        Cow::from(format!("{RUSTFMT_CUSTOM_COMMENT_PREFIX}{}", s.trim_end()))
+    } else {
+        Cow::from(s)
+    }
+}
+
+/// Rustdoc shows a line starting with `##` as code with the first `#` removed; this is how a
+/// visible line starting with `# ` is written. Returns the code rustdoc sees for the line.
+fn unescape_sharp(s: &str) -> Cow<'_, str> {
+    if s.trim_start().starts_with("##") {
+        Cow::from(s.replacen("##", "#", 1))
+    } else {
+        Cow::from(s)
+    }
+}
+
+/// The reverse of `unescape_sharp`: escapes a formatted line of code that rustdoc would
+/// otherwise take for a hidden line. Lines like `#[derive(Debug)]` are left as they are.
+fn escape_sharp(s: &str) -> Cow<'_, str> {
+    let s_trimmed = s.trim();
+    if s_trimmed.starts_with("# ") || s_trimmed == "#" {
+        Cow::from(s.replacen('#', "##", 1))
     } else {
         Cow::from(s)
     }
@@ -961,23 +2332,149 @@ fn trim_custom_comment_prefix(s: &str) -> String {
         .join("\n")
 }
 
+/// Reveals a hidden line of a formatted code block, see `hide_sharp_behind_comment`. The line
+/// gets the indentation the formatter gave to its comment in place of the one it was written
+/// with, so that it lines up with the formatted code around it.
+fn reindent_hidden_line(line: &str) -> String {
+    let hidden = line.trim_start();
+    match hidden.strip_prefix(RUSTFMT_CUSTOM_COMMENT_PREFIX) {
+        Some(code) => format!(
+            "{}{}",
+            &line[..line.len() - hidden.len()],
+            code.trim_start()
+        ),
+        None => line.to_owned(),
+    }
+}
+
 /// Returns `true` if the given string MAY include URLs or alike.
 fn has_url(s: &str) -> bool {
-    // A regex matching reference doc links.
-    //
-    // ```markdown
-    // /// An [example].
-    // ///
-    // /// [example]: this::is::a::link
-    // ```
-    let reference_link_url = static_regex!(r"^\[.+\]\s?:");
-
     // This function may return false positive, but should get its job done in most cases.
-    s.contains("https://")
-        || s.contains("http://")
-        || s.contains("ftp://")
-        || s.contains("file://")
-        || reference_link_url.is_match(s)
+    find_url_scheme(s).is_some() || is_reference_link_definition(s)
+}
+
+/// Returns `true` if the given string is a reference doc link definition.
+///
+/// ```markdown
+/// /// An [example].
+/// ///
+/// /// [example]: this::is::a::link
+/// ```
+fn is_reference_link_definition(s: &str) -> bool {
+    static_regex!(r"^\[.+\]\s?:").is_match(s)
+}
+
+/// Splits `s` into the text before its first URL, the URL and the text after it. Returns `None`
+/// if the URL is part of a larger word, e.g. of a Markdown link, which can't be split.
+fn split_around_url(s: &str) -> Option<(&str, &str, &str)> {
+    let scheme_start = find_url_scheme(s)?;
+    let start = s[..scheme_start]
+        .trim_end_matches(|c: char| !c.is_whitespace())
+        .len();
+    if !matches!(&s[start..scheme_start], "" | "<" | "(") {
+        return None;
+    }
+    let end = s[scheme_start..]
+        .find(char::is_whitespace)
+        .map_or(s.len(), |len| scheme_start + len);
+    Some((s[..start].trim_end(), &s[start..end], s[end..].trim_start()))
+}
+
+/// Returns a mask over `lines` where `true` marks the lines of hand-drawn ASCII tables, i.e. the
+/// runs of consecutive lines that have `|` or `+` column separators in at least two of the same
+/// columns, such as
+///
+/// ```text
+/// +------+-------+
+/// | Name | Value |
+/// +------+-------+
+/// ```
+fn ascii_table_lines(lines: &[&str]) -> Vec<bool> {
+    let separator_columns = |line: &str| {
+        line.match_indices(['|', '+'])
+            .map(|(i, _)| unicode_str_width(&line[..i]))
+            .collect::<Vec<_>>()
+    };
+    let columns = lines
+        .iter()
+        .map(|line| separator_columns(line))
+        .collect::<Vec<_>>();
+    let mut mask = vec![false; lines.len()];
+    for i in 1..lines.len() {
+        let shared_columns = columns[i]
+            .iter()
+            .filter(|column| columns[i - 1].contains(column))
+            .count();
+        if shared_columns >= 2 {
+            mask[i - 1] = true;
+            mask[i] = true;
+        }
+    }
+    mask
+}
+
+/// Returns a mask over `lines` where `true` marks the lines of math blocks, which start with a
+/// line opening with `$$` and end with a line closing with `$$`, such as
+///
+/// ```text
+/// $$
+/// \sum_{i=1}^n i = \frac{n(n+1)}{2} \\
+/// $$
+/// ```
+///
+/// A block that is never closed is not a math block.
+fn math_block_lines(lines: &[&str]) -> Vec<bool> {
+    let mut mask = vec![false; lines.len()];
+    let mut start = None;
+    for (i, line) in lines.iter().enumerate() {
+        let line = line.trim();
+        match start {
+            None if line.starts_with("$$") => {
+                if line.len() >= 4 && line.ends_with("$$") {
+                    mask[i] = true;
+                } else {
+                    start = Some(i);
+                }
+            }
+            Some(first) if line.ends_with("$$") => {
+                mask[first..=i].fill(true);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    mask
+}
+
+/// Returns a mask over `lines` where `true` marks the lines of HTML definition lists, which
+/// are passed through as they are instead of being wrapped, such as
+///
+/// ```text
+/// <dl>
+///   <dt>Term</dt>
+///   <dd>Definition</dd>
+/// </dl>
+/// ```
+///
+/// Nested lists are part of the outermost one. A list that is never closed is left alone.
+fn html_definition_list_lines(lines: &[&str]) -> Vec<bool> {
+    let mut mask = vec![false; lines.len()];
+    let mut start = None;
+    let mut depth = 0usize;
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if start.is_none() && !(trimmed.starts_with("<dl>") || trimmed.starts_with("<dl ")) {
+            continue;
+        }
+        depth += trimmed.matches("<dl>").count() + trimmed.matches("<dl ").count();
+        depth = depth.saturating_sub(trimmed.matches("</dl>").count());
+        let first = *start.get_or_insert(i);
+        if depth == 0 {
+            mask[first..=i].fill(true);
+            start = None;
+        }
+    }
+    mask
 }
 
 /// Returns true if the given string may be part of a Markdown table.
@@ -1710,7 +3207,19 @@ pub(crate) fn recover_comment_removed(
     context: &RewriteContext<'_>,
 ) -> Option<String> {
     let snippet = context.snippet(span);
-    if snippet != new && changed_comment_content(snippet, &new) {
+    let changed_comments = if context.in_fn_body
+        && context.config.comment_only_lines_in_fn() == CommentOnlyLines::Strip
+    {
+        // The comments removed by `comment_only_lines_in_fn = Strip` are not missed.
+        changed_comment_content_except(snippet, &new, |offset, comment| {
+            let line_start = snippet[..offset].rfind('\n').map_or(0, |i| i + 1);
+            snippet[line_start..offset].trim().is_empty()
+                && is_strippable_comment_line(comment.trim(), context.config)
+        })
+    } else {
+        changed_comment_content(snippet, &new)
+    };
+    if snippet != new && changed_comments {
         // We missed some comments. Warn and keep the original text.
         if context.config.error_on_unformatted() {
             context.report.append(
@@ -1728,6 +3237,53 @@ pub(crate) fn recover_comment_removed(
     }
 }
 
+/// Returns `true` if `line`, a trimmed line of a function body that only holds a comment, is
+/// removed by `comment_only_lines_in_fn = Strip`. Doc comments, block comments and skip pragmas
+/// are kept.
+pub(crate) fn is_strippable_comment_line(line: &str, config: &Config) -> bool {
+    line.starts_with("//")
+        && !line.starts_with("///")
+        && !line.starts_with("//!")
+        && !config.rustfmt_skip_pragma_patterns().matches(line)
+}
+
+/// Returns `code` without the comments that `comment_only_lines_in_fn = Strip` removes from it,
+/// which are not missed once formatted.
+pub(crate) fn without_stripped_comments<'a>(
+    code: &'a str,
+    context: &RewriteContext<'_>,
+) -> Cow<'a, str> {
+    if context.in_fn_body && context.config.comment_only_lines_in_fn() == CommentOnlyLines::Strip {
+        Cow::Owned(strip_comment_only_lines(code, context.config))
+    } else {
+        Cow::Borrowed(code)
+    }
+}
+
+/// Removes the lines of `code` that only hold a comment removed by
+/// `comment_only_lines_in_fn = Strip`.
+fn strip_comment_only_lines(code: &str, config: &Config) -> String {
+    // The kind of the first character of each line that is not whitespace.
+    let mut line_kinds = vec![];
+    let mut first_kind = None;
+    for (kind, (_, c)) in CharClasses::new(code.char_indices()) {
+        if c == '\n' {
+            line_kinds.push(first_kind.take());
+        } else if first_kind.is_none() && !c.is_whitespace() {
+            first_kind = Some(kind);
+        }
+    }
+    line_kinds.push(first_kind);
+    code.split_inclusive('\n')
+        .zip(line_kinds)
+        .filter(|(line, kind)| {
+            *kind != Some(FullCodeCharKind::StartComment)
+                || !is_strippable_comment_line(line.trim(), config)
+        })
+        .map(|(line, _)| line)
+        .collect()
+}
+
 pub(crate) fn filter_normal_code(code: &str) -> String {
     let mut buffer = String::with_capacity(code.len());
     LineClasses::new(code).for_each(|(kind, line)| match kind {
@@ -1772,6 +3328,31 @@ fn changed_comment_content(orig: &str, new: &str) -> bool {
     res
 }
 
+/// Like `changed_comment_content`, but the comments of `orig` for which `may_be_removed` returns
+/// `true`, given their offset in `orig` and their text, may be missing from `new`.
+fn changed_comment_content_except(
+    orig: &str,
+    new: &str,
+    may_be_removed: impl Fn(usize, &str) -> bool,
+) -> bool {
+    let comments = |code| {
+        UngroupedCommentCodeSlices::new(code).filter(|(kind, _, _)| *kind == CodeCharKind::Comment)
+    };
+    let new_content = comments(new)
+        .flat_map(|(_, _, s)| CommentReducer::new(s))
+        .collect::<Vec<_>>();
+    let mut pos = 0;
+    for (_, offset, comment) in comments(orig) {
+        let content = CommentReducer::new(comment).collect::<Vec<_>>();
+        if new_content[pos..].starts_with(&content) {
+            pos += content.len();
+        } else if !may_be_removed(offset, comment) {
+            return true;
+        }
+    }
+    pos != new_content.len()
+}
+
 /// Iterator over the 'payload' characters of a comment.
 /// It skips whitespace, comment start/end marks, and '*' at the beginning of lines.
 /// The comment must be one comment, ie not more than one start mark (no multiple line comments,
@@ -1840,6 +3421,7 @@ fn remove_comment_header(comment: &str) -> &str {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::config::CodeBlockLanguages;
 
     #[test]
     fn char_classes() {
@@ -1897,6 +3479,41 @@ mod test {
         assert_eq!(None, iter.next());
     }
 
+    #[test]
+    fn comments_without_text() {
+        let mut config: Config = Default::default();
+        config.set().wrap_comments(true);
+        let shape = Shape::legacy(100, Indent::empty());
+        let rewrite =
+            |comment, block_style| rewrite_comment(comment, block_style, shape, &config).unwrap();
+        assert_eq!(rewrite("", false), "//");
+        assert_eq!(rewrite("/* */", false), "/* */");
+        assert_eq!(rewrite("/*  \n */", true), "/*\n */");
+        assert_eq!(rewrite("/// just text", false), "/// just text");
+    }
+
+    #[test]
+    fn inner_and_outer_doc_comments_wrap_alike() {
+        let mut config: Config = Default::default();
+        config.set().wrap_comments(true);
+        let text = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor \
+                    incididunt ut labore et dolore magna aliqua.";
+        for indent in [0, 4] {
+            for width in 20..60 {
+                let shape = Shape::legacy(width, Indent::new(indent, 0));
+                let rewrite = |comment: &str| rewrite_comment(comment, false, shape, &config);
+                let outer = rewrite(&format!("/// {text}"));
+                let inner = rewrite(&format!("//! {text}"));
+                assert!(outer.as_ref().map_or(false, |c| c.contains('\n')));
+                assert_eq!(inner.map(|c| c.replace("//!", "///")), outer);
+
+                let outer = rewrite(&format!("/** {text} */"));
+                let inner = rewrite(&format!("/*! {text} */"));
+                assert_eq!(inner.map(|c| c.replacen("/*!", "/**", 1)), outer);
+            }
+        }
+    }
+
     #[test]
     #[rustfmt::skip]
     fn format_doc_comments() {
@@ -2056,6 +3673,91 @@ fn main() {
         assert_eq!(s, filter_normal_code(s_with_comment));
     }
 
+    #[test]
+    fn test_code_block_attribute() {
+        let config = Config::default();
+        let is_rust = |attributes| {
+            matches!(
+                CodeBlockAttribute::new(attributes, &config),
+                CodeBlockAttribute::Rust
+            )
+        };
+        assert!(is_rust(""));
+        assert!(is_rust("rust"));
+        assert!(is_rust("rust, no_run"));
+        assert!(is_rust("should_panic,edition2021"));
+        assert!(is_rust("rs"));
+        assert!(is_rust("RS"));
+        assert!(is_rust("Rs, edition2021"));
+        assert!(is_rust(" rust"));
+        assert!(is_rust("rust  "));
+        assert!(is_rust("rust no_run"));
+        assert!(is_rust(" should_panic , edition2021 "));
+
+        assert!(!is_rust("text"));
+        assert!(!is_rust("sh"));
+        assert!(!is_rust("console"));
+        assert!(!is_rust("ignore"));
+        assert!(!is_rust("compile_fail,E0308"));
+        assert!(!is_rust("rust,ignore"));
+        assert!(!is_rust("no_run,text"));
+        assert!(!is_rust("rsx"));
+        assert!(!is_rust("rs,ignore"));
+        assert!(!is_rust("no_run,rs"));
+        assert!(!is_rust("rust ignore"));
+        assert!(!is_rust(" text"));
+
+        let mut config = Config::default();
+        config
+            .set()
+            .doc_comment_code_block_languages(CodeBlockLanguages(vec![
+                "rust".to_owned(),
+                "pseudo".to_owned(),
+            ]));
+        config
+            .set()
+            .doc_comment_code_block_skip_languages(CodeBlockLanguages(vec!["rust".to_owned()]));
+        let is_rust = |attributes| {
+            matches!(
+                CodeBlockAttribute::new(attributes, &config),
+                CodeBlockAttribute::Rust
+            )
+        };
+        assert!(is_rust("pseudo"));
+        assert!(is_rust("pseudo,no_run"));
+        assert!(!is_rust("pseudo,text"));
+        assert!(!is_rust(""));
+        assert!(!is_rust("rs"));
+        assert!(!is_rust("no_run"));
+    }
+
+    #[test]
+    fn test_hide_sharp_behind_comment() {
+        assert_eq!(
+            hide_sharp_behind_comment("# use std::collections::HashMap;   "),
+            "//#### # use std::collections::HashMap;"
+        );
+        assert_eq!(hide_sharp_behind_comment("    #\t"), "//####     #");
+        assert_eq!(hide_sharp_behind_comment("let x = 1;  "), "let x = 1;  ");
+    }
+
+    #[test]
+    fn test_escape_sharp() {
+        // Hidden lines and attributes are not escaped.
+        assert_eq!(unescape_sharp("# use std::fmt;"), "# use std::fmt;");
+        assert_eq!(unescape_sharp("#[derive(Debug)]"), "#[derive(Debug)]");
+        assert_eq!(unescape_sharp("    ## not hidden"), "    # not hidden");
+        assert_eq!(unescape_sharp("##[derive(Debug)]"), "#[derive(Debug)]");
+
+        assert_eq!(escape_sharp("#[derive(Debug)]"), "#[derive(Debug)]");
+        assert_eq!(escape_sharp("    # not hidden"), "    ## not hidden");
+        assert_eq!(escape_sharp("#"), "##");
+        assert_eq!(
+            escape_sharp("let x = 1; # not a comment"),
+            "let x = 1; # not a comment"
+        );
+    }
+
     #[test]
     fn test_itemized_block_first_line_handling() {
         fn run_test(
@@ -2136,4 +3838,55 @@ fn main() {
             );
         }
     }
+
+    #[test]
+    fn test_indented_code_block_indent() {
+        let mut config = Config::default();
+        assert_eq!(indented_code_block_indent(&config), "    ");
+        config.set().tab_spaces(2);
+        assert_eq!(indented_code_block_indent(&config), "    ");
+        config.set().tab_spaces(8);
+        assert_eq!(indented_code_block_indent(&config), "        ");
+        config.set().hard_tabs(true);
+        assert_eq!(indented_code_block_indent(&config), "\t");
+    }
+
+    #[test]
+    fn test_normalize_emphasis() {
+        let text = "**x** and __y__, *a* and _b_, ***c*** and ___d___";
+        assert_eq!(
+            normalize_emphasis(text, '*'),
+            "**x** and **y**, *a* and *b*, ***c*** and ***d***"
+        );
+        assert_eq!(
+            normalize_emphasis(text, '_'),
+            "__x__ and __y__, _a_ and _b_, ___c___ and ___d___"
+        );
+        assert_eq!(
+            normalize_emphasis("**bold _and_ italic** and __bold *and* italic__", '*'),
+            "**bold *and* italic** and **bold *and* italic**"
+        );
+        assert_eq!(
+            normalize_emphasis("`` a`*b*`c `` and *d*", '_'),
+            "`` a`*b*`c `` and _d_"
+        );
+        assert!(matches!(
+            normalize_emphasis("**intra**word, `__code__`, __open and a_b_c", '_'),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(
+            prose("**x** and __y__", false, EmphasisMarker::Preserve),
+            "**x** and __y__"
+        );
+    }
+
+    #[test]
+    fn dashes_alone_on_a_line_are_kept() {
+        assert_eq!(normalize_dashes("a — b"), "a --- b");
+        assert_eq!(
+            normalize_dashes("Heading\n—\nmore – text"),
+            "Heading\n—\nmore -- text"
+        );
+        assert_eq!(normalize_dashes(" – "), " – ");
+    }
 }
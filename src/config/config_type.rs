@@ -1,6 +1,9 @@
 use crate::config::file_lines::FileLines;
 use crate::config::macro_names::MacroSelectors;
-use crate::config::options::{IgnoreList, WidthHeuristics};
+use crate::config::options::{
+    AttributeNames, CodeBlockLanguages, IgnoreList, SkipPragmas, TrailingBlankLines,
+    WidthHeuristics,
+};
 
 /// Trait for types that can be used in `Config`.
 pub(crate) trait ConfigType: Sized {
@@ -53,6 +56,30 @@ impl ConfigType for MacroSelectors {
     }
 }
 
+impl ConfigType for AttributeNames {
+    fn doc_hint() -> String {
+        String::from("[<string>, ...]")
+    }
+}
+
+impl ConfigType for CodeBlockLanguages {
+    fn doc_hint() -> String {
+        String::from("[<string>, ...]")
+    }
+}
+
+impl ConfigType for SkipPragmas {
+    fn doc_hint() -> String {
+        String::from("[<string>, ...]")
+    }
+}
+
+impl ConfigType for TrailingBlankLines {
+    fn doc_hint() -> String {
+        String::from("[Preserve|<unsigned integer>]")
+    }
+}
+
 impl ConfigType for WidthHeuristics {
     fn doc_hint() -> String {
         String::new()
@@ -231,6 +258,61 @@ macro_rules! create_config {
                 }
             }
 
+            /// Returns the options whose value differs from the default.
+            #[allow(unreachable_pub)]
+            pub fn non_default_options(&self) -> PartialConfig {
+                let default = Config::default();
+                PartialConfig {
+                    $(
+                        $i: if self.$i.2 != default.$i.2 {
+                                Some(self.$i.2.clone())
+                            } else {
+                                None
+                            },
+                    )+
+                }
+            }
+
+            /// Returns the names and values of the options whose value differs from the default,
+            /// in the order of their declaration.
+            #[allow(unreachable_pub)]
+            pub fn enabled_options(&self) -> Vec<(&'static str, String)> {
+                let default = Config::default();
+                let mut options = Vec::new();
+                $(
+                    if self.$i.2 != default.$i.2 {
+                        options.push((stringify!($i), self.$i.2.to_string()));
+                    }
+                )+
+                options
+            }
+
+            /// Returns a config in which every option takes the more restrictive of its values
+            /// in `a` and `b`, which gives a shared baseline for crates whose configs
+            /// differ slightly. A lower value of an option that limits a width or a number of
+            /// lines is more restrictive, and so is any variant over `Preserve` or `Auto`.
+            /// Options whose values can't be ordered keep the value of `a`.
+            #[allow(unreachable_pub)]
+            pub fn intersect(a: &Config, b: &Config) -> Config {
+                let mut config = a.clone();
+                $(
+                    // `FileLines` can't be serialized, and is not a formatting preference anyway.
+                    if stringify!($i) != "file_lines"
+                        && a.$i.2 != b.$i.2
+                        && $crate::config::config_type::is_more_restrictive(
+                            stringify!($i),
+                            &a.$i.2,
+                            &b.$i.2,
+                        )
+                    {
+                        config.$i.2 = b.$i.2.clone();
+                    }
+                    config.$i.1 = a.$i.1 || b.$i.1;
+                )+
+                config.set_heuristics();
+                config
+            }
+
             #[allow(unreachable_pub)]
             pub fn all_options(&self) -> PartialConfig {
                 PartialConfig {
@@ -502,6 +584,41 @@ macro_rules! create_config {
     )
 }
 
+/// The numeric options whose values are upper limits, so that a lower value is more restrictive.
+/// Other numbers are thresholds, lower bounds or use sentinels such as `0` for "off", and can't be
+/// ordered.
+const UPPER_LIMIT_OPTIONS: &[&str] = &[
+    "max_width",
+    "comment_width",
+    "doc_comment_code_block_width",
+    "fn_call_width",
+    "attr_fn_like_width",
+    "struct_lit_width",
+    "struct_variant_width",
+    "array_width",
+    "chain_width",
+    "single_line_if_else_max_width",
+    "single_line_let_else_max_width",
+    "blank_lines_upper_bound",
+    "max_empty_lines_in_doc_comments",
+];
+
+/// Returns `true` if `b` is a more restrictive value of the option `name` than `a`, see
+/// `Config::intersect`.
+pub(crate) fn is_more_restrictive<T: serde::Serialize>(name: &str, a: &T, b: &T) -> bool {
+    match (toml::Value::try_from(a), toml::Value::try_from(b)) {
+        (Ok(toml::Value::Integer(a)), Ok(toml::Value::Integer(b))) => {
+            UPPER_LIMIT_OPTIONS.contains(&name) && b < a
+        }
+        // Any value is more restrictive than `Preserve`, including the numbers of
+        // `TrailingBlankLines`.
+        (Ok(toml::Value::String(a)), Ok(_)) => {
+            matches!(a.as_str(), "Preserve" | "Auto")
+        }
+        _ => false,
+    }
+}
+
 pub(crate) fn is_stable_option_and_value<T>(
     option_name: &str,
     option_stable: bool,
@@ -1,4 +1,5 @@
 use std::cell::Cell;
+use std::fmt;
 use std::fs::File;
 use std::io::{Error, ErrorKind, Read};
 use std::path::{Path, PathBuf};
@@ -35,10 +36,16 @@ pub(crate) mod style_edition;
 create_config! {
     // Fundamental stuff
     max_width: usize, 100, true, "Maximum width of each line";
+    max_width_in_tests: usize, 0, false,
+        "Maximum width of each line in modules only compiled for tests, 0 uses max_width";
     hard_tabs: bool, false, true, "Use tab characters for indentation, spaces for alignment";
     tab_spaces: usize, 4, true, "Number of spaces per tab";
     newline_style: NewlineStyle, NewlineStyle::Auto, true, "Unix or Windows line endings";
+    line_endings_in_multiline_strings: StringLineEndings, StringLineEndings::Preserve, false,
+        "Whether line endings inside multi-line string literals follow newline_style";
     indent_style: IndentStyle, IndentStyle::Block, false, "How do we indent expressions or items";
+    let_chain_indent_style: IndentStyle, IndentStyle::Block, false,
+        "Indent style of the conditions of a multi-line let chain";
 
     // Width Heuristics
     use_small_heuristics: Heuristics, Heuristics::Default, true, "Whether to use different \
@@ -64,21 +71,84 @@ create_config! {
 
     // Comments. macros, and strings
     wrap_comments: bool, false, false, "Break comments to fit on the line";
+    comment_urls_on_own_line: bool, false, false,
+        "Put the URLs of wrapped comments on lines of their own";
+    comment_reflow_blockquotes: bool, false, false,
+        "Join the lines of quoted paragraphs in comments before wrapping them";
+    prefer_block_comments_for_long_code: bool, false, false,
+        "Write `//` comments that wrap to many lines as `/* */` comments";
+    block_comment_min_lines: usize, 4, false,
+        "Number of lines a wrapped comment must exceed to be written as a `/* */` comment";
     format_code_in_doc_comments: bool, false, false, "Format the code snippet in doc comments.";
+    doc_comment_include_private: bool, true, false,
+        "Format code blocks in the doc comments of items that are not `pub`";
+    doc_comment_preserve_prose: bool, false, false,
+        "Leave doc comments as they are written, apart from the code blocks that \
+        format_code_in_doc_comments formats";
+    doc_comment_fence_indented_code_blocks: bool, false, false,
+        "Turn the indented code blocks of doc comments into fenced code blocks";
     doc_comment_code_block_width: usize, 100, false, "Maximum width for code snippets in doc \
         comments. No effect unless format_code_in_doc_comments = true";
+    doc_comment_code_block_languages: CodeBlockLanguages, CodeBlockLanguages::default(), false,
+        "Languages of the doc comment code blocks that format_code_in_doc_comments formats";
+    doc_comment_code_block_skip_languages: CodeBlockLanguages, CodeBlockLanguages(Vec::new()),
+        false, "Languages of the doc comment code blocks that are never formatted";
     comment_width: usize, 80, false,
         "Maximum length of comments. No effect unless wrap_comments = true";
     normalize_comments: bool, false, false, "Convert /* */ comments to // comments where possible";
+    comment_preserve_sentence_spacing: bool, false, false, "Put two spaces after a sentence \
+        that ends a wrapped comment line when the next line is joined to it. No effect unless \
+        wrap_comments = true";
+    comment_collapse_blank_lines: bool, false, false, "Collapse consecutive blank comment lines \
+        into one. No effect unless wrap_comments or normalize_comments = true";
+    max_empty_lines_in_doc_comments: usize, 1, false, "Maximum number of blank lines in a row \
+        within a doc comment. No effect unless wrap_comments or normalize_comments = true";
+    comment_only_lines_in_fn: CommentOnlyLines, CommentOnlyLines::Preserve, false,
+        "What to do with the lines of function bodies that only hold a `//` comment";
     normalize_doc_attributes: bool, false, false, "Normalize doc attributes as doc comments";
+    doc_comment_thematic_break: ThematicBreakStyle, ThematicBreakStyle::Preserve, false,
+        "Marker used for thematic breaks in doc comments";
+    doc_comment_hard_break: HardBreakStyle, HardBreakStyle::Preserve, false,
+        "Marker used for hard line breaks in doc comments";
+    doc_comment_emphasis_marker: EmphasisMarker, EmphasisMarker::Preserve, false,
+        "Delimiter used for emphasis and strong emphasis in doc comments";
+    doc_comment_trim_leading_blank_lines: bool, false, false,
+        "Remove the blank lines at the start of doc comments";
+    doc_comment_trailing_blank_lines: TrailingBlankLines, TrailingBlankLines::Preserve, false,
+        "Number of blank lines doc comments end with, or Preserve to leave them as they are";
+    doc_comment_remove_unused_link_definitions: bool, false, false,
+        "Remove the reference-link definitions that nothing in their doc comment refers to";
+    doc_comment_sort_link_definitions: bool, false, false,
+        "Sort the reference-link definitions of doc comments by their labels";
+    doc_comment_sort_footnote_definitions: bool, false, false,
+        "Sort the footnote definitions of doc comments in the order they are referred to";
+    markdown_normalize_dashes: bool, false, false,
+        "Replace en and em dashes in doc comments with `--` and `---`";
     format_strings: bool, false, false, "Format string literals where necessary";
+    string_break_preference: BreakPreference, BreakPreference::Whitespace, false,
+        "Where to prefer breaking string literals and wrapped comments";
+    break_long_code_spans: bool, false, false,
+        "Break inline code spans that are too wide for a line at their spaces when wrapping \
+         comments";
+    break_long_words: bool, false, false,
+        "Split words that are too wide for a line of a string literal or of a wrapped comment";
+    normalize_line_endings_in_strings: bool, false, false,
+        "Replace `\\r\\n` and `\\r` escapes in string literals with `\\n`";
+    coerce_strings_to_byte_strings: bool, false, false,
+        "Write ASCII string literals bound to `&[u8]` or `Vec<u8>` as byte string literals";
     format_macro_matchers: bool, false, false,
         "Format the metavariable matching patterns in macros";
     format_macro_bodies: bool, true, false, "Format the bodies of declarative macro definitions";
     skip_macro_invocations: MacroSelectors, MacroSelectors::default(), false,
         "Skip formatting the bodies of macros invoked with the following names.";
+    rustfmt_skip_pragma_patterns: SkipPragmas, SkipPragmas::default(), false,
+        "Comments that mark the next item or statement as not to be formatted";
+    format_attribute_args: AttributeNames, AttributeNames::default(), false,
+        "Wrap the arguments of the listed attributes even if they are not plain meta items";
     hex_literal_case: HexLiteralCase, HexLiteralCase::Preserve, false,
         "Format hexadecimal integer literals";
+    allow_mixed_separator_in_numbers: bool, true, false,
+        "Allow number literals of a file to differ in whether they use `_` separators";
 
     // Single line expressions and items
     empty_item_single_line: bool, true, false,
@@ -87,6 +157,14 @@ create_config! {
         "Put small struct literals on a single line";
     fn_single_line: bool, false, false, "Put single-expression functions on a single line";
     where_single_line: bool, false, false, "Force where-clauses to be on a single line";
+    force_where_clause_always: bool, false, false,
+        "Move the bounds of type parameters to the where-clause";
+    force_where_clause_never: bool, false, false,
+        "Move the bound of a single-predicate where-clause onto its type parameter";
+    type_alias_style: TypeAliasStyle, TypeAliasStyle::Preserve, false,
+        "Where to put the where clause of a type alias";
+    const_generics_style: ConstGenericsStyle, ConstGenericsStyle::Preserve, false,
+        "Add or remove the defaults of const generic parameters in types";
 
     // Imports
     imports_indent: IndentStyle, IndentStyle::Block, false, "Indent of imports";
@@ -95,16 +173,26 @@ create_config! {
         "Merge or split imports to the provided granularity";
     group_imports: GroupImportsTactic, GroupImportsTactic::Preserve, false,
         "Controls the strategy for how imports are grouped together";
+    imports_group_blank_line_threshold: usize, 1, false,
+        "Number of blank lines between the import groups created by `group_imports`";
+    use_glob_imports: ReportLevel, ReportLevel::Ignore, false,
+        "Report wildcard imports such as `use foo::*`";
     merge_imports: bool, false, false, "(deprecated: use imports_granularity instead)";
 
     // Ordering
     reorder_imports: bool, true, true, "Reorder import and extern crate statements alphabetically";
+    reorder_imports_by_visibility: bool, false, false,
+        "Order reordered imports by visibility: private, then restricted, then `pub`";
     reorder_modules: bool, true, true, "Reorder module statements alphabetically in group";
     reorder_impl_items: bool, false, false, "Reorder impl items";
+    group_consecutive_impl_blocks: bool, false, false,
+        "Merge adjacent impl blocks that share the same header into a single block";
 
     // Spaces around punctuation
     type_punctuation_density: TypeDensity, TypeDensity::Wide, false,
         "Determines if '+' or '=' are wrapped in spaces in the punctuation of types";
+    type_punctuation_in_generics: GenericsPunctuation, GenericsPunctuation::Spaced, false,
+        "Determines if '+' is wrapped in spaces in the bounds of generic parameters";
     space_before_colon: bool, false, false, "Leave a space before the colon";
     space_after_colon: bool, true, false, "Leave a space after the colon";
     spaces_around_ranges: bool, false, false, "Put spaces around the  .. and ..= range operators";
@@ -116,8 +204,21 @@ create_config! {
     combine_control_expr: bool, true, false, "Combine control expressions with function calls";
     short_array_element_width_threshold: usize, 10, true,
         "Width threshold for an array element to be considered short";
+    array_layout: ArrayLayout, ArrayLayout::AutoCompact, false,
+        "Layout of the elements of array literals that don't fit on one line";
+    tuple_layout: TupleLayout, TupleLayout::AutoCompact, false,
+        "Layout of the elements of tuple expressions and patterns";
+    tuple_tall_threshold: usize, 3, false,
+        "Number of elements above which tuples have one element per line when \
+         tuple_layout = Tall";
     overflow_delimited_expr: bool, false, false,
         "Allow trailing bracket/brace delimited expressions to overflow";
+    max_inline_nesting_depth: usize, 0, false,
+        "Maximum nesting depth of calls before their arguments are put on separate lines. \
+         0 means no limit";
+    inline_nesting_ignore_wrappers: bool, true, false,
+        "Don't count single-argument constructor calls like `Some(x)` towards \
+         max_inline_nesting_depth";
     struct_field_align_threshold: usize, 0, false,
         "Align struct fields if their diffs fits within threshold";
     enum_discrim_align_threshold: usize, 0, false,
@@ -147,6 +248,8 @@ create_config! {
         "Minimum number of blank lines which must be put between items";
     edition: Edition, Edition::Edition2015, true, "The edition of the parser (RFC 2052)";
     version: Version, Version::One, false, "Version of formatting rules";
+    format_version_stamps: bool, false, false,
+        "Format each file according to the `// @rustfmt-version: N` stamp on its first line";
     inline_attribute_width: usize, 0, false,
         "Write an item and its attribute on the same line \
         if their combined width is below a threshold";
@@ -156,6 +259,8 @@ create_config! {
 
     // Options that can change the source code beyond whitespace/blocks (somewhat linty things)
     merge_derives: bool, true, true, "Merge multiple `#[derive(...)]` into a single one";
+    sort_lint_attributes: bool, false, false,
+        "Sort and deduplicate the lints of `#[allow(...)]` and other lint attributes";
     use_try_shorthand: bool, false, true, "Replace uses of the try! macro by the ? shorthand";
     use_field_init_shorthand: bool, false, true, "Use field initialization shorthand if possible";
     force_explicit_abi: bool, true, true, "Always print the abi for extern items";
@@ -174,11 +279,15 @@ create_config! {
     hide_parse_errors: bool, false, false, "(deprecated: use show_parse_errors instead)";
     show_parse_errors: bool, true, false, "Show errors from the parser (unstable)";
     error_on_line_overflow: bool, false, false, "Error if unable to get all lines within max_width";
+    forbid_long_lines: bool, false, false, "Error with exit code 2 if any line, including \
+        comments and string literals, exceeds max_width";
     error_on_unformatted: bool, false, false,
         "Error if unable to get comments or string literals within max_width, \
          or they are left with trailing whitespaces";
     ignore: IgnoreList, IgnoreList::default(), false,
         "Skip formatting the specified files and directories";
+    diff_tool_max_files: usize, 10, false,
+        "Maximum number of mismatched files that `--diff-tool` opens";
 
     // Not user-facing
     verbose: Verbosity, Verbosity::Normal, false, "How much to information to emit to the user";
@@ -191,6 +300,24 @@ create_config! {
     print_misformatted_file_names: bool, false, true,
         "Prints the names of mismatched files that were formatted. Prints the names of \
          files that would be formatted when used with `--check` mode. ";
+    summary: SummaryFormat, SummaryFormat::Off, false,
+        "Prints the number of lines that would change in each file, and in total, \
+         when used with `--check` mode.";
+    suggest_skips: bool, false, false,
+        "Suggest adding `#[rustfmt::skip]` to items that could not be formatted";
+    bump_format_version: bool, false, false,
+        "Stamp formatted files with the latest version when `format_version_stamps` is set";
+    diff_tool: bool, false, false,
+        "Open mismatched files with `diff_command` when used with `--check` mode";
+    diff_command: String, String::new(), false,
+        "Command that `--diff-tool=CMD` opens mismatched files with; `{old}` and `{new}` are \
+         replaced by the paths of the original and the formatted file";
+    show_ruler: bool, false, false,
+        "Mark `max_width` and highlight overlong lines when used with `--emit stdout`";
+    detect_edition: bool, false, false,
+        "Format files that don't parse with `edition` with the first edition they parse with";
+    chunk_large_files: bool, false, false,
+        "Format files too large for the parser in chunks split at top-level items";
 }
 
 #[derive(Error, Debug)]
@@ -205,6 +332,14 @@ impl PartialConfig {
         cloned.verbose = None;
         cloned.width_heuristics = None;
         cloned.print_misformatted_file_names = None;
+        cloned.summary = None;
+        cloned.suggest_skips = None;
+        cloned.bump_format_version = None;
+        cloned.diff_tool = None;
+        cloned.diff_command = None;
+        cloned.show_ruler = None;
+        cloned.detect_edition = None;
+        cloned.chunk_large_files = None;
         cloned.merge_imports = None;
         cloned.fn_args_layout = None;
         cloned.hide_parse_errors = None;
@@ -305,12 +440,12 @@ impl Config {
     }
 
     pub(crate) fn from_toml(toml: &str, dir: &Path) -> Result<Config, String> {
-        let parsed: ::toml::Value = toml
+        let mut parsed: ::toml::Value = toml
             .parse()
             .map_err(|e| format!("Could not parse TOML: {}", e))?;
         let mut err = String::new();
         let table = parsed
-            .as_table()
+            .as_table_mut()
             .ok_or_else(|| String::from("Parsed config was not table"))?;
         for key in table.keys() {
             if !Config::is_valid_name(key) {
@@ -318,6 +453,13 @@ impl Config {
                 err.push_str(msg)
             }
         }
+        // A checked-in config file must not be able to make `--check` run a command.
+        if table.remove("diff_command").is_some() {
+            err.push_str(
+                "Warning: `diff_command` is ignored in config files, pass the command as \
+                 `--diff-tool=CMD` instead\n",
+            );
+        }
         match parsed.try_into() {
             Ok(parsed_config) => {
                 if !err.is_empty() {
@@ -335,6 +477,18 @@ impl Config {
     }
 }
 
+/// Prints the options that differ from their default value as TOML, which can be saved as a
+/// `rustfmt.toml` that reproduces the config.
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let toml = self
+            .non_default_options()
+            .to_toml()
+            .map_err(|_| fmt::Error)?;
+        f.write_str(&toml)
+    }
+}
+
 /// Loads a config by checking the client-supplied options and if appropriate, the
 /// file system (including searching the file system for overrides).
 pub fn load_config<O: CliOptions>(
@@ -586,6 +740,43 @@ mod test {
         assert_eq!(config.was_set().verbose(), false);
     }
 
+    #[test]
+    fn test_display_prints_non_default_options() {
+        assert_eq!(Config::default().to_string(), "");
+
+        let toml = "hard_tabs = true\nimports_granularity = \"Crate\"\nignore = [\"target\"]\n";
+        let mut config = Config::from_toml(toml, Path::new("")).unwrap();
+        config.set().newline_style(NewlineStyle::Windows);
+        config.set().verbose(Verbosity::Verbose);
+        let displayed = config.to_string();
+        assert_eq!(
+            displayed,
+            "hard_tabs = true\nnewline_style = \"Windows\"\nimports_granularity = \"Crate\"\n\
+             ignore = [\"target\"]\n"
+        );
+
+        let reparsed = Config::from_toml(&displayed, Path::new("")).unwrap();
+        assert_eq!(reparsed.to_string(), displayed);
+        assert_eq!(reparsed.newline_style(), NewlineStyle::Windows);
+    }
+
+    #[test]
+    fn test_enabled_options() {
+        assert_eq!(Config::default().enabled_options(), vec![]);
+
+        let toml = "hard_tabs = true\nimports_granularity = \"Crate\"\nmax_width = 100\n";
+        let mut config = Config::from_toml(toml, Path::new("")).unwrap();
+        config.set().newline_style(NewlineStyle::Windows);
+        assert_eq!(
+            config.enabled_options(),
+            vec![
+                ("hard_tabs", "true".to_owned()),
+                ("newline_style", "Windows".to_owned()),
+                ("imports_granularity", "Crate".to_owned()),
+            ]
+        );
+    }
+
     const PRINT_DOCS_STABLE_OPTION: &str = "stable_option <boolean> Default: false";
     const PRINT_DOCS_UNSTABLE_OPTION: &str = "unstable_option <boolean> Default: false (unstable)";
     const PRINT_DOCS_PARTIALLY_UNSTABLE_OPTION: &str =
@@ -617,14 +808,94 @@ mod test {
         assert_eq!(s.contains(PRINT_DOCS_PARTIALLY_UNSTABLE_OPTION), true);
     }
 
+    #[test]
+    fn test_intersect_takes_more_restrictive_values() {
+        let a = Config::from_toml(
+            r#"
+                max_width = 120
+                imports_granularity = "Crate"
+                newline_style = "Unix"
+                hard_tabs = true
+            "#,
+            Path::new(""),
+        )
+        .unwrap();
+        let b = Config::from_toml(
+            r#"
+                max_width = 90
+                tab_spaces = 2
+                newline_style = "Native"
+                max_width_in_tests = 80
+                blank_lines_lower_bound = 1
+                doc_comment_trailing_blank_lines = 1
+            "#,
+            Path::new(""),
+        )
+        .unwrap();
+
+        let config = Config::intersect(&a, &b);
+        assert_eq!(config.max_width(), 90);
+        assert_eq!(config.fn_call_width(), 60);
+        assert_eq!(config.tab_spaces(), 4);
+        assert_eq!(config.max_width_in_tests(), 0);
+        assert_eq!(config.blank_lines_lower_bound(), 0);
+        assert_eq!(
+            config.doc_comment_trailing_blank_lines(),
+            TrailingBlankLines::Exactly(1)
+        );
+        assert_eq!(config.imports_granularity(), ImportGranularity::Crate);
+        assert_eq!(config.newline_style(), NewlineStyle::Unix);
+        assert_eq!(config.hard_tabs(), true);
+        assert_eq!(config.was_set().tab_spaces(), true);
+        assert_eq!(config.was_set().hard_tabs(), true);
+        assert_eq!(config.was_set().array_width(), false);
+    }
+
+    #[test]
+    fn test_diff_command_is_ignored_in_config_files() {
+        let config = Config::from_toml("diff_command = \"meld\"", Path::new("")).unwrap();
+        assert_eq!(config.diff_command(), "");
+        assert_eq!(config.was_set().diff_command(), false);
+    }
+
+    #[test]
+    fn test_doc_comment_trailing_blank_lines() {
+        let config = Config::from_toml(
+            "doc_comment_trailing_blank_lines = \"Preserve\"",
+            Path::new(""),
+        )
+        .unwrap();
+        assert_eq!(
+            config.doc_comment_trailing_blank_lines(),
+            TrailingBlankLines::Preserve
+        );
+        let config =
+            Config::from_toml("doc_comment_trailing_blank_lines = 2", Path::new("")).unwrap();
+        assert_eq!(
+            config.doc_comment_trailing_blank_lines(),
+            TrailingBlankLines::Exactly(2)
+        );
+        assert!(Config::from_toml("doc_comment_trailing_blank_lines = -1", Path::new("")).is_err());
+
+        let mut config = Config::default();
+        config.override_value("doc_comment_trailing_blank_lines", "0");
+        assert_eq!(
+            config.doc_comment_trailing_blank_lines(),
+            TrailingBlankLines::Exactly(0)
+        );
+    }
+
     #[test]
     fn test_dump_default_config() {
         let default_config = format!(
             r#"max_width = 100
+max_width_in_tests = 0
 hard_tabs = false
 tab_spaces = 4
 newline_style = "Auto"
+line_endings_in_multiline_strings = "Preserve"
 indent_style = "Block"
+let_chain_indent_style = "Block"
 use_small_heuristics = "Default"
 fn_call_width = 60
 attr_fn_like_width = 70
@@ -635,28 +906,67 @@ chain_width = 60
 single_line_if_else_max_width = 50
 single_line_let_else_max_width = 50
 wrap_comments = false
+comment_urls_on_own_line = false
+comment_reflow_blockquotes = false
+prefer_block_comments_for_long_code = false
+block_comment_min_lines = 4
 format_code_in_doc_comments = false
+doc_comment_include_private = true
+doc_comment_preserve_prose = false
+doc_comment_fence_indented_code_blocks = false
 doc_comment_code_block_width = 100
+doc_comment_code_block_languages = ["rust"]
+doc_comment_code_block_skip_languages = []
 comment_width = 80
 normalize_comments = false
+comment_preserve_sentence_spacing = false
+comment_collapse_blank_lines = false
+max_empty_lines_in_doc_comments = 1
+comment_only_lines_in_fn = "Preserve"
 normalize_doc_attributes = false
+doc_comment_thematic_break = "Preserve"
+doc_comment_hard_break = "Preserve"
+doc_comment_emphasis_marker = "Preserve"
+doc_comment_trim_leading_blank_lines = false
+doc_comment_trailing_blank_lines = "Preserve"
+doc_comment_remove_unused_link_definitions = false
+doc_comment_sort_link_definitions = false
+doc_comment_sort_footnote_definitions = false
+markdown_normalize_dashes = false
 format_strings = false
+string_break_preference = "Whitespace"
+break_long_code_spans = false
+break_long_words = false
+normalize_line_endings_in_strings = false
+coerce_strings_to_byte_strings = false
 format_macro_matchers = false
 format_macro_bodies = true
 skip_macro_invocations = []
+rustfmt_skip_pragma_patterns = ["rustfmt::skip"]
+format_attribute_args = []
 hex_literal_case = "Preserve"
+allow_mixed_separator_in_numbers = true
 empty_item_single_line = true
 struct_lit_single_line = true
 fn_single_line = false
 where_single_line = false
+force_where_clause_always = false
+force_where_clause_never = false
+type_alias_style = "Preserve"
+const_generics_style = "Preserve"
 imports_indent = "Block"
 imports_layout = "Mixed"
 imports_granularity = "Preserve"
 group_imports = "Preserve"
+imports_group_blank_line_threshold = 1
+use_glob_imports = "Ignore"
 reorder_imports = true
+reorder_imports_by_visibility = false
 reorder_modules = true
 reorder_impl_items = false
+group_consecutive_impl_blocks = false
 type_punctuation_density = "Wide"
+type_punctuation_in_generics = "Spaced"
 space_before_colon = false
 space_after_colon = true
 spaces_around_ranges = false
@@ -664,7 +974,12 @@ binop_separator = "Front"
 remove_nested_parens = true
 combine_control_expr = true
 short_array_element_width_threshold = 10
+array_layout = "AutoCompact"
+tuple_layout = "AutoCompact"
+tuple_tall_threshold = 3
 overflow_delimited_expr = false
+max_inline_nesting_depth = 0
+inline_nesting_ignore_wrappers = true
 struct_field_align_threshold = 0
 enum_discrim_align_threshold = 0
 match_arm_blocks = true
@@ -680,10 +995,12 @@ blank_lines_upper_bound = 1
 blank_lines_lower_bound = 0
 edition = "2015"
 version = "One"
+format_version_stamps = false
 inline_attribute_width = 0
 format_generated_files = true
 generated_marker_line_search_limit = 5
 merge_derives = true
+sort_lint_attributes = false
 use_try_shorthand = false
 use_field_init_shorthand = false
 force_explicit_abi = true
@@ -695,8 +1012,10 @@ disable_all_formatting = false
 skip_children = false
 show_parse_errors = true
 error_on_line_overflow = false
+forbid_long_lines = false
 error_on_unformatted = false
 ignore = []
+diff_tool_max_files = 10
 emit_mode = "Files"
 make_backup = false
 "#,
@@ -1065,4 +1384,24 @@ make_backup = false
             ])
         );
     }
+
+    #[test]
+    fn test_override_rustfmt_skip_pragma_patterns() {
+        let mut config = Config::default();
+        config.override_value("rustfmt_skip_pragma_patterns", r#"["nofmt"]"#);
+        assert_eq!(
+            config.rustfmt_skip_pragma_patterns(),
+            SkipPragmas(vec!["nofmt".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_override_format_attribute_args() {
+        let mut config = Config::default();
+        config.override_value("format_attribute_args", r#"["serde", "arg"]"#);
+        assert_eq!(
+            config.format_attribute_args(),
+            AttributeNames(vec!["serde".to_owned(), "arg".to_owned()])
+        );
+    }
 }
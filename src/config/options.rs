@@ -26,6 +26,24 @@ pub enum NewlineStyle {
     Native,
 }
 
+/// What to do with the lines of a function body that only hold a `//` comment.
+#[config_type]
+pub enum CommentOnlyLines {
+    /// Keep the comments.
+    Preserve,
+    /// Remove the comments, e.g. to get rid of commented-out code.
+    Strip,
+}
+
+/// How the line endings inside multi-line string literals are handled.
+#[config_type]
+pub enum StringLineEndings {
+    /// Keep the line endings of string literals as they are.
+    Preserve,
+    /// Convert the line endings of string literals to the `newline_style` of the file.
+    Normalize,
+}
+
 #[config_type]
 /// Where to put the opening brace of items (`fn`, `impl`, etc.).
 pub enum BraceStyle {
@@ -49,6 +67,17 @@ pub enum ControlBraceStyle {
     AlwaysNextLine,
 }
 
+#[config_type]
+/// Where to put the where clause of a type alias that has a type on the right of `=`.
+pub enum TypeAliasStyle {
+    /// After the type, e.g. `type Foo<T> = Bar<T> where T: Baz;`.
+    EqBound,
+    /// Before the `=`, e.g. `type Foo<T> where T: Baz = Bar<T>;`.
+    WhereBound,
+    /// Keep the where clause where it is.
+    Preserve,
+}
+
 #[config_type]
 /// How to indent.
 pub enum IndentStyle {
@@ -71,6 +100,28 @@ pub enum Density {
     Vertical,
 }
 
+#[config_type]
+/// How to lay out the elements of an array literal that doesn't fit on one line.
+pub enum ArrayLayout {
+    /// Put short, simple elements several to a line, and every other element on its own line.
+    AutoCompact,
+    /// Put every element on its own line.
+    Tall,
+    /// Fit as many elements on each line as possible, unless one of them spans several lines
+    /// or has a comment.
+    Compressed,
+}
+
+#[config_type]
+/// How to lay out the elements of tuple expressions and patterns.
+pub enum TupleLayout {
+    /// Lay out tuples like the arguments of function calls.
+    AutoCompact,
+    /// Put every element on its own line in tuples with more than `tuple_tall_threshold`
+    /// elements.
+    Tall,
+}
+
 #[config_type]
 /// Spacing around type combinators.
 pub enum TypeDensity {
@@ -80,6 +131,17 @@ pub enum TypeDensity {
     Wide,
 }
 
+#[config_type]
+/// Spacing around `+` in the bounds of generic parameters.
+pub enum GenericsPunctuation {
+    /// Spaces around " + "
+    Spaced,
+    /// No spaces around "+"
+    Compact,
+    /// Keep the spacing of the original source
+    Preserve,
+}
+
 #[config_type]
 /// Heuristic settings that can be used to simply
 /// the configuration of the granular width configurations
@@ -144,6 +206,77 @@ pub enum HexLiteralCase {
     Lower,
 }
 
+/// Whether rustfmt should spell out the defaults of const generic parameters in types.
+#[config_type]
+pub enum ConstGenericsStyle {
+    /// Add the defaults of the trailing const generic parameters that are left out
+    Explicit,
+    /// Remove the trailing const generic arguments that are the same as their default
+    Elided,
+    /// Leave const generic arguments as-is
+    Preserve,
+}
+
+/// Which marker rustfmt should use for thematic breaks in doc comments.
+#[config_type]
+pub enum ThematicBreakStyle {
+    /// Leave thematic breaks as-is
+    Preserve,
+    /// Use `---`
+    Dash,
+    /// Use `***`
+    Asterisk,
+    /// Use `___`
+    Underscore,
+}
+
+/// Where rustfmt should prefer to break long strings and comments.
+#[config_type]
+pub enum BreakPreference {
+    /// Break at the last whitespace that fits, falling back to punctuation
+    Whitespace,
+    /// Break after the last punctuation that fits, falling back to whitespace
+    Punctuation,
+    /// Break at whichever whitespace or punctuation fills the line the most
+    Widest,
+    /// Break at the last whitespace after the end of a sentence that fits, falling back to
+    /// whitespace
+    Sentence,
+}
+
+/// Which marker rustfmt should use for hard line breaks in doc comments.
+#[config_type]
+pub enum HardBreakStyle {
+    /// Leave hard line breaks as-is
+    Preserve,
+    /// End the line with two spaces
+    Spaces,
+    /// End the line with a backslash
+    Backslash,
+}
+
+/// Which delimiter rustfmt should use for emphasis and strong emphasis in doc comments.
+#[config_type]
+pub enum EmphasisMarker {
+    /// Leave emphasis delimiters as-is
+    Preserve,
+    /// Use `*emphasis*` and `**strong emphasis**`
+    Asterisk,
+    /// Use `_emphasis_` and `__strong emphasis__`
+    Underscore,
+}
+
+/// How severely rustfmt should report a construct it is asked to flag.
+#[config_type]
+pub enum ReportLevel {
+    /// Emit a warning, but don't change the exit status.
+    Warn,
+    /// Emit an error and fail the run.
+    Error,
+    /// Don't report anything.
+    Ignore,
+}
+
 #[config_type]
 pub enum ReportTactic {
     Always,
@@ -175,6 +308,17 @@ pub enum EmitMode {
     Diff,
 }
 
+/// How to report the number of reformatted lines once a `--check` run has finished.
+#[config_type]
+pub enum SummaryFormat {
+    /// Don't print a summary.
+    Off,
+    /// Print the count for each file, followed by the total, as plain text.
+    Text,
+    /// Print the counts and the total as a single JSON object.
+    Json,
+}
+
 /// Client-preference for coloured output.
 #[config_type]
 pub enum Color {
@@ -409,6 +553,196 @@ impl FromStr for IgnoreList {
     }
 }
 
+/// A list of attribute names, like `serde` or `arg`.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct AttributeNames(pub Vec<String>);
+
+impl AttributeNames {
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.iter().any(|n| n == name)
+    }
+}
+
+impl fmt::Display for AttributeNames {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.iter().format(", "))
+    }
+}
+
+// This impl is needed for `Config::override_value` to work for use in tests.
+impl FromStr for AttributeNames {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s).map(AttributeNames)
+    }
+}
+
+/// A list of the languages of code blocks, like `rust`.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct CodeBlockLanguages(pub Vec<String>);
+
+impl CodeBlockLanguages {
+    /// Returns `true` if `language` is in the list, in any case.
+    pub fn contains(&self, language: &str) -> bool {
+        self.0.iter().any(|l| l.eq_ignore_ascii_case(language))
+    }
+}
+
+impl Default for CodeBlockLanguages {
+    fn default() -> CodeBlockLanguages {
+        CodeBlockLanguages(vec!["rust".to_owned()])
+    }
+}
+
+impl fmt::Display for CodeBlockLanguages {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.iter().format(", "))
+    }
+}
+
+// This impl is needed for `Config::override_value` to work for use in tests.
+impl FromStr for CodeBlockLanguages {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s).map(CodeBlockLanguages)
+    }
+}
+
+/// The comments that mark the next item or statement as not to be formatted, like `rustfmt::skip`.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct SkipPragmas(pub Vec<String>);
+
+impl SkipPragmas {
+    /// Returns `true` if `comment` is one of the pragmas, once its delimiters are removed.
+    pub fn matches(&self, comment: &str) -> bool {
+        let text = if let Some(text) = comment.strip_prefix("//") {
+            text
+        } else if let Some(text) = comment.strip_prefix("/*") {
+            match text.strip_suffix("*/") {
+                Some(text) => text,
+                None => return false,
+            }
+        } else {
+            return false;
+        };
+        let text = text.trim();
+        self.0.iter().any(|pragma| pragma == text)
+    }
+}
+
+impl Default for SkipPragmas {
+    fn default() -> SkipPragmas {
+        SkipPragmas(vec!["rustfmt::skip".to_owned()])
+    }
+}
+
+impl fmt::Display for SkipPragmas {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.iter().format(", "))
+    }
+}
+
+// This impl is needed for `Config::override_value` to work for use in tests.
+impl FromStr for SkipPragmas {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s).map(SkipPragmas)
+    }
+}
+
+/// The number of blank lines doc comments end with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TrailingBlankLines {
+    /// Leave the blank lines at the end of doc comments as they are.
+    #[default]
+    Preserve,
+    /// End doc comments with exactly this many blank lines.
+    Exactly(usize),
+}
+
+impl fmt::Display for TrailingBlankLines {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrailingBlankLines::Preserve => write!(f, "Preserve"),
+            TrailingBlankLines::Exactly(count) => write!(f, "{count}"),
+        }
+    }
+}
+
+impl FromStr for TrailingBlankLines {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("preserve") {
+            return Ok(TrailingBlankLines::Preserve);
+        }
+        s.parse()
+            .map(TrailingBlankLines::Exactly)
+            .map_err(|_| "expected `Preserve` or a non-negative integer")
+    }
+}
+
+impl Serialize for TrailingBlankLines {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            TrailingBlankLines::Preserve => serializer.serialize_str("Preserve"),
+            TrailingBlankLines::Exactly(count) => serializer.serialize_u64(count as u64),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TrailingBlankLines {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct TrailingBlankLinesVisitor;
+        impl<'v> Visitor<'v> for TrailingBlankLinesVisitor {
+            type Value = TrailingBlankLines;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("`Preserve` or a non-negative integer")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                if value.eq_ignore_ascii_case("preserve") {
+                    Ok(TrailingBlankLines::Preserve)
+                } else {
+                    Err(E::invalid_value(serde::de::Unexpected::Str(value), &self))
+                }
+            }
+
+            fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                usize::try_from(value)
+                    .map(TrailingBlankLines::Exactly)
+                    .map_err(|_| E::invalid_value(serde::de::Unexpected::Unsigned(value), &self))
+            }
+
+            fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                usize::try_from(value)
+                    .map(TrailingBlankLines::Exactly)
+                    .map_err(|_| E::invalid_value(serde::de::Unexpected::Signed(value), &self))
+            }
+        }
+        deserializer.deserialize_any(TrailingBlankLinesVisitor)
+    }
+}
+
 /// Maps client-supplied options to Rustfmt's internals, mostly overriding
 /// values in a config with values from the command line.
 pub trait CliOptions {
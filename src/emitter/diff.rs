@@ -1,18 +1,167 @@
 use super::*;
-use crate::config::Config;
-use crate::rustfmt_diff::{make_diff, print_diff};
+use crate::config::{Config, SummaryFormat};
+use crate::rustfmt_diff::{make_diff, print_diff, DiffLine, Mismatch};
+use serde::Serialize;
+use serde_json::to_string as to_json_string;
+use std::cmp::Ordering;
+use std::fs::{DirBuilder, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fs, process};
 
 pub(crate) struct DiffEmitter {
     config: Config,
+    summary: DiffSummary,
+    /// The number of mismatched files handed to the diff tool so far.
+    diff_tool_files: usize,
+}
+
+/// The number of changed lines for each mismatched file. Only the counts are kept, so the
+/// memory used doesn't grow with the size of the diffs.
+#[derive(Debug, Default, Serialize)]
+struct DiffSummary {
+    files: Vec<FileSummary>,
+    total: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct FileSummary {
+    name: String,
+    lines: usize,
 }
 
 impl DiffEmitter {
     pub(crate) fn new(config: Config) -> Self {
-        Self { config }
+        Self {
+            config,
+            summary: DiffSummary::default(),
+            diff_tool_files: 0,
+        }
     }
+
+    /// Writes the original and the formatted text to temporary files, opens them with the
+    /// `diff_command` and waits for it to exit. The temporary files are removed afterwards.
+    ///
+    /// The command is split at whitespace, quoting its arguments is not supported.
+    fn run_diff_tool(
+        &self,
+        filename: &FileName,
+        original_text: &str,
+        formatted_text: &str,
+    ) -> Result<(), io::Error> {
+        let command = self.config.diff_command();
+        if command.contains(['"', '\'']) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the diff tool command is split at whitespace, quotes are not supported",
+            ));
+        }
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "`--diff-tool` needs a command, as in `--diff-tool=CMD`",
+            )
+        })?;
+
+        let name = match filename {
+            FileName::Real(path) => path
+                .file_name()
+                .map_or_else(|| "file".into(), |name| name.to_string_lossy()),
+            FileName::Stdin => "stdin".into(),
+        };
+        let dir = create_private_temp_dir()?;
+        let (old, new) = (
+            dir.join(format!("old-{name}")),
+            dir.join(format!("new-{name}")),
+        );
+
+        let result = write_new_file(&old, original_text)
+            .and_then(|_| write_new_file(&new, formatted_text))
+            .and_then(|_| {
+                let (old, new) = (old.to_string_lossy(), new.to_string_lossy());
+                let mut args: Vec<String> = words
+                    .map(|word| word.replace("{old}", &old).replace("{new}", &new))
+                    .collect();
+                if !command.contains("{old}") && !command.contains("{new}") {
+                    args.extend([old.into_owned(), new.into_owned()]);
+                }
+                // The exit status is ignored, diff tools commonly report the files differ.
+                Command::new(program).args(args).status()
+            });
+        let _ = fs::remove_dir_all(&dir);
+        result.map(|_| ())
+    }
+
+    fn add_to_summary(&mut self, filename: &FileName, mismatch: &[Mismatch]) {
+        // Both the removed and the added lines count as changed.
+        let lines = mismatch
+            .iter()
+            .flat_map(|m| &m.lines)
+            .filter(|line| !matches!(line, DiffLine::Context(_)))
+            .count();
+        self.summary.files.push(FileSummary {
+            name: filename.to_string(),
+            lines,
+        });
+        self.summary.total += lines;
+    }
+}
+
+/// Creates a new directory in the system temp dir that only the current user can access, so that
+/// the files in it can't be replaced or read by others. An existing directory or symlink of the
+/// same name is never reused.
+fn create_private_temp_dir() -> Result<PathBuf, io::Error> {
+    let mut builder = DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.subsec_nanos());
+    for attempt in 0..100 {
+        let dir = env::temp_dir().join(format!(
+            "rustfmt-diff-tool-{}-{nanos}-{attempt}",
+            process::id()
+        ));
+        match builder.create(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(error),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "could not create a temporary directory for the diff tool",
+    ))
+}
+
+/// Writes `text` to a file at `path`, which must not exist yet.
+fn write_new_file(path: &Path, text: &str) -> Result<(), io::Error> {
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?
+        .write_all(text.as_bytes())
 }
 
 impl Emitter for DiffEmitter {
+    fn emit_footer(&self, output: &mut dyn Write) -> Result<(), io::Error> {
+        match self.config.summary() {
+            SummaryFormat::Off => {}
+            SummaryFormat::Text => {
+                for file in &self.summary.files {
+                    writeln!(output, "{}: {} lines changed", file.name, file.lines)?;
+                }
+                writeln!(output, "Total: {} lines changed", self.summary.total)?;
+            }
+            SummaryFormat::Json => {
+                writeln!(output, "{}", &to_json_string(&self.summary)?)?;
+            }
+        }
+        Ok(())
+    }
+
     fn emit_formatted_file(
         &mut self,
         output: &mut dyn Write,
@@ -27,6 +176,9 @@ impl Emitter for DiffEmitter {
         let has_diff = !mismatch.is_empty();
 
         if has_diff {
+            if self.config.summary() != SummaryFormat::Off {
+                self.add_to_summary(filename, &mismatch);
+            }
             if self.config.print_misformatted_file_names() {
                 writeln!(output, "{filename}")?;
             } else {
@@ -36,6 +188,31 @@ impl Emitter for DiffEmitter {
                     &self.config,
                 );
             }
+            if self.config.diff_tool() {
+                let max_files = self.config.diff_tool_max_files();
+                match self.diff_tool_files.cmp(&max_files) {
+                    Ordering::Less => {
+                        output.flush()?;
+                        self.diff_tool_files += 1;
+                        self.run_diff_tool(filename, original_text, formatted_text)
+                            .map_err(|error| {
+                                io::Error::new(
+                                    error.kind(),
+                                    format!("failed to open the diff tool: {error}"),
+                                )
+                            })?;
+                    }
+                    Ordering::Equal => {
+                        writeln!(
+                            output,
+                            "Not opening more than {max_files} files in the diff tool \
+                             (see `diff_tool_max_files` option)"
+                        )?;
+                        self.diff_tool_files += 1;
+                    }
+                    Ordering::Greater => {}
+                }
+            }
         } else if original_text != formatted_text {
             // This occurs when the only difference between the original and formatted values
             // is the newline style. This happens because The make_diff function compares the
@@ -132,4 +309,87 @@ mod tests {
             String::from("Incorrect newline style in src/lib.rs\n")
         );
     }
+
+    fn emit_summary_for_two_files(summary: SummaryFormat) -> String {
+        let mut config = Config::default();
+        config.set().summary(summary);
+        config.set().print_misformatted_file_names(true);
+        let mut emitter = DiffEmitter::new(config);
+        let mut writer = Vec::new();
+        let _ = emitter
+            .emit_formatted_file(
+                &mut writer,
+                FormattedFile {
+                    filename: &FileName::Real(PathBuf::from("src/bin.rs")),
+                    original_text: "fn main() {\nprintln!(\"Hello\");\n}\n",
+                    formatted_text: "fn main() {\n    println!(\"Hello\");\n}\n",
+                },
+            )
+            .unwrap();
+        let _ = emitter
+            .emit_formatted_file(
+                &mut writer,
+                FormattedFile {
+                    filename: &FileName::Real(PathBuf::from("src/lib.rs")),
+                    original_text: "fn a() {\nfoo();\nbar();\n}\n",
+                    formatted_text: "fn a() {\n    foo();\n    bar();\n}\n",
+                },
+            )
+            .unwrap();
+        let mut footer = Vec::new();
+        emitter.emit_footer(&mut footer).unwrap();
+        String::from_utf8(footer).unwrap()
+    }
+
+    #[test]
+    fn prints_text_summary_of_changed_lines() {
+        assert_eq!(
+            emit_summary_for_two_files(SummaryFormat::Text),
+            "src/bin.rs: 2 lines changed\n\
+             src/lib.rs: 4 lines changed\n\
+             Total: 6 lines changed\n",
+        );
+    }
+
+    #[test]
+    fn prints_json_summary_of_changed_lines() {
+        let files = r#"[{"name":"src/bin.rs","lines":2},{"name":"src/lib.rs","lines":4}]"#;
+        assert_eq!(
+            emit_summary_for_two_files(SummaryFormat::Json),
+            format!("{{\"files\":{files},\"total\":6}}\n"),
+        );
+    }
+
+    #[test]
+    fn prints_zero_total_when_no_files_reformatted() {
+        let mut config = Config::default();
+        config.set().summary(SummaryFormat::Text);
+        let mut emitter = DiffEmitter::new(config);
+        let mut writer = Vec::new();
+        let _ = emitter
+            .emit_formatted_file(
+                &mut writer,
+                FormattedFile {
+                    filename: &FileName::Real(PathBuf::from("src/lib.rs")),
+                    original_text: "fn empty() {}\n",
+                    formatted_text: "fn empty() {}\n",
+                },
+            )
+            .unwrap();
+        emitter.emit_footer(&mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            "Total: 0 lines changed\n"
+        );
+
+        let mut config = Config::default();
+        config.set().summary(SummaryFormat::Json);
+        let emitter = DiffEmitter::new(config);
+        let mut writer = Vec::new();
+        emitter.emit_footer(&mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            "{\"files\":[],\"total\":0}\n"
+        );
+    }
 }
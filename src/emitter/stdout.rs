@@ -1,14 +1,102 @@
+use std::io::IsTerminal;
+
 use super::*;
-use crate::config::Verbosity;
+use crate::comment::{CharClasses, FullCodeCharKind};
+use crate::config::{Color, Config, Verbosity};
+use crate::utils::unicode_str_width;
 
 #[derive(Debug)]
 pub(crate) struct StdoutEmitter {
     verbosity: Verbosity,
+    /// Set when running with `--show-ruler`.
+    ruler: Option<Ruler>,
+}
+
+/// Marks `max_width` above each emitted file and keeps count of the lines exceeding it.
+#[derive(Debug)]
+struct Ruler {
+    max_width: usize,
+    use_color: bool,
+    overlong_strings: usize,
+    overlong_comments: usize,
+    overlong_code: usize,
 }
 
 impl StdoutEmitter {
-    pub(crate) fn new(verbosity: Verbosity) -> Self {
-        Self { verbosity }
+    pub(crate) fn new(config: &Config) -> Self {
+        let ruler = config.show_ruler().then(|| Ruler {
+            max_width: config.max_width(),
+            use_color: match config.color() {
+                Color::Always => true,
+                Color::Auto => io::stdout().is_terminal(),
+                Color::Never => false,
+            },
+            overlong_strings: 0,
+            overlong_comments: 0,
+            overlong_code: 0,
+        });
+        Self {
+            verbosity: config.verbose(),
+            ruler,
+        }
+    }
+}
+
+impl Ruler {
+    /// Returns a line like `----+----1----+----2` that is `max_width` columns wide.
+    fn line(&self) -> String {
+        (1..=self.max_width)
+            .map(|column| match column % 10 {
+                0 => char::from_digit((column / 10 % 10) as u32, 10).unwrap_or('|'),
+                5 => '+',
+                _ => '-',
+            })
+            .collect()
+    }
+
+    fn write_text(&mut self, output: &mut dyn Write, text: &str) -> Result<(), io::Error> {
+        let mut line_start = 0;
+        // The offset into the current line of its first character past `max_width`.
+        let mut overflow = None;
+        for (kind, (i, c)) in CharClasses::new(text.char_indices()) {
+            if c == '\n' {
+                self.write_line(output, &text[line_start..i], overflow.take())?;
+                writeln!(output)?;
+                line_start = i + 1;
+                continue;
+            }
+            // The line is measured the same way as everywhere else in rustfmt.
+            if overflow.is_none()
+                && unicode_str_width(&text[line_start..i + c.len_utf8()]) > self.max_width
+            {
+                overflow = Some((i - line_start, kind));
+            }
+        }
+        self.write_line(output, &text[line_start..], overflow)
+    }
+
+    fn write_line(
+        &mut self,
+        output: &mut dyn Write,
+        line: &str,
+        overflow: Option<(usize, FullCodeCharKind)>,
+    ) -> Result<(), io::Error> {
+        let Some((offset, kind)) = overflow else {
+            return write!(output, "{line}");
+        };
+        if kind.is_string() || kind == FullCodeCharKind::EndString {
+            self.overlong_strings += 1;
+        } else if kind.is_comment() {
+            self.overlong_comments += 1;
+        } else {
+            self.overlong_code += 1;
+        }
+        if self.use_color {
+            let (fits, overflows) = line.split_at(offset);
+            write!(output, "{fits}\x1b[1;31m{overflows}\x1b[0m")
+        } else {
+            write!(output, "{line}")
+        }
     }
 }
 
@@ -25,7 +113,29 @@ impl Emitter for StdoutEmitter {
         if self.verbosity != Verbosity::Quiet {
             writeln!(output, "{filename}:\n")?;
         }
-        write!(output, "{formatted_text}")?;
+        match self.ruler {
+            Some(ref mut ruler) => {
+                writeln!(output, "{}", ruler.line())?;
+                ruler.write_text(output, formatted_text)?;
+            }
+            None => write!(output, "{formatted_text}")?,
+        }
         Ok(EmitterResult::default())
     }
+
+    fn emit_footer(&self, output: &mut dyn Write) -> Result<(), io::Error> {
+        if let Some(ref ruler) = self.ruler {
+            let total = ruler.overlong_strings + ruler.overlong_comments + ruler.overlong_code;
+            writeln!(
+                output,
+                "Lines exceeding max_width ({}): {} (string: {}, comment: {}, code: {})",
+                ruler.max_width,
+                total,
+                ruler.overlong_strings,
+                ruler.overlong_comments,
+                ruler.overlong_code,
+            )?;
+        }
+        Ok(())
+    }
 }
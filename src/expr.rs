@@ -1,19 +1,21 @@
 use std::borrow::Cow;
-use std::cmp::min;
+use std::cmp::{max, min};
 
 use itertools::Itertools;
 use rustc_ast::token::{Delimiter, Lit, LitKind};
 use rustc_ast::{ast, ptr, token, ForLoopKind, MatchKind};
+use rustc_span::symbol::sym;
 use rustc_span::{BytePos, Span};
 
 use crate::chains::rewrite_chain;
 use crate::closures;
 use crate::comment::{
     combine_strs_with_missing_comments, contains_comment, recover_comment_removed, rewrite_comment,
-    rewrite_missing_comment, CharClasses, FindUncommented,
+    rewrite_missing_comment, without_stripped_comments, CharClasses, CodeCharKind,
+    CommentCodeSlices, FindUncommented,
 };
 use crate::config::lists::*;
-use crate::config::{Config, ControlBraceStyle, HexLiteralCase, IndentStyle, Version};
+use crate::config::{Config, ControlBraceStyle, HexLiteralCase, IndentStyle, TupleLayout, Version};
 use crate::lists::{
     definitive_tactic, itemize_list, shape_for_tactic, struct_lit_formatting, struct_lit_shape,
     struct_lit_tactic, write_list, ListFormatting, Separator,
@@ -129,9 +131,14 @@ pub(crate) fn format_expr(
                 shape,
             )
         }
-        ast::ExprKind::Tup(ref items) => {
-            rewrite_tuple(context, items.iter(), expr.span, shape, items.len() == 1)
-        }
+        ast::ExprKind::Tup(ref items) => rewrite_tuple(
+            context,
+            items.iter(),
+            expr.span,
+            shape,
+            items.len() == 1,
+            is_tall_tuple(context, items.len()),
+        ),
         ast::ExprKind::Let(ref pat, ref expr, _span, _) => rewrite_let(context, shape, pat, expr),
         ast::ExprKind::If(..)
         | ast::ExprKind::ForLoop { .. }
@@ -268,7 +275,7 @@ pub(crate) fn format_expr(
         }
         ast::ExprKind::Repeat(ref expr, ref repeats) => rewrite_pair(
             &**expr,
-            &*repeats.value,
+            repeats,
             PairParts::new("[", "; ", "]"),
             context,
             shape,
@@ -814,6 +821,10 @@ impl<'a> ControlFlow<'a> {
     ) -> Option<String> {
         assert!(self.allow_single_line);
         let else_block = self.else_block?;
+        // Line comments around the `else` keyword need lines of their own.
+        if has_line_breaking_comment(context.snippet(self.block.span.between(else_block.span))) {
+            return None;
+        }
         let fixed_cost = self.keyword.len() + "  {  } else {  }".len();
 
         if let ast::ExprKind::Block(ref else_node, _) = else_block.kind {
@@ -864,6 +875,14 @@ fn last_line_offsetted(start_column: usize, pat_str: &str) -> bool {
     leading_whitespaces > start_column
 }
 
+/// Returns `true` if `snippet` has a comment that ends its line, i.e. a line comment or a comment
+/// spanning several lines.
+fn has_line_breaking_comment(snippet: &str) -> bool {
+    CommentCodeSlices::new(snippet).any(|(kind, _, slice)| {
+        kind == CodeCharKind::Comment && (slice.starts_with("//") || slice.contains('\n'))
+    })
+}
+
 impl<'a> ControlFlow<'a> {
     fn rewrite_pat_expr(
         &self,
@@ -1185,7 +1204,10 @@ fn extract_comment(span: Span, context: &RewriteContext<'_>, shape: Shape) -> Op
 }
 
 pub(crate) fn block_contains_comment(context: &RewriteContext<'_>, block: &ast::Block) -> bool {
-    contains_comment(context.snippet(block.span))
+    contains_comment(&without_stripped_comments(
+        context.snippet(block.span),
+        context,
+    ))
 }
 
 // Checks that a block contains no statements, an expression and no comments or
@@ -1249,7 +1271,8 @@ pub(crate) fn rewrite_literal(
     shape: Shape,
 ) -> Option<String> {
     match token_lit.kind {
-        token::LitKind::Str => rewrite_string_lit(context, span, shape),
+        token::LitKind::Str => rewrite_string_lit(context, span, shape, "\""),
+        token::LitKind::CStr => rewrite_string_lit(context, span, shape, "c\""),
         token::LitKind::Integer => rewrite_int_lit(context, token_lit, span, shape),
         _ => wrap_str(
             context.snippet(span).to_owned(),
@@ -1259,8 +1282,20 @@ pub(crate) fn rewrite_literal(
     }
 }
 
-fn rewrite_string_lit(context: &RewriteContext<'_>, span: Span, shape: Shape) -> Option<String> {
+/// Rewrites a string literal that starts with `opener`, i.e. `"` or `c"` for C strings.
+fn rewrite_string_lit(
+    context: &RewriteContext<'_>,
+    span: Span,
+    shape: Shape,
+    opener: &str,
+) -> Option<String> {
     let string_lit = context.snippet(span);
+    let string_lit = if context.config.normalize_line_endings_in_strings() {
+        normalize_line_ending_escapes(string_lit)
+    } else {
+        Cow::Borrowed(string_lit)
+    };
+    let string_lit = &*string_lit;
 
     if !context.config.format_strings() {
         if string_lit
@@ -1275,16 +1310,154 @@ fn rewrite_string_lit(context: &RewriteContext<'_>, span: Span, shape: Shape) ->
         }
     }
 
-    // Remove the quote characters.
-    let str_lit = &string_lit[1..string_lit.len() - 1];
+    // Remove the opener and the closing quote.
+    let str_lit = &string_lit[opener.len()..string_lit.len() - 1];
 
     rewrite_string(
         str_lit,
-        &StringFormat::new(shape.visual_indent(0), context.config),
-        shape.width.saturating_sub(2),
+        &StringFormat {
+            opener,
+            ..StringFormat::new(shape.visual_indent(0), context.config)
+        },
+        shape.width.saturating_sub(opener.len() + 1),
     )
 }
 
+/// The `"...".as_bytes()` or `"...".as_bytes().to_vec()` initializer of a `&[u8]` or `Vec<u8>`
+/// binding, written with a byte string literal as requested by `coerce_strings_to_byte_strings`.
+pub(crate) struct ByteStringInit {
+    byte_string_lit: String,
+    suffix: &'static str,
+}
+
+impl ByteStringInit {
+    /// Returns the byte string form of `init`, if it initializes a binding of type `ty` and its
+    /// string literal only contains ASCII characters.
+    pub(crate) fn new(
+        context: &RewriteContext<'_>,
+        ty: &ast::Ty,
+        init: &ast::Expr,
+    ) -> Option<ByteStringInit> {
+        if !context.config.coerce_strings_to_byte_strings() {
+            return None;
+        }
+        let (as_bytes, suffix) = match ty.kind {
+            ast::TyKind::Ref(_, ast::MutTy { ref ty, mutbl })
+                if mutbl == ast::Mutability::Not
+                    && matches!(ty.kind, ast::TyKind::Slice(ref elem) if is_u8(elem)) =>
+            {
+                (init, "")
+            }
+            ast::TyKind::Path(None, ref path) if is_vec_of_u8(path) => {
+                (method_call_receiver(init, "to_vec")?, ".to_vec()")
+            }
+            _ => return None,
+        };
+        let lit_expr = method_call_receiver(as_bytes, "as_bytes")?;
+        let ast::ExprKind::Lit(token_lit) = lit_expr.kind else {
+            return None;
+        };
+        let string_lit = context.snippet(lit_expr.span);
+        let is_ascii = match token_lit.kind {
+            token::LitKind::Str => {
+                token_lit.symbol.as_str().is_ascii() && !string_lit.contains("\\u")
+            }
+            token::LitKind::StrRaw(_) => token_lit.symbol.as_str().is_ascii(),
+            _ => false,
+        };
+        if !is_ascii || token_lit.suffix.is_some() || contains_comment(context.snippet(init.span)) {
+            return None;
+        }
+        Some(ByteStringInit {
+            byte_string_lit: format!("b{string_lit}"),
+            suffix,
+        })
+    }
+}
+
+impl Rewrite for ByteStringInit {
+    fn rewrite(&self, context: &RewriteContext<'_>, shape: Shape) -> Option<String> {
+        wrap_str(
+            format!("{}{}", self.byte_string_lit, self.suffix),
+            context.config.max_width(),
+            shape,
+        )
+    }
+}
+
+/// Returns the receiver of `expr` if it is a call of the method `name` without arguments.
+fn method_call_receiver<'a>(expr: &'a ast::Expr, name: &str) -> Option<&'a ast::Expr> {
+    match expr.kind {
+        ast::ExprKind::MethodCall(ref call)
+            if call.seg.ident.name.as_str() == name
+                && call.seg.args.is_none()
+                && call.args.is_empty() =>
+        {
+            Some(&call.receiver)
+        }
+        _ => None,
+    }
+}
+
+/// Returns `true` if `ty` is the `u8` type.
+fn is_u8(ty: &ast::Ty) -> bool {
+    match ty.kind {
+        ast::TyKind::Path(None, ref path) => {
+            path.segments.len() == 1
+                && path.segments[0].ident.name == sym::u8
+                && path.segments[0].args.is_none()
+        }
+        _ => false,
+    }
+}
+
+/// Returns `true` if `path` is `Vec<u8>`, possibly qualified.
+fn is_vec_of_u8(path: &ast::Path) -> bool {
+    let Some(segment) = path.segments.last() else {
+        return false;
+    };
+    if segment.ident.name != sym::Vec {
+        return false;
+    }
+    match segment.args.as_deref() {
+        Some(ast::GenericArgs::AngleBracketed(args)) => matches!(
+            args.args.as_slice(),
+            [ast::AngleBracketedArg::Arg(ast::GenericArg::Type(ty))] if is_u8(ty)
+        ),
+        _ => false,
+    }
+}
+
+/// Replaces the `\r\n` and `\r` escapes in the snippet of a string literal with `\n`.
+fn normalize_line_ending_escapes(string_lit: &str) -> Cow<'_, str> {
+    if !string_lit.contains("\\r") {
+        return Cow::Borrowed(string_lit);
+    }
+    let mut result = String::with_capacity(string_lit.len());
+    let mut chars = string_lit.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('r') => {
+                if chars.as_str().starts_with("\\n") {
+                    chars.nth(1);
+                }
+                result.push_str("\\n");
+            }
+            // Any other escape, including `\\`, is kept as is.
+            Some(escaped) => {
+                result.push(c);
+                result.push(escaped);
+            }
+            None => result.push(c),
+        }
+    }
+    Cow::Owned(result)
+}
+
 fn rewrite_int_lit(
     context: &RewriteContext<'_>,
     token_lit: token::Lit,
@@ -1338,6 +1511,24 @@ pub(crate) fn rewrite_call(
     span: Span,
     shape: Shape,
 ) -> Option<String> {
+    let max_depth = context.config.max_inline_nesting_depth();
+    if max_depth > 0
+        && !context.inside_deeply_nested_call.get()
+        && call_nesting_depth(context, callee, args) > max_depth
+    {
+        // Only the outermost call that is nested too deeply is broken up.
+        context.inside_deeply_nested_call.replace(true);
+        let result = overflow::rewrite_vertically_with_parens(
+            context,
+            callee,
+            args.iter(),
+            shape,
+            span,
+            choose_separator_tactic(context, span),
+        );
+        context.inside_deeply_nested_call.replace(false);
+        return result;
+    }
     overflow::rewrite_with_parens(
         context,
         callee,
@@ -1349,6 +1540,64 @@ pub(crate) fn rewrite_call(
     )
 }
 
+/// Returns how deeply calls, method calls and index expressions are nested in a call with the
+/// given callee and arguments, for `max_inline_nesting_depth`.
+fn call_nesting_depth(
+    context: &RewriteContext<'_>,
+    callee: &str,
+    args: &[ptr::P<ast::Expr>],
+) -> usize {
+    let args_depth = args
+        .iter()
+        .map(|arg| inline_nesting_depth(context, arg))
+        .max()
+        .unwrap_or(0);
+    // Single-argument calls to the constructor of a variant or tuple struct named by a
+    // single-segment path, like `Some(x)`, are trivial wrappers.
+    let is_wrapper = context.config.inline_nesting_ignore_wrappers()
+        && args.len() == 1
+        && callee.starts_with(|c: char| c.is_uppercase())
+        && callee.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if is_wrapper {
+        args_depth
+    } else {
+        args_depth + 1
+    }
+}
+
+fn inline_nesting_depth(context: &RewriteContext<'_>, expr: &ast::Expr) -> usize {
+    match expr.kind {
+        ast::ExprKind::Call(ref callee, ref args) => match callee.kind {
+            ast::ExprKind::Path(None, ref path)
+                if path.segments.len() == 1 && path.segments[0].args.is_none() =>
+            {
+                call_nesting_depth(context, path.segments[0].ident.as_str(), args)
+            }
+            _ => call_nesting_depth(context, "", args),
+        },
+        ast::ExprKind::MethodCall(ref call) => max(
+            inline_nesting_depth(context, &call.receiver),
+            call_nesting_depth(context, "", &call.args),
+        ),
+        ast::ExprKind::Index(ref expr, ref index, _) => max(
+            inline_nesting_depth(context, expr),
+            inline_nesting_depth(context, index) + 1,
+        ),
+        ast::ExprKind::Binary(_, ref lhs, ref rhs) => max(
+            inline_nesting_depth(context, lhs),
+            inline_nesting_depth(context, rhs),
+        ),
+        ast::ExprKind::AddrOf(_, _, ref expr)
+        | ast::ExprKind::Await(ref expr, _)
+        | ast::ExprKind::Cast(ref expr, _)
+        | ast::ExprKind::Field(ref expr, _)
+        | ast::ExprKind::Paren(ref expr)
+        | ast::ExprKind::Try(ref expr)
+        | ast::ExprKind::Unary(_, ref expr) => inline_nesting_depth(context, expr),
+        _ => 0,
+    }
+}
+
 pub(crate) fn is_simple_expr(expr: &ast::Expr) -> bool {
     match expr.kind {
         ast::ExprKind::Lit(..) => true,
@@ -1719,7 +1968,7 @@ pub(crate) fn wrap_struct_field(
     let should_vertical = context.config.indent_style() == IndentStyle::Block
         && (fields_str.contains('\n')
             || !context.config.struct_lit_single_line()
-            || fields_str.len() > one_line_width);
+            || unicode_str_width(fields_str) > one_line_width);
 
     let inner_attrs = &inner_attributes(attrs);
     if inner_attrs.is_empty() {
@@ -1807,6 +2056,7 @@ fn rewrite_tuple_in_visual_indent_style<'a, T: 'a + IntoOverflowableItem<'a>>(
     span: Span,
     shape: Shape,
     is_singleton_tuple: bool,
+    is_tall: bool,
 ) -> Option<String> {
     // In case of length 1, need a trailing comma
     debug!("rewrite_tuple_in_visual_indent_style {:?}", shape);
@@ -1835,12 +2085,16 @@ fn rewrite_tuple_in_visual_indent_style<'a, T: 'a + IntoOverflowableItem<'a>>(
         false,
     );
     let item_vec: Vec<_> = items.collect();
-    let tactic = definitive_tactic(
-        &item_vec,
-        ListTactic::HorizontalVertical,
-        Separator::Comma,
-        nested_shape.width,
-    );
+    let tactic = if is_tall {
+        DefinitiveListTactic::Vertical
+    } else {
+        definitive_tactic(
+            &item_vec,
+            ListTactic::HorizontalVertical,
+            Separator::Comma,
+            nested_shape.width,
+        )
+    };
     let fmt = ListFormatting::new(nested_shape, context.config)
         .tactic(tactic)
         .ends_with_newline(false);
@@ -1889,6 +2143,7 @@ pub(crate) fn rewrite_tuple<'a, T: 'a + IntoOverflowableItem<'a>>(
     span: Span,
     shape: Shape,
     is_singleton_tuple: bool,
+    is_tall: bool,
 ) -> Option<String> {
     debug!("rewrite_tuple {:?}", shape);
     if context.use_block_indent() {
@@ -1904,20 +2159,38 @@ pub(crate) fn rewrite_tuple<'a, T: 'a + IntoOverflowableItem<'a>>(
         } else {
             None
         };
-        overflow::rewrite_with_parens(
+        if is_tall {
+            overflow::rewrite_vertically_with_parens(context, "", items, shape, span, force_tactic)
+        } else {
+            overflow::rewrite_with_parens(
+                context,
+                "",
+                items,
+                shape,
+                span,
+                context.config.fn_call_width(),
+                force_tactic,
+            )
+        }
+    } else {
+        rewrite_tuple_in_visual_indent_style(
             context,
-            "",
             items,
-            shape,
             span,
-            context.config.fn_call_width(),
-            force_tactic,
+            shape,
+            is_singleton_tuple,
+            is_tall,
         )
-    } else {
-        rewrite_tuple_in_visual_indent_style(context, items, span, shape, is_singleton_tuple)
     }
 }
 
+/// Returns `true` if a tuple expression or pattern with `len` elements should have each element
+/// on its own line, as requested by `tuple_layout`.
+pub(crate) fn is_tall_tuple(context: &RewriteContext<'_>, len: usize) -> bool {
+    context.config.tuple_layout() == TupleLayout::Tall
+        && len > context.config.tuple_tall_threshold()
+}
+
 pub(crate) fn rewrite_unary_prefix<R: Rewrite>(
     context: &RewriteContext<'_>,
     prefix: &str,
@@ -1,5 +1,5 @@
 use crate::formatting::FormattingError;
-use crate::{ErrorKind, FormatReport};
+use crate::{ErrorKind, FormatReport, ReportLevel};
 use annotate_snippets::display_list::{DisplayList, FormatOptions};
 use annotate_snippets::snippet::{Annotation, AnnotationType, Slice, Snippet, SourceAnnotation};
 use std::fmt::{self, Display};
@@ -97,11 +97,11 @@ impl<'a> Display for FormatReportFormatter<'a> {
             }
         }
 
-        if !errors_by_file.is_empty() {
-            let label = format!(
-                "rustfmt has failed to format. See previous {} errors.",
-                self.report.warning_count()
-            );
+        // Notes, such as the edition a file was formatted with, are not failures.
+        let warning_count = self.report.warning_count();
+        if warning_count > 0 {
+            let label =
+                format!("rustfmt has failed to format. See previous {warning_count} errors.");
             let snippet = Snippet {
                 title: Some(Annotation {
                     id: None,
@@ -137,6 +137,7 @@ fn slice_annotation(error: &FormattingError) -> Option<SourceAnnotation<'_>> {
 fn error_kind_to_snippet_annotation_type(error_kind: &ErrorKind) -> AnnotationType {
     match error_kind {
         ErrorKind::LineOverflow(..)
+        | ErrorKind::ForbiddenLongLine(..)
         | ErrorKind::TrailingWhitespace
         | ErrorKind::IoError(_)
         | ErrorKind::ModuleResolutionError(_)
@@ -144,7 +145,15 @@ fn error_kind_to_snippet_annotation_type(error_kind: &ErrorKind) -> AnnotationTy
         | ErrorKind::LostComment
         | ErrorKind::BadAttr
         | ErrorKind::InvalidGlobPattern(_)
-        | ErrorKind::VersionMismatch => AnnotationType::Error,
-        ErrorKind::DeprecatedAttr => AnnotationType::Warning,
+        | ErrorKind::VersionMismatch
+        | ErrorKind::GlobImport(ReportLevel::Error)
+        | ErrorKind::MixedNumberSeparators(_)
+        | ErrorKind::FileTooLarge(..) => AnnotationType::Error,
+        ErrorKind::DeprecatedAttr
+        | ErrorKind::GlobImport(_)
+        | ErrorKind::UnformattedCodeBlock(_)
+        | ErrorKind::ChunkedFile(..) => AnnotationType::Warning,
+        ErrorKind::SkipSuggestion(_) => AnnotationType::Help,
+        ErrorKind::DetectedEdition(..) => AnnotationType::Note,
     }
 }
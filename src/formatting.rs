@@ -1,25 +1,34 @@
 // High level formatting functions.
 
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fs;
 use std::io::{self, Write};
+use std::path::Path;
 use std::time::{Duration, Instant};
 
 use rustc_ast::ast;
 use rustc_span::Span;
 
 use self::newline_style::apply_newline_style;
+pub(crate) use self::newline_style::needs_raw_input_text;
 use crate::comment::{CharClasses, FullCodeCharKind};
-use crate::config::{Config, FileName, Verbosity};
+use crate::config::{Config, Edition, FileName, Verbosity};
 use crate::formatting::generated::is_generated_file;
+pub(crate) use crate::formatting::large_file::MAX_SOURCE_LEN;
+use crate::formatting::version_stamp::{stamped_config, write_latest_stamp};
 use crate::modules::Module;
 use crate::parse::parser::{DirectoryOwnership, Parser, ParserError};
 use crate::parse::session::ParseSess;
-use crate::utils::{contains_skip, count_newlines};
-use crate::visitor::FmtVisitor;
+use crate::skip::opted_in_items_of;
+use crate::utils::{contains_skip, count_newlines, unicode_str_width};
+use crate::visitor::{test_mod_config, FmtVisitor};
 use crate::{modules, source_file, ErrorKind, FormatReport, Input, Session};
 
 mod generated;
+mod large_file;
 mod newline_style;
+mod version_stamp;
 
 // A map of the files of a crate, with their new content
 pub(crate) type SourceFile = Vec<FileRecord>;
@@ -35,6 +44,21 @@ impl<'b, T: Write + 'b> Session<'b, T> {
             return Err(ErrorKind::VersionMismatch);
         }
 
+        if !self.config.disable_all_formatting() {
+            if let Some(report) = self.format_large_file(&input, MAX_SOURCE_LEN)? {
+                return Ok(report);
+            }
+        }
+
+        if self.config.detect_edition() && !self.config.disable_all_formatting() {
+            if let Some(report) = self.format_with_detected_edition(&input, is_macro_def)? {
+                self.errors.add(&report.internal.borrow().1);
+                return Ok(report);
+            }
+            // No edition parses the input, so format it with the configured one to show the
+            // parse errors.
+        }
+
         rustc_span::create_session_if_not_set_then(self.config.edition().into(), |_| {
             if self.config.disable_all_formatting() {
                 // When the input is from stdin, echo back the input.
@@ -53,6 +77,102 @@ impl<'b, T: Write + 'b> Session<'b, T> {
             })
         })
     }
+
+    /// Formats `input` with the configured edition, or with the first older edition it parses
+    /// with if it doesn't parse with the configured one, as requested by `detect_edition`. Parse
+    /// errors are not shown, and `None` is returned if the input doesn't parse with any edition.
+    ///
+    /// The input is only parsed with the other editions, so that it is formatted once at most.
+    fn format_with_detected_edition(
+        &mut self,
+        input: &Input,
+        is_macro_def: bool,
+    ) -> Result<Option<FormatReport>, ErrorKind> {
+        let configured_edition = self.config.edition();
+        let mut config = self.config.clone();
+        config.set().show_parse_errors(false);
+        let report = rustc_span::create_session_if_not_set_then(configured_edition.into(), |_| {
+            format_project(input.clone(), &config, self, is_macro_def)
+        })?;
+        if !report.internal.borrow().1.has_parsing_errors {
+            return Ok(Some(report));
+        }
+
+        let edition = [
+            Edition::Edition2021,
+            Edition::Edition2018,
+            Edition::Edition2015,
+        ]
+        .into_iter()
+        .filter(|&edition| edition != configured_edition)
+        .find(|&edition| parses_with_edition(input, &config, edition));
+        let Some(edition) = edition else {
+            return Ok(None);
+        };
+        config.set().edition(edition);
+        let report = rustc_span::create_session_if_not_set_then(edition.into(), |_| {
+            format_project(input.clone(), &config, self, is_macro_def)
+        })?;
+        report.append(
+            input.file_name(),
+            vec![FormattingError {
+                line: 1,
+                kind: ErrorKind::DetectedEdition(configured_edition, edition),
+                is_comment: false,
+                is_string: false,
+                line_buffer: String::new(),
+            }],
+        );
+        Ok(Some(report))
+    }
+}
+
+/// Returns `true` if `input` parses with `edition`.
+fn parses_with_edition(input: &Input, config: &Config, edition: Edition) -> bool {
+    let mut config = config.clone();
+    config.set().edition(edition);
+    rustc_span::create_session_if_not_set_then(edition.into(), |_| {
+        let Ok(psess) = ParseSess::new(&config) else {
+            return false;
+        };
+        Parser::parse_crate(input.clone(), &psess).is_ok()
+    })
+}
+
+/// Rough time it takes to format a line of code.
+const ESTIMATED_TIME_PER_LINE: Duration = Duration::from_micros(20);
+
+/// Returns a rough estimate of the time it takes to format `file` with `config`, so that a
+/// scheduler can start with the files that take longest.
+///
+/// Only the lines of the file are looked at, so the estimate is only useful to compare files
+/// with each other. Lines exceeding `max_width` count double since they have to be wrapped, and
+/// so do doc comments when `format_code_in_doc_comments` is enabled. A file that can't be read
+/// is estimated to take no time, since formatting it fails right away.
+pub fn estimate_format_time(file: &Path, config: &Config) -> Duration {
+    if config.disable_all_formatting() {
+        return Duration::ZERO;
+    }
+    let Ok(src) = fs::read_to_string(file) else {
+        return Duration::ZERO;
+    };
+    let lines: usize = src
+        .lines()
+        .map(|line| {
+            let is_doc_comment = {
+                let line = line.trim_start();
+                line.starts_with("///") || line.starts_with("//!")
+            };
+            if unicode_str_width(line) > config.max_width()
+                || (is_doc_comment && config.format_code_in_doc_comments())
+            {
+                2
+            } else {
+                1
+            }
+        })
+        .sum();
+    ESTIMATED_TIME_PER_LINE.saturating_mul(u32::try_from(lines).unwrap_or(u32::MAX))
 }
 
 /// Determine if a module should be skipped. True if the module should be skipped, false otherwise.
@@ -64,7 +184,7 @@ fn should_skip_module<T: FormatHandler>(
     path: &FileName,
     module: &Module<'_>,
 ) -> bool {
-    if contains_skip(module.attrs()) {
+    if contains_skip(module.attrs()) && opted_in_items_of(&module.items).is_empty() {
         return true;
     }
 
@@ -114,6 +234,13 @@ fn format_project<T: FormatHandler>(
         return Ok(FormatReport::new());
     }
 
+    // The parser normalizes `\r\n` to `\n`, so keep the original stdin input around to detect
+    // its newline style.
+    let stdin_text = match input {
+        Input::Text(ref text) if needs_raw_input_text(config) => Some(text.clone()),
+        _ => None,
+    };
+
     // Parse the crate.
     let mut report = FormatReport::new();
     let directory_ownership = input.to_directory_ownership();
@@ -131,7 +258,7 @@ fn format_project<T: FormatHandler>(
         }
     };
 
-    let mut context = FormatContext::new(&krate, report, psess, config, handler);
+    let mut context = FormatContext::new(&krate, report, psess, config, handler, stdin_text);
     let files = modules::ModResolver::new(
         &context.psess,
         directory_ownership.unwrap_or(DirectoryOwnership::UnownedViaBlock),
@@ -151,7 +278,10 @@ fn format_project<T: FormatHandler>(
     context.psess.set_silent_emitter();
 
     for (path, module) in files {
-        if input_is_stdin && contains_skip(module.attrs()) {
+        if input_is_stdin
+            && contains_skip(module.attrs())
+            && opted_in_items_of(&module.items).is_empty()
+        {
             return echo_back_stdin(context.psess.snippet_provider(module.span).entire_snippet());
         }
         should_emit_verbose(input_is_stdin, config, || println!("Formatting {}", path));
@@ -177,6 +307,7 @@ struct FormatContext<'a, T: FormatHandler> {
     psess: ParseSess,
     config: &'a Config,
     handler: &'a mut T,
+    stdin_text: Option<String>,
 }
 
 impl<'a, T: FormatHandler + 'a> FormatContext<'a, T> {
@@ -186,6 +317,7 @@ impl<'a, T: FormatHandler + 'a> FormatContext<'a, T> {
         psess: ParseSess,
         config: &'a Config,
         handler: &'a mut T,
+        stdin_text: Option<String>,
     ) -> Self {
         FormatContext {
             krate,
@@ -193,6 +325,7 @@ impl<'a, T: FormatHandler + 'a> FormatContext<'a, T> {
             psess,
             config,
             handler,
+            stdin_text,
         }
     }
 
@@ -200,6 +333,18 @@ impl<'a, T: FormatHandler + 'a> FormatContext<'a, T> {
         self.psess.ignore_file(path)
     }
 
+    // Returns the input text as it was before the parser normalized its line endings. Only
+    // needed to auto-detect the newline style, or to keep the line endings of string literals.
+    fn original_text(&self, path: &FileName) -> Option<Cow<'_, str>> {
+        if !needs_raw_input_text(self.config) {
+            return None;
+        }
+        match path {
+            FileName::Real(path) => self.psess.take_raw_text(path).map(Cow::Owned),
+            FileName::Stdin => self.stdin_text.as_deref().map(Cow::Borrowed),
+        }
+    }
+
     // Formats a single file/module.
     fn format_file(
         &mut self,
@@ -208,12 +353,14 @@ impl<'a, T: FormatHandler + 'a> FormatContext<'a, T> {
         is_macro_def: bool,
     ) -> Result<(), ErrorKind> {
         let snippet_provider = self.psess.snippet_provider(module.span);
-        let mut visitor = FmtVisitor::from_psess(
-            &self.psess,
-            self.config,
-            &snippet_provider,
-            self.report.clone(),
-        );
+        let mut config = stamped_config(snippet_provider.entire_snippet(), self.config);
+        if module.is_test_mod() {
+            if let Some(test_config) = test_mod_config(&config) {
+                config = Cow::Owned(test_config);
+            }
+        }
+        let mut visitor =
+            FmtVisitor::from_psess(&self.psess, &config, &snippet_provider, self.report.clone());
         visitor.skip_context.update_with_attrs(&self.krate.attrs);
         visitor.is_macro_def = is_macro_def;
         visitor.last_pos = snippet_provider.start_pos();
@@ -235,14 +382,23 @@ impl<'a, T: FormatHandler + 'a> FormatContext<'a, T> {
             &mut visitor.buffer,
             &path,
             &visitor.skipped_range.borrow(),
-            self.config,
+            &visitor.test_mod_range.borrow(),
+            &config,
             &self.report,
         );
 
+        if config.format_version_stamps() && config.bump_format_version() {
+            write_latest_stamp(&mut visitor.buffer);
+        }
+
+        let original_text = self.original_text(&path);
         apply_newline_style(
             self.config.newline_style(),
+            self.config.line_endings_in_multiline_strings(),
             &mut visitor.buffer,
-            snippet_provider.entire_snippet(),
+            original_text
+                .as_deref()
+                .unwrap_or_else(|| snippet_provider.entire_snippet()),
         );
 
         if visitor.macro_rewrite_failure {
@@ -252,7 +408,7 @@ impl<'a, T: FormatHandler + 'a> FormatContext<'a, T> {
             .add_non_formatted_ranges(visitor.skipped_range.borrow().clone());
 
         self.handler.handle_formatted_file(
-            &self.psess,
+            Some(&self.psess),
             path,
             visitor.buffer.to_owned(),
             &mut self.report,
@@ -264,7 +420,7 @@ impl<'a, T: FormatHandler + 'a> FormatContext<'a, T> {
 trait FormatHandler {
     fn handle_formatted_file(
         &mut self,
-        psess: &ParseSess,
+        psess: Option<&ParseSess>,
         path: FileName,
         result: String,
         report: &mut FormatReport,
@@ -275,14 +431,14 @@ impl<'b, T: Write + 'b> FormatHandler for Session<'b, T> {
     // Called for each formatted file.
     fn handle_formatted_file(
         &mut self,
-        psess: &ParseSess,
+        psess: Option<&ParseSess>,
         path: FileName,
         result: String,
         report: &mut FormatReport,
     ) -> Result<(), ErrorKind> {
         if let Some(ref mut out) = self.out {
             match source_file::write_file(
-                Some(psess),
+                psess,
                 &path,
                 &result,
                 out,
@@ -326,6 +482,7 @@ impl FormattingError {
     pub(crate) fn is_internal(&self) -> bool {
         match self.kind {
             ErrorKind::LineOverflow(..)
+            | ErrorKind::ForbiddenLongLine(..)
             | ErrorKind::TrailingWhitespace
             | ErrorKind::IoError(_)
             | ErrorKind::ParseError
@@ -335,7 +492,9 @@ impl FormattingError {
     }
 
     pub(crate) fn msg_suffix(&self) -> &str {
-        if self.is_comment || self.is_string {
+        if (self.is_comment || self.is_string)
+            && !matches!(self.kind, ErrorKind::ForbiddenLongLine(..))
+        {
             "set `error_on_unformatted = false` to suppress \
              the warning against comments or string literals\n"
         } else {
@@ -346,7 +505,9 @@ impl FormattingError {
     // (space, target)
     pub(crate) fn format_len(&self) -> (usize, usize) {
         match self.kind {
-            ErrorKind::LineOverflow(found, max) => (max, found - max),
+            ErrorKind::LineOverflow(found, max) | ErrorKind::ForbiddenLongLine(found, max) => {
+                (max, found - max)
+            }
             ErrorKind::TrailingWhitespace
             | ErrorKind::DeprecatedAttr
             | ErrorKind::BadAttr
@@ -361,6 +522,27 @@ impl FormattingError {
                     self.line_buffer.len() - trailing_ws_start,
                 )
             }
+            ErrorKind::GlobImport(_) => match self.line_buffer.find('*') {
+                Some(glob_start) => (glob_start, 1),
+                None => (0, 0),
+            },
+            ErrorKind::MixedNumberSeparators(ref lit) => {
+                match self.line_buffer.find(lit.as_str()) {
+                    Some(lit_start) => (lit_start, lit.len()),
+                    None => (0, 0),
+                }
+            }
+            ErrorKind::ChunkedFile(..) | ErrorKind::DetectedEdition(..) => (0, 0),
+            ErrorKind::SkipSuggestion(_) | ErrorKind::UnformattedCodeBlock(_) => {
+                let item_start = self
+                    .line_buffer
+                    .find(|c: char| !c.is_whitespace())
+                    .unwrap_or(0);
+                (
+                    item_start,
+                    self.line_buffer.trim_end().len().saturating_sub(item_start),
+                )
+            }
             _ => unreachable!(),
         }
     }
@@ -390,6 +572,9 @@ pub(crate) struct ReportedErrors {
 
     /// Formatted code missed something, like lost comments or extra trailing space
     pub(crate) has_unformatted_code_errors: bool,
+
+    /// Formatted code has lines exceeding `max_width` while `forbid_long_lines` is set.
+    pub(crate) has_long_line_errors: bool,
 }
 
 impl ReportedErrors {
@@ -402,6 +587,7 @@ impl ReportedErrors {
         self.has_check_errors |= other.has_check_errors;
         self.has_diff |= other.has_diff;
         self.has_unformatted_code_errors |= other.has_unformatted_code_errors;
+        self.has_long_line_errors |= other.has_long_line_errors;
     }
 }
 
@@ -474,10 +660,11 @@ fn format_lines(
     text: &mut String,
     name: &FileName,
     skipped_range: &[(usize, usize)],
+    test_mod_range: &[(usize, usize)],
     config: &Config,
     report: &FormatReport,
 ) {
-    let mut formatter = FormatLines::new(name, skipped_range, config);
+    let mut formatter = FormatLines::new(name, skipped_range, test_mod_range, config);
     formatter.iterate(text);
 
     if formatter.newline_count > 1 {
@@ -492,6 +679,7 @@ fn format_lines(
 struct FormatLines<'a> {
     name: &'a FileName,
     skipped_range: &'a [(usize, usize)],
+    test_mod_range: &'a [(usize, usize)],
     last_was_space: bool,
     line_len: usize,
     cur_line: usize,
@@ -507,11 +695,13 @@ impl<'a> FormatLines<'a> {
     fn new(
         name: &'a FileName,
         skipped_range: &'a [(usize, usize)],
+        test_mod_range: &'a [(usize, usize)],
         config: &'a Config,
     ) -> FormatLines<'a> {
         FormatLines {
             name,
             skipped_range,
+            test_mod_range,
             last_was_space: false,
             line_len: 0,
             cur_line: 1,
@@ -545,6 +735,7 @@ impl<'a> FormatLines<'a> {
             if self.last_was_space {
                 if self.should_report_error(kind, &ErrorKind::TrailingWhitespace)
                     && !self.is_skipped_line()
+                    && !self.is_doc_comment_hard_break()
                 {
                     self.push_err(
                         ErrorKind::TrailingWhitespace,
@@ -556,8 +747,13 @@ impl<'a> FormatLines<'a> {
             }
 
             // Check for any line width errors we couldn't correct.
-            let error_kind = ErrorKind::LineOverflow(self.line_len, self.config.max_width());
-            if self.line_len > self.config.max_width()
+            let max_width = self.max_width();
+            let error_kind = if self.config.forbid_long_lines() {
+                ErrorKind::ForbiddenLongLine(self.line_len, max_width)
+            } else {
+                ErrorKind::LineOverflow(self.line_len, max_width)
+            };
+            if self.line_len > max_width
                 && !self.is_skipped_line()
                 && self.should_report_error(kind, &error_kind)
             {
@@ -592,6 +788,17 @@ impl<'a> FormatLines<'a> {
         }
     }
 
+    /// Returns `true` if the current line is a doc comment line that ends in the two spaces of a
+    /// markdown hard line break. Doc comments keep them on purpose, see `doc_comment_hard_break`.
+    fn is_doc_comment_hard_break(&self) -> bool {
+        let line = self.line_buffer.trim_start();
+        let text = line
+            .strip_prefix("///")
+            .or_else(|| line.strip_prefix("//!"))
+            .unwrap_or_default();
+        text.ends_with("  ") && !text.trim().is_empty()
+    }
+
     fn push_err(&mut self, kind: ErrorKind, is_comment: bool, is_string: bool) {
         self.errors.push(FormattingError {
             line: self.cur_line,
@@ -616,11 +823,27 @@ impl<'a> FormatLines<'a> {
             ErrorKind::LineOverflow(..) => {
                 self.config.error_on_line_overflow() && allow_error_report
             }
+            // Long lines are forbidden in comments and string literals too.
+            ErrorKind::ForbiddenLongLine(..) => true,
             ErrorKind::TrailingWhitespace | ErrorKind::LostComment => allow_error_report,
             _ => true,
         }
     }
 
+    /// Returns the width the current line was formatted with, which is `max_width_in_tests`
+    /// inside test modules.
+    fn max_width(&self) -> usize {
+        let in_test_mod = self
+            .test_mod_range
+            .iter()
+            .any(|&(lo, hi)| lo <= self.cur_line && self.cur_line <= hi);
+        if in_test_mod {
+            self.config.max_width_in_tests()
+        } else {
+            self.config.max_width()
+        }
+    }
+
     /// Returns `true` if the line with the given line number was skipped by `#[rustfmt::skip]`.
     fn is_skipped_line(&self) -> bool {
         self.skipped_range
@@ -0,0 +1,265 @@
+use std::fs;
+use std::io::Write;
+
+use crate::comment::{CharClasses, FullCodeCharKind};
+use crate::config::{EmitMode, FileName, NewlineStyle, Verbosity};
+use crate::formatting::generated::is_generated_file;
+use crate::formatting::newline_style::apply_newline_style;
+use crate::formatting::{FormatHandler, FormattingError};
+use crate::ignore_path::IgnorePathSet;
+use crate::{ErrorKind, FormatReport, Input, Session};
+
+/// The largest file the parser can handle, since it addresses source text with 32-bit byte
+/// positions.
+pub(crate) const MAX_SOURCE_LEN: u64 = u32::MAX as u64;
+
+impl<'b, T: Write + 'b> Session<'b, T> {
+    /// Formats `input` if it is longer than `max_len` bytes, which is too large to be parsed at
+    /// once. Returns `None` if `input` is small enough to be formatted as usual.
+    ///
+    /// A file that is too large is an error, unless `chunk_large_files` is set. In that case it
+    /// is split at top-level items into chunks that are small enough, which are formatted on
+    /// their own and put back together.
+    pub(super) fn format_large_file(
+        &mut self,
+        input: &Input,
+        max_len: u64,
+    ) -> Result<Option<FormatReport>, ErrorKind> {
+        let len = match input {
+            Input::File(path) => fs::metadata(path)?.len(),
+            Input::Text(text) => text.len() as u64,
+        };
+        if len <= max_len {
+            return Ok(None);
+        }
+        let too_large = || ErrorKind::FileTooLarge(input.file_name(), len, max_len);
+        let Input::File(path) = input else {
+            return Err(too_large());
+        };
+        if !self.config.chunk_large_files() {
+            return Err(too_large());
+        }
+
+        let file_name = FileName::Real(path.clone());
+        let ignore_path_set = IgnorePathSet::from_ignore_list(&self.config.ignore())
+            .map_err(ErrorKind::InvalidGlobPattern)?;
+        if ignore_path_set.is_match(&file_name) {
+            return Ok(Some(FormatReport::new()));
+        }
+        let src = fs::read_to_string(path)?;
+        if !self.config.format_generated_files() && is_generated_file(&src, &self.config) {
+            return Ok(Some(FormatReport::new()));
+        }
+        let chunks = split_at_items(&src, max_len as usize).ok_or_else(too_large)?;
+
+        let mut result = String::with_capacity(src.len());
+        let mut unformatted_chunks = 0;
+        for chunk in &chunks {
+            // Blank lines at the start of a chunk are dropped by formatting it, so add back the
+            // one separating it from the previous chunk.
+            if !result.is_empty() && chunk.lines().next().map_or(false, |l| l.trim().is_empty()) {
+                result.push('\n');
+            }
+            match self.format_chunk(chunk) {
+                Some(formatted) => result.push_str(&formatted),
+                None => {
+                    unformatted_chunks += 1;
+                    result.push_str(chunk);
+                }
+            }
+        }
+        apply_newline_style(
+            self.config.newline_style(),
+            self.config.line_endings_in_multiline_strings(),
+            &mut result,
+            &src,
+        );
+
+        let mut report = FormatReport::new();
+        report.append(
+            file_name.clone(),
+            vec![FormattingError {
+                line: 1,
+                kind: ErrorKind::ChunkedFile(chunks.len(), unformatted_chunks),
+                is_comment: false,
+                is_string: false,
+                line_buffer: src.lines().next().unwrap_or_default().to_owned(),
+            }],
+        );
+        self.handle_formatted_file(None, file_name, result, &mut report)?;
+        self.errors.add(&report.internal.borrow().1);
+        Ok(Some(report))
+    }
+
+    /// Formats a chunk of a large file on its own, returning `None` if it can't be formatted.
+    fn format_chunk(&mut self, chunk: &str) -> Option<String> {
+        let mut config = self.config.clone();
+        config.set().emit_mode(EmitMode::Stdout);
+        config.set().verbose(Verbosity::Quiet);
+        config.set().newline_style(NewlineStyle::Unix);
+        config.set().show_parse_errors(false);
+        config.set().detect_edition(false);
+        config.set().chunk_large_files(false);
+
+        let mut out = Vec::with_capacity(chunk.len());
+        let mut session = Session::new(config, Some(&mut out));
+        let result = session.format_input_inner(Input::Text(chunk.to_owned()), false);
+        if result.is_err() || session.has_parsing_errors() {
+            return None;
+        }
+        drop(session);
+        String::from_utf8(out).ok()
+    }
+}
+
+/// Returns the offsets in `src` right after the lines that end a top-level item, i.e. the lines
+/// whose last code outside of any delimiters is a `}` or a `;`. Strings and comments are
+/// skipped, so that the delimiters in them don't count.
+fn item_boundaries(src: &str) -> Vec<usize> {
+    let mut boundaries = Vec::new();
+    let mut depth = 0usize;
+    let mut ends_item = false;
+    for (kind, (i, c)) in CharClasses::new(src.char_indices()) {
+        if c == '\n' {
+            if depth == 0 && ends_item {
+                boundaries.push(i + 1);
+                ends_item = false;
+            }
+            continue;
+        }
+        if kind != FullCodeCharKind::Normal || c.is_whitespace() {
+            continue;
+        }
+        match c {
+            '{' | '(' | '[' => depth += 1,
+            '}' | ')' | ']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        ends_item = depth == 0 && (c == '}' || c == ';');
+    }
+    boundaries
+}
+
+/// Splits `src` at top-level item boundaries into as few chunks of at most `max_len` bytes as
+/// possible. Returns `None` if a single item is longer than `max_len`.
+fn split_at_items(src: &str, max_len: usize) -> Option<Vec<&str>> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut last_boundary = 0;
+    for boundary in item_boundaries(src)
+        .into_iter()
+        .chain(std::iter::once(src.len()))
+    {
+        if boundary - start > max_len {
+            if last_boundary <= start {
+                return None;
+            }
+            chunks.push(&src[start..last_boundary]);
+            start = last_boundary;
+            if boundary - start > max_len {
+                return None;
+            }
+        }
+        last_boundary = boundary;
+    }
+    if start < src.len() {
+        chunks.push(&src[start..]);
+    }
+    Some(chunks)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io;
+
+    use super::*;
+    use crate::config::Config;
+    use crate::modules::{ModuleResolutionError, ModuleResolutionErrorKind};
+    use crate::test::TempDir;
+
+    #[test]
+    fn splits_after_top_level_items() {
+        let src = "fn a() {\n    let s = \"}\n\";\n}\n\n// }\nstruct B;\nmod c {\n    d!();\n}\n";
+        assert_eq!(item_boundaries(src), vec![29, 45, 65]);
+        assert_eq!(split_at_items(src, 50), Some(vec![&src[..45], &src[45..]]));
+        assert_eq!(split_at_items(src, 20), None);
+    }
+
+    #[test]
+    fn formats_large_files_in_chunks() {
+        let dir = TempDir::new("large-file");
+        let path = dir.path().join("large.rs");
+        let src: String = (0..500)
+            .map(|i| format!("fn f{i}( x:u32 )->u32 {{ x+{i} }}\n\nstruct S{i} ;\n"))
+            .collect();
+        fs::write(&path, &src).unwrap();
+        let input = Input::File(path.clone());
+        let max_len = src.len() as u64 / 10;
+
+        let mut config = Config::default();
+        config.set().emit_mode(EmitMode::Stdout);
+        config.set().verbose(Verbosity::Quiet);
+        let mut session = Session::<io::Sink>::new(config.clone(), None);
+        assert!(matches!(
+            session.format_large_file(&input, max_len),
+            Err(ErrorKind::FileTooLarge(_, len, max)) if len == src.len() as u64 && max == max_len
+        ));
+        assert!(
+            session
+                .format_large_file(&input, src.len() as u64)
+                .unwrap()
+                .is_none()
+        );
+
+        let mut expected = Vec::new();
+        Session::new(config.clone(), Some(&mut expected))
+            .format(input.clone())
+            .unwrap();
+        let mut out = Vec::new();
+        config.set().chunk_large_files(true);
+        let mut session = Session::new(config, Some(&mut out));
+        let report = session.format_large_file(&input, max_len).unwrap().unwrap();
+        drop(session);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            String::from_utf8(expected).unwrap()
+        );
+        let chunks = report
+            .internal
+            .borrow()
+            .0
+            .values()
+            .flatten()
+            .find_map(|error| match error.kind {
+                ErrorKind::ChunkedFile(chunks, unformatted) => Some((chunks, unformatted)),
+                _ => None,
+            });
+        assert!(matches!(chunks, Some((n, 0)) if n >= 10), "{chunks:?}");
+    }
+
+    #[test]
+    fn module_files_are_checked_for_size() {
+        let dir = TempDir::new("large-module");
+        let lib = dir.path().join("lib.rs");
+        fs::write(&lib, "mod large;\n").unwrap();
+        // A sparse file, which takes no space on disk.
+        let large = fs::File::create(dir.path().join("large.rs")).unwrap();
+        large.set_len(MAX_SOURCE_LEN + 1).unwrap();
+
+        let mut config = Config::default();
+        config.set().emit_mode(EmitMode::Stdout);
+        config.set().verbose(Verbosity::Quiet);
+        let mut session = Session::<io::Sink>::new(config, None);
+        let error = session.format(Input::File(lib)).err();
+        assert!(
+            matches!(
+                error,
+                Some(ErrorKind::ModuleResolutionError(ModuleResolutionError {
+                    kind: ModuleResolutionErrorKind::FileTooLarge { len, .. },
+                    ..
+                })) if len == MAX_SOURCE_LEN + 1
+            ),
+            "{error:?}"
+        );
+    }
+}
@@ -1,3 +1,8 @@
+use std::collections::HashMap;
+use std::iter::{self, Peekable};
+
+use crate::comment::CharClasses;
+use crate::config::{Config, StringLineEndings};
 use crate::NewlineStyle;
 
 /// Apply this newline style to the formatted text. When the style is set
@@ -6,17 +11,36 @@ use crate::NewlineStyle;
 ///
 /// If the style is set to `Auto` and `raw_input_text` contains no
 /// newlines, the `Native` style will be used.
+///
+/// The line endings inside string literals are only converted when
+/// `string_line_endings` is `Normalize`.
 pub(crate) fn apply_newline_style(
     newline_style: NewlineStyle,
+    string_line_endings: StringLineEndings,
     formatted_text: &mut String,
     raw_input_text: &str,
 ) {
-    *formatted_text = match effective_newline_style(newline_style, raw_input_text) {
-        EffectiveNewlineStyle::Windows => convert_to_windows_newlines(formatted_text),
-        EffectiveNewlineStyle::Unix => convert_to_unix_newlines(formatted_text),
+    let newline_style = effective_newline_style(newline_style, raw_input_text);
+    *formatted_text = match (newline_style, string_line_endings) {
+        (EffectiveNewlineStyle::Windows, StringLineEndings::Normalize) => {
+            convert_to_windows_newlines(formatted_text)
+        }
+        (EffectiveNewlineStyle::Unix, StringLineEndings::Normalize) => {
+            convert_to_unix_newlines(formatted_text)
+        }
+        (newline_style, StringLineEndings::Preserve) => {
+            convert_newlines_outside_strings(formatted_text, newline_style, raw_input_text)
+        }
     }
 }
 
+/// Returns `true` if `apply_newline_style` needs the input text as it was before the parser
+/// normalized its line endings, either to detect them or to keep them in string literals.
+pub(crate) fn needs_raw_input_text(config: &Config) -> bool {
+    config.newline_style() == NewlineStyle::Auto
+        || config.line_endings_in_multiline_strings() == StringLineEndings::Preserve
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum EffectiveNewlineStyle {
     Windows,
@@ -81,6 +105,55 @@ fn convert_to_unix_newlines(formatted_text: &str) -> String {
     formatted_text.replace(WINDOWS_NEWLINE, UNIX_NEWLINE)
 }
 
+fn convert_newlines_outside_strings(
+    formatted_text: &str,
+    newline_style: EffectiveNewlineStyle,
+    raw_input_text: &str,
+) -> String {
+    // The parser has already turned the `\r\n` in string literals into `\n`, so the literals are
+    // taken from the raw input instead. They are matched by their text, as formatting may have
+    // moved them around.
+    let raw_literals: HashMap<String, &str> = string_literals(raw_input_text)
+        .map(|literal| (literal.replace(WINDOWS_NEWLINE, UNIX_NEWLINE), literal))
+        .collect();
+    let mut transformed = String::with_capacity(formatted_text.len());
+    let mut chars = CharClasses::new(formatted_text.char_indices()).peekable();
+    while let Some((kind, (i, current_char))) = chars.next() {
+        if kind.is_string() {
+            let literal = &formatted_text[i..string_literal_end(&mut chars, formatted_text.len())];
+            let key = literal.replace(WINDOWS_NEWLINE, UNIX_NEWLINE);
+            transformed.push_str(raw_literals.get(&key).copied().unwrap_or(literal));
+            continue;
+        }
+        let next_char = chars.peek().map(|&(_, (_, next_char))| next_char);
+        match (current_char, newline_style) {
+            (LINE_FEED, EffectiveNewlineStyle::Windows) => transformed.push_str(WINDOWS_NEWLINE),
+            (CARRIAGE_RETURN, _) if next_char == Some(LINE_FEED) => {}
+            (current_char, _) => transformed.push(current_char),
+        }
+    }
+    transformed
+}
+
+/// Returns the string literals of `text`, from their opening to their closing quote.
+fn string_literals(text: &str) -> impl Iterator<Item = &str> {
+    let mut chars = CharClasses::new(text.char_indices()).peekable();
+    iter::from_fn(move || {
+        let (_, (start, _)) = chars.find(|&(kind, _)| kind.is_string())?;
+        Some(&text[start..string_literal_end(&mut chars, text.len())])
+    })
+}
+
+/// Consumes the rest of a string literal from `chars`, returning the offset right after it, or
+/// `len` if the text ends within the literal.
+fn string_literal_end<I>(chars: &mut Peekable<CharClasses<I>>, len: usize) -> usize
+where
+    I: Iterator<Item = (usize, char)>,
+{
+    while chars.next_if(|&(kind, _)| kind.is_string()).is_some() {}
+    chars.peek().map_or(len, |&(_, (i, _))| i)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -128,7 +201,12 @@ mod tests {
         let raw_input_text = "One\nTwo\nThree";
 
         let mut out = String::from(formatted_text);
-        apply_newline_style(NewlineStyle::Auto, &mut out, raw_input_text);
+        apply_newline_style(
+            NewlineStyle::Auto,
+            StringLineEndings::Preserve,
+            &mut out,
+            raw_input_text,
+        );
         assert_eq!("One\nTwo\nThree", &out, "auto should detect 'lf'");
     }
 
@@ -138,7 +216,12 @@ mod tests {
         let raw_input_text = "One\r\nTwo\r\nThree";
 
         let mut out = String::from(formatted_text);
-        apply_newline_style(NewlineStyle::Auto, &mut out, raw_input_text);
+        apply_newline_style(
+            NewlineStyle::Auto,
+            StringLineEndings::Preserve,
+            &mut out,
+            raw_input_text,
+        );
         assert_eq!("One\r\nTwo\r\nThree", &out, "auto should detect 'crlf'");
     }
 
@@ -148,7 +231,12 @@ mod tests {
         let raw_input_text = "One Two Three";
 
         let mut out = String::from(formatted_text);
-        apply_newline_style(NewlineStyle::Auto, &mut out, raw_input_text);
+        apply_newline_style(
+            NewlineStyle::Auto,
+            StringLineEndings::Preserve,
+            &mut out,
+            raw_input_text,
+        );
 
         if cfg!(windows) {
             assert_eq!(
@@ -238,13 +326,85 @@ mod tests {
         );
     }
 
+    #[test]
+    fn keeps_newlines_in_strings_unless_normalizing_them() {
+        let input = "let s = \"One\r\nTwo\nThree\";\r\nlet r = r#\"One\r\nTwo\"#;\n";
+        let mut out = String::from(input);
+        apply_newline_style(
+            NewlineStyle::Unix,
+            StringLineEndings::Preserve,
+            &mut out,
+            input,
+        );
+        assert_eq!(
+            "let s = \"One\r\nTwo\nThree\";\nlet r = r#\"One\r\nTwo\"#;\n",
+            &out
+        );
+
+        let mut out = String::from(input);
+        apply_newline_style(
+            NewlineStyle::Windows,
+            StringLineEndings::Preserve,
+            &mut out,
+            input,
+        );
+        assert_eq!(
+            "let s = \"One\r\nTwo\nThree\";\r\nlet r = r#\"One\r\nTwo\"#;\r\n",
+            &out
+        );
+
+        let mut out = String::from(input);
+        apply_newline_style(
+            NewlineStyle::Unix,
+            StringLineEndings::Normalize,
+            &mut out,
+            input,
+        );
+        assert_eq!(
+            "let s = \"One\nTwo\nThree\";\nlet r = r#\"One\nTwo\"#;\n",
+            &out
+        );
+    }
+
+    #[test]
+    fn takes_the_line_endings_of_strings_from_the_raw_input() {
+        // The parser has turned the `\r\n` in the string into `\n`.
+        let formatted_text = "fn main() {\n    let s = \"a\nb\";\n    let x = 1;\n}\n";
+        let raw_input_text = "fn main() {\r\n    let s = \"a\r\nb\";\r\n    let  x = 1;\r\n}\r\n";
+        let mut out = String::from(formatted_text);
+        apply_newline_style(
+            NewlineStyle::Auto,
+            StringLineEndings::Preserve,
+            &mut out,
+            raw_input_text,
+        );
+        assert_eq!(
+            "fn main() {\r\n    let s = \"a\r\nb\";\r\n    let x = 1;\r\n}\r\n",
+            &out
+        );
+
+        let mut out = String::from(formatted_text);
+        apply_newline_style(
+            NewlineStyle::Unix,
+            StringLineEndings::Preserve,
+            &mut out,
+            raw_input_text,
+        );
+        assert_eq!(
+            "fn main() {\n    let s = \"a\r\nb\";\n    let x = 1;\n}\n",
+            &out
+        );
+    }
+
     fn test_newlines_are_applied_correctly(
         input: &str,
         expected: &str,
         newline_style: NewlineStyle,
     ) {
-        let mut out = String::from(input);
-        apply_newline_style(newline_style, &mut out, input);
-        assert_eq!(expected, &out);
+        for string_line_endings in [StringLineEndings::Preserve, StringLineEndings::Normalize] {
+            let mut out = String::from(input);
+            apply_newline_style(newline_style, string_line_endings, &mut out, input);
+            assert_eq!(expected, &out);
+        }
     }
 }
@@ -0,0 +1,93 @@
+use std::borrow::Cow;
+
+use crate::config::{Config, Version};
+
+const STAMP_PREFIX: &str = "// @rustfmt-version:";
+
+/// The formatting behavior requested by each version stamp. The last entry is the one that
+/// `--bump-format-version` stamps files with.
+const STAMPS: &[(u32, Version)] = &[(1, Version::One), (2, Version::Two)];
+
+/// Splits a leading shebang line off `src`, since a version stamp has to follow it.
+fn split_shebang(src: &str) -> (&str, &str) {
+    if src.starts_with("#!") && !src.starts_with("#![") {
+        src.split_at(src.find('\n').map_or(src.len(), |i| i + 1))
+    } else {
+        ("", src)
+    }
+}
+
+/// Returns the stamp number of the first line of `src`, if that line is a version stamp.
+fn read_stamp(src: &str) -> Option<u32> {
+    let (_, src) = split_shebang(src);
+    src.lines()
+        .next()?
+        .strip_prefix(STAMP_PREFIX)?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Returns the config to format a file whose original text is `src` with. Files without a
+/// known stamp are formatted with `config` as is.
+pub(super) fn stamped_config<'c>(src: &str, config: &'c Config) -> Cow<'c, Config> {
+    if !config.format_version_stamps() {
+        return Cow::Borrowed(config);
+    }
+    let stamp = if config.bump_format_version() {
+        STAMPS.last()
+    } else {
+        read_stamp(src).and_then(|stamp| STAMPS.iter().find(|(number, _)| *number == stamp))
+    };
+    match stamp {
+        Some(&(_, version)) => {
+            let mut config = config.clone();
+            config.set().version(version);
+            Cow::Owned(config)
+        }
+        None => Cow::Borrowed(config),
+    }
+}
+
+/// Stamps the formatted text of a file with the latest version, replacing its current stamp.
+pub(super) fn write_latest_stamp(formatted: &mut String) {
+    let (shebang, rest) = split_shebang(formatted);
+    let start = shebang.len();
+    let end = if read_stamp(rest).is_some() {
+        start + rest.find('\n').map_or(rest.len(), |i| i + 1)
+    } else {
+        start
+    };
+    let (latest, _) = STAMPS.last().expect("at least one version stamp");
+    formatted.replace_range(start..end, &format!("{STAMP_PREFIX} {latest}\n"));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reads_the_first_line_only() {
+        assert_eq!(
+            read_stamp("// @rustfmt-version: 2\nfn main() {}\n"),
+            Some(2)
+        );
+        assert_eq!(read_stamp("#!/bin/run\n// @rustfmt-version: 1\n"), Some(1));
+        assert_eq!(read_stamp("fn main() {}\n// @rustfmt-version: 2\n"), None);
+        assert_eq!(read_stamp("// @rustfmt-version: two\n"), None);
+    }
+
+    #[test]
+    fn writes_or_replaces_the_stamp() {
+        let mut formatted = String::from("// @rustfmt-version: 1\nfn main() {}\n");
+        write_latest_stamp(&mut formatted);
+        assert_eq!(formatted, "// @rustfmt-version: 2\nfn main() {}\n");
+
+        let mut formatted = String::from("#!/bin/run\nfn main() {}\n");
+        write_latest_stamp(&mut formatted);
+        assert_eq!(
+            formatted,
+            "#!/bin/run\n// @rustfmt-version: 2\nfn main() {}\n"
+        );
+    }
+}
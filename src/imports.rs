@@ -15,7 +15,8 @@ use rustc_span::{
 use crate::comment::combine_strs_with_missing_comments;
 use crate::config::lists::*;
 use crate::config::ImportGranularity;
-use crate::config::{Edition, IndentStyle, Version};
+use crate::config::{Edition, IndentStyle, ReportLevel, Version};
+use crate::formatting::FormattingError;
 use crate::lists::{
     definitive_tactic, itemize_list, write_list, ListFormatting, ListItem, Separator,
 };
@@ -23,8 +24,9 @@ use crate::rewrite::{Rewrite, RewriteContext};
 use crate::shape::Shape;
 use crate::source_map::SpanUtils;
 use crate::spanned::Spanned;
-use crate::utils::{is_same_visibility, mk_sp, rewrite_ident};
+use crate::utils::{contains_skip, is_same_visibility, mk_sp, rewrite_ident};
 use crate::visitor::FmtVisitor;
+use crate::ErrorKind;
 
 /// Returns a name imported by a `use` declaration.
 /// E.g., returns `Ordering` for `std::cmp::Ordering` and `self` for `std::cmp::self`.
@@ -70,6 +72,39 @@ impl<'a> FmtVisitor<'a> {
             }
         }
     }
+
+    /// Reports every wildcard import found in `item`, as requested by `use_glob_imports`.
+    pub(crate) fn report_glob_imports(&mut self, item: &ast::Item) {
+        fn collect_globs(tree: &ast::UseTree, globs: &mut Vec<Span>) {
+            match tree.kind {
+                UseTreeKind::Glob => globs.push(tree.span),
+                UseTreeKind::Nested { ref items, .. } => items
+                    .iter()
+                    .for_each(|(tree, _)| collect_globs(tree, globs)),
+                UseTreeKind::Simple(..) => {}
+            }
+        }
+
+        let level = self.config.use_glob_imports();
+        if level == ReportLevel::Ignore || contains_skip(&item.attrs) {
+            return;
+        }
+        let ast::ItemKind::Use(ref tree) = item.kind else {
+            return;
+        };
+
+        let mut globs = vec![];
+        collect_globs(tree, &mut globs);
+        if globs.is_empty() {
+            return;
+        }
+        let file_name = self.psess.span_to_filename(item.span);
+        let errors = globs
+            .into_iter()
+            .map(|span| FormattingError::from_span(span, self.psess, ErrorKind::GlobImport(level)))
+            .collect();
+        self.report.append(file_name, errors);
+    }
 }
 
 // Ordering of imports
@@ -652,6 +687,16 @@ impl UseTree {
         }
     }
 
+    /// Ranks the visibility of the tree for `reorder_imports_by_visibility`: private imports
+    /// come first, then restricted ones such as `pub(crate)`, then `pub` ones.
+    pub(crate) fn visibility_rank(&self) -> u8 {
+        match self.visibility.as_ref().map(|vis| &vis.kind) {
+            None | Some(ast::VisibilityKind::Inherited) => 0,
+            Some(ast::VisibilityKind::Restricted { .. }) => 1,
+            Some(ast::VisibilityKind::Public) => 2,
+        }
+    }
+
     fn share_prefix(&self, other: &UseTree, shared_prefix: SharedPrefix) -> bool {
         if self.path.is_empty()
             || other.path.is_empty()
@@ -1419,6 +1464,16 @@ mod test {
                 "a::b::h::k",
             ]
         );
+
+        // A glob can't be split any further, and is never wrapped in braces.
+        assert_eq!(
+            parse_use_tree("std::prelude::v1::*").flatten(ImportGranularity::Item),
+            parse_use_trees!["std::prelude::v1::*"]
+        );
+        assert_eq!(
+            parse_use_tree("a::{b::{*}, c::{self, *}}").flatten(ImportGranularity::Item),
+            parse_use_trees!["a::b::*", "a::c::self", "a::c::*"]
+        );
     }
 
     #[test]
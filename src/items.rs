@@ -7,6 +7,7 @@ use regex::Regex;
 use rustc_ast::visit;
 use rustc_ast::{ast, ptr};
 use rustc_span::{symbol, BytePos, Span, DUMMY_SP};
+use thin_vec::ThinVec;
 
 use crate::attr::filter_inline_attrs;
 use crate::comment::{
@@ -15,13 +16,15 @@ use crate::comment::{
     FindUncommented,
 };
 use crate::config::lists::*;
-use crate::config::{BraceStyle, Config, IndentStyle, Version};
+use crate::config::{BraceStyle, Config, IndentStyle, TypeAliasStyle, Version};
 use crate::expr::{
     is_empty_block, is_simple_block_stmt, rewrite_assign_rhs, rewrite_assign_rhs_with,
     rewrite_assign_rhs_with_comments, rewrite_else_kw_with_comments, rewrite_let_else_block,
-    RhsAssignKind, RhsTactics,
+    ByteStringInit, RhsAssignKind, RhsTactics,
+};
+use crate::lists::{
+    definitive_tactic, itemize_list, write_list, ListFormatting, ListItem, Separator,
 };
-use crate::lists::{definitive_tactic, itemize_list, write_list, ListFormatting, Separator};
 use crate::macros::{rewrite_macro, MacroPosition};
 use crate::overflow;
 use crate::rewrite::{Rewrite, RewriteContext};
@@ -118,13 +121,17 @@ impl Rewrite for ast::Local {
             // 1 = trailing semicolon;
             let nested_shape = shape.sub_width(1)?;
 
-            result = rewrite_assign_rhs(
-                context,
-                result,
-                init,
-                &RhsAssignKind::Expr(&init.kind, init.span),
-                nested_shape,
-            )?;
+            let rhs_kind = RhsAssignKind::Expr(&init.kind, init.span);
+            let byte_string_init = self
+                .ty
+                .as_ref()
+                .and_then(|ty| ByteStringInit::new(context, ty, init));
+            result = match byte_string_init {
+                Some(ref byte_string_init) => {
+                    rewrite_assign_rhs(context, result, byte_string_init, &rhs_kind, nested_shape)?
+                }
+                None => rewrite_assign_rhs(context, result, init, &rhs_kind, nested_shape)?,
+            };
 
             if let Some(block) = else_block {
                 let else_kw_span = init.span.between(block.span);
@@ -417,7 +424,8 @@ impl<'a> FmtVisitor<'a> {
     ) -> Option<(String, FnBraceStyle)> {
         let context = self.get_context();
 
-        let mut fn_brace_style = newline_for_brace(self.config, &fn_sig.generics.where_clause);
+        let generics = normalize_generics(&context, fn_sig.generics);
+        let mut fn_brace_style = newline_for_brace(self.config, &generics.where_clause);
         let (result, _, force_newline_brace) =
             rewrite_fn_base(&context, indent, ident, fn_sig, span, fn_brace_style)?;
 
@@ -795,12 +803,14 @@ pub(crate) fn format_impl(
         context.budget(last_line_width(&result))
     };
 
+    let normalized_generics = normalize_generics(context, generics);
+    let where_clause = &normalized_generics.where_clause;
     let mut option = WhereClauseOption::snuggled(&ref_and_type);
     let snippet = context.snippet(item.span);
     let open_pos = snippet.find_uncommented("{")? + 1;
     if !contains_comment(&snippet[open_pos..])
         && items.is_empty()
-        && generics.where_clause.predicates.len() == 1
+        && where_clause.predicates.len() == 1
         && !result.contains('\n')
     {
         option.suppress_comma();
@@ -812,8 +822,8 @@ pub(crate) fn format_impl(
     let where_span_end = context.snippet_provider.opt_span_before(missing_span, "{");
     let where_clause_str = rewrite_where_clause(
         context,
-        &generics.where_clause.predicates,
-        generics.where_clause.span,
+        &where_clause.predicates,
+        where_clause.span,
         context.config.brace_style(),
         Shape::legacy(where_budget, offset.block_only()),
         false,
@@ -825,7 +835,7 @@ pub(crate) fn format_impl(
 
     // If there is no where-clause, we may have missing comments between the trait name and
     // the opening brace.
-    if generics.where_clause.predicates.is_empty() {
+    if where_clause.predicates.is_empty() {
         if let Some(hi) = where_span_end {
             match recover_missing_comment_in_span(
                 mk_sp(self_ty.span.hi(), hi),
@@ -847,7 +857,7 @@ pub(crate) fn format_impl(
             // If there is only one where-clause predicate
             // and the where-clause spans multiple lines,
             // then recover the suppressed comma in single line where-clause formatting
-            if generics.where_clause.predicates.len() == 1 {
+            if where_clause.predicates.len() == 1 {
                 result.push(',');
             }
         }
@@ -883,10 +893,14 @@ pub(crate) fn format_impl(
 
     if !items.is_empty() || contains_comment(&snippet[open_pos..]) {
         let mut visitor = FmtVisitor::from_context(context);
-        let item_indent = offset.block_only().block_indent(context.config);
+        // Deeply nested impls may leave no room for their items, and are kept as they are.
+        let item_indent = offset
+            .block_only()
+            .checked_add(context.config.tab_spaces(), context.config.max_width())?;
         visitor.block_indent = item_indent;
         visitor.last_pos = lo + BytePos(open_pos as u32);
 
+        visitor.in_trait_impl = iimpl.of_trait.is_some();
         visitor.visit_attrs(&item.attrs, ast::AttrStyle::Inner);
         visitor.visit_impl_items(items);
 
@@ -957,6 +971,7 @@ fn format_impl_ref_and_type(
             0,
         )?
     };
+    let generics = &normalize_generics(context, generics);
     let generics_str = rewrite_generics(context, "impl", generics, shape)?;
     result.push_str(&generics_str);
     result.push_str(format_constness_right(constness));
@@ -1164,8 +1179,13 @@ pub(crate) fn format_trait(
     let body_lo = context.snippet_provider.span_after(item.span, "{");
 
     let shape = Shape::indented(offset, context.config).offset_left(result.len())?;
-    let generics_str =
-        rewrite_generics(context, rewrite_ident(context, item.ident), generics, shape)?;
+    let normalized_generics = normalize_generics(context, generics);
+    let generics_str = rewrite_generics(
+        context,
+        rewrite_ident(context, item.ident),
+        &normalized_generics,
+        shape,
+    )?;
     result.push_str(&generics_str);
 
     // FIXME(#2055): rustfmt fails to format when there are comments between trait bounds.
@@ -1190,7 +1210,8 @@ pub(crate) fn format_trait(
     }
 
     // Rewrite where-clause.
-    if !generics.where_clause.predicates.is_empty() {
+    let where_clause = &normalized_generics.where_clause;
+    if !where_clause.predicates.is_empty() {
         let where_on_new_line = context.config.indent_style() != IndentStyle::Block;
 
         let where_budget = context.budget(last_line_width(&result));
@@ -1202,8 +1223,8 @@ pub(crate) fn format_trait(
         let option = WhereClauseOption::snuggled(&generics_str);
         let where_clause_str = rewrite_where_clause(
             context,
-            &generics.where_clause.predicates,
-            generics.where_clause.span,
+            &where_clause.predicates,
+            where_clause.span,
             context.config.brace_style(),
             Shape::legacy(where_budget, offset.block_only()),
             where_on_new_line,
@@ -1272,9 +1293,7 @@ pub(crate) fn format_trait(
         }
         BraceStyle::PreferSameLine => result.push(' '),
         BraceStyle::SameLineWhere => {
-            if result.contains('\n')
-                || (!generics.where_clause.predicates.is_empty() && !items.is_empty())
-            {
+            if result.contains('\n') || (!where_clause.predicates.is_empty() && !items.is_empty()) {
                 result.push_str(&offset.to_string_with_newline(context.config));
             } else {
                 result.push(' ');
@@ -1584,6 +1603,7 @@ fn format_tuple_struct(
 
     let where_clause_str = match struct_parts.generics {
         Some(generics) => {
+            let generics = &normalize_generics(context, generics);
             let budget = context.budget(last_line_width(&header_str));
             let shape = Shape::legacy(budget, offset);
             let generics_str = rewrite_generics(context, "", generics, shape)?;
@@ -1728,9 +1748,34 @@ fn rewrite_ty<R: Rewrite>(
         .where_clause
         .predicates
         .split_at(where_clauses.split);
-    if !after_where_predicates.is_empty() {
-        return None;
-    }
+    let (where_predicates, where_span, where_span_lo) = if after_where_predicates.is_empty() {
+        (
+            before_where_predicates,
+            where_clauses.before.span,
+            generics.span.hi(),
+        )
+    } else {
+        (
+            after_where_predicates,
+            where_clauses.after.span,
+            where_clauses.after.span.lo(),
+        )
+    };
+    // Only move the where clause when there is a single one and no comment could get lost.
+    let can_move_where = rhs.is_some()
+        && (before_where_predicates.is_empty() || after_where_predicates.is_empty())
+        && !contains_comment(context.snippet(mk_sp(generics.span.hi(), span.hi())));
+    let where_after_rhs = match context.config.type_alias_style() {
+        TypeAliasStyle::EqBound if can_move_where => true,
+        TypeAliasStyle::WhereBound if can_move_where => false,
+        _ if !after_where_predicates.is_empty() => return None,
+        _ => false,
+    };
+    let before_where_predicates = if where_after_rhs {
+        &[][..]
+    } else {
+        where_predicates
+    };
     result.push_str(&format!("{}type ", format_visibility(context, vis)));
     let ident_str = rewrite_ident(context, ident);
 
@@ -1762,13 +1807,13 @@ fn rewrite_ty<R: Rewrite>(
     let where_clause_str = rewrite_where_clause(
         context,
         before_where_predicates,
-        where_clauses.before.span,
+        where_span,
         context.config.brace_style(),
         Shape::legacy(where_budget, indent),
         false,
         "=",
         None,
-        generics.span.hi(),
+        where_span_lo,
         option,
     )?;
     result.push_str(&where_clause_str);
@@ -1811,9 +1856,27 @@ fn rewrite_ty<R: Rewrite>(
             _ => format!("{result}="),
         };
 
-        // 1 = `;`
-        let shape = Shape::indented(indent, context.config).sub_width(1)?;
-        rewrite_assign_rhs(context, lhs, &*ty, &RhsAssignKind::Ty, shape).map(|s| s + ";")
+        if !where_after_rhs {
+            // 1 = `;`
+            let shape = Shape::indented(indent, context.config).sub_width(1)?;
+            return rewrite_assign_rhs(context, lhs, ty, &RhsAssignKind::Ty, shape).map(|s| s + ";");
+        }
+
+        let shape = Shape::indented(indent, context.config);
+        let rhs_str = rewrite_assign_rhs(context, lhs, ty, &RhsAssignKind::Ty, shape)?;
+        let where_clause_str = rewrite_where_clause(
+            context,
+            where_predicates,
+            where_span,
+            context.config.brace_style(),
+            shape,
+            true,
+            ";",
+            None,
+            where_span.lo(),
+            WhereClauseOption::new(true, WhereClauseSpace::Newline),
+        )?;
+        Some(format!("{rhs_str}{where_clause_str};"))
     } else {
         Some(format!("{result};"))
     }
@@ -2035,16 +2098,30 @@ fn rewrite_static(
 
         // 1 = ;
         let remaining_width = context.budget(offset.block_indent + 1);
-        rewrite_assign_rhs_with_comments(
-            context,
-            &lhs,
-            &**expr,
-            Shape::legacy(remaining_width, offset.block_only()),
-            &RhsAssignKind::Expr(&expr.kind, expr.span),
-            RhsTactics::Default,
-            comments_span,
-            true,
-        )
+        let shape = Shape::legacy(remaining_width, offset.block_only());
+        let rhs_kind = RhsAssignKind::Expr(&expr.kind, expr.span);
+        match ByteStringInit::new(context, static_parts.ty, expr) {
+            Some(ref byte_string_init) => rewrite_assign_rhs_with_comments(
+                context,
+                &lhs,
+                byte_string_init,
+                shape,
+                &rhs_kind,
+                RhsTactics::Default,
+                comments_span,
+                true,
+            ),
+            None => rewrite_assign_rhs_with_comments(
+                context,
+                &lhs,
+                &**expr,
+                shape,
+                &rhs_kind,
+                RhsTactics::Default,
+                comments_span,
+                true,
+            ),
+        }
         .and_then(|res| recover_comment_removed(res, static_parts.span, context))
         .map(|s| if s.ends_with(';') { s } else { s + ";" })
     } else {
@@ -2329,7 +2406,8 @@ fn rewrite_fn_base(
 ) -> Option<(String, bool, bool)> {
     let mut force_new_line_for_brace = false;
 
-    let where_clause = &fn_sig.generics.where_clause;
+    let generics = normalize_generics(context, fn_sig.generics);
+    let where_clause = &generics.where_clause;
 
     let mut result = String::with_capacity(1024);
     result.push_str(&fn_sig.to_str(context));
@@ -2353,12 +2431,7 @@ fn rewrite_fn_base(
         offset: used_width,
     };
     let fd = fn_sig.decl;
-    let generics_str = rewrite_generics(
-        context,
-        rewrite_ident(context, ident),
-        &fn_sig.generics,
-        shape,
-    )?;
+    let generics_str = rewrite_generics(context, rewrite_ident(context, ident), &generics, shape)?;
     result.push_str(&generics_str);
 
     let snuggle_angle_bracket = generics_str
@@ -2554,7 +2627,12 @@ fn rewrite_fn_base(
         // Comment between return type and the end of the decl.
         let snippet_lo = fd.output.span().hi();
         if where_clause.predicates.is_empty() {
-            let snippet_hi = span.hi();
+            // A where-clause moved inline by `normalize_generics` is still in the snippet.
+            let snippet_hi = if is_normalized_generics(&generics) {
+                fn_sig.generics.where_clause.span.lo()
+            } else {
+                span.hi()
+            };
             let snippet = context.snippet(mk_sp(snippet_lo, snippet_hi));
             // Try to preserve the layout of the original snippet.
             let original_starts_with_newline = snippet
@@ -2854,10 +2932,174 @@ fn rewrite_generics(
         return Some(ident.to_owned());
     }
 
+    if is_normalized_generics(generics) {
+        // The bounds have been moved by `normalize_generics`, so the spans of the params no
+        // longer match what gets written and can't be used to look for comments.
+        return rewrite_normalized_generics(context, ident, generics, shape);
+    }
+
     let params = generics.params.iter();
     overflow::rewrite_with_angle_brackets(context, ident, params, shape, generics.span)
 }
 
+fn rewrite_normalized_generics(
+    context: &RewriteContext<'_>,
+    ident: &str,
+    generics: &ast::Generics,
+    shape: Shape,
+) -> Option<String> {
+    // 2 = `<>`
+    if let Some(one_line_shape) = shape
+        .offset_left(ident.len() + 1)
+        .and_then(|s| s.sub_width(1))
+    {
+        let params = generics
+            .params
+            .iter()
+            .map(|param| param.rewrite(context, one_line_shape))
+            .collect::<Option<Vec<_>>>();
+        if let Some(params) = params {
+            let params_str = params.join(", ");
+            if !params_str.contains('\n') && params_str.len() <= one_line_shape.width {
+                return Some(format!("{ident}<{params_str}>"));
+            }
+        }
+    }
+
+    let nested_shape = generics_shape_from_config(context.config, shape, ident.len())?;
+    let items = generics
+        .params
+        .iter()
+        .map(|param| param.rewrite(context, nested_shape).map(ListItem::from_str))
+        .collect::<Option<Vec<_>>>()?;
+    match context.config.indent_style() {
+        IndentStyle::Visual => {
+            let fmt = ListFormatting::new(nested_shape, context.config)
+                .tactic(DefinitiveListTactic::Vertical);
+            Some(format!("{ident}<{}>", write_list(&items, &fmt)?))
+        }
+        IndentStyle::Block => {
+            let fmt = ListFormatting::new(nested_shape, context.config)
+                .tactic(DefinitiveListTactic::Vertical)
+                .trailing_separator(context.config.trailing_comma());
+            Some(format!(
+                "{ident}<{}{}{}>",
+                nested_shape.indent.to_string_with_newline(context.config),
+                write_list(&items, &fmt)?,
+                shape.block().indent.to_string_with_newline(context.config),
+            ))
+        }
+    }
+}
+
+/// Moves the bounds of type params to the where-clause when `force_where_clause_always` is set,
+/// or the bounds of a single-predicate where-clause onto its type param when
+/// `force_where_clause_never` is set. Generics containing comments are returned unchanged, as
+/// the comments could not be kept in place.
+///
+/// The where-clause of normalized generics is given an empty span at the start of the generics,
+/// which can't come from the parser, to tell that the spans no longer match the original source.
+/// The predicates moved to the where-clause get a dummy span.
+fn normalize_generics<'a>(
+    context: &RewriteContext<'_>,
+    generics: &'a ast::Generics,
+) -> Cow<'a, ast::Generics> {
+    let config = context.config;
+    if !(config.force_where_clause_always() || config.force_where_clause_never())
+        || generics.params.is_empty()
+    {
+        return Cow::Borrowed(generics);
+    }
+
+    // Look for comments from the generics up to the body of the item, as those around the
+    // where-clause would be misplaced too.
+    let where_hi = generics.where_clause.span.hi();
+    let after_where = context.snippet(mk_sp(where_hi, context.snippet_provider.end_pos()));
+    let body_lo = where_hi + BytePos(after_where.find(['{', ';', '=']).unwrap_or(0) as u32);
+    if contains_comment(context.snippet(generics.span))
+        || contains_comment(context.snippet(mk_sp(generics.span.hi(), body_lo)))
+    {
+        return Cow::Borrowed(generics);
+    }
+
+    let is_bounded_type_param = |param: &ast::GenericParam| {
+        matches!(param.kind, ast::GenericParamKind::Type { .. }) && !param.bounds.is_empty()
+    };
+
+    if config.force_where_clause_always() {
+        if !generics.params.iter().any(is_bounded_type_param) {
+            return Cow::Borrowed(generics);
+        }
+
+        let mut generics = generics.clone();
+        let mut predicates = ThinVec::new();
+        for param in generics.params.iter_mut() {
+            if !is_bounded_type_param(param) {
+                continue;
+            }
+            let bounded_ty = ast::Ty {
+                id: ast::DUMMY_NODE_ID,
+                kind: ast::TyKind::Path(None, ast::Path::from_ident(param.ident)),
+                span: param.ident.span,
+                tokens: None,
+            };
+            predicates.push(ast::WherePredicate::BoundPredicate(
+                ast::WhereBoundPredicate {
+                    span: DUMMY_SP,
+                    bound_generic_params: ThinVec::new(),
+                    bounded_ty: ptr::P(bounded_ty),
+                    bounds: std::mem::take(&mut param.bounds),
+                },
+            ));
+        }
+        predicates.extend(std::mem::take(&mut generics.where_clause.predicates));
+        generics.where_clause = ast::WhereClause {
+            has_where_token: true,
+            predicates,
+            span: generics.span.shrink_to_lo(),
+        };
+        return Cow::Owned(generics);
+    }
+
+    let pred = match generics.where_clause.predicates[..] {
+        [ast::WherePredicate::BoundPredicate(ref pred)] if pred.bound_generic_params.is_empty() => {
+            pred
+        }
+        _ => return Cow::Borrowed(generics),
+    };
+    let bounded_ident = match pred.bounded_ty.kind {
+        ast::TyKind::Path(None, ref path)
+            if path.segments.len() == 1 && path.segments[0].args.is_none() =>
+        {
+            path.segments[0].ident
+        }
+        _ => return Cow::Borrowed(generics),
+    };
+    // Params that already have bounds are skipped, so that the bounds of a param keep coming
+    // from a single place in the source.
+    let Some(index) = generics.params.iter().position(|param| {
+        matches!(param.kind, ast::GenericParamKind::Type { .. })
+            && param.ident.name == bounded_ident.name
+            && param.bounds.is_empty()
+    }) else {
+        return Cow::Borrowed(generics);
+    };
+
+    let mut generics = generics.clone();
+    generics.params[index].bounds = pred.bounds.clone();
+    generics.where_clause = ast::WhereClause {
+        has_where_token: false,
+        predicates: ThinVec::new(),
+        span: generics.span.shrink_to_lo(),
+    };
+    Cow::Owned(generics)
+}
+
+/// Whether the generics have been changed by `normalize_generics`.
+fn is_normalized_generics(generics: &ast::Generics) -> bool {
+    !generics.params.is_empty() && generics.where_clause.span == generics.span.shrink_to_lo()
+}
+
 fn generics_shape_from_config(config: &Config, shape: Shape, offset: usize) -> Option<Shape> {
     match config.indent_style() {
         IndentStyle::Visual => shape.visual_indent(1 + offset).sub_width(offset + 2),
@@ -2947,10 +3189,13 @@ fn rewrite_where_keyword(
         }
     };
 
-    let (span_before, span_after) =
-        missing_span_before_after_where(span_end_before_where, predicates, where_span);
-    let (comment_before, comment_after) =
-        rewrite_comments_before_after_where(context, span_before, span_after, shape)?;
+    let (comment_before, comment_after) = if predicates[0].span().is_dummy() {
+        (String::new(), String::new())
+    } else {
+        let (span_before, span_after) =
+            missing_span_before_after_where(span_end_before_where, predicates, where_span);
+        rewrite_comments_before_after_where(context, span_before, span_after, shape)?
+    };
 
     let starting_newline = match where_clause_option.snuggle {
         WhereClauseSpace::Space if comment_before.is_empty() => Cow::from(" "),
@@ -2981,24 +3226,7 @@ fn rewrite_bounds_on_where_clause(
     where_clause_option: WhereClauseOption,
     force_single_line: bool,
 ) -> Option<String> {
-    let span_start = predicates[0].span().lo();
-    // If we don't have the start of the next span, then use the end of the
-    // predicates, but that means we miss comments.
-    let len = predicates.len();
-    let end_of_preds = predicates[len - 1].span().hi();
-    let span_end = span_end.unwrap_or(end_of_preds);
-    let items = itemize_list(
-        context.snippet_provider,
-        predicates.iter(),
-        terminator,
-        ",",
-        |pred| pred.span().lo(),
-        |pred| pred.span().hi(),
-        |pred| pred.rewrite(context, shape),
-        span_start,
-        span_end,
-        false,
-    );
+    let items = where_predicate_items(context, predicates, shape, terminator, span_end);
     let comma_tactic = if where_clause_option.suppress_comma || force_single_line {
         SeparatorTactic::Never
     } else {
@@ -3019,7 +3247,48 @@ fn rewrite_bounds_on_where_clause(
         .tactic(shape_tactic)
         .trailing_separator(comma_tactic)
         .preserve_newline(preserve_newline);
-    write_list(&items.collect::<Vec<_>>(), &fmt)
+    write_list(&items, &fmt)
+}
+
+/// Rewrite the predicates of a where clause as list items, along with the comments around them.
+fn where_predicate_items(
+    context: &RewriteContext<'_>,
+    predicates: &[ast::WherePredicate],
+    shape: Shape,
+    terminator: &str,
+    span_end: Option<BytePos>,
+) -> Vec<ListItem> {
+    if predicates.iter().any(|pred| pred.span().is_dummy()) {
+        // Some predicates were moved here by `normalize_generics`, which only happens when
+        // there are no comments to recover.
+        return predicates
+            .iter()
+            .map(|pred| ListItem {
+                item: pred.rewrite(context, shape),
+                ..ListItem::empty()
+            })
+            .collect();
+    }
+
+    let span_start = predicates[0].span().lo();
+    // If we don't have the start of the next span, then use the end of the
+    // predicates, but that means we miss comments.
+    let len = predicates.len();
+    let end_of_preds = predicates[len - 1].span().hi();
+    let span_end = span_end.unwrap_or(end_of_preds);
+    itemize_list(
+        context.snippet_provider,
+        predicates.iter(),
+        terminator,
+        ",",
+        |pred| pred.span().lo(),
+        |pred| pred.span().hi(),
+        |pred| pred.rewrite(context, shape),
+        span_start,
+        span_end,
+        false,
+    )
+    .collect()
 }
 
 fn rewrite_where_clause(
@@ -3061,27 +3330,11 @@ fn rewrite_where_clause(
     // FIXME: if indent_style != Visual, then the budgets below might
     // be out by a char or two.
 
-    let budget = context.config.max_width() - offset.width();
-    let span_start = predicates[0].span().lo();
-    // If we don't have the start of the next span, then use the end of the
-    // predicates, but that means we miss comments.
-    let len = predicates.len();
-    let end_of_preds = predicates[len - 1].span().hi();
-    let span_end = span_end.unwrap_or(end_of_preds);
-    let items = itemize_list(
-        context.snippet_provider,
-        predicates.iter(),
-        terminator,
-        ",",
-        |pred| pred.span().lo(),
-        |pred| pred.span().hi(),
-        |pred| pred.rewrite(context, Shape::legacy(budget, offset)),
-        span_start,
-        span_end,
-        false,
-    );
-    let item_vec = items.collect::<Vec<_>>();
-    // FIXME: we don't need to collect here
+    let where_shape = Shape::try_indented(offset, context.config.max_width())
+        .map_err(|error| debug!("where clause not formatted: {error}"))
+        .ok()?;
+    let budget = where_shape.width;
+    let item_vec = where_predicate_items(context, predicates, where_shape, terminator, span_end);
     let tactic = definitive_tactic(&item_vec, ListTactic::Vertical, Separator::Comma, budget);
 
     let mut comma_tactic = context.config.trailing_comma();
@@ -3202,6 +3455,7 @@ fn format_generics(
     span: Span,
     used_width: usize,
 ) -> Option<String> {
+    let generics = &normalize_generics(context, generics);
     let shape = Shape::legacy(context.budget(used_width + offset.width()), offset);
     let mut result = rewrite_generics(context, "", generics, shape)?;
 
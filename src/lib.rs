@@ -48,13 +48,19 @@ use crate::utils::indent_next_line;
 
 pub use crate::config::{
     load_config, CliOptions, Color, Config, Edition, EmitMode, FileLines, FileName, NewlineStyle,
-    Range, Verbosity,
+    Range, ReportLevel, SummaryFormat, Verbosity,
 };
 
+pub use crate::formatting::estimate_format_time;
+
 pub use crate::format_report_formatter::{FormatReportFormatter, FormatReportFormatterBuilder};
 
 pub use crate::rustfmt_diff::{ModifiedChunk, ModifiedLines};
 
+pub use crate::comment::{format_markdown, MarkdownError};
+
+pub use crate::string::comment_break_offsets;
+
 #[macro_use]
 mod utils;
 
@@ -83,6 +89,7 @@ mod macros;
 mod matches;
 mod missed_spans;
 pub(crate) mod modules;
+mod number_separators;
 mod overflow;
 mod pairs;
 mod parse;
@@ -114,6 +121,12 @@ pub enum ErrorKind {
          (maximum: {1} (see `max_width` option), found: {0})"
     )]
     LineOverflow(usize, usize),
+    /// Line has exceeded character limit while `forbid_long_lines` is set (found, maximum).
+    #[error(
+        "line exceeded maximum width (maximum: {1} (see `max_width` option), found: {0}); \
+         long lines are forbidden by `forbid_long_lines`"
+    )]
+    ForbiddenLongLine(usize, usize),
     /// Line ends in whitespace.
     #[error("left behind trailing whitespace")]
     TrailingWhitespace,
@@ -142,11 +155,59 @@ pub enum ErrorKind {
     /// Invalid glob pattern in `ignore` configuration option.
     #[error("Invalid glob pattern found in ignore list: {0}")]
     InvalidGlobPattern(ignore::Error),
+    /// Used a wildcard import while `use_glob_imports` is not `Ignore`.
+    #[error("wildcard import found (see `use_glob_imports` option)")]
+    GlobImport(ReportLevel),
+    /// An item could not be formatted, or was formatted with overlong lines, while
+    /// `--suggest-skips` is set.
+    #[error("`{0}` could not be formatted; consider adding `#[rustfmt::skip]`")]
+    SkipSuggestion(String),
+    /// A number literal is separated differently than the other number literals of its file
+    /// while `allow_mixed_separator_in_numbers` is `false`.
+    #[error(
+        "`{0}` is separated differently than other number literals \
+         (see `allow_mixed_separator_in_numbers` option)"
+    )]
+    MixedNumberSeparators(String),
+    /// A rust code block in a doc comment could not be formatted while `error_on_unformatted`
+    /// is set.
+    #[error("code block in doc comment could not be formatted: {0}")]
+    UnformattedCodeBlock(String),
+    /// The input is too large for the parser (file, size, maximum), and `chunk_large_files` is
+    /// not set.
+    #[error(
+        "`{0}` is too large to be parsed (maximum: {2} bytes, found: {1} bytes); \
+         use `--chunk-large-files` to format it in chunks"
+    )]
+    FileTooLarge(FileName, u64, u64),
+    /// The input was too large for the parser, so it was formatted in chunks on a best-effort
+    /// basis (chunks, chunks left unformatted).
+    #[error(
+        "file is too large to be parsed, so it was formatted in {0} chunks on a best-effort \
+         basis ({1} chunks could not be formatted)"
+    )]
+    ChunkedFile(usize, usize),
+    /// The input doesn't parse with the configured edition, so it was formatted with the first
+    /// older edition it parses with (configured edition, edition used), as requested by
+    /// `detect_edition`.
+    #[error(
+        "file doesn't parse as edition {0}, so it was formatted as edition {1}; \
+         set `edition` in rustfmt.toml or pass `--edition` to format it with the right edition"
+    )]
+    DetectedEdition(Edition, Edition),
 }
 
 impl ErrorKind {
     fn is_comment(&self) -> bool {
-        matches!(self, ErrorKind::LostComment)
+        matches!(
+            self,
+            ErrorKind::LostComment | ErrorKind::UnformattedCodeBlock(_)
+        )
+    }
+
+    /// Returns `true` if this only informs about how the input was formatted.
+    fn is_note(&self) -> bool {
+        matches!(self, ErrorKind::DetectedEdition(..))
     }
 }
 
@@ -221,7 +282,10 @@ impl FormatReport {
         if !new_errors.is_empty() {
             errs.has_formatting_errors = true;
         }
-        if errs.has_operational_errors && errs.has_check_errors && errs.has_unformatted_code_errors
+        if errs.has_operational_errors
+            && errs.has_check_errors
+            && errs.has_unformatted_code_errors
+            && errs.has_long_line_errors
         {
             return;
         }
@@ -230,6 +294,9 @@ impl FormatReport {
                 ErrorKind::LineOverflow(..) => {
                     errs.has_operational_errors = true;
                 }
+                ErrorKind::ForbiddenLongLine(..) => {
+                    errs.has_long_line_errors = true;
+                }
                 ErrorKind::TrailingWhitespace => {
                     errs.has_operational_errors = true;
                     errs.has_unformatted_code_errors = true;
@@ -240,6 +307,9 @@ impl FormatReport {
                 ErrorKind::DeprecatedAttr | ErrorKind::BadAttr | ErrorKind::VersionMismatch => {
                     errs.has_check_errors = true;
                 }
+                ErrorKind::GlobImport(ReportLevel::Error) | ErrorKind::MixedNumberSeparators(_) => {
+                    errs.has_operational_errors = true;
+                }
                 _ => {}
             }
         }
@@ -257,13 +327,41 @@ impl FormatReport {
         self.internal.borrow_mut().1.has_parsing_errors = true;
     }
 
+    /// The number of errors and warnings in the report, leaving out notes.
     fn warning_count(&self) -> usize {
         self.internal
             .borrow()
             .0
             .values()
-            .map(|errors| errors.len())
-            .sum()
+            .flatten()
+            .filter(|error| !error.kind.is_note())
+            .count()
+    }
+
+    /// The number of `#[rustfmt::skip]` suggestions in the report.
+    fn skip_suggestion_count(&self) -> usize {
+        self.internal
+            .borrow()
+            .0
+            .values()
+            .flatten()
+            .filter(|error| matches!(error.kind, ErrorKind::SkipSuggestion(_)))
+            .count()
+    }
+
+    /// Whether an error of the same kind as `error` was already reported at its line, e.g.
+    /// while a node was rewritten a second time.
+    fn contains_error(&self, file_name: &FileName, error: &FormattingError) -> bool {
+        self.internal
+            .borrow()
+            .0
+            .get(file_name)
+            .map_or(false, |errors| {
+                errors.iter().any(|reported| {
+                    reported.line == error.line
+                        && reported.kind.to_string() == error.kind.to_string()
+                })
+            })
     }
 
     /// Whether any warnings or errors are present in the report.
@@ -309,6 +407,7 @@ fn format_snippet(snippet: &str, config: &Config, is_macro_def: bool) -> Option<
         config.set().emit_mode(config::EmitMode::Stdout);
         config.set().verbose(Verbosity::Quiet);
         config.set().show_parse_errors(false);
+        config.set().detect_edition(false);
         if is_macro_def {
             config.set().error_on_unformatted(true);
         }
@@ -339,6 +438,20 @@ fn format_snippet(snippet: &str, config: &Config, is_macro_def: bool) -> Option<
     .ok()?
 }
 
+/// Why a code block could not be formatted by `try_format_code_block`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CodeBlockError {
+    /// The 1-based line of the code block the error points at, if it is known.
+    pub(crate) line: Option<usize>,
+    pub(crate) message: String,
+}
+
+impl fmt::Display for CodeBlockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
 /// Format the given code block. Mainly targeted for code block in comment.
 /// The code block may be incomplete (i.e., parser may be unable to parse it).
 /// To avoid panic in parser, we wrap the code block with a dummy function.
@@ -348,6 +461,48 @@ fn format_code_block(
     config: &Config,
     is_macro_def: bool,
 ) -> Option<FormattedSnippet> {
+    try_format_code_block(code_snippet, config, is_macro_def).ok()
+}
+
+/// Splits a leading shebang line off a code block. A line starting with `#![` is an inner
+/// attribute rather than a shebang.
+fn split_shebang(code_snippet: &str) -> Option<(&str, &str)> {
+    let rest = code_snippet.strip_prefix("#!")?;
+    if rest.trim_start().starts_with('[') {
+        return None;
+    }
+    Some(code_snippet.split_once('\n').unwrap_or((code_snippet, "")))
+}
+
+/// Like `format_code_block`, but tells why the code block could not be formatted.
+fn try_format_code_block(
+    code_snippet: &str,
+    config: &Config,
+    is_macro_def: bool,
+) -> Result<FormattedSnippet, CodeBlockError> {
+    // A shebang can't be put in the wrapping `fn main()`, so it is kept out of the code to
+    // format and put back in front of the result.
+    if let Some((shebang, rest)) = split_shebang(code_snippet) {
+        let mut formatted =
+            try_format_code_block(rest, config, is_macro_def).map_err(|e| CodeBlockError {
+                line: e.line.map(|line| line + 1),
+                ..e
+            })?;
+        formatted.snippet = if formatted.snippet.is_empty() {
+            shebang.trim_end().to_owned()
+        } else {
+            format!("{}\n{}", shebang.trim_end(), formatted.snippet)
+        };
+        formatted
+            .non_formatted_ranges
+            .iter_mut()
+            .for_each(|(low, high)| {
+                *low += 1;
+                *high += 1;
+            });
+        return Ok(formatted);
+    }
+
     const FN_MAIN_PREFIX: &str = "fn main() {\n";
 
     fn enclose_in_main_block(s: &str, config: &Config) -> String {
@@ -380,7 +535,11 @@ fn format_code_block(
     config_with_unix_newline
         .set()
         .newline_style(NewlineStyle::Unix);
-    let mut formatted = format_snippet(&snippet, &config_with_unix_newline, is_macro_def)?;
+    let mut formatted = format_snippet(&snippet, &config_with_unix_newline, is_macro_def)
+        .ok_or_else(|| CodeBlockError {
+            line: None,
+            message: "the code could not be parsed or formatted".to_owned(),
+        })?;
     // Remove wrapping main block
     formatted.unwrap_code_block();
 
@@ -410,8 +569,20 @@ fn format_code_block(
             // If there are lines that are larger than max width, we cannot tell
             // whether we have succeeded but have some comments or strings that
             // are too long, or we have failed to format code block. We will be
-            // conservative and just return `None` in this case.
-            return None;
+            // conservative and just return an error in this case. The overlong line is
+            // usually a string or a comment that was kept as is, so look it up in the
+            // original code.
+            let trimmed = line.trim();
+            return Err(CodeBlockError {
+                line: code_snippet
+                    .lines()
+                    .position(|original| original.trim() == trimmed)
+                    .map(|i| i + 1),
+                message: format!(
+                    "a line exceeds the maximum width of {} characters",
+                    config.max_width()
+                ),
+            });
         } else if line.len() > indent_str.len() {
             // Make sure that the line has leading whitespaces.
             if line.starts_with(indent_str.as_ref()) {
@@ -430,7 +601,7 @@ fn format_code_block(
         result.push_str(trimmed_line);
         is_indented = indent_next_line(kind, line, config);
     }
-    Some(FormattedSnippet {
+    Ok(FormattedSnippet {
         snippet: result,
         non_formatted_ranges: formatted.non_formatted_ranges,
     })
@@ -506,6 +677,10 @@ impl<'b, T: Write + 'b> Session<'b, T> {
         self.errors.has_unformatted_code_errors
     }
 
+    pub fn has_long_line_errors(&self) -> bool {
+        self.errors.has_long_line_errors
+    }
+
     pub fn has_no_errors(&self) -> bool {
         !(self.has_operational_errors()
             || self.has_parsing_errors()
@@ -513,6 +688,7 @@ impl<'b, T: Write + 'b> Session<'b, T> {
             || self.has_check_errors()
             || self.has_diff()
             || self.has_unformatted_code_errors()
+            || self.has_long_line_errors()
             || self.errors.has_macro_format_failure)
     }
 }
@@ -525,9 +701,7 @@ pub(crate) fn create_emitter<'a>(config: &Config) -> Box<dyn Emitter + 'a> {
         EmitMode::Files => Box::new(emitter::FilesEmitter::new(
             config.print_misformatted_file_names(),
         )),
-        EmitMode::Stdout | EmitMode::Coverage => {
-            Box::new(emitter::StdoutEmitter::new(config.verbose()))
-        }
+        EmitMode::Stdout | EmitMode::Coverage => Box::new(emitter::StdoutEmitter::new(config)),
         EmitMode::Json => Box::new(emitter::JsonEmitter::default()),
         EmitMode::ModifiedLines => Box::new(emitter::ModifiedLinesEmitter::default()),
         EmitMode::Checkstyle => Box::new(emitter::CheckstyleEmitter::default()),
@@ -543,7 +717,7 @@ impl<'b, T: Write + 'b> Drop for Session<'b, T> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Input {
     File(PathBuf),
     Text(String),
@@ -618,6 +792,34 @@ mod unit_tests {
         assert!(format_code_block(code_block, &Config::default(), false).is_none());
     }
 
+    #[test]
+    fn test_format_code_block_with_shebang() {
+        let code_block = "#!/usr/bin/env run-cargo-script\nfn  main( ) { let x=1; }";
+        let expected = "#!/usr/bin/env run-cargo-script\nfn main() {\n    let x = 1;\n}";
+        assert!(test_format_inner(format_code_block, code_block, expected));
+
+        let code_block = "#![allow(dead_code)]\nfn  foo( ) {}";
+        let expected = "#![allow(dead_code)]\nfn foo() {}";
+        assert!(test_format_inner(format_code_block, code_block, expected));
+        assert_eq!(split_shebang("#! [allow(dead_code)]"), None);
+    }
+
+    #[test]
+    fn test_try_format_code_block_error() {
+        let error = try_format_code_block("let x = ;", &Config::default(), false).unwrap_err();
+        assert_eq!(error.line, None);
+
+        let mut config = Config::default();
+        config.set().max_width(40);
+        let code_block = "let x = 1;\nlet s = \"a string literal that is too long to fit\";";
+        let error = try_format_code_block(code_block, &config, false).unwrap_err();
+        assert_eq!(error.line, Some(2));
+        assert_eq!(
+            error.message,
+            "a line exceeds the maximum width of 40 characters"
+        );
+    }
+
     #[test]
     fn test_format_code_block() {
         // simple code block
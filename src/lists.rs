@@ -842,7 +842,7 @@ pub(crate) fn total_item_width(item: &ListItem) -> usize {
 fn comment_len(comment: Option<&str>) -> usize {
     match comment {
         Some(s) => {
-            let text_len = s.trim().len();
+            let text_len = unicode_str_width(s.trim());
             if text_len > 0 {
                 // We'll put " /*" before and " */" after inline comments.
                 text_len + 6
@@ -240,12 +240,7 @@ fn rewrite_macro_inner(
     } = match parse_macro_args(context, ts, style, is_forced_bracket) {
         Some(args) => args,
         None => {
-            return return_macro_parse_failure_fallback(
-                context,
-                shape.indent,
-                position,
-                mac.span(),
-            );
+            return return_macro_parse_failure_fallback(context, shape.indent, position, mac.span());
         }
     };
 
@@ -1285,7 +1280,10 @@ impl MacroBranch {
         } else {
             shape.indent.block_indent(&config)
         };
-        let new_width = config.max_width() - body_indent.width();
+        let new_width = Shape::try_indented(body_indent, config.max_width())
+            .map_err(|error| debug!("macro body not formatted: {error}"))
+            .ok()?
+            .width;
         config.set().max_width(new_width);
 
         // First try to format as items, then as statements.
@@ -1,9 +1,12 @@
 use rustc_span::{BytePos, Pos, Span};
 
-use crate::comment::{is_last_comment_block, rewrite_comment, CodeCharKind, CommentCodeSlices};
+use crate::comment::{
+    is_last_comment_block, is_strippable_comment_line, rewrite_comment, CodeCharKind,
+    CommentCodeSlices,
+};
 use crate::config::file_lines::FileLines;
 use crate::config::FileName;
-use crate::config::Version;
+use crate::config::{CommentOnlyLines, Version};
 use crate::coverage::transform_missing_snippet;
 use crate::shape::{Indent, Shape};
 use crate::source_map::LineRangeUtils;
@@ -237,6 +240,17 @@ impl<'a> FmtVisitor<'a> {
             .find(|rev_c| ![' ', '\t'].contains(rev_c));
 
         let fix_indent = last_char.map_or(true, |rev_c| ['{', '\n'].contains(&rev_c));
+        if fix_indent && self.is_strippable_comment(subslice) {
+            status.line_start = offset + subslice.len();
+            status.cur_line += count_newlines(subslice);
+            return;
+        }
+        if let Some(len) = self.trailing_comment_len(subslice).filter(|_| !fix_indent) {
+            self.process_comment(status, snippet, big_snippet, offset, &subslice[..=len]);
+            status.line_start = offset + subslice.len();
+            status.cur_line += count_newlines(&subslice[len + 1..]);
+            return;
+        }
         let mut on_same_line = false;
 
         let comment_indent = if fix_indent {
@@ -258,10 +272,14 @@ impl<'a> FmtVisitor<'a> {
             Indent::from_width(self.config, last_line_width(&self.buffer))
         };
 
-        let comment_width = ::std::cmp::min(
-            self.config.comment_width(),
-            self.config.max_width() - self.block_indent.width(),
-        );
+        // A comment indented past `max_width` has no room left.
+        let comment_width = match Shape::try_indented(self.block_indent, self.config.max_width()) {
+            Ok(shape) => shape.width.min(self.config.comment_width()),
+            Err(error) => {
+                debug!("comment given no width: {error}");
+                0
+            }
+        };
         let comment_shape = Shape::legacy(comment_width, comment_indent);
 
         if on_same_line {
@@ -316,6 +334,27 @@ impl<'a> FmtVisitor<'a> {
         status.cur_line += count_newlines(subslice);
     }
 
+    /// Returns `true` if `comment`, which is on lines of its own, is removed by
+    /// `comment_only_lines_in_fn = Strip`.
+    pub(crate) fn is_strippable_comment(&self, comment: &str) -> bool {
+        if !self.in_fn_body || self.config.comment_only_lines_in_fn() != CommentOnlyLines::Strip {
+            return false;
+        }
+        comment
+            .lines()
+            .map(str::trim)
+            .all(|line| line.is_empty() || is_strippable_comment_line(line, self.config))
+    }
+
+    /// Returns the length of the first line of `comment`, a comment that trails code, if the
+    /// comment lines following it are removed by `comment_only_lines_in_fn = Strip`. They are part
+    /// of the same comment, but they are on lines of their own.
+    pub(crate) fn trailing_comment_len(&self, comment: &str) -> Option<usize> {
+        let len = comment.find('\n')?;
+        let own_lines = &comment[len + 1..];
+        (!own_lines.trim().is_empty() && self.is_strippable_comment(own_lines)).then_some(len)
+    }
+
     fn process_missing_code(
         &mut self,
         status: &mut SnippetStatus,
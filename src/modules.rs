@@ -11,12 +11,13 @@ use thiserror::Error;
 
 use crate::attr::MetaVisitor;
 use crate::config::FileName;
+use crate::formatting::MAX_SOURCE_LEN;
 use crate::items::is_mod_decl;
 use crate::parse::parser::{
     Directory, DirectoryOwnership, ModError, ModulePathSuccess, Parser, ParserError,
 };
 use crate::parse::session::ParseSess;
-use crate::utils::{contains_skip, mk_sp};
+use crate::utils::{contains_cfg_test, contains_skip, mk_sp};
 
 mod visitor;
 
@@ -29,6 +30,8 @@ pub(crate) struct Module<'a> {
     pub(crate) items: Cow<'a, ThinVec<rustc_ast::ptr::P<ast::Item>>>,
     inner_attr: ast::AttrVec,
     pub(crate) span: Span,
+    /// Whether the module is declared inside a module that is only compiled for tests.
+    in_test_mod: bool,
 }
 
 impl<'a> Module<'a> {
@@ -48,12 +51,18 @@ impl<'a> Module<'a> {
             inner_attr,
             span: mod_span,
             ast_mod_kind,
+            in_test_mod: false,
         }
     }
 
     pub(crate) fn attrs(&self) -> &[ast::Attribute] {
         &self.inner_attr
     }
+
+    /// Returns `true` if the module is only compiled for tests.
+    pub(crate) fn is_test_mod(&self) -> bool {
+        self.in_test_mod || contains_cfg_test(&self.inner_attr)
+    }
 }
 
 /// Maps each module to the corresponding file.
@@ -62,6 +71,8 @@ pub(crate) struct ModResolver<'ast, 'psess> {
     directory: Directory,
     file_map: FileModMap<'ast>,
     recursive: bool,
+    /// Whether the modules being visited are only compiled for tests.
+    in_test_mod: bool,
 }
 
 /// Represents errors while trying to resolve modules.
@@ -81,6 +92,11 @@ pub(crate) enum ModuleResolutionErrorKind {
     /// File cannot be found.
     #[error("{file} does not exist")]
     NotFound { file: PathBuf },
+    /// File is too large to be parsed.
+    #[error(
+        "{file} is too large to be parsed (maximum: {MAX_SOURCE_LEN} bytes, found: {len} bytes)"
+    )]
+    FileTooLarge { file: PathBuf, len: u64 },
     /// File a.rs and a/mod.rs both exist
     #[error("file for module found at both {default_path:?} and {secondary_path:?}")]
     MultipleCandidates {
@@ -114,6 +130,7 @@ impl<'ast, 'psess, 'c> ModResolver<'ast, 'psess> {
             file_map: BTreeMap::new(),
             psess,
             recursive,
+            in_test_mod: false,
         }
     }
 
@@ -226,12 +243,15 @@ impl<'ast, 'psess, 'c> ModResolver<'ast, 'psess> {
         sub_mod: Module<'ast>,
     ) -> Result<(), ModuleResolutionError> {
         let old_directory = self.directory.clone();
+        let old_in_test_mod = self.in_test_mod;
+        self.in_test_mod |= contains_cfg_test(&item.attrs);
         let sub_mod_kind = self.peek_sub_mod(item, &sub_mod)?;
         if let Some(sub_mod_kind) = sub_mod_kind {
             self.insert_sub_mod(sub_mod_kind.clone())?;
             self.visit_sub_mod_inner(sub_mod, sub_mod_kind)?;
         }
         self.directory = old_directory;
+        self.in_test_mod = old_in_test_mod;
         Ok(())
     }
 
@@ -260,13 +280,15 @@ impl<'ast, 'psess, 'c> ModResolver<'ast, 'psess> {
         sub_mod_kind: SubModKind<'c, 'ast>,
     ) -> Result<(), ModuleResolutionError> {
         match sub_mod_kind {
-            SubModKind::External(mod_path, _, sub_mod) => {
+            SubModKind::External(mod_path, _, mut sub_mod) => {
+                sub_mod.in_test_mod = self.in_test_mod;
                 self.file_map
                     .entry(FileName::Real(mod_path))
                     .or_insert(sub_mod);
             }
             SubModKind::MultiExternal(mods) => {
-                for (mod_path, _, sub_mod) in mods {
+                for (mod_path, _, mut sub_mod) in mods {
+                    sub_mod.in_test_mod = self.in_test_mod;
                     self.file_map
                         .entry(FileName::Real(mod_path))
                         .or_insert(sub_mod);
@@ -357,6 +379,10 @@ impl<'ast, 'psess, 'c> ModResolver<'ast, 'psess> {
                     module: mod_name.to_string(),
                     kind: ModuleResolutionErrorKind::ParseError { file: path },
                 }),
+                Err(ParserError::FileTooLarge(len)) => Err(ModuleResolutionError {
+                    module: mod_name.to_string(),
+                    kind: ModuleResolutionErrorKind::FileTooLarge { file: path, len },
+                }),
                 Err(..) => Err(ModuleResolutionError {
                     module: mod_name.to_string(),
                     kind: ModuleResolutionErrorKind::NotFound { file: path },
@@ -424,6 +450,13 @@ impl<'ast, 'psess, 'c> ModResolver<'ast, 'psess> {
                         module: mod_name.to_string(),
                         kind: ModuleResolutionErrorKind::ParseError { file: file_path },
                     }),
+                    Err(ParserError::FileTooLarge(len)) => Err(ModuleResolutionError {
+                        module: mod_name.to_string(),
+                        kind: ModuleResolutionErrorKind::FileTooLarge {
+                            file: file_path,
+                            len,
+                        },
+                    }),
                     Err(..) if outside_mods_empty => Err(ModuleResolutionError {
                         module: mod_name.to_string(),
                         kind: ModuleResolutionErrorKind::NotFound { file: file_path },
@@ -0,0 +1,98 @@
+//! Checks that the number literals of a file agree on whether they use `_` separators.
+
+use rustc_ast::visit::{self, AssocCtxt, Visitor};
+use rustc_ast::{ast, ptr, token};
+use rustc_span::Span;
+
+use crate::formatting::FormattingError;
+use crate::utils::contains_skip;
+use crate::visitor::FmtVisitor;
+use crate::ErrorKind;
+
+/// Literals whose integer part has fewer digits than this are too short to be separated, so
+/// they are consistent with either style.
+const MIN_SEPARABLE_DIGITS: usize = 5;
+
+/// Collects the long number literals of a file, skipping `#[rustfmt::skip]`ed nodes and
+/// out-of-line modules, which are checked as files of their own.
+#[derive(Default)]
+struct NumberLiteralCollector {
+    literals: Vec<(Span, bool)>,
+}
+
+impl<'ast> Visitor<'ast> for NumberLiteralCollector {
+    fn visit_item(&mut self, item: &'ast ast::Item) {
+        let is_out_of_line_mod = matches!(
+            item.kind,
+            ast::ItemKind::Mod(_, ast::ModKind::Unloaded)
+                | ast::ItemKind::Mod(_, ast::ModKind::Loaded(_, ast::Inline::No, _))
+        );
+        if !is_out_of_line_mod && !contains_skip(&item.attrs) {
+            visit::walk_item(self, item);
+        }
+    }
+
+    fn visit_assoc_item(&mut self, item: &'ast ast::AssocItem, ctxt: AssocCtxt) {
+        if !contains_skip(&item.attrs) {
+            visit::walk_assoc_item(self, item, ctxt);
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &'ast ast::Expr) {
+        if contains_skip(&expr.attrs) {
+            return;
+        }
+        if let ast::ExprKind::Lit(token_lit) = expr.kind {
+            if let Some(is_separated) = is_separated(token_lit) {
+                self.literals.push((expr.span, is_separated));
+            }
+        }
+        visit::walk_expr(self, expr);
+    }
+}
+
+/// Returns whether the integer part of a number literal contains `_` separators, or `None`
+/// if `lit` is not a number literal long enough to be separated.
+fn is_separated(lit: token::Lit) -> Option<bool> {
+    let symbol = lit.symbol.as_str();
+    let integer_part = match lit.kind {
+        token::LitKind::Integer => ["0x", "0o", "0b"]
+            .iter()
+            .find_map(|prefix| symbol.strip_prefix(prefix))
+            .unwrap_or(symbol),
+        token::LitKind::Float => symbol.split(['.', 'e', 'E']).next().unwrap_or(symbol),
+        _ => return None,
+    };
+    let digits = integer_part.chars().filter(|&c| c != '_').count();
+    (digits >= MIN_SEPARABLE_DIGITS).then(|| integer_part.contains('_'))
+}
+
+impl<'a> FmtVisitor<'a> {
+    /// Reports every number literal of the file containing `items` whose use of `_` separators
+    /// differs from the first one, as requested by `allow_mixed_separator_in_numbers`.
+    pub(crate) fn report_mixed_number_separators(&mut self, items: &[ptr::P<ast::Item>]) {
+        if self.config.allow_mixed_separator_in_numbers() {
+            return;
+        }
+
+        let mut collector = NumberLiteralCollector::default();
+        items.iter().for_each(|item| collector.visit_item(item));
+        let Some(&(first_span, expected)) = collector.literals.first() else {
+            return;
+        };
+
+        let errors: Vec<_> = collector
+            .literals
+            .into_iter()
+            .filter(|&(_, is_separated)| is_separated != expected)
+            .map(|(span, _)| {
+                let lit = self.snippet(span).to_owned();
+                FormattingError::from_span(span, self.psess, ErrorKind::MixedNumberSeparators(lit))
+            })
+            .collect();
+        if !errors.is_empty() {
+            let file_name = self.psess.span_to_filename(first_span);
+            self.report.append(file_name, errors);
+        }
+    }
+}
@@ -8,8 +8,8 @@ use rustc_ast::{ast, ptr};
 use rustc_span::Span;
 
 use crate::closures;
-use crate::config::Version;
 use crate::config::{lists::*, Config};
+use crate::config::{ArrayLayout, Version};
 use crate::expr::{
     can_be_overflowed_expr, is_every_expr_simple, is_method_call, is_nested_call, is_simple_expr,
     rewrite_cond,
@@ -280,6 +280,31 @@ pub(crate) fn rewrite_with_parens<'a, T: 'a + IntoOverflowableItem<'a>>(
     .rewrite(shape)
 }
 
+/// Like `rewrite_with_parens`, but always puts each item on its own line.
+pub(crate) fn rewrite_vertically_with_parens<'a, T: 'a + IntoOverflowableItem<'a>>(
+    context: &'a RewriteContext<'_>,
+    ident: &'a str,
+    items: impl Iterator<Item = &'a T>,
+    shape: Shape,
+    span: Span,
+    force_separator_tactic: Option<SeparatorTactic>,
+) -> Option<String> {
+    let mut overflow_context = Context::new(
+        context,
+        items,
+        ident,
+        shape,
+        span,
+        "(",
+        ")",
+        context.config.fn_call_width(),
+        force_separator_tactic,
+        None,
+    );
+    overflow_context.one_line_width = 0;
+    overflow_context.rewrite(shape)
+}
+
 pub(crate) fn rewrite_with_angle_brackets<'a, T: 'a + IntoOverflowableItem<'a>>(
     context: &'a RewriteContext<'_>,
     ident: &'a str,
@@ -316,7 +341,7 @@ pub(crate) fn rewrite_with_square_brackets<'a, T: 'a + IntoOverflowableItem<'a>>
         Some(Delimiter::Brace) => ("{", "}"),
         _ => ("[", "]"),
     };
-    Context::new(
+    let mut overflow_context = Context::new(
         context,
         items,
         name,
@@ -327,8 +352,9 @@ pub(crate) fn rewrite_with_square_brackets<'a, T: 'a + IntoOverflowableItem<'a>>
         context.config.array_width(),
         force_separator_tactic,
         Some(("[", "]")),
-    )
-    .rewrite(shape)
+    );
+    overflow_context.array_layout = Some(context.config.array_layout());
+    overflow_context.rewrite(shape)
 }
 
 struct Context<'a> {
@@ -344,6 +370,8 @@ struct Context<'a> {
     one_line_width: usize,
     force_separator_tactic: Option<SeparatorTactic>,
     custom_delims: Option<(&'a str, &'a str)>,
+    /// Set when the items are the elements of an array.
+    array_layout: Option<ArrayLayout>,
 }
 
 impl<'a> Context<'a> {
@@ -382,6 +410,7 @@ impl<'a> Context<'a> {
             one_line_width,
             force_separator_tactic,
             custom_delims,
+            array_layout: None,
         }
     }
 
@@ -582,12 +611,7 @@ impl<'a> Context<'a> {
                             if one_line {
                                 tactic = DefinitiveListTactic::SpecialMacro(num_args_before);
                             };
-                        } else if is_every_expr_simple(&self.items)
-                            && no_long_items(
-                                list_items,
-                                self.context.config.short_array_element_width_threshold(),
-                            )
-                        {
+                        } else if self.is_mixed_layout(list_items) {
                             tactic = DefinitiveListTactic::Mixed;
                         }
                     }
@@ -599,6 +623,27 @@ impl<'a> Context<'a> {
         tactic
     }
 
+    /// Returns `true` if the items that don't fit on one line should be put several to a line.
+    fn is_mixed_layout(&self, list_items: &[ListItem]) -> bool {
+        // `self.one_line_width == 0` means vertical layout is forced.
+        if self.one_line_width == 0 {
+            return false;
+        }
+        match self.array_layout {
+            Some(ArrayLayout::Tall) => false,
+            Some(ArrayLayout::Compressed) => list_items
+                .iter()
+                .all(|item| !item.has_comment() && !item.inner_as_ref().contains('\n')),
+            Some(ArrayLayout::AutoCompact) | None => {
+                is_every_expr_simple(&self.items)
+                    && no_long_items(
+                        list_items,
+                        self.context.config.short_array_element_width_threshold(),
+                    )
+            }
+        }
+    }
+
     fn rewrite_items(&self) -> Option<(bool, String)> {
         let span = self.items_span();
         debug!("items: {:?}", self.items);
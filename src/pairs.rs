@@ -42,12 +42,18 @@ pub(crate) fn rewrite_all_pairs(
     context: &RewriteContext<'_>,
 ) -> Option<String> {
     expr.flatten(context, shape).and_then(|list| {
-        if list.let_chain_count() > 0 && !list.can_rewrite_let_chain_single_line() {
-            rewrite_pairs_multiline(&list, shape, context)
+        let is_let_chain = list.let_chain_count() > 0;
+        let indent_style = if is_let_chain {
+            context.config.let_chain_indent_style()
+        } else {
+            context.config.indent_style()
+        };
+        if is_let_chain && !list.can_rewrite_let_chain_single_line() {
+            rewrite_pairs_multiline(&list, shape, context, indent_style)
         } else {
             // First we try formatting on one line.
             rewrite_pairs_one_line(&list, shape, context)
-                .or_else(|| rewrite_pairs_multiline(&list, shape, context))
+                .or_else(|| rewrite_pairs_multiline(&list, shape, context, indent_style))
         }
     })
 }
@@ -104,9 +110,10 @@ fn rewrite_pairs_multiline<T: Rewrite>(
     list: &PairList<'_, '_, T>,
     shape: Shape,
     context: &RewriteContext<'_>,
+    indent_style: IndentStyle,
 ) -> Option<String> {
     let rhs_offset = shape.rhs_overhead(context.config);
-    let nested_shape = (match context.config.indent_style() {
+    let nested_shape = (match indent_style {
         IndentStyle::Visual => shape.visual_indent(0),
         IndentStyle::Block => shape.block_indent(context.config.tab_spaces()),
     })
@@ -1,3 +1,4 @@
+use std::fs;
 use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::path::{Path, PathBuf};
 
@@ -9,6 +10,7 @@ use rustc_parse::{new_parser_from_file, new_parser_from_source_str, unwrap_or_em
 use rustc_span::{sym, Span};
 use thin_vec::ThinVec;
 
+use crate::formatting::MAX_SOURCE_LEN;
 use crate::parse::session::ParseSess;
 use crate::Input;
 
@@ -82,6 +84,8 @@ pub(crate) enum ParserError {
     ParserCreationError,
     ParseError,
     ParsePanicError,
+    /// The file is too large for the parser (size).
+    FileTooLarge(u64),
 }
 
 impl<'a> Parser<'a> {
@@ -104,6 +108,12 @@ impl<'a> Parser<'a> {
         path: &Path,
         span: Span,
     ) -> Result<(ast::AttrVec, ThinVec<ptr::P<ast::Item>>, Span), ParserError> {
+        // The parser aborts on a file it can't address, so check the size of every module file
+        // before loading it, like the size of the root file is checked.
+        let len = fs::metadata(path).map_or(0, |metadata| metadata.len());
+        if len > MAX_SOURCE_LEN {
+            return Err(ParserError::FileTooLarge(len));
+        }
         let result = catch_unwind(AssertUnwindSafe(|| {
             let mut parser =
                 unwrap_or_emit_fatal(new_parser_from_file(psess.inner(), path, Some(span)));
@@ -1,5 +1,8 @@
-use std::path::Path;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 use rustc_data_structures::sync::{IntoDynSyncSend, Lrc};
 use rustc_errors::emitter::{stderr_destination, DynEmitter, Emitter, HumanEmitter, SilentEmitter};
@@ -7,12 +10,13 @@ use rustc_errors::translation::Translate;
 use rustc_errors::{ColorConfig, Diag, DiagCtxt, DiagInner, Level as DiagnosticLevel};
 use rustc_session::parse::ParseSess as RawParseSess;
 use rustc_span::{
-    source_map::{FilePathMapping, SourceMap},
-    symbol, BytePos, Span,
+    source_map::{FileLoader, FilePathMapping, RealFileLoader, SourceMap, SourceMapInputs},
+    symbol, BytePos, SourceFileHashAlgorithm, Span,
 };
 
 use crate::config::file_lines::LineRange;
 use crate::config::options::Color;
+use crate::formatting::needs_raw_input_text;
 use crate::ignore_path::IgnorePathSet;
 use crate::parse::parser::{ModError, ModulePathSuccess};
 use crate::source_map::LineRangeUtils;
@@ -25,6 +29,35 @@ pub(crate) struct ParseSess {
     raw_psess: RawParseSess,
     ignore_path_set: Lrc<IgnorePathSet>,
     can_reset_errors: Lrc<AtomicBool>,
+    raw_texts: Option<RawTexts>,
+}
+
+type RawTexts = Arc<Mutex<HashMap<PathBuf, String>>>;
+
+/// Loads files like rustc does, but also keeps the text of each one as it was read from disk.
+/// The parser normalizes `\r\n` to `\n`, so this is the only place the original line endings
+/// can be recovered from.
+struct RawTextFileLoader {
+    raw_texts: RawTexts,
+}
+
+impl FileLoader for RawTextFileLoader {
+    fn file_exists(&self, path: &Path) -> bool {
+        RealFileLoader.file_exists(path)
+    }
+
+    fn read_file(&self, path: &Path) -> io::Result<String> {
+        let text = RealFileLoader.read_file(path)?;
+        self.raw_texts
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), text.clone());
+        Ok(text)
+    }
+
+    fn read_binary_file(&self, path: &Path) -> io::Result<Lrc<[u8]>> {
+        RealFileLoader.read_binary_file(path)
+    }
 }
 
 /// Emit errors against every files expect ones specified in the `ignore_path_set`.
@@ -141,7 +174,18 @@ impl ParseSess {
             Ok(ignore_path_set) => Lrc::new(ignore_path_set),
             Err(e) => return Err(ErrorKind::InvalidGlobPattern(e)),
         };
-        let source_map = Lrc::new(SourceMap::new(FilePathMapping::empty()));
+        let raw_texts = needs_raw_input_text(config).then(RawTexts::default);
+        let file_loader: Box<dyn FileLoader + Send + Sync> = match raw_texts {
+            Some(ref raw_texts) => Box::new(RawTextFileLoader {
+                raw_texts: Arc::clone(raw_texts),
+            }),
+            None => Box::new(RealFileLoader),
+        };
+        let source_map = Lrc::new(SourceMap::with_inputs(SourceMapInputs {
+            file_loader,
+            path_mapping: FilePathMapping::empty(),
+            hash_kind: SourceFileHashAlgorithm::Md5,
+        }));
         let can_reset_errors = Lrc::new(AtomicBool::new(false));
 
         let dcx = default_dcx(
@@ -157,9 +201,16 @@ impl ParseSess {
             raw_psess,
             ignore_path_set,
             can_reset_errors,
+            raw_texts,
         })
     }
 
+    /// Takes the text of `path` as it was read from disk, before the parser normalized its line
+    /// endings. Only available when `needs_raw_input_text` is `true`.
+    pub(crate) fn take_raw_text(&self, path: &Path) -> Option<String> {
+        self.raw_texts.as_ref()?.lock().unwrap().remove(path)
+    }
+
     /// Determine the submodule path for the given module identifier.
     ///
     /// * `id` - The name of the module
@@ -5,7 +5,7 @@ use rustc_span::{BytePos, Span};
 use crate::comment::{combine_strs_with_missing_comments, FindUncommented};
 use crate::config::lists::*;
 use crate::config::Version;
-use crate::expr::{can_be_overflowed_expr, rewrite_unary_prefix, wrap_struct_field};
+use crate::expr::{can_be_overflowed_expr, is_tall_tuple, rewrite_unary_prefix, wrap_struct_field};
 use crate::lists::{
     definitive_tactic, itemize_list, shape_for_tactic, struct_lit_formatting, struct_lit_shape,
     struct_lit_tactic, write_list, ListFormatting, ListItem, Separator,
@@ -18,7 +18,7 @@ use crate::shape::Shape;
 use crate::source_map::SpanUtils;
 use crate::spanned::Spanned;
 use crate::types::{rewrite_path, PathContext};
-use crate::utils::{format_mutability, mk_sp, mk_sp_lo_plus_one, rewrite_ident};
+use crate::utils::{format_mutability, mk_sp, mk_sp_lo_plus_one, rewrite_ident, unicode_str_width};
 
 /// Returns `true` if the given pattern is "short".
 /// A short pattern is defined by the following grammar:
@@ -357,7 +357,7 @@ fn rewrite_struct_pat(
     let has_trailing_comma = fmt.needs_trailing_separator();
 
     if ellipsis {
-        if fields_str.contains('\n') || fields_str.len() > one_line_width {
+        if fields_str.contains('\n') || unicode_str_width(&fields_str) > one_line_width {
             // Add a missing trailing comma.
             if !has_trailing_comma {
                 fields_str.push(',');
@@ -516,21 +516,34 @@ fn rewrite_tuple_pat(
 
     let is_last_pat_dotdot = pat_vec.last().map_or(false, |p| p.is_dotdot());
     let add_comma = path_str.is_none() && pat_vec.len() == 1 && !is_last_pat_dotdot;
+    let is_tall = path_str.is_none() && is_tall_tuple(context, pat_vec.len());
     let path_str = path_str.unwrap_or_default();
+    let force_tactic = if add_comma {
+        Some(SeparatorTactic::Always)
+    } else {
+        None
+    };
 
-    overflow::rewrite_with_parens(
-        context,
-        &path_str,
-        pat_vec.iter(),
-        shape,
-        span,
-        context.config.max_width(),
-        if add_comma {
-            Some(SeparatorTactic::Always)
-        } else {
-            None
-        },
-    )
+    if is_tall {
+        overflow::rewrite_vertically_with_parens(
+            context,
+            &path_str,
+            pat_vec.iter(),
+            shape,
+            span,
+            force_tactic,
+        )
+    } else {
+        overflow::rewrite_with_parens(
+            context,
+            &path_str,
+            pat_vec.iter(),
+            shape,
+            span,
+            context.config.max_width(),
+            force_tactic,
+        )
+    }
 }
 
 fn count_wildcard_suffix_len(
@@ -2,7 +2,8 @@
 //!
 //! `mod`, `extern crate` and `use` declarations are reordered in alphabetical
 //! order. Trait items are reordered in pre-determined order (associated types
-//! and constants comes before methods).
+//! and constants comes before methods). Adjacent `impl` blocks with the same
+//! header are merged when `group_consecutive_impl_blocks` is set.
 
 // FIXME(#2455): Reorder trait items.
 
@@ -11,9 +12,9 @@ use std::cmp::Ordering;
 use rustc_ast::{ast, attr};
 use rustc_span::{symbol::sym, Span};
 
-use crate::config::{Config, GroupImportsTactic};
+use crate::config::{Config, GroupImportsTactic, ImportGranularity, Version};
 use crate::imports::{normalize_use_trees_with_granularity, UseSegmentKind, UseTree};
-use crate::items::{is_mod_decl, rewrite_extern_crate, rewrite_mod};
+use crate::items::{format_impl, is_mod_decl, rewrite_extern_crate, rewrite_mod};
 use crate::lists::{itemize_list, write_list, ListFormatting, ListItem};
 use crate::rewrite::RewriteContext;
 use crate::shape::Shape;
@@ -119,7 +120,11 @@ fn rewrite_reorderable_or_regroupable_items(
                 GroupImportsTactic::StdExternalCrate => group_imports(normalized_items),
             };
 
-            if context.config.reorder_imports() {
+            if context.config.reorder_imports() && context.config.reorder_imports_by_visibility() {
+                regrouped_items.iter_mut().for_each(|items| {
+                    items.sort_by(|a, b| (a.visibility_rank(), a).cmp(&(b.visibility_rank(), b)))
+                })
+            } else if context.config.reorder_imports() {
                 regrouped_items.iter_mut().for_each(|items| items.sort())
             }
 
@@ -140,7 +145,8 @@ fn rewrite_reorderable_or_regroupable_items(
                 })
                 .collect::<Option<Vec<_>>>()?;
 
-            let join_string = format!("\n\n{}", shape.indent.to_string(context.config));
+            let blank_lines = "\n".repeat(context.config.imports_group_blank_line_threshold());
+            let join_string = format!("\n{blank_lines}{}", shape.indent.to_string(context.config));
             Some(item_vec.join(&join_string))
         }
         _ => {
@@ -198,6 +204,51 @@ fn group_imports(uts: Vec<UseTree>) -> Vec<Vec<UseTree>> {
     vec![std_imports, external_imports, local_imports]
 }
 
+/// A formatted `impl` block, split into the text before its opening brace, the
+/// formatted items and the text after them.
+struct ImplBlock {
+    header: String,
+    body: String,
+    footer: String,
+}
+
+fn split_impl_block(rewrite: &str) -> Option<ImplBlock> {
+    if let Some(header) = rewrite.strip_suffix(" {}") {
+        return Some(ImplBlock {
+            header: header.to_owned(),
+            body: String::new(),
+            footer: String::new(),
+        });
+    }
+    let open = rewrite.find("{\n")?;
+    let close = rewrite.rfind('}')?;
+    let header = &rewrite[..open];
+    if header.contains('{') || close < open + 2 {
+        return None;
+    }
+    let body = rewrite[open + 2..close].trim_end();
+    Some(ImplBlock {
+        header: header.to_owned(),
+        body: body.to_owned(),
+        footer: rewrite[open + 2 + body.len()..].to_owned(),
+    })
+}
+
+/// Joins the items of `impl` blocks with identical headers into a single block.
+fn merge_impl_blocks(blocks: &[ImplBlock]) -> Option<String> {
+    let non_empty = blocks.iter().find(|block| !block.body.is_empty())?;
+    let body = blocks
+        .iter()
+        .filter(|block| !block.body.is_empty())
+        .map(|block| block.body.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    Some(format!(
+        "{}{{\n{}{}",
+        non_empty.header, body, non_empty.footer
+    ))
+}
+
 /// A simplified version of `ast::ItemKind`.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 enum ReorderableItemKind {
@@ -226,6 +277,22 @@ impl ReorderableItemKind {
         ReorderableItemKind::from(item) == self
     }
 
+    /// Whether a change in `#[cfg(..)]` predicates ends a group. Imports that are merged or
+    /// regrouped are still combined across such changes.
+    fn splits_at_cfg(self, config: &Config) -> bool {
+        if config.version() != Version::Two {
+            return false;
+        }
+        match self {
+            ReorderableItemKind::ExternCrate => true,
+            ReorderableItemKind::Use => {
+                config.imports_granularity() == ImportGranularity::Preserve
+                    && config.group_imports() == GroupImportsTactic::Preserve
+            }
+            ReorderableItemKind::Mod | ReorderableItemKind::Other => false,
+        }
+    }
+
     fn is_reorderable(self, config: &Config) -> bool {
         match self {
             ReorderableItemKind::ExternCrate => config.reorder_imports(),
@@ -264,10 +331,13 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
         in_group: bool,
     ) -> usize {
         let mut last = self.psess.lookup_line_range(items[0].span());
+        let splits_at_cfg = item_kind.splits_at_cfg(self.config);
+        let first_cfg = self.cfg_predicates(items[0]);
         let item_length = items
             .iter()
             .take_while(|ppi| {
                 item_kind.is_same_item_kind(&***ppi)
+                    && (!splits_at_cfg || self.cfg_predicates(ppi) == first_cfg)
                     && (!in_group || {
                         let current = self.psess.lookup_line_range(ppi.span());
                         let in_same_group = current.lo < last.hi + 2;
@@ -277,6 +347,10 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
             })
             .count();
         let items = &items[..item_length];
+        // The items are rewritten together rather than visited one by one.
+        for item in items {
+            self.report_glob_imports(item);
+        }
 
         let at_least_one_in_file_lines = items
             .iter()
@@ -302,6 +376,107 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
         item_length
     }
 
+    /// Returns the `#[cfg(..)]` attributes of `item` with whitespace removed. Items whose
+    /// predicates differ are never reordered across each other.
+    fn cfg_predicates(&self, item: &ast::Item) -> Vec<String> {
+        item.attrs
+            .iter()
+            .filter(|attr| attr.has_name(sym::cfg))
+            .map(|attr| {
+                self.snippet(attr.span)
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Merges the leading run of adjacent `impl` blocks that share the same attributes and
+    /// header into a single block. Returns the number of items that were consumed, which is
+    /// zero when there is nothing to merge.
+    fn walk_consecutive_impl_blocks(&mut self, items: &[&ast::Item]) -> usize {
+        let is_plain_impl = |item: &ast::Item| {
+            matches!(item.kind, ast::ItemKind::Impl(..))
+                && !contains_skip(&item.attrs)
+                && item
+                    .attrs
+                    .iter()
+                    .all(|attr| attr.style == ast::AttrStyle::Outer)
+        };
+        let attrs_snippet = |item: &ast::Item| {
+            item.attrs
+                .iter()
+                .map(|attr| self.snippet(attr.span))
+                .collect::<Vec<_>>()
+        };
+
+        if !is_plain_impl(items[0]) {
+            return 0;
+        }
+        let first_attrs = attrs_snippet(items[0]);
+        let mut group_length = 1;
+        for pair in items.windows(2) {
+            let (prev, next) = (pair[0], pair[1]);
+            let between = self.snippet(mk_sp(prev.span().hi(), next.span().lo()));
+            if !is_plain_impl(next)
+                || attrs_snippet(next) != first_attrs
+                || !between.trim().is_empty()
+            {
+                break;
+            }
+            group_length += 1;
+        }
+        let items = &items[..group_length];
+        if items.len() < 2
+            || items
+                .iter()
+                .any(|item| out_of_file_lines_range!(self, item.span))
+        {
+            return 0;
+        }
+
+        let skip_context_saved = self.skip_context.clone();
+        self.skip_context.update_with_attrs(&items[0].attrs);
+        let context = self.get_context();
+        let mut blocks: Vec<ImplBlock> = Vec::with_capacity(items.len());
+        for item in items {
+            let ast::ItemKind::Impl(ref iimpl) = item.kind else {
+                break;
+            };
+            let Some(block) = format_impl(&context, item, iimpl, self.block_indent)
+                .as_deref()
+                .and_then(split_impl_block)
+            else {
+                break;
+            };
+            if blocks
+                .first()
+                .is_some_and(|first| first.header.trim_end() != block.header.trim_end())
+            {
+                break;
+            }
+            blocks.push(block);
+        }
+        let merged = match blocks.len() {
+            0 | 1 => None,
+            _ => merge_impl_blocks(&blocks),
+        };
+        let macro_rewrite_failure = context.macro_rewrite_failure.get();
+        drop(context);
+        self.macro_rewrite_failure |= macro_rewrite_failure;
+        self.skip_context = skip_context_saved;
+
+        let Some(merged) = merged else {
+            return 0;
+        };
+        let skipped = self.visit_attrs(&items[0].attrs, ast::AttrStyle::Outer);
+        debug_assert!(!skipped);
+        let span = mk_sp(items[0].span.lo(), items[blocks.len() - 1].span.hi());
+        self.push_rewrite(span, Some(merged));
+
+        blocks.len()
+    }
+
     /// Visits and format the given items. Items are reordered If they are
     /// consecutive and reorderable.
     pub(crate) fn visit_items_with_reordering(&mut self, mut items: &[&ast::Item]) {
@@ -318,6 +493,14 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
                 );
                 let (_, rest) = items.split_at(visited_items_num);
                 items = rest;
+            } else if let Some(merged_items_num @ 1..) = self
+                .config
+                .group_consecutive_impl_blocks()
+                .then(|| self.walk_consecutive_impl_blocks(items))
+            {
+                // Adjacent `impl` blocks with the same header were merged into one.
+                let (_, rest) = items.split_at(merged_items_num);
+                items = rest;
             } else {
                 // Reaching here means items were not reordered. There must be at least
                 // one item left in `items`, so calling `unwrap()` here is safe.
@@ -10,6 +10,7 @@ use crate::config::{Config, IndentStyle};
 use crate::parse::session::ParseSess;
 use crate::shape::Shape;
 use crate::skip::SkipContext;
+use crate::types::ConstGenericDefaults;
 use crate::visitor::SnippetProvider;
 use crate::FormatReport;
 
@@ -36,6 +37,8 @@ pub(crate) struct RewriteContext<'a> {
     pub(crate) is_if_else_block: Cell<bool>,
     // When rewriting chain, veto going multi line except the last element
     pub(crate) force_one_line_chain: Cell<bool>,
+    // Set while rewriting the arguments of a call that exceeds `max_inline_nesting_depth`.
+    pub(crate) inside_deeply_nested_call: Cell<bool>,
     pub(crate) snippet_provider: &'a SnippetProvider,
     // Used for `format_snippet`
     pub(crate) macro_rewrite_failure: Cell<bool>,
@@ -43,6 +46,12 @@ pub(crate) struct RewriteContext<'a> {
     pub(crate) report: FormatReport,
     pub(crate) skip_context: SkipContext,
     pub(crate) skipped_range: Rc<RefCell<Vec<(usize, usize)>>>,
+    // The const generic defaults of the types declared in the current module.
+    pub(crate) const_generic_defaults: ConstGenericDefaults,
+    // Set while rewriting an item that is not public, see `FmtVisitor::is_private_item`.
+    pub(crate) is_private_item: Cell<bool>,
+    // Set while rewriting the body of a function, see `FmtVisitor::in_fn_body`.
+    pub(crate) in_fn_body: bool,
 }
 
 pub(crate) struct InsideMacroGuard {
@@ -2,8 +2,18 @@ use std::borrow::Cow;
 use std::cmp::min;
 use std::ops::{Add, Sub};
 
+use thiserror::Error;
+
 use crate::Config;
 
+/// An invariant of `Shape` that constructing it would violate.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ShapeError {
+    /// The indent alone is wider than the line may be.
+    #[error("indent of {indent} columns exceeds the maximum width of {max_width}")]
+    IndentExceedsMaxWidth { indent: usize, max_width: usize },
+}
+
 #[derive(Copy, Clone, Debug)]
 pub(crate) struct Indent {
     // Width of the block indent, in characters. Must be a multiple of
@@ -52,6 +62,14 @@ impl Indent {
         self
     }
 
+    /// Adds `additional` columns to the block indent, like `usize::checked_add`. Returns `None`
+    /// if the indent would then be wider than `max_width`, which leaves no room for code.
+    pub(crate) fn checked_add(self, additional: usize, max_width: usize) -> Option<Indent> {
+        let block_indent = self.block_indent.checked_add(additional)?;
+        let indent = Indent::new(block_indent, self.alignment);
+        (indent.block_indent.checked_add(indent.alignment)? <= max_width).then_some(indent)
+    }
+
     pub(crate) fn block_unindent(mut self, config: &Config) -> Indent {
         if self.block_indent < config.tab_spaces() {
             Indent::new(self.block_indent, 0)
@@ -181,6 +199,19 @@ impl Shape {
         }
     }
 
+    /// Like `indented`, but fails instead of giving an empty width when `indent` exceeds
+    /// `max_width`.
+    pub(crate) fn try_indented(indent: Indent, max_width: usize) -> Result<Shape, ShapeError> {
+        let width =
+            max_width
+                .checked_sub(indent.width())
+                .ok_or(ShapeError::IndentExceedsMaxWidth {
+                    indent: indent.width(),
+                    max_width,
+                })?;
+        Ok(Shape::legacy(width, indent))
+    }
+
     pub(crate) fn with_max_width(&self, config: &Config) -> Shape {
         Shape {
             width: config.max_width().saturating_sub(self.indent.width()),
@@ -267,7 +298,7 @@ impl Shape {
     pub(crate) fn comment(&self, config: &Config) -> Shape {
         let width = min(
             self.width,
-            config.comment_width().saturating_sub(self.indent.width()),
+            min(config.comment_width(), config.max_width()).saturating_sub(self.indent.width()),
         );
         Shape { width, ..*self }
     }
@@ -302,6 +333,21 @@ mod test {
         assert_eq!(16, indent.alignment);
     }
 
+    #[test]
+    fn indent_checked_add() {
+        let indent = Indent::new(4, 8).checked_add(4, 100).unwrap();
+        assert_eq!(8, indent.block_indent);
+        assert_eq!(8, indent.alignment);
+
+        assert!(Indent::new(4, 8).checked_add(8, 20).is_some());
+        assert!(Indent::new(4, 8).checked_add(12, 20).is_none());
+        assert!(
+            Indent::new(4, 0)
+                .checked_add(usize::MAX, usize::MAX)
+                .is_none()
+        );
+    }
+
     #[test]
     fn indent_add_sub_alignment() {
         let indent = Indent::new(4, 8) + 4;
@@ -313,6 +359,26 @@ mod test {
         assert_eq!(8, indent.alignment);
     }
 
+    #[test]
+    fn shape_try_indented() {
+        let shape = Shape::try_indented(Indent::new(8, 4), 100).unwrap();
+        assert_eq!(88, shape.width);
+        assert_eq!(4, shape.offset);
+
+        let err = Shape::try_indented(Indent::new(96, 8), 100).unwrap_err();
+        assert_eq!(
+            err,
+            ShapeError::IndentExceedsMaxWidth {
+                indent: 104,
+                max_width: 100
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "indent of 104 columns exceeds the maximum width of 100"
+        );
+    }
+
     #[test]
     fn indent_to_string_spaces() {
         let config = Config::default();
@@ -1,9 +1,13 @@
 //! Module that contains skip related stuffs.
 
-use rustc_ast::ast;
+use rustc_ast::{ast, ptr};
 use rustc_ast_pretty::pprust;
+use rustc_span::Span;
 use std::collections::HashSet;
 
+use crate::spanned::Spanned;
+use crate::utils::contains_skip;
+
 /// Track which blocks of code are to be skipped when formatting.
 ///
 /// You can update it by:
@@ -85,6 +89,7 @@ impl SkipNameContext {
 
 static RUSTFMT: &str = "rustfmt";
 static SKIP: &str = "skip";
+static FORMAT: &str = "format";
 
 /// Say if you're playing with `rustfmt`'s skip attribute
 pub(crate) fn is_skip_attr(segments: &[ast::PathSegment]) -> bool {
@@ -103,6 +108,89 @@ pub(crate) fn is_skip_attr(segments: &[ast::PathSegment]) -> bool {
     }
 }
 
+/// Say if you're playing with `rustfmt`'s format attribute
+pub(crate) fn is_format_attr(segments: &[ast::PathSegment]) -> bool {
+    segments.len() == 2
+        && segments[0].ident.to_string() == RUSTFMT
+        && segments[1].ident.to_string() == FORMAT
+}
+
+/// Returns `true` if `attrs` contain `#[rustfmt::format]`, which opts an item inside a skipped
+/// scope back in to formatting.
+pub(crate) fn contains_format(attrs: &[ast::Attribute]) -> bool {
+    attrs.iter().any(|attr| match attr.kind {
+        ast::AttrKind::Normal(ref normal) => {
+            is_format_attr(&normal.item.path.segments) && attr.meta_item_list().is_none()
+        }
+        ast::AttrKind::DocComment(..) => false,
+    })
+}
+
+/// An item inside a skipped scope that is formatted anyway because of `#[rustfmt::format]`.
+pub(crate) enum OptedInItem<'a> {
+    Item(&'a ast::Item),
+    TraitItem(&'a ast::AssocItem),
+    ImplItem(&'a ast::AssocItem),
+}
+
+impl Spanned for OptedInItem<'_> {
+    fn span(&self) -> Span {
+        match self {
+            OptedInItem::Item(item) => item.span(),
+            OptedInItem::TraitItem(ai) | OptedInItem::ImplItem(ai) => ai.span(),
+        }
+    }
+}
+
+/// Returns the outermost items opted back in to formatting inside the skipped `item`, in source
+/// order. Items of inline modules, impls and traits are searched; an item carrying both
+/// `#[rustfmt::skip]` and `#[rustfmt::format]` stays skipped.
+pub(crate) fn opted_in_items(item: &ast::Item) -> Vec<OptedInItem<'_>> {
+    fn collect<'a>(item: &'a ast::Item, opted_in: &mut Vec<OptedInItem<'a>>) {
+        match item.kind {
+            ast::ItemKind::Mod(_, ast::ModKind::Loaded(ref items, ast::Inline::Yes, _)) => {
+                opted_in.extend(opted_in_items_of(items));
+            }
+            ast::ItemKind::Impl(ref iimpl) => opted_in.extend(
+                iimpl
+                    .items
+                    .iter()
+                    .filter(|ai| is_opted_in(&ai.attrs))
+                    .map(|ai| OptedInItem::ImplItem(ai)),
+            ),
+            ast::ItemKind::Trait(ref trait_) => opted_in.extend(
+                trait_
+                    .items
+                    .iter()
+                    .filter(|ai| is_opted_in(&ai.attrs))
+                    .map(|ai| OptedInItem::TraitItem(ai)),
+            ),
+            _ => {}
+        }
+    }
+
+    let mut opted_in = vec![];
+    collect(item, &mut opted_in);
+    opted_in
+}
+
+/// Like `opted_in_items`, for the items of a skipped module.
+pub(crate) fn opted_in_items_of(items: &[ptr::P<ast::Item>]) -> Vec<OptedInItem<'_>> {
+    let mut opted_in = vec![];
+    for item in items {
+        if is_opted_in(&item.attrs) {
+            opted_in.push(OptedInItem::Item(item));
+        } else {
+            opted_in.extend(opted_in_items(item));
+        }
+    }
+    opted_in
+}
+
+fn is_opted_in(attrs: &[ast::Attribute]) -> bool {
+    contains_format(attrs) && !contains_skip(attrs)
+}
+
 fn get_skip_names(kind: &str, attrs: &[ast::Attribute]) -> Vec<String> {
     let mut skip_names = vec![];
     let path = format!("{RUSTFMT}::{SKIP}::{kind}");
@@ -8,7 +8,7 @@ use crate::rewrite::{Rewrite, RewriteContext};
 use crate::shape::Shape;
 use crate::source_map::LineRangeUtils;
 use crate::spanned::Spanned;
-use crate::utils::semicolon_for_stmt;
+use crate::utils::{semicolon_for_expr, semicolon_for_stmt};
 
 pub(crate) struct Stmt<'a> {
     inner: &'a ast::Stmt,
@@ -123,7 +123,16 @@ fn format_stmt(
                 ""
             };
 
-            let shape = shape.sub_width(suffix.len())?;
+            // `format_expr` already leaves room for the semicolon of `return`, `break` and
+            // `continue` statements, so only count it once.
+            let shape = if context.config.version() == Version::Two
+                && expr_type == ExprType::Statement
+                && semicolon_for_expr(context, ex)
+            {
+                shape
+            } else {
+                shape.sub_width(suffix.len())?
+            };
             format_expr(ex, expr_type, context, shape).map(|s| s + suffix)
         }
         ast::StmtKind::MacCall(..) | ast::StmtKind::Item(..) | ast::StmtKind::Empty => None,
@@ -1,12 +1,17 @@
 // Format string literals.
 
+use std::borrow::Cow;
+use std::cmp::min;
+use std::iter;
+use std::ops::Range;
+
 use regex::Regex;
 use unicode_properties::{GeneralCategory, UnicodeGeneralCategory};
 use unicode_segmentation::UnicodeSegmentation;
 
-use crate::config::Config;
+use crate::config::{BreakPreference, Config, Version};
 use crate::shape::Shape;
-use crate::utils::{unicode_str_width, wrap_str};
+use crate::utils::{filtered_str_fits_by, unicode_str_width, versioned_str_width, SOFT_HYPHEN};
 
 const MIN_STRING: usize = 10;
 
@@ -22,11 +27,28 @@ pub(crate) struct StringFormat<'a> {
     pub(crate) line_end: &'a str,
     /// The allocated box to fit the text into
     pub(crate) shape: Shape,
-    /// Trim trailing whitespaces
-    pub(crate) trim_end: bool,
+    /// Which lines have their trailing whitespaces trimmed
+    pub(crate) trim_end: TrimEnd,
     pub(crate) config: &'a Config,
 }
 
+/// Which lines of a piece of text have their trailing whitespaces trimmed. Line feeds are kept in
+/// any case.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TrimEnd {
+    /// No line is trimmed.
+    None,
+    /// Every line is trimmed.
+    All,
+}
+
+impl TrimEnd {
+    /// Returns `true` if the lines are trimmed.
+    fn trims_lines(self) -> bool {
+        self == TrimEnd::All
+    }
+}
+
 impl<'a> StringFormat<'a> {
     pub(crate) fn new(shape: Shape, config: &'a Config) -> StringFormat<'a> {
         StringFormat {
@@ -35,7 +57,7 @@ impl<'a> StringFormat<'a> {
             line_start: " ",
             line_end: "\\",
             shape,
-            trim_end: false,
+            trim_end: TrimEnd::None,
             config,
         }
     }
@@ -46,18 +68,54 @@ impl<'a> StringFormat<'a> {
     /// If we cannot put at least a single character per line, the rewrite won't succeed.
     fn max_width_with_indent(&self) -> Option<usize> {
         Some(
-            self.shape
-                .width
-                .checked_sub(self.opener.len() + self.line_end.len() + 1)?
-                + 1,
+            self.shape.width.checked_sub(
+                unicode_str_width(&self.first_line_opener()) + unicode_str_width(self.line_end) + 1,
+            )? + 1,
         )
     }
 
+    /// Returns the opener, followed by the spaces that line the text of the first line up with
+    /// the text of the next lines when these start with a marker, as in
+    ///
+    /// ```text
+    /// /* Lorem ipsum
+    ///  * dolor sit amet */
+    /// ```
+    fn first_line_opener(&self) -> Cow<'a, str> {
+        if self.opener.is_empty() || self.opener.ends_with(' ') || self.line_start.trim().is_empty()
+        {
+            return Cow::Borrowed(self.opener);
+        }
+        let padding = unicode_str_width(self.line_start)
+            .saturating_sub(unicode_str_width(self.opener))
+            .max(1);
+        Cow::Owned(format!("{}{}", self.opener, " ".repeat(padding)))
+    }
+
+    /// Returns how much wider the closer is than `line_end`, which is the room the last line
+    /// must leave for it on top of what the other lines leave for `line_end`.
+    fn closer_overhang(&self) -> usize {
+        unicode_str_width(self.closer).saturating_sub(unicode_str_width(self.line_end))
+    }
+
     /// Like max_width_with_indent but the indentation is not subtracted.
     /// This allows to fit more graphemes from the string on a line when
     /// SnippetState::EndWithLineFeed.
     fn max_width_without_indent(&self) -> Option<usize> {
-        self.config.max_width().checked_sub(self.line_end.len())
+        self.line_max_width()
+            .checked_sub(unicode_str_width(self.line_end))
+    }
+
+    /// Returns the maximum width of a line. Comments, which have neither an opener nor a
+    /// closer, are limited by `comment_width` when it is set, clamped to `max_width`. Otherwise
+    /// they default to `max_width` like any other string.
+    fn line_max_width(&self) -> usize {
+        let is_comment = self.opener.is_empty() && self.closer.is_empty();
+        if is_comment && self.config.was_set().comment_width() {
+            min(self.config.comment_width(), self.config.max_width())
+        } else {
+            self.config.max_width()
+        }
     }
 }
 
@@ -65,6 +123,30 @@ pub(crate) fn rewrite_string<'a>(
     orig: &str,
     fmt: &StringFormat<'a>,
     newline_max_chars: usize,
+) -> Option<String> {
+    let max_width_with_indent = fmt.max_width_with_indent()?;
+    fmt.max_width_without_indent()?;
+    // Most strings are on a single line that fits already, and so are kept as they are.
+    let last_line_max_width = max_width_with_indent.saturating_sub(fmt.closer_overhang());
+    if !orig.contains(['\n', '\r'])
+        && versioned_str_width(orig, fmt.config.version()) <= last_line_max_width
+    {
+        let opener = fmt.first_line_opener();
+        let mut result = String::with_capacity(opener.len() + orig.len() + fmt.closer.len());
+        result.push_str(&opener);
+        result.push_str(orig);
+        trim_end_but_line_feed(fmt.trim_end.trims_lines(), &mut result);
+        result.push_str(fmt.closer);
+        return fit_in_shape(result, fmt);
+    }
+    rewrite_string_in_lines(orig, fmt, newline_max_chars)
+}
+
+/// Breaks `orig` into lines that fit in the shape of `fmt`, following `fmt`.
+fn rewrite_string_in_lines(
+    orig: &str,
+    fmt: &StringFormat<'_>,
+    newline_max_chars: usize,
 ) -> Option<String> {
     let max_width_with_indent = fmt.max_width_with_indent()?;
     let max_width_without_indent = fmt.max_width_without_indent()?;
@@ -73,10 +155,26 @@ pub(crate) fn rewrite_string<'a>(
 
     // Strip line breaks.
     // With this regex applied, all remaining whitespaces are significant
-    let strip_line_breaks_re = Regex::new(r"([^\\](\\\\)*)\\[\n\r][[:space:]]*").unwrap();
+    let strip_line_breaks_re = static_regex!(r"([^\\](\\\\)*)\\[\n\r][[:space:]]*");
     let stripped_str = strip_line_breaks_re.replace_all(orig, "$1");
+    // Every blank line gets its own `line_start`, so keep a single one in a row with
+    // `comment_collapse_blank_lines`.
+    let is_bareline_ok = fmt.line_start.is_empty() || is_whitespace(fmt.line_start);
+    let stripped_str = if !is_bareline_ok && fmt.config.comment_collapse_blank_lines() {
+        collapse_blank_lines(&stripped_str).into()
+    } else {
+        stripped_str
+    };
 
-    let graphemes = UnicodeSegmentation::graphemes(&*stripped_str, false).collect::<Vec<&str>>();
+    // Extended grapheme clusters keep spacing marks with their base character, so the string is
+    // never broken inside a user-perceived character. Version One keeps the legacy clusters.
+    let is_extended = fmt.config.version() == Version::Two;
+    let mut graphemes =
+        UnicodeSegmentation::graphemes(&*stripped_str, is_extended).collect::<Vec<&str>>();
+    // Inline code spans in comments that are too wide for a line may be broken at their spaces.
+    let long_code_span_width = (fmt.config.break_long_code_spans() && fmt.opener.is_empty())
+        .then_some(max_width_without_indent);
+    let break_options = BreakOptions::new(fmt.config, long_code_span_width);
 
     // `cur_start` is the position in `orig` of the start of the current line.
     let mut cur_start = 0;
@@ -86,50 +184,79 @@ pub(crate) fn rewrite_string<'a>(
             .checked_next_power_of_two()
             .unwrap_or(usize::max_value()),
     );
-    result.push_str(fmt.opener);
+    result.push_str(&fmt.first_line_opener());
 
     // Snip a line at a time from `stripped_str` until it is used up. Push the snippet
     // onto result.
     let mut cur_max_width = max_width_with_indent;
-    let is_bareline_ok = fmt.line_start.is_empty() || is_whitespace(fmt.line_start);
     loop {
-        // All the input starting at cur_start fits on the current line
-        if graphemes_width(&graphemes[cur_start..]) <= cur_max_width {
+        // All the input starting at cur_start fits on the current line, not counting the
+        // whitespace that is trimmed from its end.
+        let rest = &graphemes[cur_start..];
+        let rest_len = if fmt.trim_end.trims_lines() {
+            rest.iter()
+                .rposition(|grapheme| not_whitespace_except_line_feed(grapheme))
+                .map_or(0, |i| i + 1)
+        } else {
+            rest.len()
+        };
+        // The last line leaves room for the closer too.
+        let last_line_max_width = cur_max_width.saturating_sub(fmt.closer_overhang()).max(1);
+        if graphemes_fit(&rest[..rest_len], last_line_max_width, fmt.config.version()) {
             for (i, grapheme) in graphemes[cur_start..].iter().enumerate() {
                 if is_new_line(grapheme) {
                     // take care of blank lines
-                    result = trim_end_but_line_feed(fmt.trim_end, result);
+                    trim_end_but_line_feed(fmt.trim_end.trims_lines(), &mut result);
                     result.push('\n');
-                    if !is_bareline_ok && cur_start + i + 1 < graphemes.len() {
+                    if let Some(next) = graphemes.get(cur_start + i + 1).filter(|_| !is_bareline_ok)
+                    {
                         result.push_str(&indent_without_newline);
-                        result.push_str(fmt.line_start);
+                        result.push_str(line_start_of(fmt.line_start, next));
                     }
                 } else {
                     result.push_str(grapheme);
                 }
             }
-            result = trim_end_but_line_feed(fmt.trim_end, result);
+            trim_end_but_line_feed(fmt.trim_end.trims_lines(), &mut result);
             break;
         }
 
         // The input starting at cur_start needs to be broken
+        let max_width = if graphemes_fit(&rest[..rest_len], cur_max_width, fmt.config.version()) {
+            last_line_max_width
+        } else {
+            cur_max_width
+        };
         match break_string(
-            cur_max_width,
-            fmt.trim_end,
+            max_width,
+            fmt.trim_end.trims_lines(),
             fmt.line_end,
             &graphemes[cur_start..],
+            &break_options,
         ) {
             SnippetState::LineEnd(line, len) => {
+                result.push_str(&line);
+                trim_end_but_line_feed(fmt.trim_end.trims_lines(), &mut result);
+                result.push_str(fmt.line_end);
+                result.push_str(&indent_with_newline);
+                result.push_str(fmt.line_start);
+                cur_max_width = newline_max_chars;
+                cur_start += len;
+            }
+            SnippetState::LineEndInCodeSpan(line, len, backticks) => {
                 result.push_str(&line);
                 result.push_str(fmt.line_end);
                 result.push_str(&indent_with_newline);
                 result.push_str(fmt.line_start);
                 cur_max_width = newline_max_chars;
                 cur_start += len;
+                // Open the code span again on the next line.
+                let opener = code_span_opener(backticks, graphemes.get(cur_start));
+                graphemes.splice(cur_start..cur_start, opener);
             }
             SnippetState::EndWithLineFeed(line, len) => {
-                if line == "\n" && fmt.trim_end {
-                    result = result.trim_end().to_string();
+                if line == "\n" && fmt.trim_end.trims_lines() {
+                    result.truncate(result.trim_end().len());
                 }
                 result.push_str(&line);
                 if is_bareline_ok {
@@ -137,20 +264,117 @@ pub(crate) fn rewrite_string<'a>(
                     cur_max_width = max_width_without_indent;
                 } else {
                     result.push_str(&indent_without_newline);
-                    result.push_str(fmt.line_start);
+                    if let Some(next) = graphemes.get(cur_start + len) {
+                        result.push_str(line_start_of(fmt.line_start, next));
+                    } else {
+                        result.push_str(fmt.line_start);
+                    }
                     cur_max_width = max_width_with_indent;
                 }
                 cur_start += len;
             }
             SnippetState::EndOfInput(line) => {
                 result.push_str(&line);
+                trim_end_but_line_feed(fmt.trim_end.trims_lines(), &mut result);
                 break;
             }
         }
     }
 
     result.push_str(fmt.closer);
-    wrap_str(result, fmt.config.max_width(), fmt.shape)
+    fit_in_shape(result, fmt)
+}
+
+/// Returns `result` if it fits in the shape of `fmt`, measuring it the way it was broken.
+fn fit_in_shape(result: String, fmt: &StringFormat<'_>) -> Option<String> {
+    let version = fmt.config.version();
+    let width = |line: &str| versioned_str_width(line, version);
+    filtered_str_fits_by(&result, fmt.config.max_width(), fmt.shape, width).then_some(result)
+}
+
+/// Returns the byte offsets in `text` at which rustfmt would break it into lines of at most
+/// `width` columns, each of which starts with `prefix`, the way `wrap_comments` breaks a line of a
+/// comment. Each offset is where a new line starts, after the whitespace dropped at the break.
+///
+/// The breaks follow `string_break_preference` and `break_long_code_spans` of `config`, and never
+/// split a URL, an autolink or an inline code span that fits on a line.
+///
+/// ```
+/// use rustfmt_nightly::{comment_break_offsets, Config};
+///
+/// let text = "Lorem ipsum dolor sit amet, consectetur adipiscing elit.";
+/// let offsets = comment_break_offsets(text, 20, "// ", &Config::default());
+/// assert_eq!(offsets, vec![18, 40]);
+/// assert_eq!(&text[..offsets[0]], "Lorem ipsum dolor ");
+/// ```
+pub fn comment_break_offsets(
+    text: &str,
+    width: usize,
+    prefix: &str,
+    config: &Config,
+) -> Vec<usize> {
+    let max_width = width.saturating_sub(unicode_str_width(prefix));
+    let mut graphemes = UnicodeSegmentation::graphemes(text, true).collect::<Vec<&str>>();
+    // The backticks that open a code span again after a break get the offset of the grapheme
+    // they are put in front of.
+    let mut offsets = UnicodeSegmentation::grapheme_indices(text, true)
+        .map(|(offset, _)| offset)
+        .collect::<Vec<_>>();
+    let break_options =
+        BreakOptions::new(config, config.break_long_code_spans().then_some(max_width));
+
+    let mut breaks = vec![];
+    let mut start = 0;
+    while start < graphemes.len()
+        && !graphemes_fit(&graphemes[start..], max_width, config.version())
+    {
+        match break_string(max_width, true, "", &graphemes[start..], &break_options) {
+            SnippetState::LineEnd(_, len) => {
+                start += len;
+                breaks.extend(offsets.get(start));
+            }
+            SnippetState::LineEndInCodeSpan(_, len, backticks) => {
+                start += len;
+                let offset = offsets[start];
+                breaks.push(offset);
+                let opener = code_span_opener(backticks, graphemes.get(start));
+                offsets.splice(start..start, iter::repeat(offset).take(opener.len()));
+                graphemes.splice(start..start, opener);
+            }
+            SnippetState::EndWithLineFeed(_, len) => start += len,
+            SnippetState::EndOfInput(_) => break,
+        }
+    }
+    breaks
+}
+
+/// Returns the `line_start` of a line that begins with the grapheme `next`. A blank line only
+/// holds its `line_start`, which is trimmed then, so that `// ` becomes `//` and ` * ` becomes
+/// ` *` even when trailing whitespace is otherwise kept.
+fn line_start_of<'a>(line_start: &'a str, next: &str) -> &'a str {
+    if is_new_line(next) {
+        line_start.trim_end()
+    } else {
+        line_start
+    }
+}
+
+/// Drops the blank lines of `s` that follow another blank line. The text before the first line
+/// feed continues the current line and the text after the last one is not a line of its own, so
+/// both are always kept.
+fn collapse_blank_lines(s: &str) -> String {
+    let lines: Vec<&str> = s.split('\n').collect();
+    let last = lines.len() - 1;
+    let mut is_prev_blank = false;
+    let mut kept = Vec::with_capacity(lines.len());
+    for (i, line) in lines.into_iter().enumerate() {
+        let is_blank = 0 < i && i < last && line.trim().is_empty();
+        if !(is_blank && is_prev_blank) {
+            kept.push(line);
+        }
+        is_prev_blank = is_blank;
+    }
+    kept.join("\n")
 }
 
 /// Returns the index to the end of the URL if the split at index of the given string includes a
@@ -165,12 +389,9 @@ fn detect_url(s: &[&str], index: usize) -> Option<usize> {
         return None;
     }
     let split = s[start..].concat();
-    if split.contains("https://")
-        || split.contains("http://")
-        || split.contains("ftp://")
-        || split.contains("file://")
-    {
-        match s[index..].iter().position(|g| is_whitespace(g)) {
+    if find_url_scheme(&split).is_some() {
+        let in_file_url = file_url_spans(s);
+        match (index..s.len()).position(|pos| is_whitespace(s[pos]) && !in_file_url[pos]) {
             Some(pos) => Some(index + pos - 1),
             None => Some(s.len() - 1),
         }
@@ -179,15 +400,31 @@ fn detect_url(s: &[&str], index: usize) -> Option<usize> {
     }
 }
 
-/// Trims whitespaces to the right except for the line feed character.
-fn trim_end_but_line_feed(trim_end: bool, result: String) -> String {
-    let whitespace_except_line_feed = |c: char| c.is_whitespace() && c != '\n';
-    if trim_end && result.ends_with(whitespace_except_line_feed) {
-        result
-            .trim_end_matches(whitespace_except_line_feed)
-            .to_string()
-    } else {
-        result
+const URL_SCHEMES: [&str; 4] = ["https://", "http://", "ftp://", "file://"];
+
+/// Returns the byte offset in `s` of the first URL scheme, such as `https://`. Schemes are
+/// case-insensitive, so `HTTP://` counts too, but a scheme that is part of a larger word, as in
+/// `xhttp://`, doesn't.
+pub(crate) fn find_url_scheme(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    (0..bytes.len()).find(|&i| {
+        let starts_word = i == 0 || !(bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'_');
+        starts_word
+            && URL_SCHEMES.iter().any(|scheme| {
+                bytes[i..].get(..scheme.len()).map_or(false, |prefix| {
+                    prefix.eq_ignore_ascii_case(scheme.as_bytes())
+                })
+            })
+    })
+}
+
+/// Trims whitespaces to the right except for the line feed character, in place.
+fn trim_end_but_line_feed(trim_end: bool, result: &mut String) {
+    if trim_end {
+        let len = result
+            .trim_end_matches(|c: char| c.is_whitespace() && c != '\n')
+            .len();
+        result.truncate(len);
     }
 }
 
@@ -204,6 +441,10 @@ enum SnippetState {
     /// the text being rewritten. That length may be greater than the returned string if trailing
     /// whitespaces got trimmed.
     LineEnd(String, usize),
+    /// Like `LineEnd`, but the input was broken inside an inline code span whose runs of
+    /// backticks have the given length. The returned string closes the code span, which needs to
+    /// be opened again on the next line.
+    LineEndInCodeSpan(String, usize, usize),
     /// The input could be broken but a newline is present that cannot be trimmed. The next snippet
     /// to be rewritten *could* use more width than what is specified by the given shape. For
     /// example with a multiline string, the next snippet does not need to be indented, allowing
@@ -218,23 +459,70 @@ fn not_whitespace_except_line_feed(g: &str) -> bool {
     is_new_line(g) || !is_whitespace(g)
 }
 
+/// The options of `break_string` that come from the config.
+#[derive(Clone, Copy, Debug)]
+struct BreakOptions {
+    /// Where to prefer breaking the line.
+    preference: BreakPreference,
+    /// Inline code spans wider than this may be broken at their spaces.
+    long_code_span_width: Option<usize>,
+    /// Whether a word too long for a line may be broken anywhere.
+    break_long_words: bool,
+    /// How the graphemes are measured.
+    version: Version,
+}
+
+impl BreakOptions {
+    fn new(config: &Config, long_code_span_width: Option<usize>) -> Self {
+        BreakOptions {
+            preference: config.string_break_preference(),
+            long_code_span_width,
+            break_long_words: config.break_long_words(),
+            version: config.version(),
+        }
+    }
+}
+
+impl Default for BreakOptions {
+    fn default() -> Self {
+        BreakOptions {
+            preference: BreakPreference::Whitespace,
+            long_code_span_width: None,
+            break_long_words: false,
+            version: Version::One,
+        }
+    }
+}
+
 /// Break the input string at a boundary character around the offset `max_width`. A boundary
 /// character is either a punctuation or a whitespace.
 /// FIXME(issue#3281): We must follow UAX#14 algorithm instead of this.
-fn break_string(max_width: usize, trim_end: bool, line_end: &str, input: &[&str]) -> SnippetState {
-    let break_at = |index /* grapheme at index is included */| {
+fn break_string(
+    max_width: usize,
+    trim_end: bool,
+    line_end: &str,
+    input: &[&str],
+    options: &BreakOptions,
+) -> SnippetState {
+    let BreakOptions {
+        preference,
+        long_code_span_width,
+        break_long_words,
+        version,
+    } = *options;
+    let break_outside_code_span = |index /* grapheme at index is included */| {
         // Take in any whitespaces to the left/right of `input[index]` while
         // preserving line feeds
+        // A snippet of whitespace only leaves nothing on the line once trimmed.
         let index_minus_ws = input[0..=index]
             .iter()
-            .rposition(|grapheme| not_whitespace_except_line_feed(grapheme))
-            .unwrap_or(index);
+            .rposition(|grapheme| not_whitespace_except_line_feed(grapheme));
         // Take into account newlines occurring in input[0..=index], i.e., the possible next new
         // line. If there is one, then text after it could be rewritten in a way that the available
         // space is fully used.
         for (i, grapheme) in input[0..=index].iter().enumerate() {
             if is_new_line(grapheme) {
-                if i <= index_minus_ws {
+                if index_minus_ws.map_or(true, |index_minus_ws| i <= index_minus_ws) {
                     let mut line = &input[0..i].concat()[..];
                     if trim_end {
                         line = line.trim_end();
@@ -248,8 +536,9 @@ fn break_string(max_width: usize, trim_end: bool, line_end: &str, input: &[&str]
         let mut index_plus_ws = index;
         for (i, grapheme) in input[index + 1..].iter().enumerate() {
             if !trim_end && is_new_line(grapheme) {
+                // The line feed may be a `\r\n` grapheme, which is written out as `\n` like above.
                 return SnippetState::EndWithLineFeed(
-                    input[0..=index + 1 + i].concat(),
+                    format!("{}\n", input[0..index + 1 + i].concat()),
                     index + 2 + i,
                 );
             } else if not_whitespace_except_line_feed(grapheme) {
@@ -259,7 +548,8 @@ fn break_string(max_width: usize, trim_end: bool, line_end: &str, input: &[&str]
         }
 
         if trim_end {
-            SnippetState::LineEnd(input[0..=index_minus_ws].concat(), index_plus_ws + 1)
+            let line = index_minus_ws.map_or_else(String::new, |i| input[0..=i].concat());
+            SnippetState::LineEnd(line, index_plus_ws + 1)
         } else {
             SnippetState::LineEnd(input[0..=index_plus_ws].concat(), index_plus_ws + 1)
         }
@@ -270,7 +560,7 @@ fn break_string(max_width: usize, trim_end: bool, line_end: &str, input: &[&str]
         let mut cur_width = 0;
         let mut cur_index = 0;
         for (i, grapheme) in input.iter().enumerate() {
-            cur_width += unicode_str_width(grapheme);
+            cur_width += versioned_str_width(grapheme, version);
             cur_index = i;
             if cur_width > max_width {
                 break;
@@ -282,11 +572,99 @@ fn break_string(max_width: usize, trim_end: bool, line_end: &str, input: &[&str]
         return SnippetState::EndOfInput(input.concat());
     }
 
+    // Breaking inside an inline code span would leave two broken spans once rendered, unless it
+    // is too wide for a line anyway. Breaking inside an autolink, a wikilink or inline math would
+    // turn it back into plain text, and breaking inside a version would make it hard to read.
+    // Inline HTML comments are kept whole as well.
+    let widths = prefix_widths(input, version);
+    let mut unbreakable = vec![false; input.len()];
+    let mut long_code_spans = vec![];
+    for (span, backticks) in inline_code_spans(input) {
+        let width = widths[span.end] - widths[span.start];
+        if long_code_span_width.map_or(false, |max_width| width > max_width) {
+            // Only the spaces between the words of the code may be broken at, if the line still
+            // fits once the code span is closed.
+            let words = span.start + backticks + 1..span.end - backticks - 1;
+            for pos in span.clone() {
+                let line_width = widths[pos];
+                // A backtick right before the break is followed by a space to close the span.
+                let padding = usize::from(pos > span.start && input[pos - 1] == "`");
+                unbreakable[pos] = !words.contains(&pos)
+                    || !is_whitespace(input[pos])
+                    || line_width + padding + backticks > max_width;
+            }
+            long_code_spans.push((span, backticks));
+        } else {
+            unbreakable[span].fill(true);
+        }
+    }
+    for spans in [
+        autolink_spans(input),
+        wikilink_spans(input),
+        inline_math_spans(input),
+        html_comment_spans(input),
+        file_url_spans(input),
+        version_spans(input),
+    ] {
+        for (unbreakable, in_span) in unbreakable.iter_mut().zip(spans) {
+            *unbreakable |= in_span;
+        }
+    }
+    let break_at = |index| match break_outside_code_span(index) {
+        SnippetState::LineEnd(mut line, len) => {
+            // The hyphen of a soft hyphen that a comment is broken at is shown.
+            if line_end.is_empty() && line.ends_with(SOFT_HYPHEN) {
+                line.pop();
+                line.push('-');
+            }
+            let code_span = long_code_spans.iter().find(|(span, backticks)| {
+                span.start + backticks < len && len < span.end - backticks
+            });
+            match code_span {
+                Some(&(_, backticks)) => {
+                    // A backtick of the code right before the closing run would lengthen it.
+                    if line.ends_with('`') {
+                        line.push(' ');
+                    }
+                    line.push_str(&"`".repeat(backticks));
+                    SnippetState::LineEndInCodeSpan(line, len, backticks)
+                }
+                None => SnippetState::LineEnd(line, len),
+            }
+        }
+        state => state,
+    };
+    // A soft hyphen is a break point if the line still fits once its hyphen is shown.
+    let is_soft_hyphen_break =
+        |pos: usize| input[pos].starts_with(SOFT_HYPHEN) && widths[pos] < max_width;
+    let is_valid_linebreak = |pos: usize| {
+        !unbreakable[pos] && (is_valid_linebreak(input, pos, version) || is_soft_hyphen_break(pos))
+    };
+
+    // The last break of the given kind before `max_width`, if what is on its left side is big
+    // enough.
+    let last_break_before = |is_break: &dyn Fn(usize) -> bool| {
+        (0..max_width_index_in_input)
+            .rev()
+            .find(|&pos| is_break(pos))
+            .filter(|&index| index >= MIN_STRING)
+    };
+    let is_whitespace_break =
+        |pos: usize| (is_whitespace(input[pos]) || is_soft_hyphen_break(pos)) && !unbreakable[pos];
+    let is_sentence_break =
+        |pos: usize| pos > 0 && is_sentence_end(input[pos - 1]) && is_whitespace_break(pos);
+    let sentence_break = match preference {
+        BreakPreference::Sentence => last_break_before(&is_sentence_break),
+        _ => None,
+    };
+
     // Find the position in input for breaking the string
     if line_end.is_empty()
         && trim_end
         && !is_whitespace(input[max_width_index_in_input - 1])
         && is_whitespace(input[max_width_index_in_input])
+        && !unbreakable[max_width_index_in_input]
+        && (sentence_break.is_none() || is_sentence_end(input[max_width_index_in_input - 1]))
     {
         // At a breaking point already
         // The line won't invalidate the rewriting because:
@@ -308,47 +686,258 @@ fn break_string(max_width: usize, trim_end: bool, line_end: &str, input: &[&str]
         };
     }
 
-    match input[0..max_width_index_in_input]
-        .iter()
-        .rposition(|grapheme| is_whitespace(grapheme))
-    {
-        // Found a whitespace and what is on its left side is big enough.
-        Some(index) if index >= MIN_STRING => break_at(index),
-        // No whitespace found, try looking for a punctuation instead
-        _ => match (0..max_width_index_in_input)
-            .rev()
-            .skip_while(|pos| !is_valid_linebreak(input, *pos))
-            .next()
-        {
-            // Found a punctuation and what is on its left side is big enough.
-            Some(index) if index >= MIN_STRING => break_at(index),
-            // Either no boundary character was found to the left of `input[max_chars]`, or the line
-            // got too small. We try searching for a boundary character to the right.
-            _ => match (max_width_index_in_input..input.len())
-                .skip_while(|pos| !is_valid_linebreak(input, *pos))
-                .next()
-            {
+    let is_punctuation_break = |pos: usize| !is_whitespace(input[pos]) && is_valid_linebreak(pos);
+    let backward_break = match preference {
+        BreakPreference::Whitespace => last_break_before(&is_whitespace_break),
+        BreakPreference::Punctuation => last_break_before(&is_punctuation_break),
+        BreakPreference::Sentence => {
+            sentence_break.or_else(|| last_break_before(&is_whitespace_break))
+        }
+        BreakPreference::Widest => None,
+    }
+    .or_else(|| last_break_before(&is_valid_linebreak));
+
+    match backward_break {
+        Some(index) => break_at(index),
+        // Either no boundary character was found to the left of `input[max_chars]`, or the line
+        // got too small. We try searching for a boundary character to the right.
+        None if break_long_words => {
+            // A word that is too wide for a line starts a line of its own, on which it is split
+            // at the width limit, unless it must not be broken.
+            let word_start = (1..=max_width_index_in_input).rev().find(|&pos| {
+                is_whitespace_break(pos)
+                    || pos < max_width_index_in_input && is_valid_linebreak(pos)
+            });
+            let split = escape_sequence_start(input, max_width_index_in_input);
+            match word_start {
+                Some(index) => break_at(index),
+                None if split > 0 && !unbreakable[split] => {
+                    SnippetState::LineEnd(input[..split].concat(), split)
+                }
+                None => match (max_width_index_in_input..input.len())
+                    .find(|&pos| is_valid_linebreak(pos))
+                {
+                    Some(index) => break_at(index),
+                    None => SnippetState::EndOfInput(input.concat()),
+                },
+            }
+        }
+        None => {
+            match (max_width_index_in_input..input.len()).find(|&pos| is_valid_linebreak(pos)) {
                 // A boundary was found after the line limit
                 Some(index) => break_at(index),
                 // No boundary to the right, the input cannot be broken
                 None => SnippetState::EndOfInput(input.concat()),
-            },
-        },
+            }
+        }
+    }
+}
+
+/// Returns `index`, or the start of the escape sequence that the grapheme at `index` is part of,
+/// so that splitting a string literal there doesn't split the escape sequence.
+fn escape_sequence_start(input: &[&str], index: usize) -> usize {
+    let Some(start) = input[..index]
+        .iter()
+        .rposition(|grapheme| *grapheme == "\\")
+    else {
+        return index;
+    };
+    let backslashes = input[..=start]
+        .iter()
+        .rev()
+        .take_while(|grapheme| **grapheme == "\\")
+        .count();
+    if backslashes % 2 == 0 {
+        // The backslash at `start` is escaped itself.
+        return index;
     }
+    let end = match input.get(start + 1) {
+        Some(&"x") => start + 4,
+        Some(&"u") => input[start..]
+            .iter()
+            .position(|grapheme| *grapheme == "}")
+            .map_or(input.len(), |i| start + i + 1),
+        _ => start + 2,
+    };
+    if index < end { start } else { index }
 }
 
-fn is_valid_linebreak(input: &[&str], pos: usize) -> bool {
+fn is_valid_linebreak(input: &[&str], pos: usize, version: Version) -> bool {
     let is_whitespace = is_whitespace(input[pos]);
     if is_whitespace {
         return true;
     }
     let is_punctuation = is_punctuation(input[pos]);
-    if is_punctuation && !is_part_of_type(input, pos) {
+    if is_punctuation
+        && !is_part_of_type(input, pos)
+        && !(version == Version::Two && is_within_punctuation_run(input, pos))
+    {
         return true;
     }
     false
 }
 
+/// Returns `true` if `grapheme` ends a sentence.
+fn is_sentence_end(grapheme: &str) -> bool {
+    matches!(grapheme, "." | "?" | "!")
+}
+
+/// Returns `true` if the punctuation at `pos` is followed by the same punctuation, like the first
+/// two dots of `...`. With version Two, runs such as `!!!` are kept whole, while mixed punctuation
+/// such as `.)` or `?"` may still be broken after its first character.
+fn is_within_punctuation_run(input: &[&str], pos: usize) -> bool {
+    input.get(pos + 1) == Some(&input[pos])
+}
+
+/// Returns the graphemes that open an inline code span again with runs of `backticks` in front of
+/// the grapheme `next`. A space separates them if `next` is a backtick itself, which would
+/// otherwise lengthen the opening run.
+fn code_span_opener(backticks: usize, next: Option<&&str>) -> Vec<&'static str> {
+    let mut opener = vec!["`"; backticks];
+    if next == Some(&"`") {
+        opener.push(" ");
+    }
+    opener
+}
+
+/// Returns the ranges of `input` taken by inline code spans, i.e. an opening run of backticks, the
+/// enclosed text and the closing run of the same length, along with the length of the runs. A
+/// run of backticks without a matching closing run is not a code span.
+fn inline_code_spans(input: &[&str]) -> Vec<(Range<usize>, usize)> {
+    let backtick_run_len = |start: usize| {
+        input[start..]
+            .iter()
+            .take_while(|grapheme| **grapheme == "`")
+            .count()
+    };
+
+    let mut spans = vec![];
+    let mut pos = 0;
+    while pos < input.len() {
+        if input[pos] != "`" {
+            pos += 1;
+            continue;
+        }
+        let opener_len = backtick_run_len(pos);
+        let mut closer = None;
+        let mut cur = pos + opener_len;
+        while cur < input.len() {
+            if input[cur] == "`" {
+                let run_len = backtick_run_len(cur);
+                if run_len == opener_len {
+                    closer = Some(cur);
+                    break;
+                }
+                cur += run_len;
+            } else {
+                cur += 1;
+            }
+        }
+        match closer {
+            Some(closer) => {
+                let end = closer + opener_len;
+                spans.push((pos..end, opener_len));
+                pos = end;
+            }
+            None => pos += opener_len,
+        }
+    }
+    spans
+}
+
+/// Returns a mask over `input` where `true` marks the graphemes belonging to a Markdown autolink,
+/// such as `<mailto:foo@example.org>` or `<foo@example.org>`.
+fn autolink_spans(input: &[&str]) -> Vec<bool> {
+    let autolink = static_regex!(
+        r"(?x)
+        <(?:
+            [A-Za-z][A-Za-z0-9+.-]{1,31}:[^\s<>]*  # URI autolink
+            | [A-Za-z0-9.!\#$%&'*+/=?^_`{|}~-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*  # email autolink
+        )>"
+    );
+    regex_spans(input, autolink)
+}
+
+/// Returns a mask over `input` where `true` marks the graphemes belonging to a wikilink, such as
+/// `[[Target]]` or `[[Target|Display]]`.
+fn wikilink_spans(input: &[&str]) -> Vec<bool> {
+    regex_spans(input, static_regex!(r"\[\[[^\[\]\n]+\]\]"))
+}
+
+/// Returns a mask over `input` where `true` marks the graphemes belonging to inline math, such as
+/// `$x^2 + y^2$` or `$$e^{i\pi} = -1$$`. The text between single dollar signs may neither start
+/// nor end with whitespace, so that prices like `$5 or $10` are not math.
+fn inline_math_spans(input: &[&str]) -> Vec<bool> {
+    regex_spans(
+        input,
+        static_regex!(r"\$\$[^$\n]+\$\$|\$[^\s$](?:[^$\n]*[^\s$])?\$"),
+    )
+}
+
+/// Returns a mask over `input` where `true` marks the graphemes belonging to an inline HTML
+/// comment, such as `<!-- note -->`.
+fn html_comment_spans(input: &[&str]) -> Vec<bool> {
+    regex_spans(input, static_regex!(r"<!--[^\n]*?-->"))
+}
+
+/// Returns a mask over `input` where `true` marks the graphemes belonging to a `file://` URL.
+/// Unlike other URLs, the path of a file URL may contain spaces, as in
+/// `file:///C:/Program Files/app.exe`. A word after a space is taken as part of the path if it
+/// contains a path separator.
+fn file_url_spans(input: &[&str]) -> Vec<bool> {
+    regex_spans(
+        input,
+        static_regex!(r"(?i)\bfile://\S*(?:[ \t]+[^\s/\\]*[/\\]\S*)*"),
+    )
+}
+
+/// Returns a mask over `input` where `true` marks the graphemes belonging to a version or a
+/// range of versions, such as `1.0.0-alpha.1+build.5` or `>=1.0.0,<2.0.0`. A version has at
+/// least three numbers, and must make up a whole word, apart from brackets around it and the
+/// punctuation after it. This keeps dotted runs in other words, like `foo.bar.baz`, breakable.
+fn version_spans(input: &[&str]) -> Vec<bool> {
+    let version = static_regex!(
+        r"(?x)
+        ^[(\[]?
+        (?:
+            [<>=~^]{0,2} v? \d+ \. \d+ \. \d+
+            (?: - [0-9A-Za-z-]+ (?: \. [0-9A-Za-z-]+ )* )?  # pre-release
+            (?: \+ [0-9A-Za-z-]+ (?: \. [0-9A-Za-z-]+ )* )?  # build metadata
+            ,?
+        )+
+        [)\].,;:!?]?$"
+    );
+    let mut mask = vec![false; input.len()];
+    let mut start = 0;
+    while start < input.len() {
+        let end = input[start..]
+            .iter()
+            .position(|grapheme| is_whitespace(grapheme))
+            .map_or(input.len(), |len| start + len);
+        if end > start && version.is_match(&input[start..end].concat()) {
+            mask[start..end].fill(true);
+        }
+        start = end + 1;
+    }
+    mask
+}
+
+/// Returns a mask over `input` where `true` marks the graphemes belonging to a match of `re`.
+fn regex_spans(input: &[&str], re: &Regex) -> Vec<bool> {
+    let text = input.concat();
+    let mut mask = vec![false; input.len()];
+    let mut grapheme_start = 0;
+    let mut matches = re.find_iter(&text).peekable();
+    for (pos, grapheme) in input.iter().enumerate() {
+        while matches.next_if(|m| m.end() <= grapheme_start).is_some() {}
+        mask[pos] = matches
+            .peek()
+            .map_or(false, |m| m.start() <= grapheme_start);
+        grapheme_start += grapheme.len();
+    }
+    mask
+}
+
 fn is_part_of_type(input: &[&str], pos: usize) -> bool {
     input.get(pos..=pos + 1) == Some(&[":", ":"])
         || input.get(pos.saturating_sub(1)..=pos) == Some(&[":", ":"])
@@ -359,8 +948,14 @@ fn is_new_line(grapheme: &str) -> bool {
     bytes.starts_with(b"\n") || bytes.starts_with(b"\r\n")
 }
 
+/// Spaces that are meant to keep the words around them together, such as in `10\u{a0}kg`.
+const NON_BREAKING_SPACES: [char; 3] = ['\u{a0}', '\u{202f}', '\u{2060}'];
+
+/// Returns `true` if the grapheme is whitespace that a line may be broken at.
 fn is_whitespace(grapheme: &str) -> bool {
-    grapheme.chars().all(char::is_whitespace)
+    grapheme
+        .chars()
+        .all(|c| c.is_whitespace() && !NON_BREAKING_SPACES.contains(&c))
 }
 
 fn is_punctuation(grapheme: &str) -> bool {
@@ -369,15 +964,40 @@ fn is_punctuation(grapheme: &str) -> bool {
         .all(|c| c.general_category() == GeneralCategory::OtherPunctuation)
 }
 
-fn graphemes_width(graphemes: &[&str]) -> usize {
-    graphemes.iter().map(|s| unicode_str_width(s)).sum()
+/// Returns the widths of the prefixes of `graphemes`, where the width of `graphemes[..i]` is at
+/// index `i`. Looking the width of a prefix up keeps the search for a break linear in the length
+/// of a line, rather than measuring the prefix of every candidate again.
+fn prefix_widths(graphemes: &[&str], version: Version) -> Vec<usize> {
+    iter::once(0)
+        .chain(graphemes.iter().scan(0, |width, grapheme| {
+            *width += versioned_str_width(grapheme, version);
+            Some(*width)
+        }))
+        .collect()
+}
+
+/// Returns `true` if `graphemes` are at most `max_width` wide. Stops measuring as soon as the
+/// width is exceeded, as the graphemes are often the whole remainder of a long string.
+fn graphemes_fit(graphemes: &[&str], max_width: usize, version: Version) -> bool {
+    let mut width = 0;
+    graphemes.iter().all(|grapheme| {
+        width += versioned_str_width(grapheme, version);
+        width <= max_width
+    })
 }
 
 #[cfg(test)]
 mod test {
-    use super::{break_string, detect_url, rewrite_string, SnippetState, StringFormat};
-    use crate::config::Config;
+    use std::iter;
+
+    use super::{
+        break_string, comment_break_offsets, detect_url, escape_sequence_start, find_url_scheme,
+        prefix_widths, rewrite_string, rewrite_string_in_lines, trim_end_but_line_feed,
+        version_spans, BreakOptions, SnippetState, StringFormat, TrimEnd,
+    };
+    use crate::config::{BreakPreference, Config, Version};
     use crate::shape::{Indent, Shape};
+    use crate::utils::{unicode_str_width, SOFT_HYPHEN};
     use unicode_segmentation::UnicodeSegmentation;
 
     #[test]
@@ -387,12 +1007,110 @@ mod test {
         rewrite_string("eq_", &fmt, 2);
     }
 
+    #[test]
+    fn fitting_strings_are_kept_as_they_are() {
+        let config: Config = Default::default();
+        let strings = [
+            "",
+            "a",
+            "Lorem ipsum",
+            "Lorem ipsum dolor sit amet",
+            "trailing whitespace   ",
+            "  leading whitespace",
+            "escaped \\\" quote",
+            "日本語の文字列",
+            "👨‍👩‍👧‍👦 family",
+            "e\u{301}\u{301} combining",
+            "tab\tin between",
+        ];
+        for width in [10, 20, 40] {
+            let shape = Shape::legacy(width, Indent::from_width(&config, 4));
+            let comment_fmt = StringFormat {
+                opener: "",
+                closer: "",
+                line_start: "// ",
+                line_end: "",
+                shape,
+                trim_end: TrimEnd::All,
+                config: &config,
+            };
+            for fmt in [StringFormat::new(shape, &config), comment_fmt] {
+                for s in strings {
+                    assert_eq!(
+                        rewrite_string(s, &fmt, width),
+                        rewrite_string_in_lines(s, &fmt, width),
+                        "{s:?} at width {width}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn whitespace_only_string_in_narrow_shape() {
+        let config = Default::default();
+        for width in 1..=3 {
+            let shape = Shape::legacy(width, Indent::empty());
+            let comment_fmt = StringFormat {
+                opener: "",
+                closer: "",
+                line_start: "// ",
+                line_end: "",
+                shape,
+                trim_end: TrimEnd::All,
+                config: &config,
+            };
+            for fmt in [StringFormat::new(shape, &config), comment_fmt] {
+                for s in ["   \n   ", "\n", " \n \n ", "\\\n   ", "      ", "\n\n  \n"] {
+                    for newline_max_chars in 0..=width {
+                        let Some(rewrite) = rewrite_string(s, &fmt, newline_max_chars) else {
+                            // A string needs room for its quotes, a character and a `\\`.
+                            assert!(!fmt.opener.is_empty());
+                            continue;
+                        };
+                        assert!(
+                            fmt.trim_end == TrimEnd::None
+                                || rewrite.lines().all(|l| l == l.trim_end()),
+                            "{s:?} at width {width}: {rewrite:?}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn trim_end_policies() {
+        let string = "Lorem ipsum   \ndolor sit amet, consectetur   ";
+        let config: Config = Default::default();
+        let rewrite = |trim_end| {
+            let fmt = StringFormat {
+                opener: "",
+                closer: "",
+                line_start: "",
+                line_end: "",
+                shape: Shape::legacy(30, Indent::empty()),
+                trim_end,
+                config: &config,
+            };
+            rewrite_string(string, &fmt, 30)
+        };
+        assert_eq!(
+            rewrite(TrimEnd::None),
+            Some("Lorem ipsum   \ndolor sit amet, consectetur   ".to_string())
+        );
+        assert_eq!(
+            rewrite(TrimEnd::All),
+            Some("Lorem ipsum\ndolor sit amet, consectetur".to_string())
+        );
+    }
+
     #[test]
     fn line_break_at_valid_points_test() {
         let string = "[TheName](Dont::break::my::type::That::would::be::very::nice) break here";
         let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
         assert_eq!(
-            break_string(20, false, "", &graphemes[..]),
+            break_string(20, false, "", &graphemes[..], &BreakOptions::default()),
             SnippetState::LineEnd(
                 "[TheName](Dont::break::my::type::That::would::be::very::nice) ".to_string(),
                 62
@@ -405,125 +1123,365 @@ mod test {
         let string = "Placerat felis. Mauris porta ante sagittis purus.";
         let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
         assert_eq!(
-            break_string(20, false, "", &graphemes[..]),
+            break_string(20, false, "", &graphemes[..], &BreakOptions::default()),
             SnippetState::LineEnd("Placerat felis. ".to_string(), 16)
         );
         assert_eq!(
-            break_string(20, true, "", &graphemes[..]),
+            break_string(20, true, "", &graphemes[..], &BreakOptions::default()),
             SnippetState::LineEnd("Placerat felis.".to_string(), 16)
         );
     }
 
     #[test]
-    fn should_break_on_punctuation() {
-        let string = "Placerat_felis._Mauris_porta_ante_sagittis_purus.";
+    fn should_not_break_on_non_breaking_space() {
+        let string = "Placerat felis mauris 10\u{a0}kg porta";
         let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
         assert_eq!(
-            break_string(20, false, "", &graphemes[..]),
-            SnippetState::LineEnd("Placerat_felis.".to_string(), 15)
+            break_string(26, false, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("Placerat felis mauris ".to_string(), 22)
         );
     }
 
     #[test]
-    fn should_break_forward() {
-        let string = "Venenatis_tellus_vel_tellus. Aliquam aliquam dolor at justo.";
+    fn should_break_at_soft_hyphen() {
+        let string = "Placerat felis mauris\u{ad}porta\u{ad}tellus";
         let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        let break_with = |max_width, line_end| {
+            break_string(
+                max_width,
+                true,
+                line_end,
+                &graphemes[..],
+                &BreakOptions::default(),
+            )
+        };
         assert_eq!(
-            break_string(20, false, "", &graphemes[..]),
-            SnippetState::LineEnd("Venenatis_tellus_vel_tellus. ".to_string(), 29)
+            break_with(27, ""),
+            SnippetState::LineEnd("Placerat felis mauris\u{ad}porta-".to_string(), 28)
         );
+        // The shown hyphen must fit on the line too.
         assert_eq!(
-            break_string(20, true, "", &graphemes[..]),
-            SnippetState::LineEnd("Venenatis_tellus_vel_tellus.".to_string(), 29)
+            break_with(26, ""),
+            SnippetState::LineEnd("Placerat felis mauris-".to_string(), 22)
+        );
+        // A string literal keeps its soft hyphens.
+        assert_eq!(
+            break_with(26, "\\"),
+            SnippetState::LineEnd("Placerat felis mauris\u{ad}".to_string(), 22)
         );
     }
 
     #[test]
-    fn nothing_to_break() {
-        let string = "Venenatis_tellus_vel_tellus";
-        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
-        assert_eq!(
-            break_string(20, false, "", &graphemes[..]),
-            SnippetState::EndOfInput("Venenatis_tellus_vel_tellus".to_string())
-        );
+    fn prefix_widths_add_up_grapheme_widths() {
+        let string = "a\u{ad}日本e\u{301}x";
+        let graphemes = UnicodeSegmentation::graphemes(string, true).collect::<Vec<&str>>();
+        let widths = prefix_widths(&graphemes, Version::One);
+        assert_eq!(widths, vec![0, 1, 1, 3, 5, 6, 7]);
+        for (i, &width) in widths.iter().enumerate() {
+            assert_eq!(width, unicode_str_width(&graphemes[..i].concat()));
+        }
     }
 
     #[test]
-    fn significant_whitespaces() {
-        let string = "Neque in sem.      \n      Pellentesque tellus augue.";
-        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+    fn long_line_with_soft_hyphens_breaks_within_width() {
+        let words = "lo\u{ad}rem\u{ad}ip\u{ad}sum 日本語 dolor\u{301} ";
+        let string = words.repeat(5000 / words.chars().count());
+        let offsets = comment_break_offsets(&string, 40, "// ", &Config::default());
+        assert!(offsets.len() > 100);
+        let starts = iter::once(0).chain(offsets.iter().copied());
+        let ends = offsets.iter().copied().chain(iter::once(string.len()));
+        for (start, end) in starts.zip(ends) {
+            let line = string[start..end].trim_end();
+            // A line broken at a soft hyphen shows a hyphen.
+            let shown_hyphen = usize::from(line.ends_with(SOFT_HYPHEN));
+            assert!(unicode_str_width(line) + shown_hyphen <= 37, "{line:?}");
+        }
+    }
+
+    #[test]
+    fn break_preference_orders_whitespace_and_punctuation() {
+        let break_with = |string: &str, preference| {
+            let graphemes = UnicodeSegmentation::graphemes(string, false).collect::<Vec<&str>>();
+            break_string(
+                30,
+                true,
+                "",
+                &graphemes[..],
+                &BreakOptions {
+                    preference,
+                    ..BreakOptions::default()
+                },
+            )
+        };
+
+        let punctuation_first = "Lorem ipsum, dolor sit amet consectetur adipiscing";
         assert_eq!(
-            break_string(15, false, "", &graphemes[..]),
-            SnippetState::EndWithLineFeed("Neque in sem.      \n".to_string(), 20)
+            break_with(punctuation_first, BreakPreference::Whitespace),
+            SnippetState::LineEnd("Lorem ipsum, dolor sit amet".to_string(), 28)
         );
         assert_eq!(
-            break_string(25, false, "", &graphemes[..]),
-            SnippetState::EndWithLineFeed("Neque in sem.      \n".to_string(), 20)
+            break_with(punctuation_first, BreakPreference::Punctuation),
+            SnippetState::LineEnd("Lorem ipsum,".to_string(), 13)
+        );
+        assert_eq!(
+            break_with(punctuation_first, BreakPreference::Widest),
+            SnippetState::LineEnd("Lorem ipsum, dolor sit amet".to_string(), 28)
         );
 
+        let whitespace_first = "Lorem ipsum dolor sit amet,consectetur adipiscing";
         assert_eq!(
-            break_string(15, true, "", &graphemes[..]),
-            SnippetState::LineEnd("Neque in sem.".to_string(), 19)
+            break_with(whitespace_first, BreakPreference::Whitespace),
+            SnippetState::LineEnd("Lorem ipsum dolor sit".to_string(), 22)
         );
         assert_eq!(
-            break_string(25, true, "", &graphemes[..]),
-            SnippetState::EndWithLineFeed("Neque in sem.\n".to_string(), 20)
+            break_with(whitespace_first, BreakPreference::Punctuation),
+            SnippetState::LineEnd("Lorem ipsum dolor sit amet,".to_string(), 27)
+        );
+        assert_eq!(
+            break_with(whitespace_first, BreakPreference::Widest),
+            SnippetState::LineEnd("Lorem ipsum dolor sit amet,".to_string(), 27)
         );
     }
 
     #[test]
-    fn big_whitespace() {
-        let string = "Neque in sem.            Pellentesque tellus augue.";
+    fn should_break_on_punctuation() {
+        let string = "Placerat_felis._Mauris_porta_ante_sagittis_purus.";
         let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
         assert_eq!(
-            break_string(20, false, "", &graphemes[..]),
-            SnippetState::LineEnd("Neque in sem.            ".to_string(), 25)
-        );
-        assert_eq!(
-            break_string(20, true, "", &graphemes[..]),
-            SnippetState::LineEnd("Neque in sem.".to_string(), 25)
+            break_string(20, false, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("Placerat_felis.".to_string(), 15)
         );
     }
 
     #[test]
-    fn newline_in_candidate_line() {
-        let string = "Nulla\nconsequat erat at massa. Vivamus id mi.";
-
-        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+    fn should_not_break_within_punctuation_run() {
+        let break_at = |string: &str, max_width, version| {
+            let graphemes = UnicodeSegmentation::graphemes(string, false).collect::<Vec<&str>>();
+            let options = BreakOptions {
+                version,
+                ..BreakOptions::default()
+            };
+            break_string(max_width, false, "", &graphemes[..], &options)
+        };
         assert_eq!(
-            break_string(25, false, "", &graphemes[..]),
-            SnippetState::EndWithLineFeed("Nulla\n".to_string(), 6)
+            break_at("Placerat_felis!!!Mauris_porta", 15, Version::One),
+            SnippetState::LineEnd("Placerat_felis!".to_string(), 15)
         );
+        let break_at = |string: &str, max_width| break_at(string, max_width, Version::Two);
         assert_eq!(
-            break_string(25, true, "", &graphemes[..]),
-            SnippetState::EndWithLineFeed("Nulla\n".to_string(), 6)
+            break_at("Placerat_felis!!!Mauris_porta", 15),
+            SnippetState::LineEnd("Placerat_felis!!!".to_string(), 17)
         );
-
-        let mut config: Config = Default::default();
-        config.set().max_width(27);
-        let fmt = StringFormat::new(Shape::legacy(25, Indent::empty()), &config);
-        let rewritten_string = rewrite_string(string, &fmt, 27);
         assert_eq!(
-            rewritten_string,
-            Some("\"Nulla\nconsequat erat at massa. \\\n Vivamus id mi.\"".to_string())
+            break_at("Placerat_felis...Mauris_porta", 15),
+            SnippetState::LineEnd("Placerat_felis...".to_string(), 17)
+        );
+        assert_eq!(
+            break_at("Placerat_felis.)Mauris_porta", 15),
+            SnippetState::LineEnd("Placerat_felis.".to_string(), 15)
+        );
+        assert_eq!(
+            break_at("Placerat_felis?\"Mauris_porta", 15),
+            SnippetState::LineEnd("Placerat_felis?".to_string(), 15)
         );
     }
 
     #[test]
-    fn last_line_fit_with_trailing_whitespaces() {
-        let string = "Vivamus id mi.  ";
-        let config: Config = Default::default();
-        let mut fmt = StringFormat::new(Shape::legacy(25, Indent::empty()), &config);
-
-        fmt.trim_end = true;
-        let rewritten_string = rewrite_string(string, &fmt, 25);
-        assert_eq!(rewritten_string, Some("\"Vivamus id mi.\"".to_string()));
-
-        fmt.trim_end = false; // default value of trim_end
+    fn should_break_forward() {
+        let string = "Venenatis_tellus_vel_tellus. Aliquam aliquam dolor at justo.";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(
+            break_string(20, false, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("Venenatis_tellus_vel_tellus. ".to_string(), 29)
+        );
+        assert_eq!(
+            break_string(20, true, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("Venenatis_tellus_vel_tellus.".to_string(), 29)
+        );
+    }
+
+    #[test]
+    fn nothing_to_break() {
+        let string = "Venenatis_tellus_vel_tellus";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(
+            break_string(20, false, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::EndOfInput("Venenatis_tellus_vel_tellus".to_string())
+        );
+    }
+
+    #[test]
+    fn break_long_words() {
+        let string = format!("Key: {}", "x".repeat(100));
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        let break_with = |line_end, break_long_words| {
+            break_string(
+                30,
+                false,
+                line_end,
+                &graphemes[..],
+                &BreakOptions {
+                    break_long_words,
+                    ..BreakOptions::default()
+                },
+            )
+        };
+        assert_eq!(
+            break_with("", false),
+            SnippetState::EndOfInput(string.clone())
+        );
+        assert_eq!(
+            break_with("\\", false),
+            SnippetState::EndOfInput(string.clone())
+        );
+        // The word starts a line of its own first.
+        assert_eq!(
+            break_with("", true),
+            SnippetState::LineEnd("Key: ".to_string(), 5)
+        );
+        assert_eq!(
+            break_with("\\", true),
+            SnippetState::LineEnd("Key: ".to_string(), 5)
+        );
+
+        let graphemes = &graphemes[5..];
+        assert_eq!(
+            break_string(
+                30,
+                false,
+                "\\",
+                graphemes,
+                &BreakOptions {
+                    break_long_words: true,
+                    ..BreakOptions::default()
+                }
+            ),
+            SnippetState::LineEnd("x".repeat(30), 30)
+        );
+    }
+
+    #[test]
+    fn split_words_keep_escape_sequences() {
+        let graphemes = |string| UnicodeSegmentation::graphemes(string, false).collect::<Vec<_>>();
+        assert_eq!(escape_sequence_start(&graphemes(r"ab\u{1F600}cd"), 5), 2);
+        assert_eq!(escape_sequence_start(&graphemes(r"ab\u{1F600}cd"), 10), 2);
+        assert_eq!(escape_sequence_start(&graphemes(r"ab\u{1F600}cd"), 11), 11);
+        assert_eq!(escape_sequence_start(&graphemes(r"ab\x41cd"), 4), 2);
+        assert_eq!(escape_sequence_start(&graphemes(r"ab\ncd"), 3), 2);
+        assert_eq!(escape_sequence_start(&graphemes(r"ab\\cd"), 3), 2);
+        assert_eq!(escape_sequence_start(&graphemes(r"ab\\cd"), 4), 4);
+    }
+
+    #[test]
+    fn significant_whitespaces() {
+        let string = "Neque in sem.      \n      Pellentesque tellus augue.";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(
+            break_string(15, false, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::EndWithLineFeed("Neque in sem.      \n".to_string(), 20)
+        );
+        assert_eq!(
+            break_string(25, false, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::EndWithLineFeed("Neque in sem.      \n".to_string(), 20)
+        );
+
+        assert_eq!(
+            break_string(15, true, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("Neque in sem.".to_string(), 19)
+        );
+        assert_eq!(
+            break_string(25, true, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::EndWithLineFeed("Neque in sem.\n".to_string(), 20)
+        );
+    }
+
+    #[test]
+    fn big_whitespace() {
+        let string = "Neque in sem.            Pellentesque tellus augue.";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(
+            break_string(20, false, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("Neque in sem.            ".to_string(), 25)
+        );
+        assert_eq!(
+            break_string(20, true, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("Neque in sem.".to_string(), 25)
+        );
+    }
+
+    #[test]
+    fn newline_in_candidate_line() {
+        let string = "Nulla\nconsequat erat at massa. Vivamus id mi.";
+
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(
+            break_string(25, false, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::EndWithLineFeed("Nulla\n".to_string(), 6)
+        );
+        assert_eq!(
+            break_string(25, true, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::EndWithLineFeed("Nulla\n".to_string(), 6)
+        );
+
+        let mut config: Config = Default::default();
+        config.set().max_width(27);
+        let fmt = StringFormat::new(Shape::legacy(25, Indent::empty()), &config);
+        let rewritten_string = rewrite_string(string, &fmt, 27);
+        assert_eq!(
+            rewritten_string,
+            Some("\"Nulla\nconsequat erat at massa. \\\n Vivamus id mi.\"".to_string())
+        );
+    }
+
+    #[test]
+    fn last_line_fit_with_trailing_whitespaces() {
+        let string = "Vivamus id mi.  ";
+        let config: Config = Default::default();
+        let mut fmt = StringFormat::new(Shape::legacy(25, Indent::empty()), &config);
+
+        fmt.trim_end = TrimEnd::All;
+        let rewritten_string = rewrite_string(string, &fmt, 25);
+        assert_eq!(rewritten_string, Some("\"Vivamus id mi.\"".to_string()));
+
+        fmt.trim_end = TrimEnd::None; // default value of trim_end
         let rewritten_string = rewrite_string(string, &fmt, 25);
         assert_eq!(rewritten_string, Some("\"Vivamus id mi.  \"".to_string()));
     }
 
+    #[test]
+    fn block_comment_with_line_starts() {
+        let string = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod \
+                      tempor incididunt ut labore.";
+        let config: Config = Default::default();
+        let fmt = StringFormat {
+            opener: "/*",
+            closer: " */",
+            line_start: " * ",
+            line_end: "",
+            shape: Shape::legacy(30, Indent::empty()),
+            trim_end: TrimEnd::All,
+            config: &config,
+        };
+
+        assert_eq!(
+            rewrite_string(string, &fmt, 27),
+            Some(
+                "/* Lorem ipsum dolor sit amet,\n * consectetur adipiscing\n * elit, sed do \
+                 eiusmod tempor\n * incididunt ut labore. */"
+                    .to_string()
+            )
+        );
+        // The closer doesn't fit after the text, so the last word goes on a line of its own.
+        assert_eq!(
+            rewrite_string("Lorem ipsum dolor sit amet,", &fmt, 27),
+            Some("/* Lorem ipsum dolor sit\n * amet, */".to_string())
+        );
+        assert_eq!(
+            rewrite_string("Lorem ipsum", &fmt, 27),
+            Some("/* Lorem ipsum */".to_string())
+        );
+    }
+
     #[test]
     fn last_line_fit_with_newline() {
         let string = "Vivamus id mi.\nVivamus id mi.";
@@ -534,7 +1492,7 @@ mod test {
             line_start: "// ",
             line_end: "",
             shape: Shape::legacy(100, Indent::from_width(&config, 4)),
-            trim_end: true,
+            trim_end: TrimEnd::All,
             config: &config,
         };
 
@@ -555,7 +1513,7 @@ mod test {
             line_start: "// ",
             line_end: "",
             shape: Shape::legacy(30, Indent::from_width(&config, 8)),
-            trim_end: true,
+            trim_end: TrimEnd::All,
             config: &config,
         };
 
@@ -578,7 +1536,7 @@ mod test {
             line_start: "// ",
             line_end: "@",
             shape: Shape::legacy(30, Indent::from_width(&config, 8)),
-            trim_end: true,
+            trim_end: TrimEnd::All,
             config: &config,
         };
 
@@ -591,6 +1549,90 @@ mod test {
         );
     }
 
+    #[test]
+    fn overflow_with_multi_byte_line_end() {
+        let comment = "Aenean metus.\nVestibulum ac lacus. Vivamus porttitor";
+        let config: Config = Default::default();
+        let fmt = StringFormat {
+            opener: "",
+            closer: "",
+            line_start: "// ",
+            line_end: " ↵",
+            shape: Shape::legacy(31, Indent::from_width(&config, 8)),
+            trim_end: TrimEnd::All,
+            config: &config,
+        };
+
+        assert_eq!(
+            rewrite_string(comment, &fmt, 31),
+            Some(
+                "Aenean metus.\n        // Vestibulum ac lacus. Vivamus ↵\n        // porttitor"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn wide_opener() {
+        let string = "Vivamus porttitors nisi et finibus mollis";
+        let config: Config = Default::default();
+        let fmt = StringFormat {
+            opener: "「",
+            closer: "」",
+            line_start: "",
+            line_end: "",
+            shape: Shape::legacy(20, Indent::empty()),
+            trim_end: TrimEnd::All,
+            config: &config,
+        };
+
+        // The first line is exactly 20 columns wide, although it is 21 bytes long.
+        assert_eq!(
+            rewrite_string(string, &fmt, 20),
+            Some("「Vivamus porttitors\nnisi et finibus\nmollis」".to_string())
+        );
+    }
+
+    #[test]
+    fn comment_width_after_line_feed() {
+        let comment = "Aenean metus.\nVestibulum ac lacus. Vivamus porttitor, nisi et \
+                       finibus mollis, urna est aliquam leo. Nulla facilisi.";
+        let rewrite = |config: &Config| {
+            let fmt = StringFormat {
+                opener: "",
+                closer: "",
+                line_start: "",
+                line_end: "",
+                shape: Shape::legacy(40, Indent::empty()),
+                trim_end: TrimEnd::All,
+                config,
+            };
+            rewrite_string(comment, &fmt, 40)
+        };
+
+        let wrapped_at_50 = "Aenean metus.\nVestibulum ac lacus. Vivamus porttitor, nisi et\n\
+                             finibus mollis, urna est aliquam leo.\nNulla facilisi.";
+
+        // Without `comment_width`, the line that follows the line feed may use all of `max_width`.
+        let mut config: Config = Default::default();
+        assert_eq!(
+            rewrite(&config),
+            Some(
+                "Aenean metus.\nVestibulum ac lacus. Vivamus porttitor, nisi et finibus mollis, \
+                 urna est aliquam leo. Nulla\nfacilisi."
+                    .to_string()
+            )
+        );
+
+        config.override_value("comment_width", "50");
+        assert_eq!(rewrite(&config), Some(wrapped_at_50.to_string()));
+
+        // `comment_width` is clamped to `max_width`.
+        config.override_value("comment_width", "120");
+        config.set().max_width(50);
+        assert_eq!(rewrite(&config), Some(wrapped_at_50.to_string()));
+    }
+
     #[test]
     fn blank_line_with_non_empty_line_start() {
         let config: Config = Default::default();
@@ -600,7 +1642,7 @@ mod test {
             line_start: "// ",
             line_end: "",
             shape: Shape::legacy(30, Indent::from_width(&config, 4)),
-            trim_end: true,
+            trim_end: TrimEnd::All,
             config: &config,
         };
 
@@ -627,6 +1669,53 @@ mod test {
         );
     }
 
+    #[test]
+    fn blank_line_with_block_comment_line_start() {
+        let config: Config = Default::default();
+        let mut fmt = StringFormat {
+            opener: "",
+            closer: "",
+            line_start: " * ",
+            line_end: "",
+            shape: Shape::legacy(30, Indent::from_width(&config, 4)),
+            trim_end: TrimEnd::All,
+            config: &config,
+        };
+
+        let comment = "Aenean metus. Vestibulum\n\nac lacus. Vivamus porttitor";
+        assert_eq!(
+            rewrite_string(comment, &fmt, 30),
+            Some(
+                "Aenean metus. Vestibulum\n     *\n     * ac lacus. Vivamus porttitor".to_string()
+            )
+        );
+
+        // The blank line doesn't keep the trailing space of `line_start`, even if other
+        // trailing whitespace is kept.
+        fmt.trim_end = TrimEnd::None;
+        assert_eq!(
+            rewrite_string(comment, &fmt, 30),
+            Some(
+                "Aenean metus. Vestibulum\n     *\n     * ac lacus. Vivamus porttitor".to_string()
+            )
+        );
+
+        fmt.trim_end = TrimEnd::All;
+        fmt.shape = Shape::legacy(15, Indent::from_width(&config, 4));
+        let comment = "Aenean\n\nmetus. Vestibulum ac lacus. Vivamus porttitor";
+        assert_eq!(
+            rewrite_string(comment, &fmt, 15),
+            Some(
+                r#"Aenean
+     *
+     * metus. Vestibulum
+     * ac lacus. Vivamus
+     * porttitor"#
+                    .to_string()
+            )
+        );
+    }
+
     #[test]
     fn retain_blank_lines() {
         let config: Config = Default::default();
@@ -636,7 +1725,7 @@ mod test {
             line_start: "// ",
             line_end: "",
             shape: Shape::legacy(20, Indent::from_width(&config, 4)),
-            trim_end: true,
+            trim_end: TrimEnd::All,
             config: &config,
         };
 
@@ -659,6 +1748,84 @@ mod test {
             rewrite_string(comment, &fmt, 20),
             Some("Aenean\n    //\n    // metus. Vestibulum ac\n    // lacus.".to_string())
         );
+
+        let comment = "Aenean\n\n\n\nmetus. Vestibulum ac lacus.\n\n\n";
+        assert_eq!(
+            rewrite_string(comment, &fmt, 20),
+            Some(
+                concat!(
+                    "Aenean\n    //\n    //\n    //\n    // metus. Vestibulum ac\n",
+                    "    // lacus.\n    //\n    //\n"
+                )
+                .to_string()
+            )
+        );
+
+        let mut collapsing_config = config.clone();
+        collapsing_config.set().comment_collapse_blank_lines(true);
+        let fmt = StringFormat {
+            config: &collapsing_config,
+            ..fmt
+        };
+        assert_eq!(
+            rewrite_string(comment, &fmt, 20),
+            Some(
+                "Aenean\n    //\n    // metus. Vestibulum ac\n    // lacus.\n    //\n".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        // `\r\n` is a single grapheme, so the lengths read match the `\n` case.
+        let string = "Neque in sem.      \r\n      Pellentesque tellus augue.";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        for (max_width, trim_end) in [(15, false), (25, false), (25, true)] {
+            let expected = if trim_end {
+                "Neque in sem.\n"
+            } else {
+                "Neque in sem.      \n"
+            };
+            assert_eq!(
+                break_string(
+                    max_width,
+                    trim_end,
+                    "",
+                    &graphemes[..],
+                    &BreakOptions::default()
+                ),
+                SnippetState::EndWithLineFeed(expected.to_string(), 20)
+            );
+        }
+
+        let config: Config = Default::default();
+        let comment_fmt = StringFormat {
+            opener: "",
+            closer: "",
+            line_start: "// ",
+            line_end: "",
+            shape: Shape::legacy(20, Indent::from_width(&config, 4)),
+            trim_end: TrimEnd::All,
+            config: &config,
+        };
+        let mut string_config: Config = Default::default();
+        string_config.set().max_width(27);
+        let string_fmt = StringFormat::new(Shape::legacy(25, Indent::empty()), &string_config);
+
+        for (fmt, max_chars) in [(&comment_fmt, 20), (&string_fmt, 27)] {
+            for lf in [
+                "Aenean\n\nmetus. Vestibulum ac lacus.\n\n",
+                "Aenean metus.\nVestibulum ac lacus. Vivamus porttitor",
+                "Nulla\nconsequat erat at massa. Vivamus id mi.",
+                "Nulla   \n   consequat erat at massa.\\\n   Vivamus id mi.",
+                "Neque in sem.             \nPellentesque sit amet enim.",
+            ] {
+                let crlf = lf.replace('\n', "\r\n");
+                let rewritten = rewrite_string(&crlf, fmt, max_chars);
+                assert_eq!(rewritten, rewrite_string(lf, fmt, max_chars), "{crlf:?}");
+                assert!(!rewritten.unwrap_or_default().contains('\r'), "{crlf:?}");
+            }
+        }
     }
 
     #[test]
@@ -670,7 +1837,7 @@ mod test {
             line_start: "// ",
             line_end: "",
             shape: Shape::legacy(13, Indent::from_width(&config, 4)),
-            trim_end: true,
+            trim_end: TrimEnd::All,
             config: &config,
         };
 
@@ -680,14 +1847,14 @@ mod test {
             Some("Aenean metus.\n    // Vestibulum ac\n    // lacus.".to_string())
         );
 
-        fmt.trim_end = false;
+        fmt.trim_end = TrimEnd::None;
         let comment = "Vestibulum ac lacus.";
         assert_eq!(
             rewrite_string(comment, &fmt, 13),
             Some("Vestibulum \n    // ac lacus.".to_string())
         );
 
-        fmt.trim_end = true;
+        fmt.trim_end = TrimEnd::All;
         fmt.line_end = "\\";
         let comment = "Vestibulum ac lacus.";
         assert_eq!(
@@ -696,6 +1863,353 @@ mod test {
         );
     }
 
+    #[test]
+    fn should_not_break_inside_inline_code_span() {
+        let string = "Placerat felis, call `foo_bar_baz qux()` to do the thing.";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(
+            break_string(35, false, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("Placerat felis, call ".to_string(), 21)
+        );
+        assert_eq!(
+            break_string(35, true, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("Placerat felis, call".to_string(), 21)
+        );
+    }
+
+    #[test]
+    fn should_not_break_inside_html_comment() {
+        let string = "Placerat felis, see <!-- a note --> for the thing.";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(
+            break_string(30, true, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("Placerat felis, see".to_string(), 20)
+        );
+    }
+
+    #[test]
+    fn should_not_break_inside_double_backtick_code_span() {
+        let string = "Placerat felis, call ``foo `bar` baz`` to do the thing.";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(
+            break_string(35, true, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("Placerat felis, call".to_string(), 21)
+        );
+    }
+
+    #[test]
+    fn should_break_inside_code_span_too_wide_for_a_line() {
+        let string = "Placerat felis, call `foo bar baz qux quux corge` to do the thing.";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(
+            break_string(
+                35,
+                true,
+                "",
+                &graphemes[..],
+                &BreakOptions {
+                    long_code_span_width: Some(20),
+                    ..BreakOptions::default()
+                }
+            ),
+            SnippetState::LineEndInCodeSpan(
+                "Placerat felis, call `foo bar baz`".to_string(),
+                34,
+                1
+            )
+        );
+        assert_eq!(
+            break_string(
+                35,
+                true,
+                "",
+                &graphemes[..],
+                &BreakOptions {
+                    long_code_span_width: Some(30),
+                    ..BreakOptions::default()
+                }
+            ),
+            SnippetState::LineEnd("Placerat felis, call".to_string(), 21)
+        );
+    }
+
+    #[test]
+    fn code_span_with_backticks_is_closed_and_opened_again_apart_from_them() {
+        let mut config: Config = Default::default();
+        config.set().break_long_code_spans(true);
+        config.set().max_width(33);
+        let fmt = StringFormat {
+            opener: "",
+            closer: "",
+            line_start: "// ",
+            line_end: "",
+            shape: Shape::legacy(33, Indent::empty()),
+            trim_end: TrimEnd::All,
+            config: &config,
+        };
+        assert_eq!(
+            rewrite_string(
+                "call `` aaa` bbb` ccc` ddd` eee` fff` ggg` `` now",
+                &fmt,
+                33
+            ),
+            Some("call `` aaa` bbb` ccc` ddd` ``\n// ``eee` fff` ggg` `` now".to_owned())
+        );
+        assert_eq!(
+            rewrite_string("call ``` aaa`` bbb`` ccc`` ``ddd ``eee ``` now", &fmt, 33),
+            Some("call ``` aaa`` bbb`` ccc`` ```\n// ``` ``ddd ``eee ``` now".to_owned())
+        );
+    }
+
+    #[test]
+    fn unmatched_backtick_is_not_a_code_span() {
+        let string = "Placerat felis, call `foo_bar_baz qux() to do the thing.";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(
+            break_string(35, true, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("Placerat felis, call `foo_bar_baz".to_string(), 34)
+        );
+    }
+
+    #[test]
+    fn should_not_break_inside_autolink() {
+        let string = "Placerat felis, mail <foo.bar@example.org> to do the thing.";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(
+            break_string(35, true, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("Placerat felis, mail".to_string(), 21)
+        );
+
+        let string = "Placerat felis, see <mailto:foo.bar@example.org> for more.";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(
+            break_string(35, true, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("Placerat felis, see".to_string(), 20)
+        );
+    }
+
+    #[test]
+    fn should_not_break_inside_wikilink() {
+        let string = "Placerat felis, see [[Some Target|the docs]] for more.";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(
+            break_string(35, true, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("Placerat felis, see".to_string(), 20)
+        );
+
+        // Single brackets are regular text.
+        let string = "Placerat felis, see [Some Target|the docs] for more.";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(
+            break_string(35, true, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("Placerat felis, see [Some".to_string(), 26)
+        );
+    }
+
+    #[test]
+    fn should_not_break_inside_inline_math() {
+        let string = "Placerat felis, where $a^2 + b^2 = c^2$ holds.";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(
+            break_string(30, true, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("Placerat felis, where".to_string(), 22)
+        );
+
+        // Dollar signs next to whitespace are regular text.
+        let string = "Placerat felis, costs $5 or $10 at most.";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(
+            break_string(30, true, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("Placerat felis, costs $5 or".to_string(), 28)
+        );
+    }
+
+    #[test]
+    fn sentence_preference_breaks_after_a_sentence() {
+        let string = "Lorem ipsum dolor sit. Amet consectetur adipiscing elit.";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        let break_with = |preference| {
+            break_string(
+                35,
+                true,
+                "",
+                &graphemes[..],
+                &BreakOptions {
+                    preference,
+                    ..BreakOptions::default()
+                },
+            )
+        };
+        assert_eq!(
+            break_with(BreakPreference::Whitespace),
+            SnippetState::LineEnd("Lorem ipsum dolor sit. Amet".to_string(), 28)
+        );
+        assert_eq!(
+            break_with(BreakPreference::Sentence),
+            SnippetState::LineEnd("Lorem ipsum dolor sit.".to_string(), 23)
+        );
+
+        // Without a sentence end on the line, the last whitespace is used.
+        let string = "Lorem ipsum dolor sit amet consectetur adipiscing elit.";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(
+            break_string(
+                35,
+                true,
+                "",
+                &graphemes[..],
+                &BreakOptions {
+                    preference: BreakPreference::Sentence,
+                    ..BreakOptions::default()
+                }
+            ),
+            SnippetState::LineEnd("Lorem ipsum dolor sit amet".to_string(), 27)
+        );
+    }
+
+    #[test]
+    fn should_not_break_inside_file_url() {
+        let string = "Run it from file:///C:/Program Files/app.exe in a shell.";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(
+            break_string(30, true, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("Run it from".to_string(), 12)
+        );
+        assert_eq!(
+            break_string(20, true, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd(
+                "Run it from file:///C:/Program Files/app.exe".to_string(),
+                45
+            )
+        );
+    }
+
+    #[test]
+    fn should_not_break_inside_versions() {
+        let break_at_punctuation = |string: &str, max_width| {
+            let graphemes = UnicodeSegmentation::graphemes(string, false).collect::<Vec<&str>>();
+            break_string(
+                max_width,
+                true,
+                "",
+                &graphemes[..],
+                &BreakOptions {
+                    preference: BreakPreference::Punctuation,
+                    ..BreakOptions::default()
+                },
+            )
+        };
+        assert_eq!(
+            break_at_punctuation("Update the crate to 1.2.3 now", 22),
+            SnippetState::LineEnd("Update the crate to".to_string(), 20)
+        );
+        assert_eq!(
+            break_at_punctuation("Any version in >=1.0.0,<2.0.0 works", 25),
+            SnippetState::LineEnd("Any version in".to_string(), 15)
+        );
+        assert_eq!(
+            break_at_punctuation("Published as 1.0.0-alpha.1+build.5 today", 30),
+            SnippetState::LineEnd("Published as".to_string(), 13)
+        );
+        // Dotted runs that aren't versions may still be broken at their dots.
+        assert_eq!(
+            break_at_punctuation("Read section 1.2.3.4 of the spec", 17),
+            SnippetState::LineEnd("Read section 1.2.".to_string(), 17)
+        );
+
+        let versions = |string: &str| {
+            let graphemes = UnicodeSegmentation::graphemes(string, false).collect::<Vec<&str>>();
+            version_spans(&graphemes)
+                .iter()
+                .map(|&in_version| if in_version { '^' } else { ' ' })
+                .collect::<String>()
+        };
+        assert_eq!(versions("see 1.2.3."), "    ^^^^^^");
+        assert_eq!(versions("(v1.2.3) or 1.2"), "^^^^^^^^       ");
+        assert_eq!(versions("a.b.c 1.2.3a"), "            ");
+    }
+
+    #[test]
+    fn repeated_calls_give_the_same_result() {
+        let string = "Lorem ipsum dolor sit amet, \\\n    consectetur adipiscing elit, sed do \
+                      eiusmod tempor incididunt ut labore et dolore magna aliqua.";
+        let config: Config = Default::default();
+        let fmt = StringFormat::new(Shape::legacy(40, Indent::empty()), &config);
+        let first = rewrite_string(string, &fmt, 40);
+        assert!(first.is_some());
+        for _ in 0..3 {
+            assert_eq!(rewrite_string(string, &fmt, 40), first);
+        }
+    }
+
+    #[test]
+    fn trim_end_keeps_line_feeds() {
+        let mut result = "foo \t\n \t".to_owned();
+        trim_end_but_line_feed(true, &mut result);
+        assert_eq!(result, "foo \t\n");
+
+        let mut result = "foo  ".to_owned();
+        trim_end_but_line_feed(false, &mut result);
+        assert_eq!(result, "foo  ");
+    }
+
+    #[test]
+    fn long_multi_line_string() {
+        let line = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod.  ";
+        let string = vec![line; 200].join("\n");
+        let config: Config = Default::default();
+        let mut fmt = StringFormat::new(Shape::legacy(100, Indent::empty()), &config);
+        fmt.trim_end = TrimEnd::All;
+        let expected = vec![line.trim_end(); 200].join("\n");
+        assert_eq!(
+            rewrite_string(&string, &fmt, 100),
+            Some(format!("\"{}\"", expected))
+        );
+    }
+
+    #[test]
+    fn combining_marks_have_no_width() {
+        let string = "re\u{301}sume\u{301} re\u{301}sume\u{301} re\u{301}sume\u{301} end";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, true).collect::<Vec<&str>>();
+        assert_eq!(
+            break_string(20, false, "", &graphemes[..], &BreakOptions::default()),
+            SnippetState::LineEnd("re\u{301}sume\u{301} re\u{301}sume\u{301} ".to_string(), 14)
+        );
+    }
+
+    #[test]
+    fn only_break_between_grapheme_clusters() {
+        let mut config: Config = Default::default();
+        let string =
+            "Le\u{301}on et Ame\u{301}lie ont visite\u{301} \u{93f}le muse\u{301}e d'Orsay";
+
+        let fmt = StringFormat::new(Shape::legacy(30, Indent::empty()), &config);
+        // Version One uses legacy clusters, which split the spacing mark from the space.
+        let expected = "\"Le\u{301}on et Ame\u{301}lie ont visite\u{301} \\\n\
+                        \x20\u{93f}le muse\u{301}e d'Orsay\"";
+        assert_eq!(rewrite_string(string, &fmt, 30), Some(expected.to_owned()));
+
+        // The spacing mark forms one grapheme cluster with the space before it, so the string
+        // cannot be broken at that space.
+        config.set().version(Version::Two);
+        let fmt = StringFormat::new(Shape::legacy(30, Indent::empty()), &config);
+        let expected = "\"Le\u{301}on et Ame\u{301}lie ont \\\n\
+                        \x20visite\u{301} \u{93f}le muse\u{301}e d'Orsay\"";
+        assert_eq!(rewrite_string(string, &fmt, 30), Some(expected.to_owned()));
+    }
+
+    #[test]
+    fn zwj_emoji_sequence_at_the_width_boundary() {
+        let mut config: Config = Default::default();
+        config.set().version(Version::Two);
+        let fmt = StringFormat::new(Shape::legacy(20, Indent::empty()), &config);
+        let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}\u{200d}\u{1f466}";
+        let string = format!("Hello, my dear {family} family, how are you all?");
+        // The emoji only fits at the end of the first line when counted as a single emoji.
+        let expected = format!("\"Hello, my dear {family} \\\n family, how are you \\\n all?\"");
+        assert_eq!(rewrite_string(&string, &fmt, 20), Some(expected));
+    }
+
     #[test]
     fn detect_urls() {
         let string = "aaa http://example.org something";
@@ -721,5 +2235,32 @@ mod test {
         let string = "aaa file://example.org";
         let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
         assert_eq!(detect_url(&graphemes, 8), Some(21));
+
+        let string = "aaa file:///C:/Program Files/app.exe something";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(detect_url(&graphemes, 8), Some(35));
+    }
+
+    #[test]
+    fn detect_urls_with_any_scheme_case() {
+        for string in [
+            "aaa http://example.org something",
+            "aaa HTTP://EXAMPLE.ORG something",
+            "aaa Http://Example.org something",
+            "aaa hTtP://example.org something",
+        ] {
+            let graphemes = UnicodeSegmentation::graphemes(string, false).collect::<Vec<&str>>();
+            assert_eq!(detect_url(&graphemes, 8), Some(21), "{string}");
+        }
+
+        let string = "aaa FILE:///C:/Program Files/app.exe something";
+        let graphemes = UnicodeSegmentation::graphemes(&*string, false).collect::<Vec<&str>>();
+        assert_eq!(detect_url(&graphemes, 8), Some(35));
+
+        assert_eq!(find_url_scheme("see Https://example.org"), Some(4));
+        assert_eq!(find_url_scheme("(FTP://example.org)"), Some(1));
+        assert_eq!(find_url_scheme("xhttp://example.org"), None);
+        assert_eq!(find_url_scheme("a_HTTPS://example.org"), None);
+        assert_eq!(find_url_scheme("no urls here"), None);
     }
 }
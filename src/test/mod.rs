@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
 use std::io::{self, BufRead, BufReader, Read, Write};
@@ -8,12 +8,16 @@ use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::str::Chars;
 use std::thread;
+use std::time::Duration;
 
-use crate::config::{Color, Config, EmitMode, FileName, NewlineStyle};
+use crate::config::{Color, Config, EmitMode, FileName, HardBreakStyle, NewlineStyle, ReportLevel};
 use crate::formatting::{ReportedErrors, SourceFile};
 use crate::rustfmt_diff::{make_diff, print_diff, DiffLine, Mismatch, ModifiedChunk, OutputWriter};
 use crate::source_file;
-use crate::{is_nightly_channel, FormatReport, FormatReportFormatterBuilder, Input, Session};
+use crate::{
+    format_markdown, is_nightly_channel, ErrorKind, FormatReport, FormatReportFormatterBuilder,
+    Input, MarkdownError, Session,
+};
 
 use rustfmt_config_proc_macro::nightly_only_test;
 
@@ -50,6 +54,29 @@ fn init_log() {
     let _ = tracing_subscriber::fmt().with_test_writer().try_init();
 }
 
+/// A directory for a test to write files to. It is removed when dropped, so also when the test
+/// fails.
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn new(name: &str) -> TempDir {
+        let path = env::temp_dir().join(format!("rustfmt-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
 struct TestSetting {
     /// The size of the stack of the thread that run tests.
     stack_size: usize,
@@ -99,8 +126,26 @@ fn is_file_skip(path: &Path) -> bool {
 // given path. The `recursive` argument controls if files from subdirectories
 // are also returned.
 fn get_test_files(path: &Path, recursive: bool) -> Vec<PathBuf> {
+    get_test_files_with_extension(path, recursive, "rs")
+}
+
+// Like `get_test_files`, but returns the files with the given extension.
+fn get_test_files_with_extension(path: &Path, recursive: bool, extension: &str) -> Vec<PathBuf> {
+    collect_test_files(path, recursive, extension, &mut HashSet::new())
+}
+
+// Collects the test files of `get_test_files_with_extension`. The directories are followed
+// through symlinks, but each one is only read once, which is tracked by its canonical path in
+// `visited`. A symlink back to an ancestor thus can't loop, nor count the same files twice.
+fn collect_test_files(
+    path: &Path,
+    recursive: bool,
+    extension: &str,
+    visited: &mut HashSet<PathBuf>,
+) -> Vec<PathBuf> {
     let mut files = vec![];
-    if path.is_dir() {
+    let canonical_path = fs::canonicalize(path).unwrap_or_else(|_| path.to_owned());
+    if path.is_dir() && visited.insert(canonical_path) {
         for entry in fs::read_dir(path).expect(&format!(
             "couldn't read directory {}",
             path.to_str().unwrap()
@@ -108,8 +153,10 @@ fn get_test_files(path: &Path, recursive: bool) -> Vec<PathBuf> {
             let entry = entry.expect("couldn't get `DirEntry`");
             let path = entry.path();
             if path.is_dir() && recursive {
-                files.append(&mut get_test_files(&path, recursive));
-            } else if path.extension().map_or(false, |f| f == "rs") && !is_file_skip(&path) {
+                files.append(&mut collect_test_files(
+                    &path, recursive, extension, visited,
+                ));
+            } else if path.extension().map_or(false, |f| f == extension) && !is_file_skip(&path) {
                 files.push(path);
             }
         }
@@ -207,6 +254,52 @@ fn coverage_tests() {
     assert_eq!(fails, 0, "{fails} tests failed");
 }
 
+// Formats the Markdown documents in tests/markdown/source with `format_markdown` and compares
+// them with the ones of the same name in tests/markdown/target.
+#[test]
+fn markdown_tests() {
+    init_log();
+    let mut config = Config::default();
+    config.set().wrap_comments(true);
+    config.set().comment_width(60);
+    config.set().format_code_in_doc_comments(true);
+
+    let files = get_test_files_with_extension(Path::new("tests/markdown/source"), false, "md");
+    assert!(!files.is_empty(), "no Markdown tests found");
+    let mut failures = HashMap::new();
+    for file in files {
+        let source = fs::read_to_string(&file).expect("couldn't read source");
+        let target = file
+            .to_str()
+            .unwrap()
+            .replace("tests/markdown/source", "tests/markdown/target");
+        let expected = fs::read_to_string(&target).expect("couldn't read target");
+        let formatted = format_markdown(&source, &config).expect("couldn't format Markdown");
+        let compare = make_diff(&expected, &formatted, DIFF_CONTEXT_SIZE);
+        if !compare.is_empty() {
+            failures.insert(file, compare);
+        }
+    }
+    if !failures.is_empty() {
+        print_mismatches_default_message(failures);
+        panic!("Markdown tests failed");
+    }
+}
+
+#[test]
+fn markdown_with_unformatted_code_block_is_an_error() {
+    let mut config = Config::default();
+    config.set().format_code_in_doc_comments(true);
+    let input = "# Title\n\n```rust\nfn main() {\n    let x = ;\n}\n```\n";
+    assert!(format_markdown(input, &config).is_ok());
+
+    config.set().error_on_unformatted(true);
+    assert!(matches!(
+        format_markdown(input, &config),
+        Err(MarkdownError::UnformattedCodeBlock { line: 4, .. })
+    ));
+}
+
 #[test]
 fn checkstyle_test() {
     init_log();
@@ -468,6 +561,36 @@ fn format_files_find_new_files_via_cfg_if() {
     });
 }
 
+#[test]
+fn estimate_format_time_orders_files_by_size() {
+    let dir = Path::new("tests/estimate-format-time");
+    let config = Config::default();
+    let small = crate::estimate_format_time(&dir.join("short.rs"), &config);
+    let large = crate::estimate_format_time(&dir.join("long.rs"), &config);
+    assert!(small < large);
+
+    // Lines are measured in columns, not in bytes, so a line of 93 columns but 183 bytes still
+    // fits in `max_width`.
+    assert_eq!(
+        crate::estimate_format_time(&dir.join("non_ascii.rs"), &config),
+        small
+    );
+
+    let missing = dir.join("no-such-file.rs");
+    assert_eq!(
+        crate::estimate_format_time(&missing, &config),
+        Duration::ZERO
+    );
+
+    let mut doc_config = Config::default();
+    doc_config.set().format_code_in_doc_comments(true);
+    let with_docs = dir.join("doc_comments.rs");
+    assert!(
+        crate::estimate_format_time(&with_docs, &config)
+            < crate::estimate_format_time(&with_docs, &doc_config)
+    );
+}
+
 #[test]
 fn stdin_formatting_smoke_test() {
     init_log();
@@ -643,6 +766,219 @@ fn format_lines_errors_are_reported_with_tabs() {
     assert!(session.has_formatting_errors());
 }
 
+#[test]
+fn doc_comment_hard_breaks_are_not_trailing_whitespace() {
+    init_log();
+    let input = "/// A hard  \n/// break.\nfn a() {}\n";
+    for style in [HardBreakStyle::Preserve, HardBreakStyle::Spaces] {
+        let mut config = Config::default();
+        config.set().error_on_unformatted(true);
+        config.set().doc_comment_hard_break(style);
+        let mut session = Session::<io::Sink>::new(config, None);
+        session.format(Input::Text(input.to_owned())).unwrap();
+        assert!(!session.has_formatting_errors());
+    }
+}
+
+#[test]
+fn long_lines_are_forbidden() {
+    init_log();
+    let long_comment = format!("// {}\nfn a() {{}}\n", "a".repeat(120));
+
+    let mut session = Session::<io::Stdout>::new(Config::default(), None);
+    session.format(Input::Text(long_comment.clone())).unwrap();
+    assert!(!session.has_long_line_errors());
+
+    let mut config = Config::default();
+    config.set().forbid_long_lines(true);
+    let mut session = Session::<io::Stdout>::new(config, None);
+    session.format(Input::Text(long_comment)).unwrap();
+    assert!(session.has_formatting_errors());
+    assert!(session.has_long_line_errors());
+    assert!(!session.has_operational_errors());
+}
+
+#[test]
+fn glob_imports_are_reported() {
+    init_log();
+    let input = "use std::collections::*;\nuse std::io::{self, prelude::*};\nuse std::fmt;\n";
+
+    let mut config = Config::default();
+    config.set().use_glob_imports(ReportLevel::Warn);
+    let mut session = Session::<io::Stdout>::new(config, None);
+    let report = session.format(Input::Text(input.to_owned())).unwrap();
+    assert_eq!(report.warning_count(), 2);
+    assert!(!session.has_operational_errors());
+
+    let mut config = Config::default();
+    config.set().use_glob_imports(ReportLevel::Error);
+    let mut session = Session::<io::Stdout>::new(config, None);
+    let report = session.format(Input::Text(input.to_owned())).unwrap();
+    assert_eq!(report.warning_count(), 2);
+    assert!(session.has_operational_errors());
+}
+
+#[test]
+fn glob_imports_outside_of_reorderable_groups_are_reported() {
+    init_log();
+    let input = "fn main() {\n    let x = 1;\n    use std::io::*;\n}\n\
+                 struct S;\nuse std::fmt::*;\nm!(use std::cell::*;);\n";
+
+    for reorder_imports in [true, false] {
+        let mut config = Config::default();
+        config.set().use_glob_imports(ReportLevel::Warn);
+        config.set().reorder_imports(reorder_imports);
+        let mut session = Session::<io::Stdout>::new(config, None);
+        let report = session.format(Input::Text(input.to_owned())).unwrap();
+        assert_eq!(report.warning_count(), 3);
+    }
+}
+
+#[test]
+fn glob_imports_are_ignored_by_default() {
+    init_log();
+    let input = Input::Text("use std::io::*;\n".to_owned());
+    let mut session = Session::<io::Stdout>::new(Config::default(), None);
+    let report = session.format(input).unwrap();
+    assert!(!report.has_warnings());
+}
+
+#[test]
+fn skipped_glob_imports_are_not_reported() {
+    init_log();
+    let input = Input::Text("#[rustfmt::skip]\nuse std::io::*;\n".to_owned());
+    let mut config = Config::default();
+    config.set().use_glob_imports(ReportLevel::Error);
+    let mut session = Session::<io::Stdout>::new(config, None);
+    let report = session.format(input).unwrap();
+    assert!(!report.has_warnings());
+}
+
+#[test]
+fn mixed_number_separators_are_reported() {
+    init_log();
+    let input = "fn main() {\n    let a = 1_000_000;\n    let b = 0x1234;\n    \
+                 let c = 2000000;\n    let d = 3000000.5;\n    let e = 4_000.000_1;\n}\n";
+
+    let mut session = Session::<io::Stdout>::new(Config::default(), None);
+    let report = session.format(Input::Text(input.to_owned())).unwrap();
+    assert!(!report.has_warnings());
+
+    let mut config = Config::default();
+    config.set().allow_mixed_separator_in_numbers(false);
+    let mut session = Session::<io::Stdout>::new(config, None);
+    let report = session.format(Input::Text(input.to_owned())).unwrap();
+    assert_eq!(report.warning_count(), 2);
+    assert!(session.has_operational_errors());
+}
+
+#[test]
+fn consistent_number_separators_are_not_reported() {
+    init_log();
+    let input = "const A: u64 = 1_000_000;\n#[rustfmt::skip]\nconst B: u64 = 2000000;\n\
+                 const C: u64 = 1234;\nconst D: f64 = 12_345.678e10;\n";
+    let mut config = Config::default();
+    config.set().allow_mixed_separator_in_numbers(false);
+    let mut session = Session::<io::Stdout>::new(config, None);
+    let report = session.format(Input::Text(input.to_owned())).unwrap();
+    assert!(!report.has_warnings());
+}
+
+#[test]
+fn skip_suggestions_point_at_failed_items() {
+    init_log();
+    let input = r#"fn short() {
+    let x = 1;
+}
+
+fn long() {
+    let x = "a very long string literal that cannot be broken";
+}
+
+impl Foo {
+    fn fine(&self) {}
+
+    fn table(&self) -> u32 {
+        match self { _ => "another long string literal that will not fit".len() as u32 }
+    }
+}
+
+#[rustfmt::skip]
+fn skipped() { let x = "a very long string literal that cannot be broken"; }
+"#;
+
+    let suggestions = |suggest_skips: bool| {
+        let mut config = Config::default();
+        config.set().max_width(40);
+        config.set().suggest_skips(suggest_skips);
+        let mut session = Session::<io::Stdout>::new(config, None);
+        let report = session.format(Input::Text(input.to_owned())).unwrap();
+        let errors = &report.internal.borrow().0;
+        errors
+            .values()
+            .flatten()
+            .filter_map(|error| match error.kind {
+                ErrorKind::SkipSuggestion(ref name) => Some((error.line, name.clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        suggestions(true),
+        vec![(5, "long".to_owned()), (12, "table".to_owned())]
+    );
+    assert!(suggestions(false).is_empty());
+}
+
+#[test]
+fn unformatted_doc_code_blocks_are_reported() {
+    let input = r#"/// Adds things.
+///
+/// ```
+/// let x = ;
+/// ```
+pub fn add() {}
+
+struct S {
+    /// ```
+    /// let s = "a very long string literal that does not fit";
+    /// ```
+    f: u8,
+}
+"#;
+
+    let warnings = |error_on_unformatted: bool| {
+        let mut config = Config::default();
+        config.set().format_code_in_doc_comments(true);
+        config.set().doc_comment_code_block_width(40);
+        config.set().error_on_unformatted(error_on_unformatted);
+        let mut session = Session::<io::Stdout>::new(config, None);
+        let report = session.format(Input::Text(input.to_owned())).unwrap();
+        let errors = &report.internal.borrow().0;
+        errors
+            .values()
+            .flatten()
+            .filter_map(|error| match error.kind {
+                ErrorKind::UnformattedCodeBlock(ref message) => Some((error.line, message.clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        warnings(true),
+        vec![
+            (4, "the code could not be parsed or formatted".to_owned()),
+            (
+                10,
+                "a line exceeds the maximum width of 40 characters".to_owned()
+            ),
+        ]
+    );
+    assert!(warnings(false).is_empty());
+}
+
 // For each file, run rustfmt and collect the output.
 // Returns the number of files checked and the number of failures.
 fn check_files(files: Vec<PathBuf>, opt_config: &Option<PathBuf>) -> (Vec<FormatReport>, u32, u32) {
@@ -799,39 +1135,101 @@ fn get_config(config_file: Option<&Path>) -> Config {
     Config::from_toml(&def_config, Path::new("tests/config/")).expect("invalid TOML")
 }
 
-// Reads significant comments of the form: `// rustfmt-key: value` into a hash map.
+// Reads significant comments of the form: `// rustfmt-key: value` into a hash map. The options
+// may also be listed on the lines of a block comment at the start of the file, like
+// `/* rustfmt-key: value */`, or
+//
+// ```
+// /*
+//  * rustfmt-key: value
+//  * rustfmt-other_key: value
+//  */
+// ```
 fn read_significant_comments(file_name: &Path) -> HashMap<String, String> {
     let file = fs::File::open(file_name)
         .unwrap_or_else(|_| panic!("couldn't read file {}", file_name.display()));
-    let reader = BufReader::new(file);
-    let pattern = r"^\s*//\s*rustfmt-([^:]+):\s*(\S+)";
-    let regex = regex::Regex::new(pattern).expect("failed creating pattern 1");
+    parse_significant_comments(BufReader::new(file))
+}
+
+// Reads the significant comments of a test file from `reader`, see `read_significant_comments`.
+fn parse_significant_comments(reader: impl BufRead) -> HashMap<String, String> {
+    let regex = static_regex!(r"^\s*//\s*rustfmt-([^:]+):\s*(\S+)");
+    let block_regex = static_regex!(r"^\s*(?:/\*+|\*)?\s*rustfmt-([^:]+):\s*(\S+?)\s*(?:\*/\s*)?$");
 
     // Matches lines containing significant comments or whitespace.
-    let line_regex = regex::Regex::new(r"(^\s*$)|(^\s*//\s*rustfmt-[^:]+:\s*\S+)")
-        .expect("failed creating pattern 2");
+    let line_regex = static_regex!(r"(^\s*$)|(^\s*//\s*rustfmt-[^:]+:\s*\S+)");
 
-    reader
+    let mut significant_comments = HashMap::new();
+    // Whether a block comment may still start the file, and whether one is being read.
+    let mut is_file_start = true;
+    let mut in_block_comment = false;
+    for line in reader
         .lines()
         .map(|line| line.expect("failed getting line"))
-        .filter(|line| line_regex.is_match(line))
-        .filter_map(|line| {
-            regex.captures_iter(&line).next().map(|capture| {
-                (
-                    capture
-                        .get(1)
-                        .expect("couldn't unwrap capture")
-                        .as_str()
-                        .to_owned(),
-                    capture
-                        .get(2)
-                        .expect("couldn't unwrap capture")
-                        .as_str()
-                        .to_owned(),
-                )
-            })
-        })
-        .collect()
+    {
+        let is_block_comment_start = is_file_start && line.trim_start().starts_with("/*");
+        if in_block_comment || is_block_comment_start {
+            if let Some(capture) = block_regex.captures(&line) {
+                significant_comments.insert(capture[1].to_owned(), capture[2].to_owned());
+            }
+            in_block_comment = !line.contains("*/");
+            is_file_start = false;
+            continue;
+        }
+        if !line_regex.is_match(&line) {
+            is_file_start = false;
+            continue;
+        }
+        if let Some(capture) = regex.captures(&line) {
+            significant_comments.insert(capture[1].to_owned(), capture[2].to_owned());
+        }
+    }
+    significant_comments
+}
+
+#[cfg(unix)]
+#[test]
+fn get_test_files_reads_symlinked_directories_once() {
+    let temp_dir = TempDir::new("test-files");
+    let dir = temp_dir.path();
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("a.rs"), "").unwrap();
+    fs::write(dir.join("sub/b.rs"), "").unwrap();
+    std::os::unix::fs::symlink(dir, dir.join("sub/ancestor")).unwrap();
+
+    let mut files = get_test_files(dir, true);
+    files.sort();
+    assert_eq!(files, vec![dir.join("a.rs"), dir.join("sub/b.rs")]);
+    assert_eq!(get_test_files(dir, false), vec![dir.join("a.rs")]);
+}
+
+#[test]
+fn significant_comments_in_a_block_comment_header() {
+    let expected: HashMap<_, _> = [("wrap_comments", "true"), ("comment_width", "40")]
+        .into_iter()
+        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+        .collect();
+    assert_eq!(
+        read_significant_comments(Path::new("tests/source/block-comment-config-header.rs")),
+        expected
+    );
+
+    let read =
+        |header: &str| parse_significant_comments(format!("{header}\nfn main() {{}}\n").as_bytes());
+    assert_eq!(
+        read("// rustfmt-wrap_comments: true\n// rustfmt-comment_width: 40"),
+        expected
+    );
+    for header in [
+        "/* rustfmt-wrap_comments: true\n * rustfmt-comment_width: 40\n */",
+        "/*\n * rustfmt-wrap_comments: true\n * rustfmt-comment_width: 40 */",
+        "\n/** rustfmt-wrap_comments: true\n    rustfmt-comment_width: 40*/",
+        "// rustfmt-wrap_comments: true\n/* rustfmt-comment_width: 40 */",
+    ] {
+        assert_eq!(read(header), expected, "{header:?}");
+    }
+    // A block comment after some code isn't a header.
+    assert!(read("struct S;\n/* rustfmt-comment_width: 40 */").is_empty());
 }
 
 // Compares output to input.
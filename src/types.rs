@@ -1,12 +1,14 @@
+use std::collections::HashMap;
 use std::ops::Deref;
+use std::rc::Rc;
 
 use rustc_ast::ast::{self, FnRetTy, Mutability, Term};
 use rustc_ast::ptr;
-use rustc_span::{symbol::kw, BytePos, Pos, Span};
+use rustc_span::{symbol::kw, symbol::Symbol, BytePos, Pos, Span};
 
 use crate::comment::{combine_strs_with_missing_comments, contains_comment};
 use crate::config::lists::*;
-use crate::config::{IndentStyle, TypeDensity, Version};
+use crate::config::{ConstGenericsStyle, GenericsPunctuation, IndentStyle, TypeDensity, Version};
 use crate::expr::{
     format_expr, rewrite_assign_rhs, rewrite_call, rewrite_tuple, rewrite_unary_prefix, ExprType,
     RhsAssignKind,
@@ -94,6 +96,163 @@ pub(crate) fn rewrite_path(
     )
 }
 
+/// The generic parameters of the types and traits declared in a module, used to apply
+/// `const_generics_style` to the paths that refer to them. Each type or const parameter maps to
+/// the default of the const parameter, if it is a literal.
+#[derive(Clone, Default)]
+pub(crate) struct ConstGenericDefaults(Rc<HashMap<Symbol, Vec<Option<String>>>>);
+
+impl ConstGenericDefaults {
+    pub(crate) fn from_items(
+        context: &RewriteContext<'_>,
+        items: &[ptr::P<ast::Item>],
+    ) -> ConstGenericDefaults {
+        let mut declared: HashMap<Symbol, Option<Vec<Option<String>>>> = HashMap::new();
+        for item in items {
+            let generics = match item.kind {
+                ast::ItemKind::Struct(_, ref generics)
+                | ast::ItemKind::Enum(_, ref generics)
+                | ast::ItemKind::Union(_, ref generics) => generics,
+                ast::ItemKind::TyAlias(ref ty_alias) => &ty_alias.generics,
+                ast::ItemKind::Trait(ref trait_) => &trait_.generics,
+                _ => continue,
+            };
+            let params = generics
+                .params
+                .iter()
+                .filter_map(|param| match param.kind {
+                    ast::GenericParamKind::Lifetime => None,
+                    ast::GenericParamKind::Const {
+                        default: Some(ref default),
+                        ..
+                    } if matches!(default.value.kind, ast::ExprKind::Lit(..)) => {
+                        Some(Some(context.snippet(default.value.span).to_owned()))
+                    }
+                    ast::GenericParamKind::Type { .. } | ast::GenericParamKind::Const { .. } => {
+                        Some(None)
+                    }
+                })
+                .collect::<Vec<_>>();
+            // The same name may be declared more than once behind `#[cfg]`s. Such a name is only
+            // kept when all of its declarations agree.
+            declared
+                .entry(item.ident.name)
+                .and_modify(|prev| {
+                    if prev.as_ref() != Some(&params) {
+                        *prev = None;
+                    }
+                })
+                .or_insert_with(|| Some(params.clone()));
+        }
+        ConstGenericDefaults(Rc::new(
+            declared
+                .into_iter()
+                .filter_map(|(name, params)| Some((name, params?)))
+                .filter(|(_, params)| params.iter().any(Option::is_some))
+                .collect(),
+        ))
+    }
+
+    fn get(&self, path: &ast::Path) -> Option<&[Option<String>]> {
+        match path.segments[..] {
+            [ref segment] => self.0.get(&segment.ident.name).map(Vec::as_slice),
+            _ => None,
+        }
+    }
+}
+
+/// Rewrites the path of a type or trait, adding or removing the defaults of its const generic
+/// parameters according to `const_generics_style`.
+fn rewrite_type_path(
+    context: &RewriteContext<'_>,
+    qself: &Option<ptr::P<ast::QSelf>>,
+    path: &ast::Path,
+    shape: Shape,
+) -> Option<String> {
+    let style = context.config.const_generics_style();
+    let params = match style {
+        ConstGenericsStyle::Preserve => None,
+        _ if qself.is_some() || contains_comment(context.snippet(path.span)) => None,
+        _ => context.const_generic_defaults.get(path),
+    };
+    let segment = &path.segments[0];
+    let (params, args) = match (params, segment.args.as_deref()) {
+        (Some(params), Some(ast::GenericArgs::AngleBracketed(data))) => (params, &data.args[..]),
+        (Some(params), None) => (params, &[][..]),
+        _ => return rewrite_path(context, PathContext::Type, qself, path, shape),
+    };
+    let mut num_positional_args = 0;
+    for arg in args {
+        match arg {
+            ast::AngleBracketedArg::Arg(ast::GenericArg::Lifetime(..)) => (),
+            ast::AngleBracketedArg::Arg(..) => num_positional_args += 1,
+            ast::AngleBracketedArg::Constraint(..) => {
+                return rewrite_path(context, PathContext::Type, qself, path, shape);
+            }
+        }
+    }
+
+    if style == ConstGenericsStyle::Explicit {
+        let result = rewrite_path(context, PathContext::Type, qself, path, shape)?;
+        let defaults = match params.get(num_positional_args..) {
+            Some(missing) if !missing.is_empty() && !result.contains('\n') => {
+                match missing.iter().cloned().collect::<Option<Vec<_>>>() {
+                    Some(defaults) => defaults.join(", "),
+                    None => return Some(result),
+                }
+            }
+            _ => return Some(result),
+        };
+        let explicit = match result.strip_suffix('>') {
+            Some(prefix) if !args.is_empty() => format!("{prefix}, {defaults}>"),
+            _ => format!("{result}<{defaults}>"),
+        };
+        return Some(if explicit.len() <= shape.width {
+            explicit
+        } else {
+            result
+        });
+    }
+
+    // Remove the trailing arguments that are spelled like the default of their parameter.
+    let mut num_kept = args.len();
+    while num_kept > 0 {
+        let param = num_positional_args
+            .checked_sub(1)
+            .and_then(|idx| params.get(idx));
+        let is_default = match (&args[num_kept - 1], param) {
+            (ast::AngleBracketedArg::Arg(arg), Some(Some(default))) => {
+                !matches!(arg, ast::GenericArg::Lifetime(..))
+                    && context.snippet(arg.span()) == default
+            }
+            _ => false,
+        };
+        if !is_default {
+            break;
+        }
+        num_kept -= 1;
+        num_positional_args -= 1;
+    }
+    let ident = rewrite_ident(context, segment.ident);
+    if num_kept == args.len() {
+        return rewrite_path(context, PathContext::Type, qself, path, shape);
+    } else if num_kept == 0 {
+        return Some(ident.to_owned());
+    }
+    let args = args[..num_kept]
+        .iter()
+        .map(|arg| match arg {
+            ast::AngleBracketedArg::Arg(generic_arg) => SegmentParam::from_generic_arg(generic_arg),
+            ast::AngleBracketedArg::Constraint(constraint) => SegmentParam::Binding(constraint),
+        })
+        .collect::<Vec<_>>();
+    let shape = shape.offset_left(ident.len())?;
+    let span = mk_sp(segment.ident.span.hi(), args[num_kept - 1].span().hi());
+    let generics_str =
+        overflow::rewrite_with_angle_brackets(context, "", args.iter(), shape, span)?;
+    Some(format!("{ident}{generics_str}"))
+}
+
 fn rewrite_path_segments<'a, I>(
     path_context: PathContext,
     mut buffer: String,
@@ -526,7 +685,39 @@ fn rewrite_bounded_lifetime(
 
 impl Rewrite for ast::AnonConst {
     fn rewrite(&self, context: &RewriteContext<'_>, shape: Shape) -> Option<String> {
-        format_expr(&self.value, ExprType::SubExpression, context, shape)
+        let result = format_expr(&self.value, ExprType::SubExpression, context, shape)?;
+        // An inline const block in an array length or a const generic argument is never broken
+        // across lines, the enclosing construct has to wrap instead.
+        if result.contains('\n') && is_single_expr_inline_const(&self.value) {
+            return None;
+        }
+        Some(result)
+    }
+}
+
+/// Returns `true` for `const { expr }`, optionally wrapped in braces as in `Foo<{ const { N } }>`.
+fn is_single_expr_inline_const(expr: &ast::Expr) -> bool {
+    fn single_expr(block: &ast::Block) -> Option<&ast::Expr> {
+        match block.stmts.as_slice() {
+            [
+                ast::Stmt {
+                    kind: ast::StmtKind::Expr(ref expr),
+                    ..
+                },
+            ] => Some(expr),
+            _ => None,
+        }
+    }
+
+    match expr.kind {
+        ast::ExprKind::ConstBlock(ref anon_const) => match anon_const.value.kind {
+            ast::ExprKind::Block(ref block, _) => single_expr(block).is_some(),
+            _ => true,
+        },
+        ast::ExprKind::Block(ref block, None) => {
+            single_expr(block).map_or(false, is_single_expr_inline_const)
+        }
+        _ => false,
     }
 }
 
@@ -615,7 +806,14 @@ impl Rewrite for ast::GenericParam {
 
         if !self.bounds.is_empty() {
             param.push_str(type_bound_colon(context));
-            param.push_str(&self.bounds.rewrite(context, shape)?)
+            param.push_str(&join_bounds_inner(
+                context,
+                shape,
+                &self.bounds,
+                true,
+                false,
+                true,
+            )?)
         }
         if let ast::GenericParamKind::Type {
             default: Some(ref def),
@@ -677,7 +875,7 @@ impl Rewrite for ast::PolyTraitRef {
 
 impl Rewrite for ast::TraitRef {
     fn rewrite(&self, context: &RewriteContext<'_>, shape: Shape) -> Option<String> {
-        rewrite_path(context, PathContext::Type, &None, &self.path, shape)
+        rewrite_type_path(context, &None, &self.path, shape)
     }
 }
 
@@ -818,17 +1016,22 @@ impl Rewrite for ast::Ty {
                 ty.rewrite(context, Shape::legacy(budget, shape.indent + 1))
                     .map(|ty_str| format!("[{}]", ty_str))
             }
-            ast::TyKind::Tup(ref items) => {
-                rewrite_tuple(context, items.iter(), self.span, shape, items.len() == 1)
-            }
+            ast::TyKind::Tup(ref items) => rewrite_tuple(
+                context,
+                items.iter(),
+                self.span,
+                shape,
+                items.len() == 1,
+                false,
+            ),
             ast::TyKind::AnonStruct(..) => Some(context.snippet(self.span).to_owned()),
             ast::TyKind::AnonUnion(..) => Some(context.snippet(self.span).to_owned()),
             ast::TyKind::Path(ref q_self, ref path) => {
-                rewrite_path(context, PathContext::Type, q_self, path, shape)
+                rewrite_type_path(context, q_self, path, shape)
             }
             ast::TyKind::Array(ref ty, ref repeats) => rewrite_pair(
                 &**ty,
-                &*repeats.value,
+                repeats,
                 PairParts::new("[", "; ", "]"),
                 context,
                 shape,
@@ -954,15 +1157,19 @@ fn join_bounds(
     items: &[ast::GenericBound],
     need_indent: bool,
 ) -> Option<String> {
-    join_bounds_inner(context, shape, items, need_indent, false)
+    join_bounds_inner(context, shape, items, need_indent, false, false)
 }
 
+/// Joins `items` with `+`. The bounds of generic parameters, i.e. the ones within angle
+/// brackets, are spaced according to `type_punctuation_in_generics` rather than
+/// `type_punctuation_density`.
 fn join_bounds_inner(
     context: &RewriteContext<'_>,
     shape: Shape,
     items: &[ast::GenericBound],
     need_indent: bool,
     force_newline: bool,
+    is_generic_param: bool,
 ) -> Option<String> {
     debug_assert!(!items.is_empty());
 
@@ -1040,9 +1247,24 @@ fn join_bounds_inner(
                 String::from(" ")
             };
 
-            let joiner = match context.config.type_punctuation_density() {
-                TypeDensity::Compressed => String::from("+"),
-                TypeDensity::Wide => whitespace + "+ ",
+            let is_compressed = if is_generic_param {
+                match context.config.type_punctuation_in_generics() {
+                    GenericsPunctuation::Spaced => false,
+                    GenericsPunctuation::Compact => true,
+                    GenericsPunctuation::Preserve => {
+                        i > 0 && {
+                            let gap = mk_sp(items[i - 1].span().hi(), item.span().lo());
+                            !context.snippet(gap).contains(char::is_whitespace)
+                        }
+                    }
+                }
+            } else {
+                context.config.type_punctuation_density() == TypeDensity::Compressed
+            };
+            let joiner = if is_compressed {
+                String::from("+")
+            } else {
+                whitespace + "+ "
             };
             let joiner = if has_leading_comment {
                 joiner.trim_end()
@@ -1106,7 +1328,7 @@ fn join_bounds_inner(
     };
 
     if retry_with_force_newline {
-        join_bounds_inner(context, shape, items, need_indent, true)
+        join_bounds_inner(context, shape, items, need_indent, true, is_generic_param)
     } else {
         Some(result.0)
     }
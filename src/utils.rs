@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::sync::Once;
 
 use rustc_ast::ast::{
     self, Attribute, MetaItem, MetaItemKind, NestedMetaItem, NodeId, Path, Visibility,
@@ -7,7 +8,9 @@ use rustc_ast::ast::{
 use rustc_ast::ptr;
 use rustc_ast_pretty::pprust;
 use rustc_span::{sym, symbol, BytePos, LocalExpnId, Span, Symbol, SyntaxContext};
-use unicode_width::UnicodeWidthStr;
+use unicode_properties::{GeneralCategory, UnicodeGeneralCategory};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use crate::comment::{filter_normal_code, CharClasses, FullCodeCharKind, LineClasses};
 use crate::config::{Config, Version};
@@ -49,6 +52,11 @@ pub(crate) fn is_same_visibility(a: &Visibility, b: &Visibility) -> bool {
     }
 }
 
+#[inline]
+pub(crate) fn is_pub(vis: &Visibility) -> bool {
+    matches!(vis.kind, VisibilityKind::Public)
+}
+
 // Uses Cow to avoid allocating in the common cases.
 pub(crate) fn format_visibility(
     context: &RewriteContext<'_>,
@@ -271,6 +279,44 @@ pub(crate) fn contains_skip(attrs: &[Attribute]) -> bool {
         .any(|a| a.meta().map_or(false, |a| is_skip(&a)))
 }
 
+/// Returns `true` if the predicate of a `cfg` attribute only holds when compiling tests.
+fn is_test_predicate(nested: &NestedMetaItem) -> bool {
+    let Some(meta_item) = nested.meta_item() else {
+        return false;
+    };
+    match meta_item.kind {
+        MetaItemKind::Word => meta_item.has_name(sym::test),
+        MetaItemKind::List(ref l) if meta_item.has_name(sym::all) => {
+            l.iter().any(is_test_predicate)
+        }
+        _ => false,
+    }
+}
+
+fn is_cfg_test(meta_item: &MetaItem) -> bool {
+    let MetaItemKind::List(ref l) = meta_item.kind else {
+        return false;
+    };
+    if meta_item.has_name(sym::cfg) {
+        l.len() == 1 && is_test_predicate(&l[0])
+    } else if meta_item.has_name(sym::cfg_attr) {
+        l.iter()
+            .skip(1)
+            .filter_map(NestedMetaItem::meta_item)
+            .any(is_cfg_test)
+    } else {
+        false
+    }
+}
+
+/// Returns `true` if `attrs` contain a `#[cfg(test)]`, possibly nested in `all(..)` or
+/// `cfg_attr`, so that the item they belong to is only compiled for tests.
+pub(crate) fn contains_cfg_test(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .any(|a| a.meta().map_or(false, |a| is_cfg_test(&a)))
+}
+
 #[inline]
 pub(crate) fn semicolon_for_expr(context: &RewriteContext<'_>, expr: &ast::Expr) -> bool {
     // Never try to insert semicolons on expressions when we're inside
@@ -387,18 +433,75 @@ macro_rules! skip_out_of_file_lines_range_visitor {
 // Wraps String in an Option. Returns Some when the string adheres to the
 // Rewrite constraints defined for the Rewrite trait and None otherwise.
 pub(crate) fn wrap_str(s: String, max_width: usize, shape: Shape) -> Option<String> {
+    wrap_str_with_breaks(s, max_width, shape, |_| false)
+}
+
+/// Like `wrap_str`, but a line that is too wide is first broken after the last character for
+/// which `is_break` returns `true` and that leaves the line narrow enough, as often as needed.
+/// The whitespace around a break is dropped. The next line gets the indentation of the broken
+/// line, or the indentation of `shape` in spaces if the first line is broken.
+pub(crate) fn wrap_str_with_breaks<F: Fn(char) -> bool>(
+    s: String,
+    max_width: usize,
+    shape: Shape,
+    is_break: F,
+) -> Option<String> {
     if filtered_str_fits(&s, max_width, shape) {
-        Some(s)
-    } else {
-        None
+        return Some(s);
+    }
+    let first_line_indent = " ".repeat(shape.indent.width());
+    let mut result = String::with_capacity(s.len());
+    for (i, line) in s.split('\n').enumerate() {
+        let (indent, mut width, mut rest) = if i == 0 {
+            (&first_line_indent[..], shape.width, line)
+        } else {
+            result.push('\n');
+            let text = line.trim_start();
+            let indent = &line[..line.len() - text.len()];
+            result.push_str(indent);
+            (
+                indent,
+                max_width.saturating_sub(unicode_str_width(indent)),
+                text,
+            )
+        };
+        while unicode_str_width(rest) > width {
+            let break_end = rest
+                .char_indices()
+                .filter(|&(_, c)| is_break(c))
+                .map(|(pos, c)| pos + c.len_utf8())
+                .take_while(|&end| unicode_str_width(rest[..end].trim_end()) <= width)
+                .last()
+                .filter(|&end| !rest[..end].trim().is_empty() && !rest[end..].trim().is_empty());
+            let Some(end) = break_end else {
+                break;
+            };
+            result.push_str(rest[..end].trim_end());
+            result.push('\n');
+            result.push_str(indent);
+            rest = rest[end..].trim_start();
+            width = max_width.saturating_sub(unicode_str_width(indent));
+        }
+        result.push_str(rest);
     }
+    filtered_str_fits(&result, max_width, shape).then_some(result)
 }
 
 pub(crate) fn filtered_str_fits(snippet: &str, max_width: usize, shape: Shape) -> bool {
+    filtered_str_fits_by(snippet, max_width, shape, unicode_str_width)
+}
+
+/// Like `filtered_str_fits`, but measures each line with `width`.
+pub(crate) fn filtered_str_fits_by(
+    snippet: &str,
+    max_width: usize,
+    shape: Shape,
+    width: impl Fn(&str) -> usize,
+) -> bool {
     let snippet = &filter_normal_code(snippet);
     if !snippet.is_empty() {
         // First line must fits with `shape.width`.
-        if first_line_width(snippet) > shape.width {
+        if width(snippet.split('\n').next().unwrap_or("")) > shape.width {
             return false;
         }
         // If the snippet does not include newline, we are done.
@@ -406,16 +509,12 @@ pub(crate) fn filtered_str_fits(snippet: &str, max_width: usize, shape: Shape) -
             return true;
         }
         // The other lines must fit within the maximum width.
-        if snippet
-            .lines()
-            .skip(1)
-            .any(|line| unicode_str_width(line) > max_width)
-        {
+        if snippet.lines().skip(1).any(|line| width(line) > max_width) {
             return false;
         }
         // A special check for the last line, since the caller may
         // place trailing characters on this line.
-        if last_line_width(snippet) > shape.used_width() + shape.width {
+        if width(snippet.rsplit('\n').next().unwrap_or("")) > shape.used_width() + shape.width {
             return false;
         }
     }
@@ -690,13 +789,112 @@ impl NodeIdExt for NodeId {
 }
 
 pub(crate) fn unicode_str_width(s: &str) -> usize {
+    if s.contains(SOFT_HYPHEN) {
+        // A soft hyphen is invisible, unless a line is broken at it.
+        return s.split(SOFT_HYPHEN).map(unicode_str_width).sum();
+    }
+    if !s.is_ascii() && s.chars().any(is_unassigned) {
+        // Characters assigned after the Unicode version of the width table have no known width,
+        // so each grapheme holding one is taken as a single column.
+        static WARNING: Once = Once::new();
+        WARNING.call_once(|| warn!("Assuming a width of 1 for characters of unknown width"));
+        return s
+            .graphemes(true)
+            .map(|grapheme| {
+                if grapheme.chars().any(is_unassigned) {
+                    1
+                } else {
+                    unicode_str_width(grapheme)
+                }
+            })
+            .sum();
+    }
     s.width()
 }
 
+/// Like `unicode_str_width`, but with `version = "Two"` an emoji ZWJ sequence, such as a family,
+/// is measured as the single emoji it is displayed as. Its grapheme cluster is as wide as the
+/// emoji it starts with, not as all of the joined emoji together.
+pub(crate) fn versioned_str_width(s: &str, version: Version) -> usize {
+    if version == Version::One || !s.contains(ZERO_WIDTH_JOINER) {
+        return unicode_str_width(s);
+    }
+    s.graphemes(true)
+        .map(|grapheme| {
+            if grapheme.contains(ZERO_WIDTH_JOINER) {
+                grapheme.chars().next().and_then(|c| c.width()).unwrap_or(0)
+            } else {
+                unicode_str_width(grapheme)
+            }
+        })
+        .sum()
+}
+
+const ZERO_WIDTH_JOINER: char = '\u{200d}';
+
+/// Marks where a word may be broken, with a hyphen shown only if it is.
+pub(crate) const SOFT_HYPHEN: char = '\u{ad}';
+
+fn is_unassigned(c: char) -> bool {
+    c.general_category() == GeneralCategory::Unassigned
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn zwj_emoji_sequences_are_as_wide_as_one_emoji() {
+        let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}\u{200d}\u{1f466}";
+        assert_eq!(versioned_str_width(family, Version::Two), 2);
+        assert_eq!(
+            versioned_str_width(&format!("a {family} b"), Version::Two),
+            6
+        );
+        assert_eq!(versioned_str_width("\u{1f468}\u{1f469}", Version::Two), 4);
+        // Version One adds up the widths of all of the joined emoji.
+        assert_eq!(versioned_str_width(family, Version::One), 8);
+    }
+
+    #[test]
+    fn unassigned_characters_are_one_column_wide() {
+        // U+0378 is unassigned in the Greek block, U+3FFFD in a block of wide ideographs.
+        assert_eq!(unicode_str_width("\u{378}"), 1);
+        assert_eq!(unicode_str_width("a\u{3fffd}b"), 3);
+        assert_eq!(unicode_str_width("\u{3fffd}\u{301} \u{4e2d}"), 4);
+    }
+
+    #[test]
+    fn soft_hyphens_are_zero_width() {
+        assert_eq!(unicode_str_width("super\u{ad}cali"), 9);
+        assert_eq!(unicode_str_width("\u{ad}\u{4e2d}"), 2);
+    }
+
+    #[test]
+    fn wrap_str_breaks_where_allowed() {
+        let shape = Shape::legacy(12, Indent::new(4, 0));
+        let s = String::from("foo.bar.baz.qux");
+        assert_eq!(wrap_str(s.clone(), 20, shape), None);
+        assert_eq!(
+            wrap_str_with_breaks(s.clone(), 20, shape, |c| c == '.'),
+            Some(String::from("foo.bar.baz.\n    qux"))
+        );
+        assert_eq!(
+            wrap_str_with_breaks(s, 20, shape, char::is_whitespace),
+            None
+        );
+
+        let s = String::from("short\n  lorem ipsum dolor sit amet");
+        assert_eq!(
+            wrap_str_with_breaks(s, 16, shape, char::is_whitespace),
+            Some(String::from("short\n  lorem ipsum\n  dolor sit amet"))
+        );
+        assert_eq!(
+            wrap_str_with_breaks(String::from("fits"), 20, shape, |_| true),
+            Some(String::from("fits"))
+        );
+    }
+
     #[test]
     fn test_remove_trailing_white_spaces() {
         let s = "    r#\"\n        test\n    \"#";
@@ -8,7 +8,7 @@ use rustc_span::{symbol, BytePos, Pos, Span};
 use crate::attr::*;
 use crate::comment::{contains_comment, rewrite_comment, CodeCharKind, CommentCodeSlices};
 use crate::config::Version;
-use crate::config::{BraceStyle, Config, MacroSelector};
+use crate::config::{BraceStyle, Config, ConstGenericsStyle, MacroSelector};
 use crate::coverage::transform_missing_snippet;
 use crate::items::{
     format_impl, format_trait, format_trait_alias, is_mod_decl, is_use_item, rewrite_extern_crate,
@@ -19,16 +19,44 @@ use crate::modules::Module;
 use crate::parse::session::ParseSess;
 use crate::rewrite::{Rewrite, RewriteContext};
 use crate::shape::{Indent, Shape};
-use crate::skip::{is_skip_attr, SkipContext};
+use crate::skip::{
+    is_format_attr, is_skip_attr, opted_in_items, opted_in_items_of, OptedInItem, SkipContext,
+};
 use crate::source_map::{LineRangeUtils, SpanUtils};
 use crate::spanned::Spanned;
 use crate::stmt::Stmt;
+use crate::types::ConstGenericDefaults;
 use crate::utils::{
-    self, contains_skip, count_newlines, depr_skip_annotation, format_safety, inner_attributes,
-    last_line_width, mk_sp, ptr_vec_to_ref_vec, rewrite_ident, starts_with_newline, stmt_expr,
+    self, contains_cfg_test, contains_skip, count_newlines, depr_skip_annotation, format_safety,
+    inner_attributes, is_empty_line, is_pub, last_line_width, mk_sp, ptr_vec_to_ref_vec,
+    rewrite_ident, starts_with_newline, stmt_expr,
 };
 use crate::{ErrorKind, FormatReport, FormattingError};
 
+/// Returns the config to format modules that are only compiled for tests with, if
+/// `max_width_in_tests` gives them a width other than the one `config` already uses.
+pub(crate) fn test_mod_config(config: &Config) -> Option<Config> {
+    let max_width = config.max_width_in_tests();
+    if max_width == 0 || max_width == config.max_width() {
+        return None;
+    }
+    let mut config = config.clone();
+    config.set().max_width(max_width);
+    Some(config)
+}
+
+/// Impl blocks, extern blocks and macros can't be `pub`, so they are never private items.
+fn can_have_visibility(item: &ast::Item) -> bool {
+    !matches!(
+        item.kind,
+        ast::ItemKind::Impl(..)
+            | ast::ItemKind::ForeignMod(..)
+            | ast::ItemKind::GlobalAsm(..)
+            | ast::ItemKind::MacCall(..)
+            | ast::ItemKind::MacroDef(..)
+    )
+}
+
 /// Creates a string slice corresponding to the specified span.
 pub(crate) struct SnippetProvider {
     /// A pointer to the content of the file we are formatting.
@@ -83,10 +111,26 @@ pub(crate) struct FmtVisitor<'a> {
     /// List of 1-based line ranges which were annotated with skip
     /// Both bounds are inclusive.
     pub(crate) skipped_range: Rc<RefCell<Vec<(usize, usize)>>>,
+    /// List of 1-based line ranges which belong to test modules formatted with
+    /// `max_width_in_tests`. Both bounds are inclusive.
+    pub(crate) test_mod_range: Rc<RefCell<Vec<(usize, usize)>>>,
     pub(crate) macro_rewrite_failure: bool,
     pub(crate) report: FormatReport,
     pub(crate) skip_context: SkipContext,
     pub(crate) is_macro_def: bool,
+    /// Set when a rewrite inside the item currently being visited failed or produced
+    /// overlong lines. Only tracked when `suggest_skips` is enabled.
+    has_failed_rewrite: bool,
+    /// The const generic defaults of the types declared in the module being visited. Only
+    /// collected when `const_generics_style` is not `Preserve`.
+    const_generic_defaults: ConstGenericDefaults,
+    /// Whether the item being visited is not public. Only items that could be `pub` are
+    /// private; associated items follow their trait or trait impl.
+    is_private_item: bool,
+    /// Whether the items being visited belong to a trait impl.
+    pub(crate) in_trait_impl: bool,
+    /// Whether the statements being visited belong to the body of a function.
+    pub(crate) in_fn_body: bool,
 }
 
 impl<'a> Drop for FmtVisitor<'a> {
@@ -152,7 +196,7 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
             }
             ast::StmtKind::Let(..) | ast::StmtKind::Expr(..) | ast::StmtKind::Semi(..) => {
                 let attrs = get_attrs_from_stmt(stmt.as_ast_node());
-                if contains_skip(attrs) {
+                if self.has_skip_pragma(stmt.span().lo()) || contains_skip(attrs) {
                     self.push_skipped_with_span(
                         attrs,
                         stmt.span(),
@@ -165,7 +209,9 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
                 }
             }
             ast::StmtKind::MacCall(ref mac_stmt) => {
-                if self.visit_attrs(&mac_stmt.attrs, ast::AttrStyle::Outer) {
+                if self.has_skip_pragma(stmt.span().lo())
+                    || self.visit_attrs(&mac_stmt.attrs, ast::AttrStyle::Outer)
+                {
                     self.push_skipped_with_span(
                         &mac_stmt.attrs,
                         stmt.span(),
@@ -279,6 +325,15 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
             debug!("close_block: {:?} {:?} {:?}", kind, offset, sub_slice);
 
             match kind {
+                CodeCharKind::Comment
+                    if self
+                        .snippet(mk_sp(last_hi, span.lo() + BytePos::from_usize(offset)))
+                        .contains('\n')
+                        && self.is_strippable_comment(&sub_slice) =>
+                {
+                    last_hi = span.lo() + BytePos::from_usize(offset + sub_slice.len());
+                    continue;
+                }
                 CodeCharKind::Comment => {
                     if !unindented && unindent_comment && !align_to_right {
                         unindented = true;
@@ -287,6 +342,10 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
                     let span_in_between = mk_sp(last_hi, span.lo() + BytePos::from_usize(offset));
                     let snippet_in_between = self.snippet(span_in_between);
                     let mut comment_on_same_line = !snippet_in_between.contains('\n');
+                    let sub_slice = match self.trailing_comment_len(&sub_slice) {
+                        Some(len) if comment_on_same_line => &sub_slice[..len],
+                        _ => &*sub_slice,
+                    };
 
                     let mut comment_shape =
                         Shape::indented(self.block_indent, config).comment(config);
@@ -399,7 +458,10 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
         if let Some((fn_str, fn_brace_style)) = rewrite {
             self.format_missing_with_indent(source!(self, s).lo());
 
-            if let Some(rw) = self.single_line_fn(&fn_str, block, inner_attrs) {
+            let in_fn_body = std::mem::replace(&mut self.in_fn_body, true);
+            let single_line_fn = self.single_line_fn(&fn_str, block, inner_attrs);
+            self.in_fn_body = in_fn_body;
+            if let Some(rw) = single_line_fn {
                 self.push_str(&rw);
                 self.last_pos = s.hi();
                 return;
@@ -418,11 +480,18 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
             self.format_missing(source!(self, block.span).lo());
         }
 
-        self.visit_block(block, inner_attrs, true)
+        let in_fn_body = std::mem::replace(&mut self.in_fn_body, true);
+        self.visit_block(block, inner_attrs, true);
+        self.in_fn_body = in_fn_body;
     }
 
     pub(crate) fn visit_item(&mut self, item: &ast::Item) {
         skip_out_of_file_lines_range_visitor!(self, item.span);
+        let skip_suggestion_scope = self.begin_skip_suggestion_scope();
+        let is_private_item_saved = std::mem::replace(
+            &mut self.is_private_item,
+            can_have_visibility(item) && !is_pub(&item.vis),
+        );
 
         // This is where we bail out if there is a skip attribute. This is only
         // complex in the module case. It is complex because the module could be
@@ -432,11 +501,12 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
         let mut attrs = &item.attrs;
         let skip_context_saved = self.skip_context.clone();
         self.skip_context.update_with_attrs(attrs);
+        let has_skip_pragma = self.has_skip_pragma(item.span().lo());
 
         let should_visit_node_again = match item.kind {
             // For use/extern crate items, skip rewriting attributes but check for a skip attribute.
             ast::ItemKind::Use(..) | ast::ItemKind::ExternCrate(_) => {
-                if contains_skip(attrs) {
+                if has_skip_pragma || contains_skip(attrs) {
                     self.push_skipped_with_span(attrs.as_slice(), item.span(), item.span());
                     false
                 } else {
@@ -445,8 +515,8 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
             }
             // Module is inline, in this case we treat it like any other item.
             _ if !is_mod_decl(item) => {
-                if self.visit_attrs(&item.attrs, ast::AttrStyle::Outer) {
-                    self.push_skipped_with_span(item.attrs.as_slice(), item.span(), item.span());
+                if has_skip_pragma || self.visit_attrs(&item.attrs, ast::AttrStyle::Outer) {
+                    self.push_skipped_item(item);
                     false
                 } else {
                     true
@@ -465,8 +535,8 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
                 true
             }
             _ => {
-                if self.visit_attrs(&item.attrs, ast::AttrStyle::Outer) {
-                    self.push_skipped_with_span(item.attrs.as_slice(), item.span(), item.span());
+                if has_skip_pragma || self.visit_attrs(&item.attrs, ast::AttrStyle::Outer) {
+                    self.push_skipped_item(item);
                     false
                 } else {
                     true
@@ -477,7 +547,10 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
         // TODO(calebcartwright): consider enabling box_patterns feature gate
         if should_visit_node_again {
             match item.kind {
-                ast::ItemKind::Use(ref tree) => self.format_import(item, tree),
+                ast::ItemKind::Use(ref tree) => {
+                    self.report_glob_imports(item);
+                    self.format_import(item, tree);
+                }
                 ast::ItemKind::Impl(ref iimpl) => {
                     let block_indent = self.block_indent;
                     let rw = self.with_context(|ctx| format_impl(ctx, item, iimpl, block_indent));
@@ -519,7 +592,11 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
                 }
                 ast::ItemKind::Mod(safety, ref mod_kind) => {
                     self.format_missing_with_indent(source!(self, item.span).lo());
-                    self.format_mod(mod_kind, safety, &item.vis, item.span, item.ident, attrs);
+                    match test_mod_config(self.config).filter(|_| contains_cfg_test(attrs)) {
+                        Some(ref config) => self.format_test_mod(config, item, mod_kind, safety),
+                        None => self
+                            .format_mod(mod_kind, safety, &item.vis, item.span, item.ident, attrs),
+                    }
                 }
                 ast::ItemKind::MacCall(ref mac) => {
                     self.visit_mac(mac, Some(item.ident), MacroPosition::Item);
@@ -594,6 +671,10 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
             };
         }
         self.skip_context = skip_context_saved;
+        self.is_private_item = is_private_item_saved;
+        self.end_skip_suggestion_scope(skip_suggestion_scope, item.span(), |this| {
+            this.item_name(item)
+        });
     }
 
     fn visit_ty_alias_kind(
@@ -622,11 +703,19 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
         };
         skip_out_of_file_lines_range_visitor!(self, ai.span);
 
-        if self.visit_attrs(&ai.attrs, ast::AttrStyle::Outer) {
+        // Trait items and the items of trait impls are as public as the trait or the impl.
+        if matches!(visitor_kind, AssocImplItem(_)) && !self.in_trait_impl {
+            self.is_private_item = !is_pub(&ai.vis);
+        }
+        if self.has_skip_pragma(ai.span().lo())
+            || self.visit_attrs(&ai.attrs, ast::AttrStyle::Outer)
+        {
             self.push_skipped_with_span(ai.attrs.as_slice(), skip_span, skip_span);
             return;
         }
 
+        let skip_suggestion_scope = self.begin_skip_suggestion_scope();
+
         // TODO(calebcartwright): consider enabling box_patterns feature gate
         match (&ai.kind, visitor_kind) {
             (ast::AssocItemKind::Const(..), AssocTraitItem(_)) => {
@@ -667,6 +756,11 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
             }
             _ => unreachable!(),
         }
+
+        self.end_skip_suggestion_scope(skip_suggestion_scope, ai.span(), |this| match ai.kind {
+            ast::AssocItemKind::MacCall(ref mac) => format!("{}!", this.snippet(mac.path.span)),
+            _ => ai.ident.to_string(),
+        });
     }
 
     pub(crate) fn visit_trait_item(&mut self, ti: &ast::AssocItem) {
@@ -725,9 +819,73 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
 
     pub(crate) fn push_rewrite(&mut self, span: Span, rewrite: Option<String>) {
         self.format_missing_with_indent(source!(self, span).lo());
+        if self.config.suggest_skips() && !out_of_file_lines_range!(self, span) {
+            self.has_failed_rewrite |= rewrite.as_ref().map_or(true, |s| self.is_overlong(s));
+        }
         self.push_rewrite_inner(span, rewrite);
     }
 
+    /// Returns `true` if `rewrite`, starting at the current indentation, contains a line
+    /// that exceeds `max_width`.
+    fn is_overlong(&self, rewrite: &str) -> bool {
+        rewrite.lines().enumerate().any(|(i, line)| {
+            let offset = if i == 0 { self.block_indent.width() } else { 0 };
+            offset + utils::unicode_str_width(line) > self.config.max_width()
+        })
+    }
+
+    /// Starts tracking rewrite failures for a new item. Returns the state to restore
+    /// with `end_skip_suggestion_scope` once the item has been visited.
+    fn begin_skip_suggestion_scope(&mut self) -> (bool, usize) {
+        let suggestion_count = if self.config.suggest_skips() {
+            self.report.skip_suggestion_count()
+        } else {
+            0
+        };
+        (
+            std::mem::replace(&mut self.has_failed_rewrite, false),
+            suggestion_count,
+        )
+    }
+
+    /// Suggests skipping the item that was just visited if a rewrite inside it failed and
+    /// no nested item has already been suggested instead.
+    fn end_skip_suggestion_scope<F>(&mut self, saved: (bool, usize), span: Span, name: F)
+    where
+        F: FnOnce(&Self) -> String,
+    {
+        let (has_failed_rewrite, suggestion_count) = saved;
+        if self.has_failed_rewrite && self.report.skip_suggestion_count() == suggestion_count {
+            let name = name(self);
+            self.suggest_skip(span, name);
+        }
+        self.has_failed_rewrite = has_failed_rewrite;
+    }
+
+    /// Reports an item whose formatting failed as a candidate for `#[rustfmt::skip]`.
+    fn suggest_skip(&mut self, span: Span, name: String) {
+        let file_name = self.psess.span_to_filename(span);
+        let error = FormattingError::from_span(span, self.psess, ErrorKind::SkipSuggestion(name));
+        self.report.append(file_name, vec![error]);
+    }
+
+    /// Returns a short description of `item` used in skip suggestions.
+    fn item_name(&self, item: &ast::Item) -> String {
+        match item.kind {
+            _ if item.ident.name != symbol::kw::Empty => item.ident.to_string(),
+            ast::ItemKind::Impl(ref iimpl) => match iimpl.of_trait {
+                Some(ref trait_ref) => format!(
+                    "impl {} for {}",
+                    self.snippet(trait_ref.path.span),
+                    self.snippet(iimpl.self_ty.span)
+                ),
+                None => format!("impl {}", self.snippet(iimpl.self_ty.span)),
+            },
+            ast::ItemKind::MacCall(ref mac) => format!("{}!", self.snippet(mac.path.span)),
+            _ => item.kind.descr().to_owned(),
+        }
+    }
+
     pub(crate) fn push_skipped_with_span(
         &mut self,
         attrs: &[ast::Attribute],
@@ -751,6 +909,57 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
         self.skipped_range.borrow_mut().push((lo, hi));
     }
 
+    fn push_skipped_item(&mut self, item: &ast::Item) {
+        let opted_in = opted_in_items(item);
+        if opted_in.is_empty() {
+            self.push_skipped_with_span(item.attrs.as_slice(), item.span(), item.span());
+        } else {
+            self.format_missing_with_indent(source!(self, item.span()).lo());
+            self.push_skipped_with_opted_in_items(item.span().hi(), opted_in);
+        }
+    }
+
+    /// Pushes the original text up to `end`, except for the items opted back in to formatting
+    /// with `#[rustfmt::format]`. Those are formatted at the indentation they have in the source.
+    fn push_skipped_with_opted_in_items(&mut self, end: BytePos, opted_in: Vec<OptedInItem<'_>>) {
+        let block_indent = self.block_indent;
+        for opted_in_item in opted_in {
+            let lo = opted_in_item.span().lo();
+            // Keep the original text up to the end of the line before the item, the line break
+            // and the indentation are added back when formatting the item.
+            let snippet = self.snippet(mk_sp(self.last_pos, lo));
+            match snippet.rfind('\n') {
+                Some(i) if snippet[i + 1..].trim().is_empty() => {
+                    let indent = &snippet[i + 1..];
+                    let width = indent.len()
+                        + indent.matches('\t').count() * (self.config.tab_spaces() - 1);
+                    self.block_indent = Indent::from_width(self.config, width);
+                    self.push_skipped_text(self.last_pos + BytePos(i as u32));
+                }
+                _ => self.push_skipped_text(lo),
+            }
+            match opted_in_item {
+                OptedInItem::Item(item) => self.visit_item(item),
+                OptedInItem::TraitItem(ai) => self.visit_trait_item(ai),
+                OptedInItem::ImplItem(ai) => self.visit_impl_item(ai),
+            }
+        }
+        self.block_indent = block_indent;
+        self.push_skipped_text(end);
+    }
+
+    /// Pushes the original text up to `end` and excludes it from the checks for overlong lines
+    /// and trailing whitespace.
+    fn push_skipped_text(&mut self, end: BytePos) {
+        let lo = self.line_number + 1;
+        let snippet = self.snippet(mk_sp(self.last_pos, end));
+        self.push_str(snippet);
+        self.last_pos = end;
+        self.skipped_range
+            .borrow_mut()
+            .push((lo, self.line_number + 1));
+    }
+
     pub(crate) fn from_context(ctx: &'a RewriteContext<'_>) -> FmtVisitor<'a> {
         let mut visitor = FmtVisitor::from_psess(
             ctx.psess,
@@ -759,6 +968,9 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
             ctx.report.clone(),
         );
         visitor.skip_context.update(ctx.skip_context.clone());
+        visitor.const_generic_defaults = ctx.const_generic_defaults.clone();
+        visitor.is_private_item = ctx.is_private_item.get();
+        visitor.in_fn_body = ctx.in_fn_body;
         visitor.set_parent_context(ctx);
         visitor
     }
@@ -789,10 +1001,16 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
             snippet_provider,
             line_number: 0,
             skipped_range: Rc::new(RefCell::new(vec![])),
+            test_mod_range: Rc::new(RefCell::new(vec![])),
             is_macro_def: false,
             macro_rewrite_failure: false,
             report,
             skip_context,
+            has_failed_rewrite: false,
+            const_generic_defaults: ConstGenericDefaults::default(),
+            is_private_item: false,
+            in_trait_impl: false,
+            in_fn_body: false,
         }
     }
 
@@ -804,6 +1022,26 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
         self.opt_snippet(span).unwrap()
     }
 
+    /// Returns `true` if the comments right before `lo` include one of the
+    /// `rustfmt_skip_pragma_patterns`, which skips the following item like `#[rustfmt::skip]`.
+    fn has_skip_pragma(&self, lo: BytePos) -> bool {
+        let pragmas = self.config.rustfmt_skip_pragma_patterns();
+        let mut lines = self
+            .snippet(mk_sp(self.snippet_provider.start_pos(), lo))
+            .rsplit('\n');
+        // The text before the item on its own line, which is only its indentation unless a
+        // comment precedes it there.
+        let Some(same_line) = lines.next() else {
+            return false;
+        };
+        std::iter::once(same_line)
+            .filter(|line| !line.trim().is_empty())
+            .chain(lines)
+            .map(str::trim)
+            .take_while(|line| line.starts_with("//") || line.starts_with("/*"))
+            .any(|line| pragmas.matches(line))
+    }
+
     // Returns true if we should skip the following item.
     pub(crate) fn visit_attrs(&mut self, attrs: &[ast::Attribute], style: ast::AttrStyle) -> bool {
         for attr in attrs {
@@ -845,22 +1083,52 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
             return false;
         }
 
-        let rewrite = attrs.rewrite(&self.get_context(), self.shape());
-        let span = mk_sp(attrs[0].span.lo(), attrs[attrs.len() - 1].span.hi());
-        self.push_rewrite(span, rewrite);
+        // Since version Two, inner attributes separated by a blank line are formatted as separate
+        // groups, so that the blank lines between them are kept like those between items.
+        let groups: Vec<&[ast::Attribute]> = match style {
+            ast::AttrStyle::Inner if self.config.version() == Version::Two => attrs
+                .chunk_by(|a, b| !self.has_blank_line_between(a.span.hi(), b.span.lo()))
+                .collect(),
+            _ => vec![&attrs],
+        };
+        for group in groups {
+            let rewrite = group.rewrite(&self.get_context(), self.shape());
+            let span = mk_sp(group[0].span.lo(), group[group.len() - 1].span.hi());
+            self.push_rewrite(span, rewrite);
+        }
 
         false
     }
 
+    fn has_blank_line_between(&self, lo: BytePos, hi: BytePos) -> bool {
+        // Ignore the rest of the line at `lo` and the indentation before `hi`.
+        let lines: Vec<_> = self.snippet(mk_sp(lo, hi)).split('\n').collect();
+        lines.len() > 2 && lines[1..lines.len() - 1].iter().any(|l| is_empty_line(l))
+    }
+
     fn is_unknown_rustfmt_attr(&self, segments: &[ast::PathSegment]) -> bool {
         if segments[0].ident.to_string() != "rustfmt" {
             return false;
         }
-        !is_skip_attr(segments)
+        !is_skip_attr(segments) && !is_format_attr(segments)
     }
 
     fn walk_mod_items(&mut self, items: &[rustc_ast::ptr::P<ast::Item>]) {
+        // Items of the parent module are not in scope in a child module.
+        let const_generic_defaults = match self.config.const_generics_style() {
+            ConstGenericsStyle::Preserve => None,
+            _ => {
+                let defaults = ConstGenericDefaults::from_items(&self.get_context(), items);
+                Some(std::mem::replace(
+                    &mut self.const_generic_defaults,
+                    defaults,
+                ))
+            }
+        };
         self.visit_items_with_reordering(&ptr_vec_to_ref_vec(items));
+        if let Some(const_generic_defaults) = const_generic_defaults {
+            self.const_generic_defaults = const_generic_defaults;
+        }
     }
 
     fn walk_stmts(&mut self, stmts: &[Stmt<'_>], include_current_empty_semi: bool) {
@@ -910,6 +1178,51 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
         self.walk_stmts(&Stmt::from_ast_nodes(b.stmts.iter()), false)
     }
 
+    /// Formats a module that is only compiled for tests with `config`, which applies
+    /// `max_width_in_tests`, and records its lines for the checks for overlong lines.
+    fn format_test_mod(
+        &mut self,
+        config: &Config,
+        item: &ast::Item,
+        mod_kind: &ast::ModKind,
+        safety: ast::Safety,
+    ) {
+        let mut visitor = FmtVisitor::from_psess(
+            self.psess,
+            config,
+            self.snippet_provider,
+            self.report.clone(),
+        );
+        visitor.buffer = std::mem::take(&mut self.buffer);
+        visitor.last_pos = self.last_pos;
+        visitor.block_indent = self.block_indent;
+        visitor.line_number = self.line_number;
+        visitor.skipped_range = self.skipped_range.clone();
+        visitor.test_mod_range = self.test_mod_range.clone();
+        visitor.skip_context = self.skip_context.clone();
+        visitor.const_generic_defaults = self.const_generic_defaults.clone();
+        visitor.is_macro_def = self.is_macro_def;
+        visitor.is_private_item = self.is_private_item;
+        visitor.format_mod(
+            mod_kind,
+            safety,
+            &item.vis,
+            item.span,
+            item.ident,
+            &item.attrs,
+        );
+
+        let first_line = self.line_number + 1;
+        self.buffer = std::mem::take(&mut visitor.buffer);
+        self.last_pos = visitor.last_pos;
+        self.line_number = visitor.line_number;
+        self.macro_rewrite_failure |= visitor.macro_rewrite_failure;
+        self.has_failed_rewrite |= visitor.has_failed_rewrite;
+        self.test_mod_range
+            .borrow_mut()
+            .push((first_line, self.line_number + 1));
+    }
+
     fn format_mod(
         &mut self,
         mod_kind: &ast::ModKind,
@@ -964,11 +1277,18 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
 
     pub(crate) fn format_separate_mod(&mut self, m: &Module<'_>, end_pos: BytePos) {
         self.block_indent = Indent::empty();
+        if contains_skip(m.attrs()) {
+            // Only reachable if some items are opted back in to formatting.
+            let opted_in = opted_in_items_of(&m.items);
+            self.push_skipped_with_opted_in_items(end_pos, opted_in);
+            return;
+        }
         let skipped = self.visit_attrs(m.attrs(), ast::AttrStyle::Inner);
         assert!(
             !skipped,
             "Skipping module must be handled before reaching this line."
         );
+        self.report_mixed_number_separators(&m.items);
         self.walk_mod_items(&m.items);
         self.format_missing_with_indent(end_pos);
     }
@@ -1007,12 +1327,16 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
             use_block: Cell::new(false),
             is_if_else_block: Cell::new(false),
             force_one_line_chain: Cell::new(false),
+            inside_deeply_nested_call: Cell::new(false),
             snippet_provider: self.snippet_provider,
             macro_rewrite_failure: Cell::new(false),
             is_macro_def: self.is_macro_def,
             report: self.report.clone(),
             skip_context: self.skip_context.clone(),
             skipped_range: self.skipped_range.clone(),
+            const_generic_defaults: self.const_generic_defaults.clone(),
+            is_private_item: Cell::new(self.is_private_item),
+            in_fn_body: self.in_fn_body,
         }
     }
 }
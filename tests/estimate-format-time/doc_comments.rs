@@ -0,0 +1,8 @@
+/// Returns one.
+///
+/// ```
+/// assert_eq!(one(), 1);
+/// ```
+fn one() -> u32 {
+    1
+}
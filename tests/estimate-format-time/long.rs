@@ -0,0 +1,39 @@
+fn f0() -> u32 {
+    0
+}
+
+fn f1() -> u32 {
+    1
+}
+
+fn f2() -> u32 {
+    2
+}
+
+fn f3() -> u32 {
+    3
+}
+
+fn f4() -> u32 {
+    4
+}
+
+fn f5() -> u32 {
+    5
+}
+
+fn f6() -> u32 {
+    6
+}
+
+fn f7() -> u32 {
+    7
+}
+
+fn f8() -> u32 {
+    8
+}
+
+fn f9() -> u32 {
+    9
+}
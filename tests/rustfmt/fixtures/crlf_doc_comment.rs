@@ -0,0 +1,8 @@
+/// Summary.
+///
+/// ```
+/// let x=1;
+///
+/// fn f(){ }
+/// ```
+fn  foo() {}
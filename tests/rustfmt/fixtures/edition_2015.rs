@@ -0,0 +1 @@
+fn f() { let async = 1; }
@@ -0,0 +1 @@
+async fn f(x:&dyn Fn()) { g().await; }
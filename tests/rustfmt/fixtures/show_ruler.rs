@@ -0,0 +1,4 @@
+fn f() {
+    let s = "a string literal that is far too long";
+    // a comment that goes on well past the ruler
+}
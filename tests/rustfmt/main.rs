@@ -1,8 +1,8 @@
 //! Integration tests for rustfmt.
 
 use std::env;
-use std::fs::remove_file;
-use std::path::Path;
+use std::fs::{self, remove_file};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use rustfmt_config_proc_macro::rustfmt_only_ci_test;
@@ -31,6 +31,29 @@ fn rustfmt(args: &[&str]) -> (String, String) {
     }
 }
 
+/// A directory for a test to write files to. It is removed when dropped, so also when the test
+/// fails.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> TempDir {
+        let path = env::temp_dir().join(format!("rustfmt-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
 macro_rules! assert_that {
     ($args:expr, $($check:ident $check_args:tt)&&+) => {
         let (stdout, stderr) = rustfmt($args);
@@ -207,3 +230,195 @@ fn rustfmt_emits_error_when_control_brace_style_is_always_next_line() {
     let (_stdout, stderr) = rustfmt(&args);
     assert!(!stderr.contains("error[internal]: left behind trailing whitespace"))
 }
+
+#[cfg(unix)]
+#[test]
+fn check_opens_mismatched_files_with_diff_tool() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new("diff-tool-test");
+    let dir = temp_dir.path();
+    let log = dir.join("log");
+    // The fake tool records its arguments and whether both files exist while it runs.
+    let tool = dir.join("tool.sh");
+    fs::write(
+        &tool,
+        format!(
+            "#!/bin/sh\necho \"$1 $2\" >> {log}\n[ -f \"$2\" ] && [ -f \"$4\" ] && \
+             echo exists >> {log}\nexit 1\n",
+            log = log.display()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&tool, fs::Permissions::from_mode(0o755)).unwrap();
+    let files = ["a.rs", "b.rs", "c.rs"].map(|name| {
+        let path = dir.join(name);
+        fs::write(&path, "fn  main() {}\n").unwrap();
+        path.display().to_string()
+    });
+    let diff_tool = format!("--diff-tool={} --old {{old}} --new {{new}}", tool.display());
+
+    let (stdout, stderr) = rustfmt(&[
+        "--check",
+        &diff_tool,
+        "--config",
+        "diff_tool_max_files=2",
+        &files[0],
+        &files[1],
+        &files[2],
+    ]);
+    let log_text = fs::read_to_string(&log).unwrap_or_default();
+
+    let invocations: Vec<_> = log_text.lines().collect();
+    assert_eq!(invocations.len(), 4, "{log_text}\nstdout:\n{stdout}\nstderr:\n{stderr}");
+    for (lines, name) in invocations.chunks(2).zip(["a.rs", "b.rs"]) {
+        let paths: Vec<_> = lines[0].split(' ').collect();
+        assert_eq!(paths[0], "--old");
+        assert!(paths[1].ends_with(&format!("old-{name}")), "{}", lines[0]);
+        assert_eq!(lines[1], "exists");
+    }
+    assert!(stdout.contains("Not opening more than 2 files in the diff tool"));
+    // The temporary files are gone once the tool has exited.
+    for line in invocations.iter().step_by(2) {
+        assert!(!Path::new(line.split(' ').nth(1).unwrap()).exists());
+    }
+}
+
+#[test]
+fn diff_tool_failures_are_reported_for_each_file() {
+    let (_stdout, stderr) = rustfmt(&[
+        "--check",
+        "--diff-tool=true 'quoted'",
+        "tests/source/fn-simple.rs",
+        "tests/source/impls.rs",
+    ]);
+    for file in ["fn-simple.rs", "impls.rs"] {
+        assert!(
+            stderr.contains(&format!(
+                "{file}: failed to open the diff tool: the diff tool command is split at \
+                 whitespace, quotes are not supported"
+            )),
+            "{stderr}"
+        );
+    }
+}
+
+#[test]
+fn diff_tool_requires_check() {
+    let (_stdout, stderr) = rustfmt(&["--diff-tool=true", "src/lib.rs"]);
+    assert!(stderr.contains("`--diff-tool` can only be used with `--check`"));
+}
+
+#[test]
+fn auto_newline_style_keeps_crlf_in_doc_comment_code_blocks() {
+    let (stdout, _stderr) = rustfmt(&[
+        "--unstable-features",
+        "--emit=stdout",
+        "--config",
+        "format_code_in_doc_comments=true",
+        "tests/rustfmt/fixtures/crlf_doc_comment.rs",
+    ]);
+
+    let expected = "/// Summary.\r\n///\r\n/// ```\r\n/// let x = 1;\r\n///\r\n/// fn f() {}\r\n\
+                    /// ```\r\nfn foo() {}\r\n";
+    assert!(stdout.ends_with(expected), "{stdout:?}");
+}
+
+#[test]
+fn version_stamps_select_the_formatting_version_per_file() {
+    let temp_dir = TempDir::new("version-stamps");
+    let dir = temp_dir.path();
+    // The `return` statement only fits on one line in version Two.
+    let body = "fn f() {\n    loop {\n        return some_very_long_function_name_here(\
+                argument_number_one, argument_number_two, three_4);\n    }\n}\n";
+    let one_line = "return some_very_long_function_name_here(";
+    fs::write(dir.join("lib.rs"), format!("// @rustfmt-version: 1\nmod b;\n{body}")).unwrap();
+    fs::write(dir.join("b.rs"), format!("// @rustfmt-version: 2\n{body}")).unwrap();
+    let lib = dir.join("lib.rs");
+    let lib = lib.to_str().unwrap();
+
+    let (stdout, _stderr) = rustfmt(&[
+        "--unstable-features",
+        "--emit=stdout",
+        "--config",
+        "format_version_stamps=true",
+        lib,
+    ]);
+    // Files are emitted in path order.
+    let (b_output, lib_output) = stdout.split_once("lib.rs:\n").unwrap();
+    assert!(lib_output.contains("// @rustfmt-version: 1\n"), "{stdout}");
+    assert!(!lib_output.contains(&format!("{one_line}argument")), "{stdout}");
+    assert!(b_output.contains("// @rustfmt-version: 2\n"), "{stdout}");
+    assert!(b_output.contains(&format!("{one_line}argument")), "{stdout}");
+
+    let (stdout, _stderr) = rustfmt(&[
+        "--unstable-features",
+        "--bump-format-version",
+        "--config",
+        "format_version_stamps=true",
+        lib,
+    ]);
+    let lib_text = fs::read_to_string(dir.join("lib.rs")).unwrap();
+
+    assert!(lib_text.starts_with("// @rustfmt-version: 2\nmod b;\n"), "{stdout}\n{lib_text}");
+    assert!(lib_text.contains(&format!("{one_line}argument")), "{lib_text}");
+}
+
+#[test]
+fn show_ruler_marks_and_counts_overlong_lines() {
+    let file = "tests/rustfmt/fixtures/show_ruler.rs";
+    let args = ["--emit=stdout", "--show-ruler", "--config", "max_width=40"];
+    let (stdout, _stderr) = rustfmt(&[&args[..], &["--color=always", file]].concat());
+
+    assert!(
+        stdout.contains("\n----+----1----+----2----+----3----+----4\nfn f() {\n"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("    let s = \"a string literal that is fa\x1b[1;31mr too long\";\x1b[0m\n"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.ends_with("Lines exceeding max_width (40): 2 (string: 1, comment: 1, code: 0)\n"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn show_ruler_requires_emit_stdout() {
+    let (_stdout, stderr) = rustfmt(&["--show-ruler", "tests/target/empty_file.rs"]);
+    assert!(stderr.contains("`--show-ruler` can only be used with `--emit stdout`"));
+}
+
+#[test]
+fn standalone_files_default_to_the_latest_edition() {
+    let edition_2021 = "tests/rustfmt/fixtures/edition_2021.rs";
+    let edition_2015 = "tests/rustfmt/fixtures/edition_2015.rs";
+
+    let (stdout, stderr) = rustfmt(&["--emit=stdout", edition_2021]);
+    assert!(
+        stdout.contains("async fn f(x: &dyn Fn()) {\n"),
+        "{stdout}\n{stderr}"
+    );
+    assert!(!stderr.contains("doesn't parse as edition"), "{stderr}");
+
+    // Files that don't parse with the latest edition fall back to older ones.
+    let (stdout, stderr) = rustfmt(&["--emit=stdout", edition_2015]);
+    assert!(
+        stdout.contains("    let async = 1;\n"),
+        "{stdout}\n{stderr}"
+    );
+    assert!(
+        stderr.contains("doesn't parse as edition 2021, so it was formatted as edition 2015"),
+        "{stderr}"
+    );
+
+    // An explicit edition is used as is.
+    let (stdout, stderr) = rustfmt(&["--emit=stdout", "--edition=2015", edition_2021]);
+    assert!(!stdout.contains("async fn"), "{stdout}");
+    assert!(
+        stderr.contains("`async fn` is not permitted in Rust 2015"),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("doesn't parse as edition"), "{stderr}");
+}
@@ -0,0 +1,40 @@
+// Spacing inside angle brackets and around associated-type bindings is normalized.
+
+fn bounds<I: Iterator<Item=u8>, J: Iterator< Item = u8 >, K: Iterator<Item   =u8>>() {}
+
+fn signature(x: Box<dyn Iterator< Item = u8 >>, y: impl Iterator<Item=impl Into< u8 >>) -> impl Iterator<Item =u8> {}
+
+fn where_clause<T, U, V>()
+where
+    T: Fn< ( ) , Output=u8 >,
+    U: Into< Vec< u8 > > + Iterator<Item= u8>,
+    for< 'a > &'a V: IntoIterator<Item=&'a u8>,
+{
+}
+
+struct Tuple<T: Iterator<Item=u8>> (T);
+
+trait Assoc {
+    type Y: Iterator<Item=u8>;
+}
+
+impl<T> Foo for Bar< T > where T: Iterator<Item=u8> {}
+
+fn expressions() {
+    let v = x.iter().collect::< Vec< _ > >();
+    let y = <T as Trait< U >>::X;
+    let w: Box<dyn Iterator<Item=u8> + Send + 'static> = todo!();
+    println!("{}", Vec::< u8 >::new());
+}
+
+// Generic args inside macro calls that are formatted go through the type rewrite, while macros
+// with braces are left verbatim.
+fn macros() {
+    let v = vec![Vec::< u8 >::new(), Vec::<u8>::with_capacity(1)];
+    assert_eq!(x.iter().collect::< Vec< _ > >(), Box::<dyn Iterator<Item=u8>>::new(y));
+    foo!(Iterator<Item=u8>, Vec< u8 >);
+    bar! { Iterator<Item=u8> }
+    let z: m!(Vec< u8 >) = m![Foo::< Item=u8 >];
+}
+
+fn macro_in_type(x: foo!(Iterator<Item=u8>)) -> Box<dyn Iterator<Item=m!( Vec< u8 > )>> {}
@@ -0,0 +1,6 @@
+/* rustfmt-wrap_comments: true
+ * rustfmt-comment_width: 40
+ */
+
+// This comment is long enough to be wrapped at the comment width that the header sets.
+fn main() {}
@@ -0,0 +1,17 @@
+// rustfmt-wrap_comments: true
+
+// This is a paragraph of prose that is long enough to need wrapping because it goes past the comment width limit.
+// +-------+--------+-------------------+
+// | Name  | Type   | Description       |
+// +=======+========+===================+
+// | width | usize  | The maximum width |
+// +-------+--------+-------------------+
+// More prose after the table that is also long enough to need to be wrapped at the comment width.
+fn f() {}
+
+/// A doc comment with a table without outer borders that needs to be kept as it is, though.
+///
+///  name  | type  | description of the column that is long enough to need wrapping at some point
+/// -------+-------+-----------------------------------------------------------------------------
+///  width | usize | the maximum width
+fn g() {}
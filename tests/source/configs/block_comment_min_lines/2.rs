@@ -0,0 +1,12 @@
+// rustfmt-block_comment_min_lines: 2
+// rustfmt-prefer_block_comments_for_long_code: true
+// rustfmt-wrap_comments: true
+// rustfmt-comment_width: 40
+
+fn main() {
+    // Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod.
+    foo();
+
+    // Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+    bar();
+}
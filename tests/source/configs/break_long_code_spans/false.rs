@@ -0,0 +1,15 @@
+// rustfmt-break_long_code_spans: false
+// rustfmt-wrap_comments: true
+// rustfmt-comment_width: 80
+
+/// Run the tool with `cargo run --bin rustfmt -- --config-path ./rustfmt.toml --check src/lib.rs` to check a file.
+fn fits_own_line() {}
+
+/// Run the tool with `cargo run --bin rustfmt -- --config-path ./rustfmt.toml --check src/lib.rs --edition 2021` to check a file.
+fn long() {}
+
+/// A short span like `cargo fmt --all` stays whole even when the line needs to be wrapped here.
+fn short() {}
+
+// A ``double `backtick` span with many words that does not fit on a line of a comment`` here.
+fn double() {}
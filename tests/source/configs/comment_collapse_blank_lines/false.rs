@@ -0,0 +1,28 @@
+// rustfmt-comment_collapse_blank_lines: false
+// rustfmt-max_empty_lines_in_doc_comments: 3
+// rustfmt-wrap_comments: true
+
+// Lorem ipsum.
+//
+//
+//
+// Dolor sit amet.
+fn lorem() {
+    // Consectetur.
+    //
+    //
+    //
+    // Adipiscing elit.
+}
+
+/// Doc.
+///
+///
+///
+/// ```
+/// let x = 1;
+///
+///
+/// let y = 2;
+/// ```
+fn ipsum() {}
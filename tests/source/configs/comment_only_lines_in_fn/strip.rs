@@ -0,0 +1,59 @@
+// rustfmt-comment_only_lines_in_fn: Strip
+
+// Comments outside of function bodies are kept.
+fn main() {
+    // let x = old();
+    let x = 1;
+
+    // let y = 2;
+    // let z = 3;
+    println!("{}", x); // trailing comments are kept
+    // but not the lines after them
+    let w = 4; // trailing
+    // gone
+    if x > 0 {
+        // dead
+        foo();
+    }
+    let f = || {
+        // dead in a closure
+        bar()
+    };
+    // rustfmt::skip
+    let   skipped   = 1;
+    /* block comments are kept */
+    // the last line
+}
+
+fn only_comments() {
+    // nothing here
+}
+
+fn lists() {
+    match x {
+        // arm comments are kept
+        1 => {
+            // in an arm
+            a()
+        }
+        _ => b(),
+    }
+    call(
+        // argument comments are kept
+        a,
+    );
+}
+
+struct S {
+    // field comments are kept
+    a: u32,
+}
+
+impl S {
+    /// Doc comments are kept.
+    fn method(&self) {
+        // gone
+        self.go(); // kept
+        // gone
+    }
+}
@@ -0,0 +1,10 @@
+// rustfmt-comment_preserve_sentence_spacing: false
+// rustfmt-wrap_comments: true
+// rustfmt-comment_width: 60
+
+// Lorem ipsum dolor sit amet.  Consectetur adipiscing elit, sed do eiusmod tempor.  End.
+// Start of the next sentence.  It is joined to the previous line.
+fn lorem() {
+    // Only the join of the lines is affected. Sed do eiusmod tempor incididunt ut labore.
+    // Et dolore magna aliqua.
+}
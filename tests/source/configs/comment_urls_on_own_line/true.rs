@@ -0,0 +1,21 @@
+// rustfmt-wrap_comments: true
+// rustfmt-comment_urls_on_own_line: true
+
+// some text http://example.org more text
+fn text_around_url() {}
+
+// https://example.org/a/very/long/url/that/does/not/fit/on/a/line/whatever/you/do/to/it
+fn only_url() {}
+
+/// See [the docs](https://example.org) for more.
+///
+/// [docs]: https://example.org
+///
+/// ```
+/// let x = "http://example.org y";
+/// ```
+fn links_and_code() {}
+
+fn long_comment() {
+    // Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor <https://example.org/zzz> incididunt ut labore et dolore magna aliqua.
+}
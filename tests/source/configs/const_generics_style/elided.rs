@@ -0,0 +1,27 @@
+// rustfmt-const_generics_style: Elided
+
+struct Buffer<const N: usize = 16> {
+    next: Option<Box<Buffer>>,
+}
+
+struct Pair<'a, T, const N: usize = 4, const M: usize = 8>(&'a [T; N], [T; M]);
+
+trait Store<const CAP: usize = 32> {}
+
+type Alias = Buffer;
+
+impl Buffer {
+    fn new() -> Buffer<16> {
+        Buffer::<16> { next: None }
+    }
+}
+
+impl Store for Buffer<32> {}
+
+fn pairs<'a>(a: Pair<'a, u8>, b: Pair<'a, u8, 4>, c: Pair<'a, u8, 4, 8>, d: Pair<'a, u8, 2, 8>) {}
+
+fn other(x: std::collections::Buffer, y: <Buffer as Store>::Output) {}
+
+mod inner {
+    struct Local(Buffer);
+}
@@ -0,0 +1,29 @@
+// rustfmt-control_brace_style: AlwaysSameLine
+// Each branch of an else-if ladder keeps its leading comment directly above its `else`.
+
+fn main() {
+    let value = if let Some(a) = first { a } // the first fallback
+    else if let Some(b) = second { b }
+    /* the second fallback */ else if let Ok(c) = third { c }
+    // the last resort
+    else /* never dropped */ { 0 };
+    let short = if a { 1 } else // forces the brace to the next line
+    { 2 };
+
+    if let Some(a) = first {
+        one(a);
+    }
+    // second branch
+    else if let Some(b) = second {
+        two(b);
+    }
+    // third branch
+    else if let Ok(c) = third {
+        three(c);
+    }
+    // last branch
+    else // between else and brace
+    {
+        four();
+    }
+}
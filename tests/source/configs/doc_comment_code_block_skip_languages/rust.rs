@@ -0,0 +1,15 @@
+// rustfmt-doc_comment_code_block_skip_languages: ["rust"]
+// rustfmt-format_code_in_doc_comments: true
+
+/// ```
+/// fn   main( ) {}
+/// ```
+///
+/// ```rust
+/// fn   main( ) {}
+/// ```
+///
+/// ```pseudo
+/// fn   main( ) {}
+/// ```
+fn foo() {}
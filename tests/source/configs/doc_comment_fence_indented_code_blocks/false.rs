@@ -0,0 +1,15 @@
+// rustfmt-doc_comment_fence_indented_code_blocks: false
+// rustfmt-format_code_in_doc_comments: true
+
+/// Example:
+///
+///     fn main( ) { let x=1 ; }
+///
+///     # fn hidden() {}
+///
+/// - A list item
+///
+///     with an indented paragraph
+///
+/// Done.
+fn foo() {}
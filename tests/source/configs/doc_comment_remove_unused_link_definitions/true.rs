@@ -0,0 +1,33 @@
+// rustfmt-doc_comment_remove_unused_link_definitions: true
+/// Parses a [`Config`] from its [TOML] representation, see the [Config docs][config] and
+/// [`Config`] again.
+///
+/// [`Config`]: crate::Config
+/// [toml]: https://toml.io
+/// [config]: crate::config
+/// [unused]: https://example.com/unused
+/// [`Error`]: crate::Error
+fn parse() {}
+
+/// Only unused definitions are removed.
+///
+/// [first]: https://example.com/first
+/// [^note]: A footnote.
+///
+/// ```
+/// let [unused]: [u8; 1] = [0];
+/// ```
+///
+/// [titled]: https://example.com/titled
+/// "A title"
+fn footnotes() {}
+
+/// [unused]: https://example.com/unused
+fn only_definitions() {}
+
+mod m {
+    //! [Used] inner doc comment.
+    //!
+    //! [used]: crate::Used
+    //! [Unused]: crate::Unused
+}
@@ -0,0 +1,21 @@
+// rustfmt-doc_comment_sort_footnote_definitions: false
+
+/// [^unused]: Nothing refers to this note.
+/// [^Third]: The third note, which goes on
+///     over two lines.
+/// [^second]: The second note.
+/// [^first]: The first note.
+///
+/// Lorem[^first] ipsum[^second] dolor[^third], sit[^first] amet.
+///
+/// ```text
+/// [^z]: Not a definition.
+/// [^a]: Not a definition either.
+/// ```
+fn lorem() {}
+
+/// Consectetur[^b] adipiscing[^a].
+///
+/// [^a]: The second note.
+/// [^b]: The first note.
+pub struct Ipsum;
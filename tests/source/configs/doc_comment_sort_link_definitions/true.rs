@@ -0,0 +1,27 @@
+// rustfmt-doc_comment_sort_link_definitions: true
+/// Links to [Zebra], [apple] and [Mango].
+///
+/// [Zebra]: https://example.com/zebra
+/// [mango]: https://example.com/mango
+///     "The mango page"
+/// [Apple]: https://example.com/apple
+fn fruits() {}
+
+/// [second]: https://example.com/second
+/// [first]: https://example.com/first
+///
+/// ```
+/// let [b]: [u8; 1] = [0];
+/// let [a]: [u8; 1] = [0];
+/// ```
+///
+/// [d]: https://example.com/d
+/// [c]: https://example.com/c
+fn runs() {}
+
+mod m {
+    //! [Beta] and [alpha].
+    //!
+    //! [beta]: crate::Beta
+    //! [alpha]: crate::Alpha
+}
@@ -0,0 +1,21 @@
+// rustfmt-doc_comment_thematic_break: Asterisk
+
+/// Summary.
+///
+/// ***
+///
+/// - - -
+/// Paragraph
+/// ---
+/// Another paragraph
+/// * * *
+///
+///    ___
+///
+/// ```
+/// ***
+/// ```
+fn foo() {}
+
+// ***
+fn bar() {}
@@ -0,0 +1,21 @@
+// rustfmt-doc_comment_trailing_blank_lines: 0
+
+///
+///
+/// Leading blank lines.
+///
+///
+fn foo() {}
+
+mod m {
+    //!
+    //! Inner.
+    //!
+
+    /// No blank lines.
+    fn bar() {}
+
+    ///
+    ///
+    fn blank() {}
+}
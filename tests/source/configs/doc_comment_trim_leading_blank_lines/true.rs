@@ -0,0 +1,21 @@
+// rustfmt-doc_comment_trim_leading_blank_lines: true
+
+///
+///
+/// Leading blank lines.
+///
+///
+fn foo() {}
+
+mod m {
+    //!
+    //! Inner.
+    //!
+
+    /// No blank lines.
+    fn bar() {}
+
+    ///
+    ///
+    fn blank() {}
+}
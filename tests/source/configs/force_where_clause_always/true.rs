@@ -0,0 +1,34 @@
+// rustfmt-force_where_clause_always: true
+// Move type parameter bounds to the where clause
+
+fn lorem<T: Clone, U>(ipsum: T, dolor: U)
+where
+    U: Default,
+{
+    // body
+}
+
+fn single<T: Clone + Send>(x: T) {}
+
+fn lifetimes<'a, 'b: 'a, T: 'a + Clone, const N: usize>(x: &'a T) {}
+
+struct Tuple<T: Clone, U>(T, U);
+
+struct Struct<T: Clone> {
+    x: T,
+}
+
+enum Enum<T: Clone + Default> {
+    A(T),
+}
+
+impl<T: Clone> Struct<T> {
+    fn new<U: Into<T>>(x: U) -> Self { Struct { x: x.into() } }
+}
+
+trait Trait<T: Clone> {
+    fn f<U: Default>(&self, u: U);
+}
+
+// Generics with comments are left alone.
+fn commented<T: Clone /* comment */, U>(x: T, y: U) {}
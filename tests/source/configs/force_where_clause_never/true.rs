@@ -0,0 +1,56 @@
+// rustfmt-force_where_clause_never: true
+// Move the bounds of single-predicate where clauses inline
+
+fn lorem<T>(ipsum: T)
+where
+    T: Clone,
+{
+    // body
+}
+
+// Params that already have bounds are left alone.
+fn merged<T: Clone>(x: T) where T: Send {}
+
+fn multiple<T, U>(x: T, y: U)
+where
+    T: Clone,
+    U: Default,
+{
+}
+
+fn not_a_param<T>(x: T)
+where
+    Vec<T>: Clone,
+{
+}
+
+fn higher_ranked<F>(f: F)
+where
+    for<'a> F: Fn(&'a u8),
+{
+}
+
+struct Struct<T>
+where
+    T: Clone,
+{
+    x: T,
+}
+
+impl<T> Struct<T> where T: Clone {
+    fn new<U>(x: U) -> Self where U: Into<T> { Struct { x: x.into() } }
+}
+
+trait Trait<T>
+where
+    T: Clone,
+{
+}
+
+// Generics with comments are left alone.
+fn commented<T>(x: T)
+where
+    // comment
+    T: Clone,
+{
+}
@@ -0,0 +1,19 @@
+// rustfmt-format_attribute_args: ["serde","arg","command"]
+
+#[derive(Parser, Serialize)]
+#[command(version, about = "Formats the given files", long_about = None, propagate_version = true)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields, skip_serializing_if = "Option::is_none")]
+struct Cli {
+    #[arg(short, long, default_value_t = 10, value_parser = clap::value_parser!(u16).range(1..))]
+    port: u16,
+    #[arg(short = 'v', long = "verbose", action = clap::ArgAction::Count,)]
+    verbose: u8,
+    #[arg(long, help = "Stays on one line", value_enum)]
+    mode: Mode,
+    // Unknown syntax inside a listed attribute is left as it is.
+    #[arg(short, long, +default_value_t = 10, value_parser = clap::value_parser!(u16).range(1..))]
+    retries: u16,
+    // Attributes that are not listed are left as they are.
+    #[query(select name from users where id = $1 and name = "some very long string literal here")]
+    name: String,
+}
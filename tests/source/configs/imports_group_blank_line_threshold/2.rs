@@ -0,0 +1,22 @@
+// rustfmt-group_imports: StdExternalCrate
+// rustfmt-imports_group_blank_line_threshold: 2
+use chrono::Utc;
+use super::update::convert_publish_payload;
+
+use juniper::{FieldError, FieldResult};
+use uuid::Uuid;
+use alloc::alloc::Layout;
+
+use std::sync::Arc;
+
+use broker::database::PooledConnection;
+
+use super::schema::{Context, Payload};
+use core::f32;
+use crate::models::Event;
+
+mod inner {
+    use crate::foo;
+    use std::fmt;
+    use log::info;
+}
@@ -0,0 +1,10 @@
+// rustfmt-let_chain_indent_style: Visual
+
+fn main() {
+    if let Some(xxxxxxxxxxxxxxx) = foooooooooooooooooooo() && let Some(yyyyyyyyyyyyyyy) = baaaaaaaaaaaaaaar(xxxxxxxxxxxxxxx) && zzzzzzzzzz {
+        body();
+    }
+    while let Some(xxxxxxxxxxxxxxx) = foooooooooooooooooooo() && let Some(yyyyyyyyyyyyyyy) = baaaaaaaaaaaaaaar(xxxxxxxxxxxxxxx) {
+        body();
+    }
+}
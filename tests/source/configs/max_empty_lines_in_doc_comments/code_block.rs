@@ -0,0 +1,19 @@
+// rustfmt-max_empty_lines_in_doc_comments: 2
+// rustfmt-wrap_comments: true
+// Blank lines in code blocks are kept
+
+/// First paragraph.
+///
+///
+///
+///
+/// Second paragraph.
+///
+/// ```rust
+/// let a = 1;
+///
+///
+///
+/// let b = 2;
+/// ```
+fn foo() {}
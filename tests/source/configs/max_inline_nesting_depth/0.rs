@@ -0,0 +1,6 @@
+// rustfmt-max_inline_nesting_depth: 0
+
+fn main() {
+    let b = f(g(h(i(x))));
+    let c = f(g(h(i(j(k(x))))));
+}
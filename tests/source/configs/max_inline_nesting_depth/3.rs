@@ -0,0 +1,15 @@
+// rustfmt-max_inline_nesting_depth: 3
+
+fn main() {
+    // Depth 3 is left alone.
+    let a = f(g(h(x)));
+    let a = foo.bar(baz(x[idx]));
+    // Depth 4 breaks up the outermost call only.
+    let b = f(g(h(i(x))));
+    let b = foo.bar(baz(qux(x[idx(y)])));
+    let b = f(a, g(h(i(x))), b);
+    let c = f(g(h(i(j(k(x))))));
+    // Wrappers like `Some(x)` are not counted.
+    let d = Some(Ok(f(g(h(x)))));
+    let e = f(Some(g(Ok(h(x)))));
+}
@@ -0,0 +1,8 @@
+// rustfmt-max_inline_nesting_depth: 3
+// rustfmt-inline_nesting_ignore_wrappers: false
+
+fn main() {
+    let a = Some(Ok(x));
+    let d = Some(Ok(f(g(h(x)))));
+    let e = f(Some(g(Ok(h(x)))));
+}
@@ -0,0 +1,11 @@
+// rustfmt-max_inline_nesting_depth: 3
+// rustfmt-fn_call_width: 30
+
+fn main() {
+    // Too wide for `fn_call_width`, but not nested too deeply.
+    let a = first(second_argument, third(fourth_argument));
+    // Nested too deeply, but well within `fn_call_width`.
+    let b = f(g(h(i(x))));
+    // Both.
+    let c = first(second(third(fourth(fifth_argument, sixth_argument))));
+}
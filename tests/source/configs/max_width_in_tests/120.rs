@@ -0,0 +1,33 @@
+// rustfmt-max_width_in_tests: 120
+
+fn lib() {
+    let expected_configuration_value_for_the_test = compute_something(first_argument, second_argument);
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn long_binding() {
+        let expected_configuration_value_for_the_test = compute_something(first_argument, second_argument);
+    }
+
+    mod nested {
+        fn nested() {
+            let expected_value_for_the_test = compute_something(first_argument, second_argument, third);
+        }
+    }
+}
+
+#[cfg_attr(feature = "extra", cfg(all(test, unix)))]
+mod unix_tests {
+    fn long_binding() {
+        let expected_configuration_value_for_the_test = compute_something(first_argument, second_argument);
+    }
+}
+
+#[cfg(not(test))]
+mod not_tests {
+    fn long_binding() {
+        let expected_configuration_value_for_the_test = compute_something(first_argument, second_argument);
+    }
+}
@@ -0,0 +1,11 @@
+// rustfmt-normalize_line_endings_in_strings: false
+// Normalize line ending escapes in string literals
+
+fn main() {
+    let crlf = "first line\r\nsecond line\r\n";
+    let cr = "first line\rsecond line";
+    let escaped_backslash = "C:\\root\\new";
+    let raw = r"first line\r\nsecond line";
+    let bytes = b"first line\r\n";
+    println!("{}\r\n", crlf);
+}
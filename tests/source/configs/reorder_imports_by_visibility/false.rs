@@ -0,0 +1,9 @@
+// rustfmt-reorder_imports_by_visibility: false
+// rustfmt-imports_granularity: Crate
+
+pub use a::Y;
+use a::X;
+pub(crate) use a::Z;
+pub use b::V;
+use b::W;
+pub(super) use a::U;
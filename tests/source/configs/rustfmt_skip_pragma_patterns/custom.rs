@@ -0,0 +1,33 @@
+// rustfmt-rustfmt_skip_pragma_patterns: ["nofmt"]
+
+// nofmt
+const  IDENTITY : [[u8;3];3]=[
+    [1, 0, 0],
+    [0, 1, 0],
+    [0, 0, 1],
+];
+
+// rustfmt::skip
+fn   lorem ( ) { }
+
+// nofmt
+
+fn   ipsum ( ) { }
+
+fn   main ( ) {
+    // nofmt
+    let   x  =  1 ;
+    let   y  =  1 ; // nofmt
+    let   z  =  1 ;
+    // nofmt
+    println ! ( "{}" , x+y+z ) ;
+}
+
+impl   S {
+    // A comment.
+    // nofmt
+    fn   dolor ( ) { }
+
+    // A regular comment.
+    fn   sit ( ) { }
+}
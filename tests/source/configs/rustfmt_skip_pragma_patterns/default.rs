@@ -0,0 +1,37 @@
+// rustfmt-rustfmt_skip_pragma_patterns: ["rustfmt::skip"]
+
+// rustfmt::skip
+const  IDENTITY : [[u8;3];3]=[
+    [1, 0, 0],
+    [0, 1, 0],
+    [0, 0, 1],
+];
+
+// rustfmt::skip
+fn   lorem ( ) { }
+
+// rustfmt::skip
+
+fn   ipsum ( ) { }
+
+fn   main ( ) {
+    // rustfmt::skip
+    let   x  =  1 ;
+    let   y  =  1 ; // rustfmt::skip
+    let   z  =  1 ;
+    // rustfmt::skip
+    println ! ( "{}" , x+y+z ) ;
+}
+
+impl   S {
+    // A comment.
+    // rustfmt::skip
+    fn   dolor ( ) { }
+
+    // A regular comment.
+    fn   sit ( ) { }
+}
+
+// Other tools' pragmas are not used by default.
+// fmt::skip
+fn   amet ( ) { }
@@ -0,0 +1,11 @@
+// rustfmt-sort_lint_attributes: false
+// Lint lists are left in their original order.
+
+#[allow(unused_variables, dead_code, unused_variables)]
+#[allow(dead_code)]
+#[deny(clippy::all, rustdoc::broken_intra_doc_links, missing_docs, clippy::all)]
+#[expect(unused_mut, dead_code, reason = "the fields are filled in by a macro")]
+#[warn(unused, reason = "a reason in the middle is left alone", dead_code)]
+#[allow(non_snake_case, unused_imports, unused_variables, clippy::too_many_arguments, dead_code,)]
+#[cfg_attr(test, allow(unused, dead_code))]
+fn f() {}
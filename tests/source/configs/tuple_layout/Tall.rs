@@ -0,0 +1,15 @@
+// rustfmt-tuple_layout: Tall
+fn main() {
+    let pair = (1, 2);
+    let triple = (first, second, third);
+    let quad = (1, 2, 3, 4);
+    let (a, b, c, d) = quad;
+    let (x, y, ..) = (alpha(), beta(), gamma(), delta());
+    let nested = ((1, 2, 3, 4), (a, b));
+    match point {
+        (0, 0, 0, 0) => origin(),
+        Point(w, x, y, z) => other(w, x, y, z),
+        _ => {}
+    }
+    let t: (u8, u16, u32, u64) = (1, 2, 3, 4);
+}
@@ -1,4 +1,5 @@
 // rustfmt-type_punctuation_density: Compressed
+// rustfmt-type_punctuation_in_generics: Compact
 // Type punctuation density
 
 fn lorem<Ipsum:Dolor+Sit=Amet>() {
@@ -0,0 +1,11 @@
+// rustfmt-wrap_comments: true
+// rustfmt-comment_width: 60
+
+/// This paragraph mentions an autolink <https://example.org/a/very/long/path/that/goes/on/and/on> in the middle of it.
+///
+/// - A list item with an autolink <https://example.org/a/very/long/path/that/goes/on/and/on> inside of it, that wraps.
+/// - Contact us at <mailto:someone-with-a-long-name@example-organisation.org> if anything breaks for you.
+/// - Or write to <someone-with-a-long-name@example-organisation.org.invalid> if anything breaks.
+///
+/// Or write to <someone-with-a-long-name@example-organisation.org.invalid> if anything breaks please.
+fn foo() {}
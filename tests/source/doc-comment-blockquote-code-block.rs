@@ -0,0 +1,25 @@
+// rustfmt-format_code_in_doc_comments: true
+
+/// Quoted code:
+///
+/// > ```rust
+/// > fn main(){println!("hi");}
+/// > ```
+///
+/// Nested, with a lazy continuation line before the code:
+///
+/// > > Some text
+/// that continues lazily.
+/// >
+/// > > ```
+/// > > let x=1;
+/// > >
+/// > > let y=2;
+/// > > ```
+///
+/// Lazy end:
+///
+/// > ```rust
+/// > let z=3;
+/// not quoted
+fn foo() {}
@@ -0,0 +1,15 @@
+// rustfmt-wrap_comments: true
+// rustfmt-format_code_in_doc_comments: true
+
+/// Options:
+///
+/// `fast`
+/// :   Runs without checking anything, which is quick but is not recommended for anything that matters much.
+///
+/// `safe`
+/// : Checks everything first:
+///
+///   ```rust
+///   let   x=check( all );
+///   ```
+fn foo() {}
@@ -0,0 +1,7 @@
+// rustfmt-wrap_comments: true
+// rustfmt-comment_width: 60
+
+/// This is a long paragraph of prose with an inline <!-- note that should stay whole --> and then more text after it that goes on.
+/// <!-- own line comment that is long enough to need wrapping at some point in time -->
+/// Short line.
+fn foo() {}
@@ -0,0 +1,33 @@
+// rustfmt-format_code_in_doc_comments: true
+
+/// Indented code blocks are formatted like fenced ones:
+///
+///     fn main() {
+///     let x=1;
+///
+///     println!("{}",x);
+///     }
+///
+/// Lines starting with `#` are kept:
+///
+///     # fn hidden( ) {}
+///     let   y = vec![1,2,3];
+///
+///
+/// Code that does not parse is left alone:
+///
+///     this is not rust(
+///
+/// An indented line that continues a paragraph
+///     is not a code block.
+fn foo() {}
+
+///     let   first = "line";
+///
+/// The block can start the comment.
+fn bar() {}
+
+/// The block can end the comment.
+///
+///     let   last = "line";
+fn baz() {}
@@ -0,0 +1,23 @@
+// rustfmt-format_code_in_doc_comments: true
+
+/// Steps:
+///
+/// - First level
+///   - Second level:
+///
+///     ```rust
+///     fn main() { let x=1; println!("{}",x); }
+///     ```
+///
+///   - Another second level item
+///
+/// 1. Back at the top
+///
+///    ```
+///    let   y = 2;
+///    ```
+///
+/// Once the list is over, indented text is code:
+///
+///     let   z = 3;
+fn f() {}
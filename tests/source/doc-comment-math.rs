@@ -0,0 +1,14 @@
+// rustfmt-wrap_comments: true
+// rustfmt-doc_comment_hard_break: Spaces
+
+/// The sum of the first $n$ numbers, which is computed in constant time as $n (n + 1) / 2$ rather than by adding.
+///
+/// $$
+/// \begin{aligned}
+/// - S_n &= 1 + 2 + \cdots + n \\
+/// + S_n &= \frac{n(n+1)}{2}
+/// \end{aligned}
+/// $$
+fn sum(n: u64) -> u64 {
+    n * (n + 1) / 2
+}
@@ -0,0 +1,28 @@
+// rustfmt-format_code_in_doc_comments: true
+
+/// Code blocks tagged with another language are left as they are, even if they look like Rust.
+///
+/// ```text
+/// fn   main( ) {}
+/// ```
+///
+/// ```sh
+/// fn   main( ) {}
+/// ```
+///
+/// ```console
+/// fn   main( ) {}
+/// ```
+///
+/// ```ignore
+/// fn   main( ) {}
+/// ```
+///
+/// ```compile_fail,E0308
+/// fn   main( ) {}
+/// ```
+///
+/// ```rust
+/// fn   main( ) {}
+/// ```
+fn foo() {}
@@ -0,0 +1,23 @@
+// rustfmt-format_code_in_doc_comments: true
+
+/// A tilde fence stays a tilde fence.
+///
+/// ~~~rust
+/// fn main( ) { let x=1 ; }
+/// ~~~
+///
+/// A backtick fence stays a backtick fence.
+///
+/// ```rust
+/// fn main( ) { let x=1 ; }
+/// ```
+///
+/// Backticks don't close a tilde fence.
+///
+/// ~~~~
+/// let s = r"
+/// ```
+/// ";
+/// let   y=2;
+/// ~~~~
+fn foo() {}
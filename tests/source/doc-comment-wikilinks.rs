@@ -0,0 +1,10 @@
+// rustfmt-wrap_comments: true
+// rustfmt-comment_width: 60
+
+/// This paragraph links to [[Some Target Page|the documentation about the thing]] in the middle of it.
+///
+/// - A list item linking to [[Another Target|a page with a long display text]] that wraps.
+/// - A plain wikilink [[Yet Another Target Page With A Long Name]] in a list item.
+///
+/// Single brackets as in the [regular text with|a pipe in it] are wrapped as usual.
+fn foo() {}
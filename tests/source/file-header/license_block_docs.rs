@@ -0,0 +1,12 @@
+// rustfmt-version: Two
+/* Copyright 2024 The Project Developers.
+ * Licensed under the MIT license. */
+#![no_std]
+//! Crate-level documentation.
+
+//! A second paragraph.
+
+
+
+use core::fmt;
+use alloc::vec;
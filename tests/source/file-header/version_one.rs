@@ -0,0 +1,20 @@
+#!/usr/bin/env rust
+// rustfmt-version: One
+// Copyright 2024 The Project Developers.
+// Licensed under the MIT license.
+
+
+
+#![no_std]
+
+
+#![deny(missing_docs)]
+#![allow(dead_code)]
+// A comment after the inner attributes.
+
+//! Crate-level documentation.
+//! More documentation.
+use core::fmt;
+use alloc::vec;
+
+fn main() {}
@@ -0,0 +1,18 @@
+// rustfmt-reorder_imports: true
+// rustfmt-version: One
+// `cfg` predicates do not split runs of imports with version One.
+
+#[cfg(feature = "b")]
+use zed;
+#[cfg(feature = "b")]
+use bar;
+#[cfg(feature = "a")]
+use foo;
+#[cfg(feature="a")]
+use alpha;
+use std::io;
+use core::fmt;
+#[cfg(all(unix, feature = "a"))]
+use libc::c_int;
+#[cfg(all(unix, feature = "a"))]
+use libc::c_char;
@@ -0,0 +1,18 @@
+// rustfmt-reorder_imports: true
+// rustfmt-version: Two
+// Imports are only sorted within runs that share the same `cfg` predicates.
+
+#[cfg(feature = "b")]
+use zed;
+#[cfg(feature = "b")]
+use bar;
+#[cfg(feature = "a")]
+use foo;
+#[cfg(feature="a")]
+use alpha;
+use std::io;
+use core::fmt;
+#[cfg(all(unix, feature = "a"))]
+use libc::c_int;
+#[cfg(all(unix, feature = "a"))]
+use libc::c_char;
@@ -0,0 +1,5 @@
+// rustfmt-imports_granularity: Item
+
+use std::prelude::v1::*;
+use std::{io::*, fmt::{self, *}};
+use a::{b::{*}, c};
@@ -0,0 +1,38 @@
+// rustfmt-imports_granularity: Module
+
+use a::b;
+use x::{y, z::w};
+use a::c;
+
+#[cfg(feature = "x")]
+mod imp {
+    use a::b;
+    use x::{y, z::w};
+    use a::c;
+
+    #[cfg(feature = "y")]
+    mod inner {
+        use a::b;
+        use x::{y, z::w};
+        use a::c;
+
+        mod innermost {
+            use a::b;
+            use x::{y, z::w};
+            use a::c;
+            use some_crate::module_one::{first_item, second_item};
+            use some_crate::module_two::{third_item, fourth_item, fifth_item, sixth_item};
+        }
+    }
+}
+
+fn main() {
+    use a::b;
+    use x::{y, z::w};
+    use a::c;
+
+    {
+        use x::{y, z::w};
+        use a::b;
+    }
+}
@@ -0,0 +1,15 @@
+// rustfmt-wrap_comments: true
+// rustfmt-comment_width: 60
+
+/// A nested list may use a different bullet at each level:
+///
+/// - outer item that is quite long and will need to wrap around when the comment width is small
+///   * inner item that is also quite long and will need to wrap around when the comment width is small
+///     + innermost item that is long too and will need to wrap around when the width is small
+///   * another inner item
+/// - second outer item that is quite long and will need to wrap around when the comment width is small
+///
+/// 1. ordered outer item that is quite long and will need to wrap around when the comment is small
+///    - inner item that is also quite long and will need to wrap around when the comment width is small
+/// 2. second ordered item
+fn nested_bullets() {}
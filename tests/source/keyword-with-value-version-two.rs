@@ -0,0 +1,20 @@
+// rustfmt-version: Two
+// The semicolon of `return` and `break` statements is only counted once.
+
+fn fits() {
+    loop {
+        return some_very_long_function_name_here(argument_number_one, argument_number_two, three_4);
+        break some_very_long_function_name_here(argument_number_one, argument_number_two, three_44);
+        break 'outer some_very_long_function_name_here(argument_number_one, argument_two, three_44);
+        yield some_very_long_function_name_here(argument_number_one, argument_number_two, three_44);
+    }
+}
+
+fn too_long() {
+    loop {
+        return some_very_long_function_name_here(argument_number_one, argument_number_two, three_44);
+        break some_very_long_function_name_here(argument_number_one, argument_number_two, three_444);
+        break 'outer some_very_long_function_name_here(argument_number_one, argument_two, three_444);
+        yield some_very_long_function_name_here(argument_number_one, argument_number_two, three_444);
+    }
+}
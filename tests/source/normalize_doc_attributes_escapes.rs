@@ -0,0 +1,8 @@
+// rustfmt-normalize_doc_attributes: true
+
+#[doc = "Line with a \\n in it"]
+#[doc = "A tab\tand a \"quote\" and \u{e9}"]
+#[doc = r"Raw \n stays as written"]
+#[doc = "Two\nlines"]
+#[doc = "Bare \r carriage return"]
+fn foo() {}
@@ -0,0 +1,46 @@
+#[rustfmt::skip]
+mod generated {
+    pub fn   a() -> u32 {   1 }
+
+    // A comment kept as is.
+    #[rustfmt::format]
+    pub fn b( x : u32 ) -> u32 { x+1 }
+
+    impl   Foo {
+        fn   c() {}
+        #[rustfmt::format]
+        fn d( ) { let x=1; }
+    }
+
+    // Override of an override.
+    #[rustfmt::format]
+    mod formatted {
+        fn   e() {}
+
+        #[rustfmt::skip]
+        mod skipped_again {
+            fn   f() {}
+            #[rustfmt::format]
+            fn g( ) {  }
+        }
+    }
+
+    // Skip wins over format on the same item.
+    #[rustfmt::skip]
+    #[rustfmt::format]
+    fn   h() {}
+}
+
+mod inner_skip {
+    #![rustfmt::skip]
+    fn   i() {}
+    #[rustfmt::format]
+    fn j( ) -> Vec< u8 > { vec![ 1,2,3 ] }
+}
+
+#[rustfmt::skip]
+impl   Bar {
+    const  X: u32 = 1;
+    #[rustfmt::format]
+    const Y : u32=2;
+}
@@ -0,0 +1,9 @@
+// rustfmt-wrap_comments: true
+// rustfmt-max_width: 50
+
+/// Placerat felis sed ac, call `Foo::bar(baz, qux)` to do the thing.
+///
+/// Placerat felis sed ac, call ``foo `bar` baz`` to do the thing.
+fn main() {
+    // Vestibulum ac lacus call `Foo::bar(baz, qux)` to do the thing.
+}
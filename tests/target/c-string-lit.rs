@@ -0,0 +1,16 @@
+// rustfmt-format_strings: true
+// rustfmt-edition: 2021
+
+fn main() {
+    let short = c"hello";
+    let wide = c"Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor \
+                incididunt ut labore et dolore magna aliqua.";
+    let escaped = c"Ut enim ad minim veniam, quis nostrud exercitation\n ullamco laboris nisi ut \
+                   aliquip ex ea commodo consequat.";
+    let continued = c"Duis aute irure dolor in reprehenderit in voluptate velit esse cillum \
+                     dolore eu fugiat nulla pariatur.";
+    ffi_call(
+        c"Excepteur sint occaecat cupidatat non proident, sunt in culpa qui officia deserunt \
+         mollit anim.",
+    );
+}
@@ -0,0 +1,55 @@
+// Chains whose receiver is a macro invocation
+
+fn json_rooted() {
+    let s = json!({ "name": "value", "another_key": "another value", "third": [1, 2, 3] })
+        .to_string()
+        .len();
+    let x = json!({
+        "a": 1,
+        "b": 2,
+    })
+    .to_string()
+    .into_bytes()
+    .into_iter()
+    .map(|b| b as u32)
+    .collect::<Vec<_>>();
+}
+
+fn try_after_macro() -> Result<(), Error> {
+    let v = parse_macro!(
+        input,
+        some_other_long_argument,
+        yet_another_argument_here,
+        more
+    )?
+    .validate()?;
+    let y = parse_macro!(input, other)?.validate()?;
+    let z = parse_macro!(
+        aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa,
+        bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb
+    )??
+    .validate()?
+    .finish();
+    Ok(())
+}
+
+fn in_function_argument() -> Result<(), Error> {
+    foo(
+        json!({ "name": "value", "another_key": "another value", "third": [1, 2, 3] })
+            .to_string()
+            .len(),
+    );
+    foo(json!({
+        "a": 1,
+    })
+    .to_string()?
+    .len());
+    foo(parse_macro!(
+        input,
+        some_other_long_argument,
+        yet_another_argument_here,
+        more
+    )?
+    .validate()?);
+    Ok(())
+}
@@ -0,0 +1,34 @@
+// rustfmt-array_layout: AutoCompact
+fn main() {
+    let short = [1, 2, 3];
+    let numbers = [
+        100, 200, 300, 400, 500, 600, 700, 800, 900, 1000, 1100, 1200, 1300, 1400,
+    ];
+    let names = [
+        "alpha", "beta", "gamma", "delta", "epsilon", "zeta", "eta", "theta", "iota",
+    ];
+    let calls = [
+        foo(a, b),
+        bar(c, d),
+        baz(e, f),
+        qux(g, h),
+        quux(i, j),
+        corge(k, l),
+        grault(m),
+    ];
+    let with_comment = [
+        alpha_value,
+        beta_value,
+        gamma_value, // gamma
+        delta_value,
+        epsilon_value,
+        zeta_value,
+    ];
+    let v = vec![
+        first_element,
+        second_element,
+        third_element,
+        fourth_element,
+        fifth,
+    ];
+}
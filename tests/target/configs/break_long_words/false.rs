@@ -0,0 +1,10 @@
+// rustfmt-break_long_words: false
+// rustfmt-wrap_comments: true
+// rustfmt-format_strings: true
+// rustfmt-max_width: 80
+
+fn main() {
+    // The key is
+    // QUJDREVGR0hJSktMTU5PUFFSU1RVVldYWVphYmNkZWZnaGlqa2xtbm9wcXJzdHV2d3h5ejAxMjM0NTY3ODkrLwAAAAAAAAAAAAAA
+    let key = "The key is QUJDREVGR0hJSktMTU5PUFFSU1RVVldYWVphYmNkZWZnaGlqa2xtbm9wcXJzdHV2d3h5ejAxMjM0NTY3ODkrLwAAAAAAAAAAAAAA";
+}
@@ -0,0 +1,16 @@
+// rustfmt-coerce_strings_to_byte_strings: false
+
+const MAGIC: &[u8] = "\x7fELF".as_bytes();
+static RAW: &'static [u8] = r#"a "quoted" string"#.as_bytes();
+const UNICODE: &[u8] = "héllo".as_bytes();
+const ESCAPE: &[u8] = "\u{41}".as_bytes();
+
+fn main() {
+    let header: Vec<u8> = "GET / HTTP/1.1".as_bytes().to_vec();
+    let header: std::vec::Vec<u8> = "GET".as_bytes().to_vec();
+    let name = "ferris".as_bytes();
+    let slice: &[u8] = "ferris".as_bytes();
+    let mutable: &mut [u8] = "ferris".as_bytes();
+    let chars: &[char] = "ferris".as_bytes();
+    let vec: Vec<u8> = "ferris".as_bytes().to_owned();
+}
@@ -0,0 +1,21 @@
+// rustfmt-comment_collapse_blank_lines: true
+// rustfmt-wrap_comments: true
+
+// Lorem ipsum.
+//
+// Dolor sit amet.
+fn lorem() {
+    // Consectetur.
+    //
+    // Adipiscing elit.
+}
+
+/// Doc.
+///
+/// ```
+/// let x = 1;
+///
+///
+/// let y = 2;
+/// ```
+fn ipsum() {}
@@ -0,0 +1,42 @@
+// rustfmt-comment_only_lines_in_fn: Strip
+
+// Comments outside of function bodies are kept.
+fn main() {
+    let x = 1;
+
+    println!("{}", x); // trailing comments are kept
+    let w = 4; // trailing
+    if x > 0 {
+        foo();
+    }
+    let f = || bar();
+    // rustfmt::skip
+    let   skipped   = 1;
+    /* block comments are kept */
+}
+
+fn only_comments() {}
+
+fn lists() {
+    match x {
+        // arm comments are kept
+        1 => a(),
+        _ => b(),
+    }
+    call(
+        // argument comments are kept
+        a,
+    );
+}
+
+struct S {
+    // field comments are kept
+    a: u32,
+}
+
+impl S {
+    /// Doc comments are kept.
+    fn method(&self) {
+        self.go(); // kept
+    }
+}
@@ -0,0 +1,15 @@
+// rustfmt-comment_reflow_blockquotes: false
+// rustfmt-wrap_comments: true
+// rustfmt-comment_width: 44
+
+/// > A quoted paragraph that goes on
+/// > over several lines, which are joined
+/// > before they are wrapped to fit.
+///
+/// > > A paragraph quoted twice that goes
+/// > > on
+/// > > over several lines as well.
+/// > > - A list item
+/// >
+/// > Back to the first level.
+fn block_quote() {}
@@ -0,0 +1,33 @@
+// rustfmt-const_generics_style: Explicit
+
+struct Buffer<const N: usize = 16> {
+    next: Option<Box<Buffer<16>>>,
+}
+
+struct Pair<'a, T, const N: usize = 4, const M: usize = 8>(&'a [T; N], [T; M]);
+
+trait Store<const CAP: usize = 32> {}
+
+type Alias = Buffer<16>;
+
+impl Buffer<16> {
+    fn new() -> Buffer<16> {
+        Buffer::<16> { next: None }
+    }
+}
+
+impl Store<32> for Buffer<32> {}
+
+fn pairs<'a>(
+    a: Pair<'a, u8, 4, 8>,
+    b: Pair<'a, u8, 4, 8>,
+    c: Pair<'a, u8, 4, 8>,
+    d: Pair<'a, u8, 2, 8>,
+) {
+}
+
+fn other(x: std::collections::Buffer, y: <Buffer<16> as Store>::Output) {}
+
+mod inner {
+    struct Local(Buffer);
+}
@@ -0,0 +1,18 @@
+// rustfmt-doc_comment_emphasis_marker: Underscore
+// Emphasis delimiters in doc comments
+
+/// Some _italic_ and _italic_ text, with __bold__ and __bold__ text.
+/// A snake_case_name, a glob like *.rs, 2 * 3 * 4 and an intra*word*span.
+/// Code spans like `*a*` and `_b_` are left alone, as are <https://a.b/_c_> and
+/// [links](https://a.b/_c_/*d*).
+///
+/// * A list item with _emphasis_.
+/// - Another one with __strong__ emphasis and an escaped \*star\*.
+///
+/// ```
+/// let _a_ = *b * c*;
+/// ```
+fn foo() {}
+
+// A regular comment with _emphasis_ is left alone.
+fn bar() {}
@@ -0,0 +1,18 @@
+// rustfmt-doc_comment_hard_break: Backslash
+// rustfmt-wrap_comments: true
+// rustfmt-comment_width: 50
+
+/// A paragraph with a hard break at a line that
+/// has to be wrapped\
+/// before it continues here.
+/// Backslash break\
+/// continues too.
+/// Escaped backslash \\
+/// is not a break.
+/// Trailing spaces at the end of a paragraph  
+///
+/// ```
+/// let x = 1;  
+/// let y = 2;\
+/// ```
+fn foo() {}
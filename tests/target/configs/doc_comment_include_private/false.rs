@@ -0,0 +1,83 @@
+// rustfmt-doc_comment_include_private: false
+// rustfmt-format_code_in_doc_comments: true
+
+/// ```
+/// let x = 1;
+/// ```
+pub fn public() {}
+
+/// ```
+/// let x=1;
+/// ```
+fn private() {}
+
+/// ```
+/// let x=1;
+/// ```
+pub(crate) struct Crate;
+
+/// ```
+/// let x = 1;
+/// ```
+impl Crate {
+    /// ```
+    /// let x=1;
+    /// ```
+    fn private_method() {}
+
+    /// ```
+    /// let x = 1;
+    /// ```
+    pub fn public_method() {}
+}
+
+/// ```
+/// let x = 1;
+/// ```
+impl Default for Crate {
+    /// ```
+    /// let x = 1;
+    /// ```
+    fn default() -> Self {
+        Crate
+    }
+}
+
+/// ```
+/// let x=1;
+/// ```
+trait Private {
+    /// ```
+    /// let x=1;
+    /// ```
+    fn f();
+}
+
+/// ```
+/// let x = 1;
+/// ```
+pub enum E {
+    /// ```
+    /// let x = 1;
+    /// ```
+    A,
+}
+
+/// ```
+/// let x = 1;
+/// ```
+pub mod m {
+    //! ```
+    //! let x = 1;
+    //! ```
+
+    /// ```
+    /// let x=1;
+    /// ```
+    fn inner() {}
+
+    /// ```
+    /// let x = 1;
+    /// ```
+    pub fn inner_pub() {}
+}
@@ -0,0 +1,29 @@
+// rustfmt-doc_comment_preserve_prose: false
+// rustfmt-format_code_in_doc_comments: true
+// rustfmt-wrap_comments: true
+// rustfmt-comment_width: 50
+
+//! Crate docs   with _odd_  spacing that run on
+//! past the comment width.
+//!
+//! ```rust
+//! fn main() {
+//!     let x = 1;
+//! }
+//! ```
+
+/// Some   text with _emphasis_ and a [link]
+/// that goes on past the comment width.
+///
+/// ```
+/// # use   std::io ;
+/// let v = vec![1, 2, 3];
+/// ```
+///
+/// ```text
+/// keep    this   as    is
+/// ```
+///
+/// [link]: https://example.com
+/// [unused]: https://example.org
+fn foo() {}
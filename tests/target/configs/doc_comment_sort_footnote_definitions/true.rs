@@ -0,0 +1,21 @@
+// rustfmt-doc_comment_sort_footnote_definitions: true
+
+/// [^first]: The first note.
+/// [^second]: The second note.
+/// [^Third]: The third note, which goes on
+///     over two lines.
+/// [^unused]: Nothing refers to this note.
+///
+/// Lorem[^first] ipsum[^second] dolor[^third], sit[^first] amet.
+///
+/// ```text
+/// [^z]: Not a definition.
+/// [^a]: Not a definition either.
+/// ```
+fn lorem() {}
+
+/// Consectetur[^b] adipiscing[^a].
+///
+/// [^b]: The first note.
+/// [^a]: The second note.
+pub struct Ipsum;
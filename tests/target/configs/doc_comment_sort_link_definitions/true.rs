@@ -0,0 +1,27 @@
+// rustfmt-doc_comment_sort_link_definitions: true
+/// Links to [Zebra], [apple] and [Mango].
+///
+/// [Apple]: https://example.com/apple
+/// [mango]: https://example.com/mango
+///     "The mango page"
+/// [Zebra]: https://example.com/zebra
+fn fruits() {}
+
+/// [first]: https://example.com/first
+/// [second]: https://example.com/second
+///
+/// ```
+/// let [b]: [u8; 1] = [0];
+/// let [a]: [u8; 1] = [0];
+/// ```
+///
+/// [c]: https://example.com/c
+/// [d]: https://example.com/d
+fn runs() {}
+
+mod m {
+    //! [Beta] and [alpha].
+    //!
+    //! [alpha]: crate::Alpha
+    //! [beta]: crate::Beta
+}
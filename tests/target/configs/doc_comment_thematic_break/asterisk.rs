@@ -0,0 +1,21 @@
+// rustfmt-doc_comment_thematic_break: Asterisk
+
+/// Summary.
+///
+/// ***
+///
+/// ***
+/// Paragraph
+/// ---
+/// Another paragraph
+/// ***
+///
+/// ***
+///
+/// ```
+/// ***
+/// ```
+fn foo() {}
+
+// ***
+fn bar() {}
@@ -0,0 +1,21 @@
+// rustfmt-doc_comment_thematic_break: Dash
+
+/// Summary.
+///
+/// ---
+///
+/// ---
+/// Paragraph
+/// ---
+/// Another paragraph
+/// * * *
+///
+/// ---
+///
+/// ```
+/// ***
+/// ```
+fn foo() {}
+
+// ***
+fn bar() {}
@@ -0,0 +1,21 @@
+// rustfmt-doc_comment_thematic_break: Underscore
+
+/// Summary.
+///
+/// ___
+///
+/// ___
+/// Paragraph
+/// ---
+/// Another paragraph
+/// ___
+///
+/// ___
+///
+/// ```
+/// ***
+/// ```
+fn foo() {}
+
+// ***
+fn bar() {}
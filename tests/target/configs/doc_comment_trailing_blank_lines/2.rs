@@ -0,0 +1,26 @@
+// rustfmt-doc_comment_trailing_blank_lines: 2
+// rustfmt-max_empty_lines_in_doc_comments: 2
+// rustfmt-wrap_comments: true
+
+///
+///
+/// Leading blank lines.
+///
+///
+fn foo() {}
+
+mod m {
+    //!
+    //! Inner.
+    //!
+    //!
+
+    /// No blank lines.
+    ///
+    ///
+    fn bar() {}
+
+    ///
+    ///
+    fn blank() {}
+}
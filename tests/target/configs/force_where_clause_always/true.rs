@@ -0,0 +1,64 @@
+// rustfmt-force_where_clause_always: true
+// Move type parameter bounds to the where clause
+
+fn lorem<T, U>(ipsum: T, dolor: U)
+where
+    T: Clone,
+    U: Default,
+{
+    // body
+}
+
+fn single<T>(x: T)
+where
+    T: Clone + Send,
+{
+}
+
+fn lifetimes<'a, 'b: 'a, T, const N: usize>(x: &'a T)
+where
+    T: 'a + Clone,
+{
+}
+
+struct Tuple<T, U>(T, U)
+where
+    T: Clone;
+
+struct Struct<T>
+where
+    T: Clone,
+{
+    x: T,
+}
+
+enum Enum<T>
+where
+    T: Clone + Default,
+{
+    A(T),
+}
+
+impl<T> Struct<T>
+where
+    T: Clone,
+{
+    fn new<U>(x: U) -> Self
+    where
+        U: Into<T>,
+    {
+        Struct { x: x.into() }
+    }
+}
+
+trait Trait<T>
+where
+    T: Clone,
+{
+    fn f<U>(&self, u: U)
+    where
+        U: Default;
+}
+
+// Generics with comments are left alone.
+fn commented<T: Clone /* comment */, U>(x: T, y: U) {}
@@ -0,0 +1,52 @@
+// rustfmt-force_where_clause_never: true
+// Move the bounds of single-predicate where clauses inline
+
+fn lorem<T: Clone>(ipsum: T) {
+    // body
+}
+
+// Params that already have bounds are left alone.
+fn merged<T: Clone>(x: T)
+where
+    T: Send,
+{
+}
+
+fn multiple<T, U>(x: T, y: U)
+where
+    T: Clone,
+    U: Default,
+{
+}
+
+fn not_a_param<T>(x: T)
+where
+    Vec<T>: Clone,
+{
+}
+
+fn higher_ranked<F>(f: F)
+where
+    for<'a> F: Fn(&'a u8),
+{
+}
+
+struct Struct<T: Clone> {
+    x: T,
+}
+
+impl<T: Clone> Struct<T> {
+    fn new<U: Into<T>>(x: U) -> Self {
+        Struct { x: x.into() }
+    }
+}
+
+trait Trait<T: Clone> {}
+
+// Generics with comments are left alone.
+fn commented<T>(x: T)
+where
+    // comment
+    T: Clone,
+{
+}
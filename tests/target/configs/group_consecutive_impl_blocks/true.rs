@@ -0,0 +1,64 @@
+// rustfmt-group_consecutive_impl_blocks: true
+// Merge adjacent impl blocks with the same header
+
+struct Foo;
+
+impl Display for Foo {
+    #[cfg(unix)]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "unix")
+    }
+
+    #[cfg(windows)]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "windows")
+    }
+}
+
+impl<T> Bar<T>
+where
+    T: Clone,
+{
+    fn a(&self) {}
+
+    const B: usize = 1;
+
+    fn b(&self) {}
+
+    fn c(&self) {}
+}
+
+// Different attributes are kept apart.
+#[cfg(unix)]
+impl Baz {
+    fn a() {}
+}
+#[cfg(windows)]
+impl Baz {
+    fn a() {}
+}
+
+// Comments between blocks are kept apart.
+impl Qux {
+    fn a() {}
+}
+// qux
+impl Qux {
+    fn b() {}
+}
+
+// Different self types are kept apart.
+impl Qux {
+    fn c() {}
+}
+impl Quux {
+    fn c() {}
+}
+
+mod inner {
+    impl super::Foo {
+        fn a() {}
+
+        fn b() {}
+    }
+}
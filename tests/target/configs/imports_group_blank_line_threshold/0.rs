@@ -0,0 +1,12 @@
+// rustfmt-group_imports: StdExternalCrate
+// rustfmt-imports_group_blank_line_threshold: 0
+use alloc::alloc::Layout;
+use core::f32;
+use std::sync::Arc;
+use broker::database::PooledConnection;
+use chrono::Utc;
+use juniper::{FieldError, FieldResult};
+use uuid::Uuid;
+use super::schema::{Context, Payload};
+use super::update::convert_publish_payload;
+use crate::models::Event;
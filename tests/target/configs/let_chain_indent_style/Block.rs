@@ -0,0 +1,15 @@
+// rustfmt-let_chain_indent_style: Block
+
+fn main() {
+    if let Some(xxxxxxxxxxxxxxx) = foooooooooooooooooooo()
+        && let Some(yyyyyyyyyyyyyyy) = baaaaaaaaaaaaaaar(xxxxxxxxxxxxxxx)
+        && zzzzzzzzzz
+    {
+        body();
+    }
+    while let Some(xxxxxxxxxxxxxxx) = foooooooooooooooooooo()
+        && let Some(yyyyyyyyyyyyyyy) = baaaaaaaaaaaaaaar(xxxxxxxxxxxxxxx)
+    {
+        body();
+    }
+}
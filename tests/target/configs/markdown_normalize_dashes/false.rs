@@ -0,0 +1,11 @@
+// rustfmt-markdown_normalize_dashes: false
+// Normalize dashes
+
+/// Pages 10–20 of the manual — the part about `a–b` and ``x — y``.
+/// – A dash at the start of a line, and one at its end —
+///
+/// ```text
+/// Code – is left alone.
+/// ```
+// A plain comment – is left alone.
+fn foo() {}
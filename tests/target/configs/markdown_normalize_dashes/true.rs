@@ -0,0 +1,14 @@
+// rustfmt-markdown_normalize_dashes: true
+// Normalize dashes
+
+/// Pages 10 -- 20 of the manual --- the part about `a–b` and ``x — y``.
+/// -- A dash at the start of a line, and one at its end ---
+///
+/// A line holding only a dash is kept, so it does not become a heading.
+/// —
+///
+/// ```text
+/// Code – is left alone.
+/// ```
+// A plain comment – is left alone.
+fn foo() {}
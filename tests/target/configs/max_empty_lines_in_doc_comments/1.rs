@@ -0,0 +1,25 @@
+// rustfmt-max_empty_lines_in_doc_comments: 1
+// rustfmt-wrap_comments: true
+
+/// Lorem ipsum.
+///
+/// Dolor sit amet.
+///
+/// ```
+/// let x = 1;
+///
+///
+/// let y = 2;
+/// ```
+fn lorem() {
+    // Regular comments keep their blank lines.
+    //
+    //
+    // Consectetur.
+}
+
+mod ipsum {
+    //! Inner doc.
+    //!
+    //! Adipiscing elit.
+}
@@ -0,0 +1,11 @@
+// rustfmt-normalize_line_endings_in_strings: true
+// Normalize line ending escapes in string literals
+
+fn main() {
+    let crlf = "first line\nsecond line\n";
+    let cr = "first line\nsecond line";
+    let escaped_backslash = "C:\\root\\new";
+    let raw = r"first line\r\nsecond line";
+    let bytes = b"first line\r\n";
+    println!("{}\n", crlf);
+}
@@ -0,0 +1,21 @@
+// rustfmt-prefer_block_comments_for_long_code: false
+// rustfmt-wrap_comments: true
+// rustfmt-comment_width: 40
+
+fn main() {
+    // Lorem ipsum dolor sit amet,
+    // consectetur adipiscing elit, sed do
+    // eiusmod tempor incididunt ut labore
+    // et dolore magna aliqua. Ut enim ad
+    // minim veniam.
+    foo();
+
+    // Lorem ipsum dolor sit amet,
+    // consectetur adipiscing elit.
+    bar();
+
+    // A comment that mentions */ is left as
+    // line comments because the text would
+    // end the block comment early.
+    baz();
+}
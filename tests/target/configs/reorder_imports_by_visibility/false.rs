@@ -0,0 +1,9 @@
+// rustfmt-reorder_imports_by_visibility: false
+// rustfmt-imports_granularity: Crate
+
+pub(super) use a::U;
+use a::X;
+pub use a::Y;
+pub(crate) use a::Z;
+pub use b::V;
+use b::W;
@@ -0,0 +1,9 @@
+// rustfmt-reorder_imports_by_visibility: true
+// rustfmt-imports_granularity: Crate
+
+use a::X;
+use b::W;
+pub(super) use a::U;
+pub(crate) use a::Z;
+pub use a::Y;
+pub use b::V;
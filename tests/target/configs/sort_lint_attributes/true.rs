@@ -0,0 +1,17 @@
+// rustfmt-sort_lint_attributes: true
+// Lint lists are sorted with bare lints first and deduplicated, one attribute at a time.
+
+#[allow(dead_code, unused_variables)]
+#[allow(dead_code)]
+#[deny(missing_docs, clippy::all, rustdoc::broken_intra_doc_links)]
+#[expect(dead_code, unused_mut, reason = "the fields are filled in by a macro")]
+#[warn(unused, reason = "a reason in the middle is left alone", dead_code)]
+#[allow(
+    dead_code,
+    non_snake_case,
+    unused_imports,
+    unused_variables,
+    clippy::too_many_arguments
+)]
+#[cfg_attr(test, allow(dead_code, unused))]
+fn f() {}
@@ -0,0 +1,9 @@
+// rustfmt-struct_lit_single_line: true
+// rustfmt-struct_lit_width: 24
+// Inline comments are measured by their display width
+
+fn main() {
+    let a = Foo { x: 1 /* eeeeeeee */ };
+    let b = Foo { x: 1 /* éééééééé */ };
+    let c = Foo { x: 1 /* 默认默认 */ };
+}
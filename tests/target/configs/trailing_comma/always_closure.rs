@@ -0,0 +1,18 @@
+// rustfmt-trailing_comma: Always
+// Closures as the last argument keep their trailing comma
+
+fn main() {
+    foo(|x| x + 1,);
+    foo(|x| x + 1,);
+    foo(a, |x| {
+        let y = x + 1;
+        y * 2
+    },);
+    a.foo(move || x + 1,);
+    foo(|x| -> u32 { x + 1 },);
+    foo(
+        aaaaaaaaaaaaaaaaaaaaaaaaaaaaaa,
+        bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb,
+        |x| xxxxxxxxxxx + 1,
+    );
+}
@@ -0,0 +1,18 @@
+// rustfmt-tuple_tall_threshold: 4
+// rustfmt-tuple_layout: Tall
+fn main() {
+    let quad = (1, 2, 3, 4);
+    let (
+        a,
+        b,
+        c,
+        d,
+        e,
+    ) = (
+        1,
+        2,
+        3,
+        4,
+        5,
+    );
+}
@@ -0,0 +1,32 @@
+// rustfmt-type_alias_style: EqBound
+
+type Foo<T> = Vec<T>
+where
+    T: Copy;
+
+type VeryLongTypeAliasName<T, U> = std::collections::HashMap<T, U>
+where
+    T: Iterator<Item = U> + Clone + Send + Sync,
+    U: Default;
+
+type Bar<T> = Vec<T>
+where
+    T: Copy;
+
+trait Trait {
+    type Assoc<T>
+    where
+        T: Clone;
+}
+
+impl Trait for () {
+    type Assoc<T> = Vec<T>
+    where
+        T: Clone;
+}
+
+// Comments keep the where clause where it is.
+type Commented<T>
+where
+    T: Copy, /* copy */
+= Vec<T>;
@@ -0,0 +1,36 @@
+// rustfmt-type_alias_style: WhereBound
+
+type Foo<T>
+where
+    T: Copy,
+= Vec<T>;
+
+type VeryLongTypeAliasName<T, U>
+where
+    T: Iterator<Item = U> + Clone + Send + Sync,
+    U: Default,
+= std::collections::HashMap<T, U>;
+
+type Bar<T>
+where
+    T: Copy,
+= Vec<T>;
+
+trait Trait {
+    type Assoc<T>
+    where
+        T: Clone;
+}
+
+impl Trait for () {
+    type Assoc<T>
+    where
+        T: Clone,
+    = Vec<T>;
+}
+
+// Comments keep the where clause where it is.
+type Commented<T>
+where
+    T: Copy, /* copy */
+= Vec<T>;
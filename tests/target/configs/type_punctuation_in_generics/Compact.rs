@@ -0,0 +1,9 @@
+// rustfmt-type_punctuation_in_generics: Compact
+
+fn lorem<Ipsum: Dolor+Sit, Amet: Consectetur+Adipiscing, T: 'a+Clone>()
+where
+    Ipsum: Eq + Clone,
+{
+}
+struct Foo<T: Iterator<Item = u8>+Clone>(T);
+fn foo(x: impl Iterator<Item = u8> + Clone) -> Box<dyn Fn() + Send> {}
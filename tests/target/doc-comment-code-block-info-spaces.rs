@@ -0,0 +1,20 @@
+// rustfmt-format_code_in_doc_comments: true
+
+/// The language tag of a code block may have spaces around it.
+///
+/// ``` rust
+/// fn main() {}
+/// ```
+///
+/// ```rust
+/// fn main() {}
+/// ```
+///
+/// ```rust no_run
+/// fn main() {}
+/// ```
+///
+/// ```rust ignore
+/// fn   main( ) {}
+/// ```
+fn foo() {}
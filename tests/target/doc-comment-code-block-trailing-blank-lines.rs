@@ -0,0 +1,14 @@
+// rustfmt-wrap_comments: true
+
+/// ```
+/// let x = 1;
+///
+///
+/// ```
+fn foo() {}
+
+/// > ```text
+/// > quoted
+/// >
+/// > ```
+fn bar() {}
@@ -0,0 +1,25 @@
+// rustfmt-format_code_in_doc_comments: true
+
+/// Hidden lines, escaped `#` lines and attributes in the same code block.
+///
+/// ```
+/// # use std::fmt;
+/// #[derive(Debug)]
+/// struct Foo {
+///     x: u8,
+/// }
+/// let s = "
+/// ## not hidden
+/// #";
+/// ```
+fn foo() {}
+
+/// A block that can't be formatted is left as it is.
+///
+/// ```
+/// # use std::fmt;
+/// ## not hidden
+/// #[derive(Debug)]
+/// struct Foo{x:u8}
+/// ```
+fn bar() {}
@@ -0,0 +1,13 @@
+// rustfmt-format_code_in_doc_comments: true
+// Hidden lines of a code block
+
+/// ```
+/// # use std::fmt;
+/// let x = 1;
+/// if x > 0 {
+///     # let hidden = 2;
+///     println!("{}", x);
+///     # let also_hidden = 3;
+/// }
+/// ```
+fn foo() {}
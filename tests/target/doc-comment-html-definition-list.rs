@@ -0,0 +1,15 @@
+// rustfmt-wrap_comments: true
+// rustfmt-comment_width: 60
+
+/// Lorem ipsum dolor sit amet, consectetur adipiscing elit,
+/// sed do eiusmod tempor.
+/// <dl>
+///   <dt>Lorem</dt>
+///   <dd>Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod</dd>
+///   <dt>Ipsum</dt>
+///   <dd>
+///     Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi.
+///   </dd>
+/// </dl>
+/// After.
+fn f() {}
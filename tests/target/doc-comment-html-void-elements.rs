@@ -0,0 +1,14 @@
+// rustfmt-wrap_comments: true
+// rustfmt-format_code_in_doc_comments: true
+
+/// Lines can be broken with <br> and separated by rules:
+///
+/// <hr>
+///
+/// <img src="https://example.com/logo.png" alt="logo" width="64"><br/>
+/// <p>Paragraphs are closed explicitly.</p>
+///
+/// ```
+/// let html = "<br>";
+/// ```
+pub fn render() {}
@@ -0,0 +1,15 @@
+// rustfmt-format_code_in_doc_comments: true
+// rustfmt-hard_tabs: true
+
+/// Indented code blocks are indented with a tab:
+///
+/// 	fn main() {
+/// 		let x = 1;
+///
+/// 		println!("{}", x);
+/// 	}
+///
+/// Lines that are already indented with a tab are part of the block too:
+///
+/// 	let y = vec![1, 2, 3];
+fn foo() {}
@@ -0,0 +1,24 @@
+// rustfmt-format_code_in_doc_comments: true
+
+/// ```
+/// #![allow(dead_code)]
+/// fn foo() {
+///     let x = 1;
+/// }
+/// ```
+///
+/// ```
+/// # #![allow(unused)]
+/// //! Crate docs.
+/// #![deny(missing_docs)]
+///
+/// fn main() {}
+/// ```
+///
+/// ```
+/// #!/usr/bin/env run-cargo-script
+/// fn main() {
+///     println!("hello")
+/// }
+/// ```
+pub fn f() {}
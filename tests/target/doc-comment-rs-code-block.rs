@@ -0,0 +1,20 @@
+// rustfmt-format_code_in_doc_comments: true
+
+/// Code blocks tagged `rs` are Rust code.
+///
+/// ```rs
+/// fn main() {}
+/// ```
+///
+/// ```RS
+/// fn main() {}
+/// ```
+///
+/// ```rs,edition2021
+/// fn main() {}
+/// ```
+///
+/// ```rsx
+/// fn   main( ) {}
+/// ```
+fn foo() {}
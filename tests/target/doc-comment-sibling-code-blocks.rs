@@ -0,0 +1,46 @@
+// rustfmt-format_code_in_doc_comments: true
+// rustfmt-max_empty_lines_in_doc_comments: 2
+
+/// A block that fails to parse keeps its original text, the others are still formatted.
+///
+/// ```
+/// fn broken( {
+/// ```
+///
+/// ```
+/// let x = 1;
+/// ```
+fn one_broken() {}
+
+/// Blank lines between sibling code blocks are collapsed into one.
+///
+/// ```
+/// let x = 1;
+/// ```
+///
+/// ```
+/// let y = 2;
+/// ```
+///
+/// Other blank lines are kept.
+///
+///
+/// ```
+/// let z = 3;
+/// ```
+fn separated() {}
+
+/// Tilde fences are siblings too.
+///
+/// ~~~
+/// let x = 1;
+/// ~~~
+///
+/// ~~~rust
+/// let y = 2;
+/// ~~~
+///
+/// ```
+/// let z = 3;
+/// ```
+fn tildes() {}
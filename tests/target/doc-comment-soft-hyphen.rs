@@ -0,0 +1,7 @@
+// rustfmt-wrap_comments: true
+// rustfmt-comment_width: 40
+
+/// The configuration of the super­cali-
+/// fragilistic­expialidocious thing is
+/// described here.
+fn foo() {}
@@ -0,0 +1,12 @@
+// rustfmt-wrap_comments: true
+
+fn f(a: u8 /* */, b: u8) {
+    let x = 1; /* */
+    //
+    /* */
+
+    //
+    //
+    //
+    let y = 2;
+}
@@ -0,0 +1,17 @@
+// rustfmt-version: Two
+// rustfmt-blank_lines_upper_bound: 2
+// Copyright 2024 The Project Developers.
+
+
+#![no_std]
+
+
+#![deny(missing_docs)]
+// A comment after the inner attributes.
+
+
+//! Crate-level documentation.
+
+
+use alloc::vec;
+use core::fmt;
@@ -0,0 +1,18 @@
+// rustfmt-reorder_imports: true
+// rustfmt-version: One
+// `cfg` predicates do not split runs of imports with version One.
+
+#[cfg(feature = "a")]
+use alpha;
+#[cfg(feature = "b")]
+use bar;
+use core::fmt;
+#[cfg(feature = "a")]
+use foo;
+#[cfg(all(unix, feature = "a"))]
+use libc::c_char;
+#[cfg(all(unix, feature = "a"))]
+use libc::c_int;
+use std::io;
+#[cfg(feature = "b")]
+use zed;
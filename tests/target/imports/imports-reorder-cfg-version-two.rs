@@ -0,0 +1,18 @@
+// rustfmt-reorder_imports: true
+// rustfmt-version: Two
+// Imports are only sorted within runs that share the same `cfg` predicates.
+
+#[cfg(feature = "b")]
+use bar;
+#[cfg(feature = "b")]
+use zed;
+#[cfg(feature = "a")]
+use alpha;
+#[cfg(feature = "a")]
+use foo;
+use core::fmt;
+use std::io;
+#[cfg(all(unix, feature = "a"))]
+use libc::c_char;
+#[cfg(all(unix, feature = "a"))]
+use libc::c_int;
@@ -0,0 +1,8 @@
+// rustfmt-imports_granularity: Item
+
+use a::b::*;
+use a::c;
+use std::fmt::*;
+use std::fmt::{self};
+use std::io::*;
+use std::prelude::v1::*;
@@ -0,0 +1,34 @@
+// rustfmt-imports_granularity: Crate
+// rustfmt-imports_indent: Visual
+
+use a::{b, c};
+use x::{y, z::w};
+
+#[cfg(feature = "x")]
+mod imp {
+    use a::{b, c};
+    use x::{y, z::w};
+
+    #[cfg(feature = "y")]
+    mod inner {
+        use a::{b, c};
+        use x::{y, z::w};
+
+        mod innermost {
+            use a::{b, c};
+            use some_crate::{module_one::{first_item, second_item},
+                             module_two::{fifth_item, fourth_item, sixth_item, third_item}};
+            use x::{y, z::w};
+        }
+    }
+}
+
+fn main() {
+    use a::{b, c};
+    use x::{y, z::w};
+
+    {
+        use a::b;
+        use x::{y, z::w};
+    }
+}
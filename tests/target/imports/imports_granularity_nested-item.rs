@@ -0,0 +1,48 @@
+// rustfmt-imports_granularity: Item
+
+use a::b;
+use a::c;
+use x::y;
+use x::z::w;
+
+#[cfg(feature = "x")]
+mod imp {
+    use a::b;
+    use a::c;
+    use x::y;
+    use x::z::w;
+
+    #[cfg(feature = "y")]
+    mod inner {
+        use a::b;
+        use a::c;
+        use x::y;
+        use x::z::w;
+
+        mod innermost {
+            use a::b;
+            use a::c;
+            use some_crate::module_one::first_item;
+            use some_crate::module_one::second_item;
+            use some_crate::module_two::fifth_item;
+            use some_crate::module_two::fourth_item;
+            use some_crate::module_two::sixth_item;
+            use some_crate::module_two::third_item;
+            use x::y;
+            use x::z::w;
+        }
+    }
+}
+
+fn main() {
+    use a::b;
+    use a::c;
+    use x::y;
+    use x::z::w;
+
+    {
+        use a::b;
+        use x::y;
+        use x::z::w;
+    }
+}
@@ -0,0 +1,39 @@
+// rustfmt-imports_granularity: Module
+
+use a::{b, c};
+use x::y;
+use x::z::w;
+
+#[cfg(feature = "x")]
+mod imp {
+    use a::{b, c};
+    use x::y;
+    use x::z::w;
+
+    #[cfg(feature = "y")]
+    mod inner {
+        use a::{b, c};
+        use x::y;
+        use x::z::w;
+
+        mod innermost {
+            use a::{b, c};
+            use some_crate::module_one::{first_item, second_item};
+            use some_crate::module_two::{fifth_item, fourth_item, sixth_item, third_item};
+            use x::y;
+            use x::z::w;
+        }
+    }
+}
+
+fn main() {
+    use a::{b, c};
+    use x::y;
+    use x::z::w;
+
+    {
+        use a::b;
+        use x::y;
+        use x::z::w;
+    }
+}
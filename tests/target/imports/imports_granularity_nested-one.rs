@@ -0,0 +1,47 @@
+// rustfmt-imports_granularity: One
+
+use {
+    a::{b, c},
+    x::{y, z::w},
+};
+
+#[cfg(feature = "x")]
+mod imp {
+    use {
+        a::{b, c},
+        x::{y, z::w},
+    };
+
+    #[cfg(feature = "y")]
+    mod inner {
+        use {
+            a::{b, c},
+            x::{y, z::w},
+        };
+
+        mod innermost {
+            use {
+                a::{b, c},
+                some_crate::{
+                    module_one::{first_item, second_item},
+                    module_two::{fifth_item, fourth_item, sixth_item, third_item},
+                },
+                x::{y, z::w},
+            };
+        }
+    }
+}
+
+fn main() {
+    use {
+        a::{b, c},
+        x::{y, z::w},
+    };
+
+    {
+        use {
+            a::b,
+            x::{y, z::w},
+        };
+    }
+}
@@ -0,0 +1,33 @@
+// rustfmt-edition: 2021
+
+fn array_lengths() {
+    let a = [0u8; const { N * 2 }];
+    let b = [0u8; const { compute_length(argument_one, argument_two) }];
+    let long_binding_name =
+        [0u8; const { compute_a_very_long_function_name_here(argument_one, two) }];
+    x = [0u8;
+        const { compute_a_very_long_function_name_here(argument_one, argument_two, x, yyyyyyyy) }];
+}
+
+struct S<const N: usize>([u8; const { N + 1 }]);
+
+fn const_generic_args() {
+    let c = foo::<{ const { compute() } }>();
+    let d = foo::<
+        { const { compute_a_very_long_function_name_here(argument_one, argument_two, x) } },
+    >();
+}
+
+type T = Foo<{ const { N + 1 } }>;
+
+fn statement_position() {
+    const {
+        let x = 1;
+        assert!(x == 1);
+    }
+    let e = const { 1 + 2 };
+    let f = const {
+        let y = 2;
+        y * 2
+    };
+}
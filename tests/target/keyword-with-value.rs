@@ -0,0 +1,109 @@
+// Long values of `return`, `break` and `yield` keep the keyword on the first line.
+
+fn chains() {
+    loop {
+        return some_long_builder_function_name()
+            .with_many(argument_one, argument_two)
+            .finish();
+        break some_long_builder_function_name()
+            .with_many(argument_one, argument_two)
+            .finish();
+        break 'outer some_long_builder_function_name()
+            .with_many(argument_one, arg_two)
+            .finish();
+        yield some_long_builder_function_name()
+            .with_many(argument_one, argument_two)
+            .finish();
+    }
+}
+
+fn calls() {
+    loop {
+        return some_very_long_function_name_here(
+            argument_number_one,
+            argument_number_two,
+            three_44,
+        );
+        break some_very_long_function_name_here(
+            argument_number_one,
+            argument_number_two,
+            three_444,
+        );
+        break 'a_very_long_label_name_here_to_force some_very_long_function_name_here(
+            argument_one_,
+        );
+        yield some_very_long_function_name_here(
+            argument_number_one,
+            argument_number_two,
+            three_444,
+        );
+    }
+}
+
+fn struct_literals() {
+    loop {
+        return SomeStruct {
+            field_one: value_one,
+            field_two: value_two,
+            field_three: value_3,
+        };
+        break SomeStruct {
+            field_one: value_one,
+            field_two: value_two,
+            field_three: value_three,
+        };
+        break 'a SomeStruct {
+            field_one: value_one,
+            field_two: value_two,
+            field_three: value_3,
+        };
+        yield SomeStruct {
+            field_one: value_one,
+            field_two: value_two,
+            field_three: value_three,
+        };
+    }
+}
+
+fn matches() {
+    loop {
+        return match some_value {
+            Some(x) => x,
+            None => default_value_for_this_case_here(),
+        };
+        break match some_value {
+            Some(x) => x,
+            None => default_value_for_this_case_here(),
+        };
+        break 'label match some_value {
+            Some(x) => x,
+            None => default_value_for_this_case(),
+        };
+        yield match some_value {
+            Some(x) => x,
+            None => default_value_for_this_case_here(),
+        };
+    }
+}
+
+fn match_arms() {
+    match x {
+        Some(x) => {
+            return some_long_builder_function_name()
+                .with_many(argument_one, argument_two)
+                .finish()
+        }
+        None => {
+            break 'outer some_long_builder_function_name()
+                .with_many(argument_one, arg_two)
+                .finish()
+        }
+        _ => {
+            yield SomeStruct {
+                field_one: value_one,
+                field_two: value_two,
+                field_three: value_3,
+            }
+        }
+    }
+}
@@ -0,0 +1,9 @@
+// rustfmt-normalize_doc_attributes: true
+
+///Line with a \n in it
+///A tab	and a "quote" and é
+///Raw \n stays as written
+///Two
+///lines
+#[doc = "Bare \r carriage return"]
+fn foo() {}
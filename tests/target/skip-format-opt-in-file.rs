@@ -0,0 +1,10 @@
+#![rustfmt::skip]
+
+fn   generated_a() {   }
+
+#[rustfmt::format]
+fn maintained(x: u32) -> u32 {
+    x + 1
+}
+
+fn   generated_b() {   }
@@ -0,0 +1,9 @@
+// rustfmt-edition: 2021
+// The prefixes of string literals only have a single valid order, `rb"..."` and `rc"..."` are
+// rejected by the lexer, so they are kept as written.
+
+fn main() {
+    let bytes = (b"x", br"x", br#"x"#);
+    let c_strings = (c"x", cr"x", cr#"x"#);
+    let raw = (r"x", r#"x"#);
+}